@@ -0,0 +1,70 @@
+// ================================================
+// FILE: benches/hot_paths.rs
+// ================================================
+// Baselines for the paths that get slow with real-world data sizes:
+// markdown rendering, drawing long chats, and filtering big launcher lists.
+// Run with `cargo bench`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ratatui::{backend::TestBackend, Terminal};
+use bpt::api::{AppModel, SearchSource};
+use bpt::app::{App, AppAction, ChatMessage};
+use bpt::ui;
+
+fn large_markdown(sections: usize) -> String {
+    let mut md = String::new();
+    for i in 0..sections {
+        md.push_str(&format!("## Section {}\n\nSome **bold** and *italic* text with `inline code` and a [link](https://example.com/{}).\n\n", i, i));
+        md.push_str("- first item\n- second item with more words in it\n- third\n\n");
+        md.push_str("```rust\nfn main() {\n    println!(\"hello\");\n}\n```\n\n> a quoted line\n\n");
+    }
+    md
+}
+
+fn chat_app(messages: usize) -> App {
+    let mut app = App::new();
+    app.reduce(AppAction::SwitchTab);
+    let answer = large_markdown(2);
+    for i in 0..messages {
+        let (role, content) = if i % 2 == 0 { ("user", format!("question number {}", i)) } else { ("assistant", answer.clone()) };
+        let sources = if i % 2 == 1 {
+            (0..3).map(|s| SearchSource { title: format!("Result {}", s), url: format!("https://example.com/{}", s), content: String::new(), engine: "duckduckgo".into() }).collect()
+        } else { vec![] };
+        app.messages.push(ChatMessage { sources, ..ChatMessage::new(role, content) });
+    }
+    app
+}
+
+fn many_apps(n: usize) -> Vec<AppModel> {
+    (0..n).map(|i| AppModel {
+        id: i.to_string(),
+        name: format!("service-{}", i),
+        description: Some(format!("app number {} #tag{} #group{}", i, i % 50, i % 7)),
+        command: format!("./run.sh --port {}", 8000 + i % 1000),
+        url: format!("http://localhost:{}", 8000 + i % 1000),
+        ..Default::default()
+    }).collect()
+}
+
+fn bench_markdown(c: &mut Criterion) {
+    let doc = large_markdown(500);
+    c.bench_function("markdown_to_text/500_sections", |b| b.iter(|| ui::markdown_to_text(black_box(&doc))));
+}
+
+fn bench_chat_render(c: &mut Criterion) {
+    let mut app = chat_app(1000);
+    let mut terminal = Terminal::new(TestBackend::new(160, 50)).unwrap();
+    c.bench_function("draw/search_1k_messages", |b| b.iter(|| { terminal.draw(|f| ui::draw(f, &mut app)).unwrap(); }));
+}
+
+fn bench_filter(c: &mut Criterion) {
+    let mut app = App::new();
+    app.reduce(AppAction::AppsLoaded(many_apps(10_000)));
+    app.reduce(AppAction::ToggleFilter);
+    c.bench_function("filter/10k_apps_keystroke", |b| b.iter(|| {
+        app.reduce(AppAction::EnterFilterChar('7'));
+        app.reduce(AppAction::BackspaceFilter);
+    }));
+}
+
+criterion_group!(benches, bench_markdown, bench_chat_render, bench_filter);
+criterion_main!(benches);
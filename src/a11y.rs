@@ -0,0 +1,104 @@
+// ================================================
+// FILE: src/a11y.rs
+// ================================================
+// Accessibility mode (`--a11y`): state that is otherwise only a color gets
+// a word as well (`[focused]` after the title of the pane keys go to, `> `
+// before the selected row, `[up]`/`[down]` for URL checks, `[error]` before
+// failing Output lines), and spinners and ticking timers are replaced by
+// fixed text so the screen only changes when something happens.
+// `--a11y-out` also writes each finished answer, as plain text without
+// Markdown, to stdout or a file / FIFO an external screen reader follows.
+use std::io::Write;
+use std::path::PathBuf;
+use pulldown_cmark::{Event, Parser, Tag};
+use crate::logbuf::Severity;
+
+/// Appended to the title of the focused pane.
+pub const FOCUSED: &str = "[focused] ";
+/// Drawn before the selected row of a list.
+pub const SELECTED: &str = "> ";
+
+/// After an app's name, instead of the colored dot of its URL check.
+pub fn health(up: Option<bool>) -> &'static str {
+    match up { Some(true) => " [up]", Some(false) => " [down]", None => " [unchecked]" }
+}
+
+/// Before an Output line, instead of its color alone.
+pub fn severity(s: Severity) -> &'static str {
+    match s { Severity::Info => "", Severity::Warning => "[warning] ", Severity::Stderr => "[stderr] ", Severity::Error => "[error] " }
+}
+
+/// Where `--a11y-out` sends answers: `-` is stdout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sink { Stdout, File(PathBuf) }
+
+impl Sink {
+    pub fn parse(s: &str) -> Self {
+        if s == "-" { Sink::Stdout } else { Sink::File(s.into()) }
+    }
+}
+
+/// An answer as it reads aloud: Markdown markers dropped, list items on
+/// lines of their own, at most one blank line between blocks.
+pub fn spoken(markdown: &str) -> String {
+    let mut out = String::new();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) | Event::Code(t) => out.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Start(Tag::Item) => out.push_str("- "),
+            Event::End(Tag::Paragraph | Tag::Heading(..) | Tag::CodeBlock(_) | Tag::BlockQuote) => out.push_str("\n\n"),
+            Event::End(Tag::Item | Tag::List(_)) => out.push('\n'),
+            _ => {},
+        }
+    }
+    let mut text = String::new();
+    for line in out.lines().map(str::trim_end) {
+        if line.is_empty() && (text.is_empty() || text.ends_with("\n\n")) { continue; }
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.trim_end().to_string()
+}
+
+/// Writes `text` and a blank line to `sink` on one long-lived thread, which
+/// keeps the file open so a FIFO reader sees one stream. Opening a FIFO
+/// waits for its reader; a failed write is retried with a fresh open.
+pub fn speak(sink: Sink, text: String) {
+    static WRITER: std::sync::OnceLock<std::sync::mpsc::Sender<(Sink, String)>> = std::sync::OnceLock::new();
+    let writer = WRITER.get_or_init(|| {
+        let (jobs, rx) = std::sync::mpsc::channel::<(Sink, String)>();
+        std::thread::spawn(move || {
+            let mut open: Option<(PathBuf, std::fs::File)> = None;
+            for (sink, text) in rx {
+                let _ = match sink {
+                    Sink::Stdout => { let mut out = std::io::stdout().lock(); writeln!(out, "{}\n", text).and_then(|_| out.flush()) },
+                    Sink::File(path) => {
+                        if open.as_ref().is_none_or(|(p, _)| *p != path) {
+                            open = std::fs::OpenOptions::new().create(true).append(true).open(&path).ok().map(|f| (path, f));
+                        }
+                        let res = open.as_mut().map_or(Ok(()), |(_, f)| writeln!(f, "{}\n", text).and_then(|_| f.flush()));
+                        if res.is_err() { open = None; }
+                        res
+                    },
+                };
+            }
+        });
+        jobs
+    });
+    let _ = writer.send((sink, text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_are_spoken_without_markdown() {
+        let answer = "# Setup\n\nRun **cargo** with `--release`:\n\n```sh\ncargo build\n```\n\n- one\n- two\n\n\n> done";
+        assert_eq!(spoken(answer), "Setup\n\nRun cargo with --release:\n\ncargo build\n\n- one\n- two\n\ndone");
+        assert_eq!(spoken(""), "");
+        assert_eq!((Sink::parse("-"), Sink::parse("/tmp/bpt.fifo")), (Sink::Stdout, Sink::File("/tmp/bpt.fifo".into())));
+        assert_eq!((health(Some(false)), severity(Severity::Error)), (" [down]", "[error] "));
+    }
+}
@@ -0,0 +1,121 @@
+// ================================================
+// FILE: src/ansi.rs
+// ================================================
+// ANSI escape sequences in launch output. SGR codes (colors, bold, ...) are
+// turned into styled spans for the Output pane; every other sequence (cursor
+// movement, window titles) is dropped, as are stray control characters. A
+// carriage return starts the line over, the way progress bars expect.
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+
+/// `line` split into styled spans; `colors: false` keeps only the text.
+pub fn spans(line: &str, colors: bool) -> Vec<Span<'static>> {
+    let mut out: Vec<Span<'static>> = vec![];
+    let (mut style, mut text) = (Style::default(), String::new());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut params = String::new();
+                    for p in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&p) {
+                            if p == 'm' && colors {
+                                if !text.is_empty() { out.push(Span::styled(std::mem::take(&mut text), style)); }
+                                style = sgr(style, &params);
+                            }
+                            break;
+                        }
+                        params.push(p);
+                    }
+                },
+                // OSC (e.g. window title) ends with BEL or ESC \.
+                Some(']') => {
+                    while let Some(p) = chars.next() {
+                        if p == '\x07' { break; }
+                        if p == '\x1b' { chars.next_if_eq(&'\\'); break; }
+                    }
+                },
+                _ => {},
+            },
+            '\r' => { out.clear(); text.clear(); },
+            '\t' => text.push_str("    "),
+            c if c.is_control() => {},
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() { out.push(Span::styled(text, style)); }
+    out
+}
+
+/// The text of `line` without escape sequences.
+pub fn strip(line: &str) -> String {
+    if !line.contains(['\x1b', '\r', '\t']) && !line.chars().any(char::is_control) { return line.to_string(); }
+    spans(line, false).into_iter().map(|s| s.content.into_owned()).collect()
+}
+
+const BASIC: [Color; 8] = [Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::Gray];
+const BRIGHT: [Color; 8] = [Color::DarkGray, Color::LightRed, Color::LightGreen, Color::LightYellow, Color::LightBlue, Color::LightMagenta, Color::LightCyan, Color::White];
+
+/// `style` after the SGR parameters `params` (`1;31`, `38;5;208`, ...).
+fn sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = params.split([';', ':']).map(|p| p.parse().unwrap_or(0)).collect();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            n @ 30..=37 => style.fg = Some(BASIC[(n - 30) as usize]),
+            n @ 40..=47 => style.bg = Some(BASIC[(n - 40) as usize]),
+            n @ 90..=97 => style.fg = Some(BRIGHT[(n - 90) as usize]),
+            n @ 100..=107 => style.bg = Some(BRIGHT[(n - 100) as usize]),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            n @ (38 | 48) => {
+                let color = match codes.get(i + 1) {
+                    Some(5) => { i += 2; codes.get(i).map(|c| Color::Indexed(*c as u8)) },
+                    Some(2) => { i += 4; codes.get(i - 2..=i).map(|c| Color::Rgb(c[0] as u8, c[1] as u8, c[2] as u8)) },
+                    _ => None,
+                };
+                if n == 38 { style.fg = color.or(style.fg); } else { style.bg = color.or(style.bg); }
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_become_styled_spans() {
+        let s = spans("\x1b[1;31merror\x1b[0m: \x1b[38;5;208mwarn\x1b[39m \x1b[48;2;1;2;3mbg", true);
+        assert_eq!(s.iter().map(|s| s.content.as_ref()).collect::<Vec<_>>(), ["error", ": ", "warn", " ", "bg"]);
+        assert_eq!(s[0].style, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+        assert_eq!(s[1].style, Style::default());
+        assert_eq!(s[2].style.fg, Some(Color::Indexed(208)));
+        assert_eq!(s[3].style.fg, None);
+        assert_eq!(s[4].style.bg, Some(Color::Rgb(1, 2, 3)));
+    }
+
+    #[test]
+    fn strips_other_sequences() {
+        assert_eq!(strip("\x1b]0;title\x07\x1b[2K\x1b[32mok\x1b[m\x07"), "ok");
+        assert_eq!(strip("10%\r50%\r100% done"), "100% done");
+        assert_eq!(strip("plain"), "plain");
+        assert_eq!(spans("\x1b[31mred", false)[0].style, Style::default(), "colors off");
+    }
+}
@@ -6,13 +6,17 @@ use futures::stream::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use eventsource_stream::Eventsource; 
+use std::sync::RwLock;
 use std::time::Duration;
 use crate::app::AppAction;
+use crate::followup;
+use crate::tokens;
 use tokio::sync::mpsc::UnboundedSender;
 
 // --- Launcher Models (UNCHANGED) ---
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct AppModel {
     #[serde(default, skip_serializing_if = "String::is_empty")] 
     pub id: String,
@@ -20,9 +24,28 @@ pub struct AppModel {
     pub description: Option<String>,
     pub command: String,
     pub url: String,
+    #[serde(flatten)]
+    pub launch: LaunchEnv,
+    /// Name of an ssh.rs host to launch on instead of the backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Extra environment variables and working directory for an app's process.
+/// Stored with the app and sent in the body of launch and exec requests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct LaunchEnv {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+impl LaunchEnv {
+    pub fn is_empty(&self) -> bool { self.env.is_empty() && self.cwd.is_none() }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LaunchResponse {
     pub success: bool,
     pub message: String,
@@ -30,29 +53,62 @@ pub struct LaunchResponse {
     pub stderr: String,
 }
 
+/// A process started through the launcher backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ProcessInfo {
+    #[serde(deserialize_with = "id_string")]
+    pub id: String,
+    #[serde(default, alias = "appId")]
+    pub app_id: Option<String>,
+    #[serde(default, alias = "appName")]
+    pub name: String,
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default, alias = "startedAt")]
+    pub started_at: Option<u64>,  // Unix seconds
+    #[serde(default)]
+    pub status: String,
+}
+
+/// Accepts ids sent as either strings or numbers.
+fn id_string<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<String, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::String(s) => s,
+        other => other.to_string(),
+    })
+}
+
 // --- Search Models (NEW) ---
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: i64,
     pub title: String,
+    #[serde(default, alias = "updatedAt", skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Model {
     pub id: String,
     pub name: String,
+    /// Parameter count as the provider reports it (`8.0B`), shown after the name.
+    #[serde(default, alias = "parameter_size", alias = "parameterSize", skip_serializing_if = "Option::is_none")]
+    pub params: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub id: i64,
     pub name: String,
     #[serde(rename = "type")]
     pub type_: String,
     pub is_enabled: bool,
+    /// Type-specific settings (`url`, `api_key`, ...), as the backend stores them.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub config: Value,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SearchSource {
     pub title: String,
     pub url: String,
@@ -60,82 +116,333 @@ pub struct SearchSource {
     pub engine: String,
 }
 
+/// Binary payload streamed alongside an answer (e.g. a generated chart).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(default, alias = "name")]
+    pub filename: Option<String>,
+    #[serde(default, alias = "mimeType", alias = "mime_type")]
+    pub mime: Option<String>,
+    #[serde(alias = "base64", alias = "content")]
+    pub data: String,
+}
+
+/// A step the search pipeline ran for an answer (a scraper, the calculator,
+/// ...), from `tool-call` and `tool-result` events. `result` is `None` while
+/// the call is running.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: Option<String>,
+    pub name: String,
+    pub args: String,
+    pub result: Option<String>,
+}
+
+impl ToolCall {
+    /// A call or result event's payload. Arguments and results may be JSON
+    /// of any shape; anything but a string is kept as compact JSON.
+    pub fn from_event(data: &Value) -> Self {
+        let text = |obj: &Value, keys: &[&str]| keys.iter().map(|k| &obj[*k]).find(|v| !v.is_null()).map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string));
+        // OpenAI nests the name and arguments under `function`.
+        let call = if data["function"].is_object() { &data["function"] } else { data };
+        ToolCall {
+            id: text(data, &["id", "call_id", "callId", "tool_call_id"]),
+            name: text(call, &["name", "tool"]).unwrap_or_else(|| "tool".to_string()),
+            args: text(call, &["arguments", "args", "input"]).unwrap_or_default(),
+            result: text(data, &["result", "output", "content"]),
+        }
+    }
+}
+
+/// Everything needed to run one query against the search backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchRequest {
+    pub query: String,
+    /// Title for a newly created conversation (the query without attached context).
+    pub title: String,
+    pub convo_id: Option<i64>,
+    pub model: String,
+    pub provider: String,
+    pub active_providers: Vec<i64>,
+    /// From the active preset; `None` sends DEFAULT_SYSTEM_PROMPT and the backend's temperature.
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f32>,
+    /// Earlier turns (role, text) to use in place of the stored conversation,
+    /// when some were left out; `None` lets the backend use all of them.
+    pub history: Option<Vec<(String, String)>>,
+}
+
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful TUI assistant that provides concise markdown responses.";
+
 // --- Launcher API Functions (UNCHANGED) ---
-const BASE_URL: &str = "http://localhost:5660/api/apps";
-const SEARCH_URL: &str = "http://localhost:3001/api";
+pub const DEFAULT_LAUNCHER_URL: &str = "http://localhost:5660";
+pub const DEFAULT_SEARCH_URL: &str = "http://localhost:3001";
+
+/// Launcher and search backend base URLs, from config.rs; the defaults until set.
+static BACKENDS: RwLock<Option<(String, String)>> = RwLock::new(None);
+
+pub fn set_backends(launcher: &str, search: &str) {
+    let urls = (launcher.trim().trim_end_matches('/').to_string(), search.trim().trim_end_matches('/').to_string());
+    *BACKENDS.write().unwrap_or_else(|e| e.into_inner()) = Some(urls);
+}
+
+/// `<launcher>/api/<path>`.
+fn launcher_url(path: &str) -> String {
+    let urls = BACKENDS.read().unwrap_or_else(|e| e.into_inner());
+    format!("{}/api/{}", urls.as_ref().map_or(DEFAULT_LAUNCHER_URL, |u| u.0.as_str()), path)
+}
+
+/// `<search>/api`.
+fn search_url() -> String {
+    let urls = BACKENDS.read().unwrap_or_else(|e| e.into_inner());
+    format!("{}/api", urls.as_ref().map_or(DEFAULT_SEARCH_URL, |u| u.1.as_str()))
+}
+
+/// Name of the throwaway app behind ad-hoc commands on backends without `/api/exec`.
+pub const TEMP_APP_NAME: &str = "__TEMP_CMD__";
 
 pub async fn fetch_apps() -> Result<Vec<AppModel>> {
     let client = Client::builder().timeout(Duration::from_secs(2)).build()?;
-    let resp = client.get(BASE_URL).send().await?;
+    let resp = client.get(launcher_url("apps")).send().await?;
     Ok(resp.json::<Vec<AppModel>>().await?)
 }
 
 pub async fn create_app(app: &AppModel) -> Result<AppModel> {
     let client = Client::new();
-    let resp = client.post(BASE_URL).json(app).send().await?;
+    let resp = client.post(launcher_url("apps")).json(app).send().await?;
     Ok(resp.json::<AppModel>().await?)
 }
 
 pub async fn update_app(app: &AppModel) -> Result<()> {
     let client = Client::new();
-    client.put(format!("{}/{}", BASE_URL, app.id)).json(app).send().await?;
+    client.put(format!("{}/{}", launcher_url("apps"), app.id)).json(app).send().await?;
     Ok(())
 }
 
 pub async fn delete_app(id: &str) -> Result<()> {
     let client = Client::new();
-    client.delete(format!("{}/{}", BASE_URL, id)).send().await?;
+    client.delete(format!("{}/{}", launcher_url("apps"), id)).send().await?.error_for_status()?;
     Ok(())
 }
 
-pub async fn launch_app(id: String) -> Result<LaunchResponse> {
+/// A launch request, with the app's env/cwd in the body when it has any.
+fn launch_request(client: &Client, url: String, env: &LaunchEnv) -> reqwest::RequestBuilder {
+    let req = client.post(url);
+    if env.is_empty() { req } else { req.json(env) }
+}
+
+pub async fn launch_app(id: String, env: &LaunchEnv) -> Result<LaunchResponse> {
     let client = Client::new();
-    let resp = client.post(format!("{}/{}/launch", BASE_URL, id)).send().await?;
+    let resp = launch_request(&client, format!("{}/{}/launch", launcher_url("apps"), id), env).send().await?;
     Ok(resp.json::<LaunchResponse>().await?)
 }
 
+/// Asks the backend to stop whatever it started for app `id`. Returns its message.
+pub async fn stop_app(id: &str) -> Result<String> {
+    let client = Client::new();
+    let resp = client.post(format!("{}/{}/stop", launcher_url("apps"), id)).send().await?.error_for_status()?;
+    let body: Value = resp.json().await.unwrap_or_default();
+    if body["success"].as_bool() == Some(false) { anyhow::bail!("{}", body["message"].as_str().unwrap_or("stop failed")); }
+    Ok(body["message"].as_str().unwrap_or("").to_string())
+}
+
+/// Streams a launch from `{id}/launch/stream`, sending each output line as
+/// `LaunchOutput`. The endpoint may answer with SSE (`stdout`/`stderr` events
+/// carrying a line, then `exit` with `{"success": bool}`) or plain chunked
+/// text. Returns `Ok(None)` when the backend has no streaming endpoint, so the
+/// caller can fall back to `launch_app`.
+pub async fn launch_app_stream(id: &str, env: &LaunchEnv, seq: u64, tx: &UnboundedSender<AppAction>) -> Result<Option<bool>> {
+    let client = Client::new();
+    let resp = launch_request(&client, format!("{}/{}/launch/stream", launcher_url("apps"), id), env).send().await?;
+    if matches!(resp.status(), reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED) { return Ok(None); }
+    stream_output(resp.error_for_status()?, seq, tx).await.map(Some)
+}
+
+/// Runs `command` directly via `/api/exec` (`{"command": ..., "env": ..., "cwd": ...}`),
+/// streamed like `launch_app_stream`. `Ok(None)` when the backend has no such endpoint.
+pub async fn exec_stream(command: &str, env: &LaunchEnv, seq: u64, tx: &UnboundedSender<AppAction>) -> Result<Option<bool>> {
+    let client = Client::new();
+    let mut body = serde_json::to_value(env)?;
+    body["command"] = command.into();
+    let resp = client.post(launcher_url("exec")).json(&body).send().await?;
+    if matches!(resp.status(), reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED) { return Ok(None); }
+    stream_output(resp.error_for_status()?, seq, tx).await.map(Some)
+}
+
+async fn stream_output(resp: reqwest::Response, seq: u64, tx: &UnboundedSender<AppAction>) -> Result<bool> {
+    let is_sse = resp.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).is_some_and(|v| v.starts_with("text/event-stream"));
+    let send = |line: &str, stderr: bool| { let _ = tx.send(AppAction::LaunchOutput { seq, line: line.to_string(), stderr }); };
+
+    if is_sse {
+        let mut stream = resp.bytes_stream().eventsource();
+        while let Some(event) = stream.next().await {
+            let evt = event?;
+            match evt.event.as_str() {
+                "stdout" | "message" => evt.data.lines().for_each(|l| send(l, false)),
+                "stderr" => evt.data.lines().for_each(|l| send(l, true)),
+                "exit" | "done" => {
+                    let data: Value = serde_json::from_str(&evt.data).unwrap_or_default();
+                    return Ok(data["success"].as_bool().unwrap_or_else(|| data["code"].as_i64() == Some(0)));
+                },
+                _ => {}
+            }
+        }
+        Ok(true)
+    } else {
+        let mut stream = resp.bytes_stream();
+        let mut pending = String::new();
+        while let Some(chunk) = stream.next().await {
+            pending.push_str(&String::from_utf8_lossy(&chunk?));
+            while let Some(pos) = pending.find('\n') {
+                let line: String = pending.drain(..=pos).collect();
+                send(line.trim_end_matches(['\r', '\n']), false);
+            }
+        }
+        if !pending.is_empty() { send(&pending, false); }
+        Ok(true)
+    }
+}
+
+/// GETs an app's web page for the preview: `(content type, body)`.
+pub async fn fetch_page(url: &str) -> Result<(String, String)> {
+    let client = Client::builder().timeout(Duration::from_secs(5)).build()?;
+    let resp = client.get(url).send().await?.error_for_status()?;
+    let kind = resp.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+    Ok((kind, resp.text().await?))
+}
+
+/// Whether something answers a HEAD request at `url`. Any response below 500
+/// counts: an app that says 404 or 405 for `/` is still up.
+pub async fn check_health(url: &str) -> bool {
+    let Ok(client) = Client::builder().timeout(Duration::from_secs(2)).build() else { return false };
+    client.head(url).send().await.is_ok_and(|r| !r.status().is_server_error())
+}
+
+/// `Ok(None)` when the backend has no process endpoints.
+pub async fn fetch_processes() -> Result<Option<Vec<ProcessInfo>>> {
+    let client = Client::new();
+    let resp = client.get(launcher_url("processes")).send().await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(Some(resp.error_for_status()?.json::<Vec<ProcessInfo>>().await?))
+}
+
+/// `action` is `stop` or `restart`. Returns the backend's message, if any.
+pub async fn process_action(id: &str, action: &str) -> Result<String> {
+    let client = Client::new();
+    let resp = client.post(format!("{}/{}/{}", launcher_url("processes"), id, action)).send().await?.error_for_status()?;
+    let body: Value = resp.json().await.unwrap_or_default();
+    Ok(body["message"].as_str().unwrap_or("").to_string())
+}
+
+pub async fn process_output(id: &str) -> Result<String> {
+    let client = Client::new();
+    let resp = client.get(format!("{}/{}/output", launcher_url("processes"), id)).send().await?.error_for_status()?;
+    let text = resp.text().await?;
+    // Either plain text or `{"stdout": ..., "stderr": ...}`.
+    Ok(match serde_json::from_str::<Value>(&text) {
+        Ok(v) if v.is_object() => format!("{}{}", v["stdout"].as_str().unwrap_or(""), v["stderr"].as_str().unwrap_or("")),
+        _ => text,
+    })
+}
+
 // --- Searchrs API Functions (UPDATED) ---
 
+/// Whether `e` means the backend couldn't be reached at all, as opposed to
+/// it answering with an error.
+pub fn is_offline(e: &anyhow::Error) -> bool {
+    e.chain().filter_map(|c| c.downcast_ref::<reqwest::Error>()).any(|e| e.is_connect() || e.is_timeout())
+}
+
 pub async fn fetch_conversations() -> Result<Vec<Conversation>> {
     let client = Client::new();
-    let resp = client.get(format!("{}/conversations", SEARCH_URL)).send().await?;
+    let resp = client.get(format!("{}/conversations", search_url())).send().await?;
     Ok(resp.json::<Vec<Conversation>>().await?)
 }
 
 pub async fn load_conversation(id: i64) -> Result<Value> {
     let client = Client::new();
-    let resp = client.get(format!("{}/conversations/{}", SEARCH_URL, id)).send().await?;
+    let resp = client.get(format!("{}/conversations/{}", search_url(), id)).send().await?;
     Ok(resp.json::<Value>().await?)
 }
 
+pub async fn delete_conversation(id: i64) -> Result<()> {
+    let client = Client::new();
+    client.delete(format!("{}/conversations/{}", search_url(), id)).send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Web results taken from SearxNG for a direct chat query.
+pub const SEARXNG_RESULTS: usize = 8;
+
+/// Searches a SearxNG instance through its JSON API (`format=json` has to be
+/// enabled in its settings.yml), without the search backend.
+pub async fn searxng_search(base: &str, query: &str) -> Result<Vec<SearchSource>> {
+    let client = Client::new();
+    let resp = client.get(format!("{}/search", base)).query(&[("q", query), ("format", "json")]).timeout(Duration::from_secs(15)).send().await?.error_for_status()?;
+    Ok(searxng_results(&resp.json::<Value>().await?))
+}
+
+/// The sources in a SearxNG response, best first.
+pub fn searxng_results(resp: &Value) -> Vec<SearchSource> {
+    resp["results"].as_array().into_iter().flatten()
+        .filter_map(|r| {
+            let url = r["url"].as_str().filter(|u| !u.is_empty())?;
+            let engine = r["engine"].as_str().or_else(|| r["engines"][0].as_str()).unwrap_or("searxng");
+            Some(SearchSource { title: r["title"].as_str().unwrap_or(url).to_string(), url: url.to_string(), content: r["content"].as_str().unwrap_or("").trim().to_string(), engine: engine.to_string() })
+        })
+        .take(SEARXNG_RESULTS)
+        .collect()
+}
+
+/// `query` with the web results to answer it from, numbered for citing.
+pub fn grounded_prompt(query: &str, sources: &[SearchSource]) -> String {
+    if sources.is_empty() { return query.to_string(); }
+    let results: Vec<String> = sources.iter().enumerate().map(|(i, s)| format!("[{}] {}\n{}\n{}", i + 1, s.title, s.url, s.content)).collect();
+    format!("Answer the question using the web search results below, citing them as [n] where they support the answer.\n\n{}\n\nQuestion: {}", results.join("\n\n"), query)
+}
+
+/// Creates search provider `p` (id 0) or updates it in place.
+pub async fn save_provider(p: &ProviderConfig) -> Result<()> {
+    let client = Client::new();
+    let req = if p.id == 0 { client.post(format!("{}/providers", search_url())) } else { client.put(format!("{}/providers/{}", search_url(), p.id)) };
+    req.json(p).send().await?.error_for_status()?;
+    Ok(())
+}
+
+pub async fn delete_provider(id: i64) -> Result<()> {
+    let client = Client::new();
+    client.delete(format!("{}/providers/{}", search_url(), id)).send().await?.error_for_status()?;
+    Ok(())
+}
+
+pub async fn rename_conversation(id: i64, title: &str) -> Result<()> {
+    let client = Client::new();
+    client.patch(format!("{}/conversations/{}", search_url(), id)).json(&serde_json::json!({ "title": title })).send().await?.error_for_status()?;
+    Ok(())
+}
+
 pub async fn fetch_providers_list() -> Result<Vec<ProviderConfig>> {
     let client = Client::new();
-    let resp = client.get(format!("{}/providers", SEARCH_URL)).send().await?;
+    let resp = client.get(format!("{}/providers", search_url())).send().await?;
     Ok(resp.json::<Vec<ProviderConfig>>().await?)
 }
 
 pub async fn fetch_models(provider: &str) -> Result<Vec<Model>> {
     let client = Client::new();
-    let resp = client.get(format!("{}/models?provider={}", SEARCH_URL, provider)).send().await?;
+    let resp = client.get(format!("{}/models?provider={}", search_url(), provider)).send().await?;
     Ok(resp.json::<Vec<Model>>().await?)
 }
 
-pub async fn start_search_stream(
-    query: String,
-    convo_id: Option<i64>,
-    model: String,
-    provider: String,
-    active_providers: Vec<i64>,
-    tx: UnboundedSender<AppAction>
-) -> Result<()> {
+pub async fn start_search_stream(req: SearchRequest, tx: UnboundedSender<AppAction>) -> Result<()> {
+    let SearchRequest { query, title, convo_id, model, provider, active_providers, system_prompt, temperature, history } = req;
     let client = Client::new();
 
     // 1. Create or Use Conversation
     let id = if let Some(cid) = convo_id {
         cid
     } else {
-        let convo_res = client.post(format!("{}/conversations", SEARCH_URL))
-            .json(&serde_json::json!({ "title": query }))
+        let convo_res = client.post(format!("{}/conversations", search_url()))
+            .json(&serde_json::json!({ "title": title }))
             .send()
             .await?;
         let convo_json: Value = convo_res.json().await?;
@@ -145,17 +452,21 @@ pub async fn start_search_stream(
     };
 
     // 2. Start Stream
-    let body = serde_json::json!({
+    let mut body = serde_json::json!({
         "query": query,
         "timeframe": "", // Default all time
         "providers": active_providers,
         "provider": provider, 
         "model": model,
-        "systemPrompt": "You are a helpful TUI assistant that provides concise markdown responses."
+        "systemPrompt": system_prompt.as_deref().unwrap_or(DEFAULT_SYSTEM_PROMPT),
     });
+    if let Some(t) = temperature { body["temperature"] = serde_json::json!(t); }
+    if let Some(turns) = history {
+        body["history"] = turns.into_iter().map(|(role, content)| serde_json::json!({ "role": role, "content": content })).collect();
+    }
 
     let mut stream = client
-        .post(format!("{}/conversations/{}/query", SEARCH_URL, id))
+        .post(format!("{}/conversations/{}/query", search_url(), id))
         .json(&body)
         .send()
         .await?
@@ -178,10 +489,58 @@ pub async fn start_search_stream(
                             }
                         }
                     },
+                    "reasoning" | "thinking" | "reasoning-chunk" | "thinking-chunk" => {
+                        // `{"text": ...}` like summary chunks, or the bare text.
+                        let text = match serde_json::from_str::<Value>(&evt.data) {
+                            Ok(Value::String(t)) => Some(t),
+                            Ok(data) if data.is_object() => data["text"].as_str().map(str::to_string),
+                            _ => Some(evt.data),
+                        };
+                        if let Some(text) = text { let _ = tx.send(AppAction::SearchReasoningToken(text)); }
+                    },
+                    "tool-call" | "tool_call" | "function-call" | "function_call" => {
+                        if let Ok(data) = serde_json::from_str::<Value>(&evt.data) {
+                            let _ = tx.send(AppAction::ToolCallStarted(ToolCall { result: None, ..ToolCall::from_event(&data) }));
+                        }
+                    },
+                    "tool-result" | "tool_result" | "function-result" | "function_result" => {
+                        let call = match serde_json::from_str::<Value>(&evt.data) {
+                            Ok(data) if data.is_object() => ToolCall::from_event(&data),
+                            _ => ToolCall { result: Some(evt.data), ..Default::default() },
+                        };
+                        let _ = tx.send(AppAction::ToolCallFinished(call));
+                    },
+                    "title" | "conversation-title" => {
+                        let title = match serde_json::from_str::<Value>(&evt.data) {
+                            Ok(Value::String(t)) => t,
+                            Ok(data) => data["title"].as_str().unwrap_or_default().to_string(),
+                            Err(_) => evt.data.clone(),
+                        };
+                        let title = title.trim().to_string();
+                        if !title.is_empty() { let _ = tx.send(AppAction::ConversationRenamed { id, title }); }
+                    },
+                    "follow-ups" | "followups" | "suggestions" | "related-questions" => {
+                        let questions = serde_json::from_str::<Value>(&evt.data).map(|data| followup::from_event(&data)).unwrap_or_default();
+                        if !questions.is_empty() { let _ = tx.send(AppAction::FollowUpsReceived(questions)); }
+                    },
+                    "attachment" | "image" | "file" => {
+                        if let Ok(att) = serde_json::from_str::<Attachment>(&evt.data) {
+                            let _ = tx.send(AppAction::AttachmentReceived(att));
+                        }
+                    },
+                    "usage" => {
+                        if let Some(usage) = serde_json::from_str::<Value>(&evt.data).ok().as_ref().and_then(tokens::parse_usage) {
+                            let _ = tx.send(AppAction::SearchUsage(usage));
+                        }
+                    },
                     "error" => {
                         let _ = tx.send(AppAction::SearchError(evt.data));
                     },
                     "summary-done" => {
+                        // Some backends report usage with the closing event instead.
+                        if let Some(usage) = serde_json::from_str::<Value>(&evt.data).ok().as_ref().and_then(tokens::parse_usage) {
+                            let _ = tx.send(AppAction::SearchUsage(usage));
+                        }
                         let _ = tx.send(AppAction::SearchDone);
                         break;
                     },
@@ -196,4 +555,54 @@ pub async fn start_search_stream(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// A one-off question outside the chat, asked in a throwaway conversation
+/// (deleted afterwards) with no web providers. `on_chunk` gets the answer as
+/// it streams in.
+pub async fn ask_once(query: &str, model: &str, provider: &str, mut on_chunk: impl FnMut(&str)) -> Result<()> {
+    let client = Client::new();
+    let convo: Value = client.post(format!("{}/conversations", search_url()))
+        .json(&serde_json::json!({ "title": "bpt (temporary)" }))
+        .send().await?.error_for_status()?.json().await?;
+    let id = convo["id"].as_i64().ok_or_else(|| anyhow::anyhow!("backend returned no conversation id"))?;
+
+    let body = serde_json::json!({ "query": query, "timeframe": "", "providers": [], "provider": provider, "model": model });
+    let result: Result<()> = async {
+        let mut stream = client.post(format!("{}/conversations/{}/query", search_url(), id)).json(&body).send().await?.error_for_status()?.bytes_stream().eventsource();
+        while let Some(event) = stream.next().await {
+            let evt = event?;
+            match evt.event.as_str() {
+                "summary-chunk" => {
+                    if let Some(text) = serde_json::from_str::<Value>(&evt.data).ok().and_then(|d| d["text"].as_str().map(str::to_string)) { on_chunk(&text); }
+                },
+                "error" => anyhow::bail!("{}", evt.data),
+                "summary-done" => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }.await;
+    let _ = delete_conversation(id).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_searxng_results_into_a_prompt() {
+        let resp = json!({"results": [
+            {"title": "Rust", "url": "https://rust-lang.org", "content": " A language. ", "engine": "duckduckgo"},
+            {"title": "No link", "url": ""},
+            {"url": "https://doc.rust-lang.org", "engines": ["google", "bing"]},
+        ]});
+        let sources = searxng_results(&resp);
+        assert_eq!(sources.iter().map(|s| (s.title.as_str(), s.engine.as_str())).collect::<Vec<_>>(), [("Rust", "duckduckgo"), ("https://doc.rust-lang.org", "google")]);
+        let prompt = grounded_prompt("what is rust", &sources);
+        assert!(prompt.contains("[1] Rust\nhttps://rust-lang.org\nA language.") && prompt.ends_with("Question: what is rust"));
+        assert_eq!(grounded_prompt("hi", &[]), "hi");
+    }
+}
@@ -1,41 +1,394 @@
 // ================================================
 // FILE: src/app.rs
 // ================================================
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::path::PathBuf;
 use tokio::sync::mpsc;
-use crate::api::{self, AppModel, Conversation, Model, ProviderConfig, SearchSource};
+use crate::ansi;
+use crate::api::{self, AppModel, Attachment, Conversation, LaunchEnv, Model, ProcessInfo, ProviderConfig, SearchRequest, SearchSource, ToolCall};
+use crate::backoff::Backoff;
+use crate::calc;
+use crate::a11y::{self, Sink};
+use crate::clock;
+use crate::colors::ColorMode;
+use crate::config::{self, Config, Endpoint, Preset, Setting, SettingRow};
+use crate::snippets::{self, CodeBlock, Verb};
+use crate::sources::Source;
+use crate::ssh::{self, SshHost};
+use crate::tmux::{self, TmuxMode};
+use crate::template;
+use crate::textarea::{self, CursorMove, TextArea};
+use crate::todo;
+use crate::tokens::{self, Usage};
+use crate::commands::{self, SlashCommand};
+use crate::convo_search::{self, Hit};
+use crate::direct;
+use crate::files::{self, FileBrowser, Pick};
+use crate::ollama;
+use crate::editor::{self, FileRef};
+use crate::followup;
+use crate::hooks::Event;
+use crate::notify::Notifications;
+use crate::plugins::{self, Manifest, Panel, Reply};
+use crate::summary;
+use crate::title;
+use crate::transcript;
+use crate::keymap::{self, Keymap};
+use crate::logbuf::LogBuffer;
+use crate::mentions;
+use crate::effects::{self, Effect};
+pub use crate::mode::InputMode;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use crate::replay::{Recorder, ReplayState};
+use crate::store;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub enum CurrentScreen {
+    #[default]
     Launcher,
     Search,
+    Processes,
+    Settings,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum InputMode {
-    Normal,         // Launcher navigation
-    Editing,        // Modal form
-    Filtering,      // Launcher filter
-    AdHocCmd,       // Ad-hoc command
-    
-    // Search Specific Modes
-    SearchInput,    // Typing query
-    SearchSidebar,  // Navigating history/settings
-    ChatHistory,    // Scrolling chat
-}
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub enum SearchSidebarState {
+    #[default]
     Hidden,
     History,
+    Highlights,
     Settings,
+    /// Panels registered by plugins (plugins.rs); only offered when there are some.
+    Plugins,
 }
 
-#[derive(Debug, Clone)]
+/// A destructive action waiting for a y/n answer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmAction {
+    DeleteApp { id: String, name: String },
+    DeleteApps(Vec<String>),
+    DeleteConversation { id: i64, title: String },
+    DeleteSource { id: i64, name: String },
+    PurgeConversation { id: i64 },
+    PurgeAll,
+    /// A shell block from an answer, run as an ad-hoc command.
+    RunSnippet(String),
+}
+
+/// Where the last run left off (store::SESSION), restored at startup. The
+/// provider, model and search sources are settings and live in config.rs.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSession {
+    pub screen: CurrentScreen,
+    pub sidebar: SearchSidebarState,
+    pub convo_id: Option<i64>,
+}
+
+impl ConfirmAction {
+    pub fn prompt(&self) -> String {
+        match self {
+            ConfirmAction::DeleteApp { name, .. } => format!("Delete app '{}'?", name),
+            ConfirmAction::DeleteApps(ids) => format!("Delete {} marked apps?", ids.len()),
+            ConfirmAction::DeleteConversation { title, .. } => format!("Delete conversation '{}'?", title),
+            ConfirmAction::DeleteSource { name, .. } => format!("Remove search source '{}' from the backend?", name),
+            ConfirmAction::PurgeConversation { .. } => "Forget local data for this conversation? The backend copy is kept.".to_string(),
+            ConfirmAction::PurgeAll => "Delete ALL local bpt data (favorites, history, bindings, local apps)?".to_string(),
+            ConfirmAction::RunSnippet(cmd) => format!("Run `{}` as an ad-hoc command?", snippets::summary(cmd)),
+        }
+    }
+}
+
+/// Something `u` can bring back. Apps are re-posted to the backend; deleted
+/// conversations are only hidden until they fall out of the buffer (or the
+/// app quits), because the backend cannot re-create one with its messages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UndoEntry {
+    App(AppModel),
+    /// A bulk delete, restored in one go.
+    Apps(Vec<AppModel>),
+    Conversation { id: i64, title: String },
+}
+
+pub const UNDO_DEPTH: usize = 5;
+
+/// Order of the launcher list. Favorites stay on top in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortMode {
+    #[default]
+    Server,     // As the backend returns them
+    Name,
+    Recent,     // Recently launched
+    MostUsed,
+    Added,      // Recently added (newest backend entries first)
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Server => SortMode::Name,
+            SortMode::Name => SortMode::Recent,
+            SortMode::Recent => SortMode::MostUsed,
+            SortMode::MostUsed => SortMode::Added,
+            SortMode::Added => SortMode::Server,
+        }
+    }
+
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            SortMode::Server => None,
+            SortMode::Name => Some("A–Z"),
+            SortMode::Recent => Some("recent"),
+            SortMode::MostUsed => Some("most used"),
+            SortMode::Added => Some("newest"),
+        }
+    }
+}
+
+/// One launch (app or ad-hoc command), kept in store::HISTORY.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub seq: u64,
+    pub app_id: Option<String>,  // None for ad-hoc commands
+    pub name: String,
+    pub started: u64,            // Unix seconds
+    pub ok: Option<bool>,        // None while running, or if bpt quit first
+    pub duration_ms: Option<u64>,
+    /// The closing status line ("Success", "Failed: exit status: 1", ...).
+    #[serde(default)]
+    pub status: String,
+    /// This launch's stdout and stderr in arrival order, capped at RECORD_LINES.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub output: Vec<RecordLine>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub dropped_lines: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RecordLine {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stderr: bool,
+}
+
+fn is_zero(n: &u64) -> bool { *n == 0 }
+
+pub const HISTORY_LIMIT: usize = 500;
+const SOURCE_READONLY: &str = "Docker containers, systemd units and plugin apps are managed by their own tools; they can't be edited or deleted here.";
+
+/// Apps listed by a source or a plugin rather than the backend.
+fn managed_elsewhere(id: &str) -> bool { Source::of(id).is_some() || plugins::owner(id).is_some() }
+
+fn list_plugin_apps(plugin: &str) -> Effect {
+    Effect::PluginCall { plugin: plugin.to_string(), method: "apps/list", params: serde_json::json!({}), reply: Reply::Apps }
+}
+
+fn render_panel(plugin: &str, id: &str) -> Effect {
+    Effect::PluginCall { plugin: plugin.to_string(), method: "panel/render", params: serde_json::json!({ "id": id }), reply: Reply::Panel { id: id.to_string() } }
+}
+/// Output lines kept per launch (the newest), and how many recent launches keep theirs.
+pub const RECORD_LINES: usize = 500;
+pub const RECORDS_WITH_OUTPUT: usize = 20;
+
+/// A pinned assistant answer, kept in store::HIGHLIGHTS across conversations.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Highlight {
+    pub convo_id: Option<i64>,   // None if pinned before the conversation was saved
+    pub convo_title: String,
+    pub content: String,
+    pub pinned_at: u64,          // Unix seconds
+}
+
+/// A conversation branched off another with `b`, kept in store::BRANCHES:
+/// the backend only has what was asked after the branch point, so the
+/// history before it is replayed from here.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Branch {
+    pub from: Option<i64>,
+    pub from_title: String,
+    /// Role and text of each question and answer up to the branch point.
+    pub seed: Vec<(String, String)>,
+}
+
+/// First wait before retrying the backend for a queued message when
+/// conversation polling is off (5s); it backs off from there.
+const OFFLINE_RETRY_TICKS: u64 = 20;
+
+/// LLM providers the search backend offers; configured endpoints follow.
+const BUILTIN_PROVIDERS: [&str; 5] = ["lmstudio", "openai", "openrouter", "google", ollama::PROVIDER];
+
+/// How long an `--ask` question waits for the model list before going with
+/// whatever model is selected (5s).
+const ASK_WAIT_TICKS: u64 = 20;
+
+/// Output lines taken along by AskAboutOutput, ending at the Output pane's view.
+const ASK_OUTPUT_LINES: usize = 60;
+
+/// Process list refresh while the Processes tab is open (ticks are 250ms).
+const PROCESS_POLL_TICKS: u64 = 8;
+
+/// How long a launch sequence waits on an app that keeps running before starting the next (2s).
+const LAUNCH_STAGGER_TICKS: u64 = 8;
+/// Chains with health checks probe the app's URL every second, for at most a minute.
+const HEALTH_PROBE_TICKS: u64 = 4;
+const HEALTH_TIMEOUT_TICKS: u64 = 240;
+
+/// A named, ordered set of apps launched one after another, kept in store::CHAINS.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Chain {
+    pub name: String,
+    pub app_ids: Vec<String>,
+    /// Wait for each app's URL to answer before starting the next one.
+    #[serde(default)]
+    pub health_check: bool,
+}
+
+/// Apps started one after another: marked apps, or a chain.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LaunchSequence {
+    pub label: String,
+    pub queue: VecDeque<String>,
+    pub health_check: bool,
+    /// Drop the rest after a failed launch (chains); bulk launches carry on.
+    pub stop_on_failure: bool,
+    pub started: usize,
+    pub step: Option<SequenceStep>,
+}
+
+/// The launch a sequence is waiting on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceStep {
+    pub seq: u64,
+    pub name: String,
+    pub since: u64,                 // Tick the launch started
+    pub watch: Option<UrlWatch>,    // None skips the health check
+}
+
+/// A launched app's URL, probed every HEALTH_PROBE_TICKS until it answers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlWatch {
+    pub seq: u64,
+    pub url: String,
+    pub since: u64,
+    pub up: bool,
+    probing: bool,
+    probed_at: u64,
+}
+
+impl UrlWatch {
+    fn new(seq: u64, url: String, tick: u64) -> Self { UrlWatch { seq, url, since: tick, up: false, probing: false, probed_at: 0 } }
+
+    /// The next probe, if one is due and none is in flight.
+    fn poll(&mut self, tick: u64) -> Option<Effect> {
+        if self.up || self.probing || (self.probed_at != 0 && tick < self.probed_at + HEALTH_PROBE_TICKS) { return None; }
+        self.probing = true;
+        self.probed_at = tick.max(1);
+        Some(Effect::CheckHealth { seq: self.seq, url: self.url.clone() })
+    }
+
+    fn answered(&mut self, up: bool) { self.probing = false; self.up |= up; }
+
+    fn timed_out(&self, tick: u64) -> bool { !self.up && tick.saturating_sub(self.since) >= HEALTH_TIMEOUT_TICKS }
+}
+
+/// `url` trimmed, if it is one bpt can probe and open.
+fn http_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    (url.starts_with("http://") || url.starts_with("https://")).then(|| url.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChainFocus { #[default] Name, Apps, Steps }
+
+/// The chain editor's working copy.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChainDraft {
+    pub index: Option<usize>,  // Chain being edited; None for a new one
+    pub name: String,
+    pub steps: Vec<String>,    // App ids in launch order
+    pub health_check: bool,
+    pub focus: ChainFocus,
+    pub app_idx: usize,        // Selection in the app picker
+    pub step_idx: usize,
+    pub error: Option<String>,
+}
+
+/// Launch counts kept on this machine, keyed by app id in store::USAGE.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LaunchStats {
+    pub count: u32,
+    pub last_launched: u64,  // Unix seconds
+}
+
+/// Models that come first when cycling, per provider, in store::MODELS.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPrefs {
+    pub starred: BTreeMap<String, BTreeSet<String>>,
+    /// Most recently queried first, at most RECENT_MODELS.
+    pub recent: BTreeMap<String, Vec<String>>,
+}
+
+pub const RECENT_MODELS: usize = 5;
+
+/// Submitted inputs kept for Up/Down and Ctrl+r.
+pub const QUERY_HISTORY: usize = 500;
+
+/// Messages longer than this many lines are folded down to FOLDED_LINES.
+pub const FOLD_OVER: usize = 20;
+pub const FOLDED_LINES: usize = 8;
+
+/// Order of the Sources pane.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SourceSort {
+    #[default]
+    FirstCited,
+    Title,
+    Engine,
+    MostCited,
+}
+
+impl SourceSort {
+    pub fn next(self) -> Self {
+        match self {
+            SourceSort::FirstCited => SourceSort::Title,
+            SourceSort::Title => SourceSort::Engine,
+            SourceSort::Engine => SourceSort::MostCited,
+            SourceSort::MostCited => SourceSort::FirstCited,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SourceSort::FirstCited => "first cited",
+            SourceSort::Title => "A–Z",
+            SourceSort::Engine => "engine",
+            SourceSort::MostCited => "most cited",
+        }
+    }
+}
+
+/// A row of the Sources pane: one URL, however many answers cited it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneSource {
+    pub source: SearchSource,
+    pub cited: usize,
+}
+
+/// The Ctrl+r prompt: what was typed and the entry it matched, if any.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuerySearch {
+    pub query: String,
+    pub hit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AppAction {
     Tick,
     Quit,
     SwitchTab,
+    ReplayFinished,
     
     // Launcher
     LoadApps,
@@ -47,31 +400,198 @@ pub enum AppAction {
     BackspaceFilter,
     OpenAddModal,
     OpenEditModal,
-    ConfirmDelete,
+    RequestDelete,
+    Confirm(bool),
+    Undo,
     CloseModal,
     CycleFormFocus,
     FormChar(char),
     FormBackspace,
+    /// Line break in the command editor.
+    FormNewline,
+    FormCursor(CursorMove),
+    /// Bracketed paste, delivered whole to whatever input has focus.
+    Paste(String),
     SubmitForm,
     LaunchSelected,
+    ToggleMark,
+    ClearMarks,
+    /// Write the marked (or selected) apps to a JSON file.
+    ExportApps,
+    LaunchApp(String),
+    StopSelected,
+    SyncApps { prefer_local: bool },
     LaunchResult(String),
+    LaunchOutput { seq: u64, line: String, stderr: bool },
+    LaunchFinished { seq: u64, ok: bool, duration_ms: u64, output: String },
+    OpenLaunchHistory,
+    /// Move focus between the Apps list (false) and the Output pane (true).
+    FocusOutput(bool),
+    ScrollOutput(i16),
+    OutputTop,
+    OutputBottom,
+    OutputSearchStart,
+    OutputSearchChar(char),
+    OutputSearchBackspace,
+    /// Enter (true) keeps the query and jumps to the newest match; Esc clears it.
+    OutputSearchDone(bool),
+    /// `n` (true) goes to the next older match, `N` to the next newer one.
+    OutputNextMatch(bool),
+    /// Save the Output pane's lines to a file.
+    ExportOutput,
+    /// Show only error and stderr lines in the Output pane, or everything again.
+    ToggleErrorsOnly,
+    /// Take the Output pane's lines up to the view's end to the Search tab as context, with a question to send.
+    AskAboutOutput,
+
+    // Launch chains
+    OpenChains,
+    ChainNext,
+    ChainPrev,
+    RunChain,
+    NewChain,
+    EditChain,
+    DeleteChain,
+    ChainFocusNext,
+    ChainChar(char),
+    /// Deletes a character from the name, or the selected step.
+    ChainBackspace,
+    ChainAddStep,
+    /// Moves the selected step; `true` is earlier.
+    ChainMoveStep(bool),
+    ChainToggleHealth,
+    SaveChain,
+    HealthChecked { seq: u64, up: bool },
+    /// Re-check every app's URL now.
+    RefreshHealth,
+    UrlHealth(Vec<(String, bool)>),
+
+    // Search across conversations (Ctrl+f)
+    OpenHistorySearch,
+    HistoryChar(char),
+    HistoryBackspace,
+    ConvoSearchNext,
+    ConvoSearchPrev,
+    /// Open the selected hit's conversation, focused on the matching message.
+    HistoryOpen,
+    /// A conversation fetched for the search index.
+    ConversationIndexed { id: i64, json: Value },
+
+    // Command palette
+    OpenPalette,
+    PaletteChar(char),
+    PaletteBackspace,
+    PaletteNext,
+    PalettePrev,
+    PaletteRun,
+    PaletteStartBind,
+    /// The key pressed while binding; `None` cancels.
+    PaletteBindKey(Option<String>),
+    PaletteUnbind,
+
+    // Settings tab
+    SettingsNext,
+    SettingsPrev,
+    /// Toggle or cycle the selected setting, start typing its value, or wait for a key to bind.
+    SettingsActivate,
+    /// Cycle the selected value forward (`true`) or back.
+    SettingsCycle(bool),
+    SettingsChar(char),
+    SettingsBackspace,
+    /// Finish typing a value: keep it (`true`) or cancel.
+    SettingsEditDone(bool),
+    /// The key pressed while binding; `None` cancels.
+    SettingsBindKey(Option<String>),
+    SettingsUnbind,
+    /// Star or unstar the selected model for the current provider.
+    ToggleStarModel,
+
+    // Presets
+    OpenPresets,
+    PresetNext,
+    PresetPrev,
+    /// Switch to the picker's selection (the first entry is "no preset").
+    PresetSelect,
+    PresetDelete,
+    /// Reopen the tab, sidebar and conversation of the last run.
+    RestoreSession(UiSession),
+
+    // Processes
+    LoadProcesses,
+    ProcessesLoaded(Option<Vec<ProcessInfo>>),  // None: backend has no process endpoints
+    ProcessesFailed(String),
+    ProcessNext,
+    ProcessPrev,
+    StopProcess,
+    RestartProcess,
+    ShowProcessOutput,
+    ProcessOutputLoaded(String),
+    HistoryNext,
+    HistoryPrev,
+    /// A source's apps and their states, replacing what it listed before.
+    SourceLoaded(Source, Vec<(AppModel, String)>),
+    /// Stream the selected container's or unit's recent logs into the Output pane.
+    ShowLogs,
+    /// Open the selected launch's output, and scroll it.
+    ShowLaunchOutput,
+    ScrollLaunchOutput(i16),
+    OpenTagBrowser,
+    TagNext,
+    TagPrev,
+    ToggleTag,
+    ClearTags,
+    ToggleFavorite,
+    ToggleFavoritesOnly,
+    /// Open the selected app's URL in the browser after each successful launch.
+    ToggleAutoOpen,
+    /// Launch the selected app normally, or in a tmux window, pane or session.
+    CycleTmuxMode,
+    CycleSort,
     OpenAdHocModal,
+    AdHocChar(char),
+    AdHocBackspace,
     SubmitAdHoc(String),
     
     // Search Actions
     ToggleSearchSidebar,
+    /// Zen mode: the Search tab without tabs, footer or side panes.
+    ToggleZen,
+    /// Move the divider of the Launcher split or the Search sidebar by this many percent.
+    ResizePane(i16),
     CycleSearchFocus,
     SidebarNext,
     SidebarPrev,
     SidebarSelect,
     NewConversation,
+    RequestDeleteConversation,
+    /// The file browser (files.rs) over the Search tab or the app editor:
+    /// open it, show a directory read by effects.rs, move, filter and pick.
+    OpenFileBrowser(Pick),
+    FilesListed { dir: PathBuf, entries: Result<Vec<files::Entry>, String> },
+    FileBrowserMove(i32),
+    FileBrowserChar(char),
+    FileBrowserBackspace,
+    /// Enter: go into the selected directory, or pick the selected file.
+    FileBrowserOpen,
+    /// Pick the directory listed (for the Working dir field).
+    FileBrowserUseDir,
+    CloseFileBrowser,
+    /// `a` (new) or `e` (selected) on the Settings sidebar's search sources.
+    OpenSourceEditor { edit: bool },
+    SourceFormNext,
+    SourceFormChar(char),
+    SourceFormBackspace,
+    SubmitSourceForm,
     
     // Search Data Loading
     LoadSearchState,
     ConversationsLoaded(Vec<Conversation>),
+    ConversationsFailed(String),
     ProvidersLoaded(Vec<ProviderConfig>),
     ModelsLoaded(Vec<Model>),
     ConversationCreated(i64),
+    /// A conversation got a new title, from the backend or an auto-title request.
+    ConversationRenamed { id: i64, title: String },
     LoadConversation(i64),
     ConversationLoaded(Value),
     
@@ -79,18 +599,213 @@ pub enum AppAction {
     EnterSearchChar(char),
     DeleteSearchChar,
     SubmitSearch,
+    /// Ask the last question again with the current settings, replacing its answer (`r`, `/retry`).
+    RegenerateLast,
+    /// Move the chat focus to the previous or next message (`{` / `}`), or
+    /// with `mine` only between your own (`[` / `]`).
+    FocusMessage { forward: bool, mine: bool },
+    /// Focus the first (`false`, Home) or last message (`true`, End).
+    FocusMessageEdge(bool),
+    ClearMessageFocus,
+    /// Load a message back into the input (`e`); `cut` (`c`) replaces the chat from it when sent.
+    EditMessage { cut: bool },
+    /// Older / newer submitted input in the Message box (Up / Down).
+    QueryPrev,
+    QueryNext,
+    /// Ctrl+r: start a reverse search, or step to the next older match.
+    QuerySearchStart,
+    QuerySearchChar(char),
+    QuerySearchBackspace,
+    /// Leave the reverse search, taking the match into the input (`true`) or not.
+    QuerySearchDone(bool),
     ScrollChat(i16),
+    /// Top of the conversation (`g`), and its end, following new output again (`G`).
+    ChatTop,
+    ChatBottom,
+    /// Visual selection in the chat (`v`): extend it by lines, yank it to the clipboard (`y`).
+    ChatVisualStart,
+    ChatVisualMove(i16),
+    ChatYank,
+    ChatVisualCancel,
+    /// Fold or unfold the focused (else the last) message (`z`).
+    ToggleFold,
+    /// Show or hide the Sources of the focused (else the last sourced) message (`s`).
+    ToggleSources,
+    /// Show or hide the reasoning of the focused (else the last reasoned) message (`T`).
+    ToggleReasoning,
+    /// Leave the focused (else the last) question or answer out of later queries, or put it back (`x`).
+    ToggleExcluded,
+    /// Start a new conversation with the history up to the focused (else the last) message (`b`).
+    BranchFromMessage,
+    /// Show or hide the Sources pane right of the chat (Ctrl+o), and act on its selection.
+    ToggleSourcesPane,
+    SourcesPaneNext,
+    SourcesPanePrev,
+    /// Narrow the pane to the next engine, or back to all of them.
+    CycleSourceEngine,
+    CycleSourceSort,
+    OpenPaneSource,
+    PreviewPaneSource,
+    CopyPaneSourceUrl,
     SearchSourcesReceived(Vec<SearchSource>),
     SearchStreamToken(String),
+    /// Reasoning (`reasoning`/`thinking` events) streamed ahead of the answer.
+    SearchReasoningToken(String),
+    /// The backend started a tool for the streaming answer, and one finished
+    /// (matched to its call by id, else by name, else the last one running).
+    ToolCallStarted(ToolCall),
+    ToolCallFinished(ToolCall),
+    /// Suggested next questions for the latest answer, from the backend or the model.
+    FollowUpsReceived(Vec<String>),
+    /// Put suggestion `n` (from 1) in the input (`1`-`3` in the chat), or send it right away (`Alt+1`-`3`).
+    UseFollowUp { n: usize, send: bool },
+    /// Token counts for the answer being streamed.
+    SearchUsage(Usage),
     SearchError(String),
+    /// The backend couldn't be reached to send the question; it is queued instead.
+    SearchOffline(String),
     SearchDone,
+    AttachmentReceived(Attachment),
+    AttachmentSaved(String),
+    OpenLastAttachment,
+    PinLastAnswer,
+    /// Tab on an `@mention` being typed: complete it to the first matching app.
+    CompleteMention,
+    /// Launch an app the focused (or last) answer recommends; asks which when it names several.
+    LaunchRecommended,
+    /// The app picked by number after LaunchRecommended, or None to cancel.
+    ChooseLaunch(Option<usize>),
+    /// Copy, run or save a shell block of the focused (or last) answer; asks which when it has several.
+    Snippet(Verb),
+    /// The block picked by number after Snippet, or None to cancel.
+    ChooseSnippet(Option<usize>),
+    /// Copy a code block of the focused (or last) answer verbatim; opens a picker when it has several.
+    CopyCodeBlock,
+    CodePickerMove(i32),
+    /// Copy block `n` from the picker, or the highlighted one.
+    CodePickerCopy(Option<usize>),
+    CodePickerCancel,
+    /// Extract the conversation's action items into a TODO file (`/todo [file]`).
+    ExtractTodos(Option<String>),
+    TodoItem(String),
+    TodosDone { path: String, result: Result<usize, String> },
+    /// A `/summarize` answer for conversation `id`, to save as its description or show in the chat.
+    SummaryDone { id: Option<i64>, save: bool, result: Result<String, String> },
+    PreviewSelected,
+    /// Open the selected app's URL with the platform opener.
+    OpenSelectedUrl,
+    ArgsChar(char),
+    ArgsBackspace,
+    ArgsNext,
+    ArgsPrev,
+    SubmitArgs,
+    PreviewLoaded { url: String, title: String, body: String },
+    /// Fetch the open preview's page again.
+    PreviewReload,
+    PreviewScroll(i16),
+    OpenFileRef,
+    ChatNotice(String),
+    ProjectBound(String),
+    ContextAttached { label: String, content: String },
+    /// The terminal window gained (`true`) or lost focus, where it reports that.
+    TerminalFocus(bool),
+    /// `--ask` and piped stdin: open the Search tab with `input` attached and
+    /// `query` asked as soon as the models are known.
+    AskOnStart { query: Option<String>, input: Option<String> },
+    /// Spawn the plugins in the config (plugins.rs).
+    StartPlugins,
+    /// A plugin answered `initialize` with what it provides.
+    PluginReady { plugin: String, manifest: Manifest },
+    PluginExited { plugin: String, error: String },
+    /// A plugin command's answer, shown in the chat.
+    PluginMessage { plugin: String, text: String },
+    PluginApps { plugin: String, apps: Vec<(AppModel, String)> },
+    /// The plugin says its apps changed; list them again.
+    PluginAppsChanged(String),
+    PluginPanel { plugin: String, panel: String, lines: Vec<String> },
+}
+
+/// A palette row: a command and the keys currently bound to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    pub id: String,
+    pub title: String,
+    pub keys: Vec<String>,
+}
+
+/// An app's web page reduced to markdown (preview.rs). `body` is empty while loading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PagePreview {
+    pub url: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// The form asking for a command's `{{placeholder}}` values before a launch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgsPrompt {
+    pub app_id: String,
+    pub name: String,
+    pub fields: Vec<(String, String)>,  // (placeholder, value)
+    pub focus: usize,
+}
+
+/// A file (or other text) attached to the next query.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContextItem {
+    pub label: String,
+    pub content: String,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct ChatMessage {
     pub role: String,
     pub content: String,
     pub sources: Vec<SearchSource>,
+    pub attachments: Vec<String>,  // Paths of files saved from this answer
+    /// On a user message: context attached to it, sent ahead of the text.
+    pub context: Vec<ContextItem>,
+    /// When it was sent or started streaming (Unix seconds), if known.
+    pub time: Option<u64>,
+    /// On an answer: the provider and model that wrote it.
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// On an answer: the tokens the backend says it took.
+    pub usage: Option<Usage>,
+    /// On an answer: reasoning the model streamed ahead of it, shown folded.
+    pub reasoning: String,
+    /// On an answer: the tools the search pipeline called for it, in order.
+    pub tools: Vec<ToolCall>,
+    /// On an answer: suggested next questions, offered under the latest one.
+    pub follow_ups: Vec<String>,
+    /// Left out of the history sent with later queries (`x` in the chat).
+    pub excluded: bool,
+    /// On a question: not sent yet because the backend was unreachable; it
+    /// goes out once the backend answers again.
+    pub queued: bool,
+}
+
+/// Ends a user message that was sent with attached context.
+const CONTEXT_NOTE: &str = "\n\n_(with attached context)_";
+
+impl ChatMessage {
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self { role: role.into(), content: content.into(), ..Default::default() }
+    }
+
+    /// A user message's own text, without the attached-context note.
+    pub fn question(&self) -> &str { self.content.strip_suffix(CONTEXT_NOTE).unwrap_or(&self.content) }
+
+    /// What the model is sent for a user message: its context, then its
+    /// text. For an answer, just its text.
+    pub fn query(&self) -> String {
+        let mut query = String::new();
+        for ctx in &self.context {
+            query.push_str(&format!("Context from `{}`:\n```\n{}\n```\n\n", ctx.label, ctx.content.trim_end()));
+        }
+        query.push_str(self.question());
+        query
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -98,38 +813,317 @@ pub struct AppForm {
     pub id: String,
     pub name: String,
     pub desc: String,
-    pub cmd: String,
+    /// Multi-line; newlines are kept in the saved command.
+    pub cmd: TextArea,
     pub url: String,
+    /// `KEY=VALUE` pairs separated by spaces.
+    pub env: String,
+    pub cwd: String,
+    /// ssh.rs host name; empty launches through the backend.
+    pub target: String,
     pub focus_idx: usize,
+    /// Problems per field index, set by a submit attempt and kept current while editing.
+    pub errors: BTreeMap<usize, String>,
 }
 impl Default for AppForm {
     fn default() -> Self {
         Self {
-            id: String::new(), name: String::new(), desc: String::new(), cmd: String::new(), url: "http://localhost".into(), focus_idx: 0,
+            id: String::new(), name: String::new(), desc: String::new(), cmd: TextArea::default(), url: "http://localhost".into(), env: String::new(), cwd: String::new(), target: String::new(), focus_idx: 0, errors: BTreeMap::new(),
         }
     }
 }
+impl AppForm {
+    pub const FIELDS: usize = 7;
+    pub const CMD: usize = 2;
+    pub const CWD: usize = 5;
+
+    fn from_app(app: &AppModel) -> Self {
+        let env = app.launch.env.iter().map(|(k, v)| format!("{}={}", k, quote_value(v))).collect::<Vec<_>>().join(" ");
+        AppForm { id: app.id.clone(), name: app.name.clone(), desc: app.description.clone().unwrap_or_default(), cmd: TextArea::new(app.command.clone()), url: app.url.clone(), env, cwd: app.launch.cwd.clone().unwrap_or_default(), target: app.target.clone().unwrap_or_default(), focus_idx: 0, errors: BTreeMap::new() }
+    }
+
+    /// What keeps the form from being saved, by field index.
+    pub fn validate(&self) -> BTreeMap<usize, String> {
+        let mut errors = BTreeMap::new();
+        if self.name.trim().is_empty() { errors.insert(0, "required".to_string()); }
+        if self.cmd.text.trim().is_empty() { errors.insert(Self::CMD, "required".to_string()); }
+        match reqwest::Url::parse(self.url.trim()) {
+            _ if self.url.trim().is_empty() => { errors.insert(3, "required".to_string()); },
+            Ok(url) if !matches!(url.scheme(), "http" | "https") => { errors.insert(3, "must start with http:// or https://".to_string()); },
+            Ok(url) if url.host_str().is_none_or(str::is_empty) => { errors.insert(3, "missing host".to_string()); },
+            Ok(_) => {},
+            Err(e) => { errors.insert(3, e.to_string()); },
+        }
+        let valid_key = |k: &str| !k.is_empty() && !k.starts_with(|c: char| c.is_ascii_digit()) && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        match split_words(&self.env) {
+            Err(e) => { errors.insert(4, e); },
+            Ok(words) => if let Some(bad) = words.iter().find(|pair| !pair.split_once('=').is_some_and(|(k, _)| valid_key(k))) {
+                errors.insert(4, format!("`{}` is not KEY=VALUE", bad));
+            },
+        }
+        if self.target.trim().contains(char::is_whitespace) { errors.insert(6, "one host name".to_string()); }
+        errors
+    }
+
+    /// The focused single-line field; `None` on the command editor.
+    fn focused_mut(&mut self) -> Option<&mut String> {
+        match self.focus_idx { 0 => Some(&mut self.name), 1 => Some(&mut self.desc), 3 => Some(&mut self.url), 4 => Some(&mut self.env), 5 => Some(&mut self.cwd), 6 => Some(&mut self.target), _ => None }
+    }
+
+    fn to_model(&self) -> AppModel {
+        let env = split_words(&self.env).unwrap_or_default().iter().filter_map(|pair| pair.split_once('=')).filter(|(k, _)| !k.is_empty()).map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let cwd = Some(self.cwd.trim()).filter(|d| !d.is_empty()).map(str::to_string);
+        let target = Some(self.target.trim()).filter(|t| !t.is_empty()).map(str::to_string);
+        AppModel { id: self.id.clone(), name: self.name.clone(), description: Some(self.desc.clone()), command: self.cmd.text.clone(), url: self.url.clone(), launch: LaunchEnv { env, cwd }, target }
+    }
+}
+
+/// Splits a `KEY=VALUE` list on spaces, shell style: `'...'` keeps its text
+/// as is and `"..."` takes `\"` and `\\` escapes, so values can hold spaces.
+fn split_words(text: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() { return Ok(words); }
+        let mut word = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            match c {
+                '\'' => loop {
+                    match chars.next() { Some('\'') => break, Some(c) => word.push(c), None => return Err("unclosed '".into()) }
+                },
+                '"' => loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.peek(), Some('"' | '\\')) => word.extend(chars.next()),
+                        Some(c) => word.push(c),
+                        None => return Err("unclosed \"".into()),
+                    }
+                },
+                c => word.push(c),
+            }
+        }
+        words.push(word);
+    }
+}
+
+/// A value as `split_words` reads it back: quoted when it needs to be.
+fn quote_value(value: &str) -> String {
+    if !value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') { return value.to_string(); }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The search source editor (`a`/`e` on a source in the Settings sidebar).
+/// Settings are typed as `key=value` pairs; nested ones the backend keeps
+/// aren't shown and are saved back unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceForm {
+    /// 0 for a new source.
+    pub id: i64,
+    pub name: String,
+    pub kind: String,
+    /// `key=value` pairs separated by spaces; values with spaces are quoted.
+    pub settings: String,
+    pub is_enabled: bool,
+    /// The backend's settings, nested ones included.
+    original: serde_json::Map<String, Value>,
+    pub focus_idx: usize,
+    pub errors: BTreeMap<usize, String>,
+}
+
+impl SourceForm {
+    pub const FIELDS: usize = 3;
+
+    fn new_source() -> Self {
+        SourceForm { is_enabled: true, ..Default::default() }
+    }
+
+    fn from_provider(p: &ProviderConfig) -> Self {
+        let original = p.config.as_object().cloned().unwrap_or_default();
+        let settings = original.iter().filter_map(|(k, v)| match v {
+            Value::String(s) => Some(format!("{}={}", k, quote_value(s))),
+            Value::Number(_) | Value::Bool(_) => Some(format!("{}={}", k, v)),
+            _ => None,
+        }).collect::<Vec<_>>().join(" ");
+        SourceForm { id: p.id, name: p.name.clone(), kind: p.type_.clone(), settings, is_enabled: p.is_enabled, original, focus_idx: 0, errors: BTreeMap::new() }
+    }
+
+    /// What keeps the form from being saved, by field index.
+    pub fn validate(&self) -> BTreeMap<usize, String> {
+        let mut errors = BTreeMap::new();
+        if self.name.trim().is_empty() { errors.insert(0, "required".to_string()); }
+        if self.kind.trim().is_empty() { errors.insert(1, "required (searxng, brave, ...)".to_string()); }
+        match split_words(&self.settings) {
+            Err(e) => { errors.insert(2, e); },
+            Ok(words) => if let Some(bad) = words.iter().find(|pair| pair.split_once('=').is_none_or(|(k, _)| k.is_empty())) {
+                errors.insert(2, format!("`{}` is not key=value", bad));
+            },
+        }
+        errors
+    }
+
+    fn focused_mut(&mut self) -> &mut String {
+        match self.focus_idx { 0 => &mut self.name, 1 => &mut self.kind, _ => &mut self.settings }
+    }
+
+    /// Numbers and `true`/`false` are saved as such, anything else as text.
+    fn to_provider(&self) -> ProviderConfig {
+        let mut config: serde_json::Map<String, Value> = self.original.iter().filter(|(_, v)| v.is_object() || v.is_array()).map(|(k, v)| (k.clone(), v.clone())).collect();
+        for (k, v) in split_words(&self.settings).unwrap_or_default().iter().filter_map(|pair| pair.split_once('=')) {
+            let value = match serde_json::from_str::<Value>(v) {
+                Ok(n @ (Value::Number(_) | Value::Bool(_))) => n,
+                _ => Value::String(v.to_string()),
+            };
+            config.insert(k.to_string(), value);
+        }
+        let config = if config.is_empty() { Value::Null } else { Value::Object(config) };
+        ProviderConfig { id: self.id, name: self.name.trim().to_string(), type_: self.kind.trim().to_string(), is_enabled: self.is_enabled, config }
+    }
+}
 
 pub struct App {
     pub should_quit: bool,
+    /// The `v` page preview, while its popup is open.
+    pub preview: Option<PagePreview>,
+    pub preview_scroll: u16,
+    pub args_prompt: Option<ArgsPrompt>,
+    /// Index of the chat note that tracks a running TODO extraction.
+    pub todo_note: Option<usize>,
+    /// A `/summarize` request is running.
+    pub summarizing: bool,
+    pub arg_defaults: BTreeMap<String, BTreeMap<String, String>>,  // app id -> last values
+    /// Set by `OpenFileRef`; main.rs runs the editor (suspending the TUI) and clears it.
+    pub editor_request: Option<(String, FileRef)>,
+    ref_cursor: usize,  // Cycles through references on repeated `E`
     pub current_screen: CurrentScreen,
     pub input_mode: InputMode,
+    pub modal_return: InputMode,  // Mode restored when the open modal closes
     
     // --- Launcher State ---
     pub apps: Vec<AppModel>,
     pub filtered_apps: Vec<usize>,
     pub apps_idx: usize,
-    pub launcher_logs: Vec<String>,
+    pub launcher_logs: LogBuffer,
+    /// Session line number just past the last Output line shown; None follows new output.
+    pub output_end: Option<u64>,
+    /// Output search: the query, whether it is being typed, and the session line of the current match.
+    pub output_query: String,
+    pub output_searching: bool,
+    pub output_match: Option<u64>,
+    /// The Output pane lists only lines whose severity is an error.
+    pub output_errors_only: bool,
+    /// $BPT_OUTPUT_LOG: every Output line is also appended here.
+    pub output_log: Option<std::path::PathBuf>,
+    /// Render ANSI colors in the Output pane; `BPT_ANSI=strip` shows plain text.
+    pub ansi_colors: bool,
+    /// `--docker`/`--systemd`: sources listed alongside the backend's apps.
+    pub sources: BTreeSet<Source>,
+    /// Remote launch targets from store::HOSTS.
+    pub ssh_hosts: Vec<SshHost>,
+    /// Apps started in tmux instead of with captured output (store::TMUX).
+    pub tmux_modes: BTreeMap<String, TmuxMode>,
+    /// bpt itself runs inside tmux ($TMUX), so windows and panes can be opened.
+    pub in_tmux: bool,
+    /// State of each source app (`running`, `failed`, ...), by id.
+    pub source_states: BTreeMap<String, String>,
+    pub plugins: BTreeMap<String, Manifest>,     // Running plugins, by name, once initialized
+    pub plugin_panels: Vec<Panel>,
+    pub plugin_panel_idx: usize,
     pub is_loading_apps: bool,
     pub filter_input: String,
+    pub tag_index: Vec<(String, usize)>,  // Distinct #tags with app counts, sorted
+    pub selected_tags: BTreeSet<String>,
+    pub tag_idx: usize,
+    pub favorites: BTreeSet<String>,  // App ids, persisted in store::FAVORITES
+    pub favorites_only: bool,
+    pub auto_open: BTreeSet<String>,  // App ids whose URL opens after launch, persisted in store::AUTO_OPEN
+    pub auto_open_all: bool,          // $BPT_AUTO_OPEN: every app does
+    /// Launches whose URL opens in the browser once it answers: (app name, watch).
+    pub opening: Vec<(String, UrlWatch)>,
+    pub sort_mode: SortMode,
+    pub usage: BTreeMap<String, LaunchStats>,
+    pub launch_history: Vec<LaunchRecord>,  // Oldest first, persisted in store::HISTORY
+    pub history_idx: usize,                 // Selection in the history view (0 = newest)
+    pub history_output: Option<u16>,        // Scroll offset while the selected launch's output is open
+    pub running_launches: BTreeSet<u64>,    // Launch seqs still streaming output
+    pub url_health: BTreeMap<String, bool>, // App id -> URL answered at the last check
+    pub health_poll_ticks: u64,             // 0 disables background checks
+    health_pending: bool,
+
+    // Processes State
+    pub processes: Vec<ProcessInfo>,
+    pub process_idx: usize,
+    pub processes_remote: bool,  // From the backend, or tracked locally from this session's launches
+    pub process_output: Vec<String>,
+
+    // Keys & Palette
+    pub keymap: Keymap,
+    pub palette_query: String,
+    pub palette_idx: usize,
+    pub palette_binding: bool,  // Waiting for the key to bind to the selected entry
     pub active_form: AppForm,
     pub adhoc_input: String,
+    pub pending_confirm: Option<ConfirmAction>,
+    pub undo_buffer: VecDeque<UndoEntry>,
+    /// Apps marked with Space for bulk launch/delete/export.
+    pub marked: BTreeSet<String>,
+    /// Bulk launch or chain in progress.
+    pub sequence: Option<LaunchSequence>,
+    pub chains: Vec<Chain>,
+    pub chain_idx: usize,
+    /// Set while the Chains modal shows the editor instead of the list.
+    pub chain_draft: Option<ChainDraft>,
 
     // --- Searchrs State ---
     pub search_input: String,
+    /// Everything submitted from the Message box, oldest first, in store::QUERIES.
+    pub query_history: Vec<String>,
+    /// Entry shown while browsing with Up/Down, and the input typed before.
+    pub query_pos: Option<usize>,
+    pub query_draft: String,
+    pub query_search: Option<QuerySearch>,
+    /// Apps an answer recommends, waiting for a number after `L`.
+    pub launch_choice: Option<Vec<String>>,
+    /// What to do with a shell block of the answer, waiting for its number.
+    pub snippet_choice: Option<Verb>,
+    /// The code block picker after `y`: the message and the highlighted block.
+    pub code_picker: Option<(usize, usize)>,
+    /// Message picked in the chat with `[` / `]`, highlighted; `e` and `c` act on it.
+    pub chat_focus: Option<usize>,
+    /// Set when the focus moves, so the next draw scrolls to it.
+    pub chat_focus_moved: bool,
+    /// Set by `c`: sending the edited message first drops the chat from here on.
+    pub edit_from: Option<usize>,
+    /// The chat pane's lines as plain text, as last drawn.
+    pub chat_lines: Vec<String>,
+    /// Visual selection over `chat_lines`: where it started and the moving end.
+    pub chat_visual: Option<(usize, usize)>,
+    /// Messages folded (`true`) or unfolded with `z`, by index; see `is_folded`.
+    pub message_folds: BTreeMap<usize, bool>,
+    /// Messages whose Sources list is open (`s`); lists start closed.
+    pub sources_open: BTreeSet<usize>,
+    /// Messages whose reasoning is shown (`T`); it starts folded to one line.
+    pub reasoning_open: BTreeSet<usize>,
+    /// Search gets the whole terminal: no tab bar, footer, sidebar or Sources pane.
+    pub zen: bool,
+    /// The Sources pane: shown, its selection, engine filter and order.
+    pub sources_pane: bool,
+    pub sources_pane_idx: usize,
+    pub sources_engine: Option<String>,
+    pub sources_sort: SourceSort,
     pub messages: Vec<ChatMessage>,
     pub is_searching: bool,
+    /// While an answer streams: the tick it was asked at, and the tick its first token arrived.
+    pub stream_ticks: Option<(u64, Option<u64>)>,
     pub search_sidebar: SearchSidebarState,
+    pub highlights: Vec<Highlight>,    // Newest last
+    pub highlight_idx: usize,          // Sidebar selection (0 = newest)
+    pub project_bindings: BTreeMap<i64, String>,  // Conversation id -> project dir, persisted
+    pub summaries: BTreeMap<i64, String>,         // Conversation id -> `/summarize save` description, persisted
+    pub branches: BTreeMap<i64, Branch>,          // Conversation id -> where it branched from, persisted
+    pub pending_branch: Option<Branch>,           // Branch for a conversation not created yet
+    pub pending_project: Option<String>,          // Binding for a conversation not created yet
+    pub pending_context: Vec<ContextItem>,
     
     pub chat_scroll: u16,
     pub chat_auto_scroll: bool,
@@ -138,20 +1132,80 @@ pub struct App {
     pub current_convo_id: Option<i64>,
     pub conversations: Vec<Conversation>,
     pub conversation_idx: usize,
+    pub conversations_loaded: bool,
+    pub search_loaded: bool,           // LoadSearchState fired (first visit to Search)
+    pub ask_at: Option<u64>,           // Tick by which the `--ask` question goes, models loaded or not
+    pub terminal_focused: bool,        // False while the terminal reports it is in the background
+    pub dump_on_exit: bool,            // `--dump-on-exit` / `/dump`: print the conversation after the TUI closes
+    pub color_mode: ColorMode,         // What the terminal shows (`--color`, else detected); ui::draw maps colors down to it
+    pub a11y: bool,                    // `--a11y`: text markers instead of color alone, no spinners
+    pub a11y_out: Option<Sink>,        // `--a11y-out`: where finished answers are written for a screen reader
+    pub updated_convos: HashSet<i64>,  // Changed by another client since last viewed
+    pub untitled: Option<i64>,         // Created by this client, still named after its first query
+    pub convo_poll_ticks: u64,         // 0 disables background polling
+    pub tick_count: u64,
+    pub convo_backoff: Backoff,        // Polls while the backend is unreachable
+    pub process_backoff: Backoff,
     
     pub llm_providers: Vec<String>,
     pub selected_llm_provider: String,
     
     pub models: Vec<Model>,
     pub selected_model: String,
+    pub model_prefs: ModelPrefs,
     
     pub search_providers: Vec<ProviderConfig>,
     pub settings_idx: usize,
+
+    // --- Settings tab ---
+    /// Persisted in store::CONFIG; `apply_config` copies it into the fields above.
+    pub config: Config,
+    pub settings_row: usize,
+    pub settings_edit: Option<String>,  // Value being typed for the selected row
+    pub settings_binding: bool,         // Waiting for the key to bind to the selected command
+    pub settings_error: Option<String>,
+    pub preset_idx: usize,  // Picker selection; 0 is "no preset"
+    pub source_form: Option<SourceForm>,
+    /// Open over the Search tab or the app editor, taking the keys while it is.
+    pub file_browser: Option<FileBrowser>,
+    pub history_query: String,  // Ctrl+f search across conversations
+    pub convo_search_idx: usize,
+    pub convo_index: BTreeMap<i64, convo_search::Messages>,  // Fetched messages by conversation, for the search
+    pub indexing: usize,                                     // Conversations still being fetched into it
+    pub pending_jump: Option<(i64, usize)>,                  // Message to focus once its conversation loads
     
+    // --- Session recording / playback ---
+    pub recorder: Option<Recorder>,
+    pub replay: ReplayState,
+
     pub action_tx: mpsc::UnboundedSender<AppAction>,
     pub action_rx: mpsc::UnboundedReceiver<AppAction>,
 }
 
+/// `#tags` written anywhere in an app's description.
+pub fn app_tags(app: &AppModel) -> Vec<&str> {
+    app.description.as_deref().unwrap_or("").split_whitespace().filter(|s| s.starts_with('#') && s.len() > 1).collect()
+}
+
+/// Launcher filter. Whitespace-separated terms must all match; a bare term
+/// matches any field, `name:`, `desc:`, `cmd:` or `url:` targets one field.
+/// `query` is expected to be lowercased already.
+pub fn app_matches(app: &AppModel, query: &str) -> bool {
+    let desc = app.description.as_deref().unwrap_or("").to_lowercase();
+    let (name, cmd, url) = (app.name.to_lowercase(), app.command.to_lowercase(), app.url.to_lowercase());
+    query.split_whitespace().all(|term| match term.split_once(':') {
+        Some(("name", t)) => name.contains(t),
+        Some(("desc", t)) => desc.contains(t),
+        Some(("cmd", t)) => cmd.contains(t),
+        Some(("url", t)) => url.contains(t),
+        _ => name.contains(term) || desc.contains(term) || cmd.contains(term) || url.contains(term),
+    })
+}
+
+impl Default for App {
+    fn default() -> Self { Self::new() }
+}
+
 impl App {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
@@ -159,36 +1213,89 @@ impl App {
             should_quit: false,
             current_screen: CurrentScreen::Launcher,
             input_mode: InputMode::Normal,
+            modal_return: InputMode::Normal,
             
             // Launcher Defaults
             apps: vec![], filtered_apps: vec![], apps_idx: 0,
-            launcher_logs: vec!["Ready.".into()], is_loading_apps: false,
-            filter_input: String::new(), active_form: AppForm::default(), adhoc_input: String::new(),
+            launcher_logs: LogBuffer::from(vec!["Ready.".to_string()]), output_end: None, output_query: String::new(), output_searching: false, output_match: None, output_errors_only: false, output_log: None, ansi_colors: true, sources: BTreeSet::new(), source_states: BTreeMap::new(), plugins: BTreeMap::new(), plugin_panels: vec![], plugin_panel_idx: 0, ssh_hosts: vec![], tmux_modes: BTreeMap::new(), in_tmux: false, is_loading_apps: false,
+            filter_input: String::new(), tag_index: vec![], selected_tags: BTreeSet::new(), tag_idx: 0,
+            favorites: BTreeSet::new(), favorites_only: false,
+            auto_open: BTreeSet::new(), auto_open_all: false, opening: vec![],
+            sort_mode: SortMode::default(), usage: BTreeMap::new(),
+            launch_history: vec![], history_idx: 0, history_output: None, running_launches: BTreeSet::new(),
+            url_health: BTreeMap::new(), health_poll_ticks: 0, health_pending: false,
+            processes: vec![], process_idx: 0, processes_remote: false, process_output: vec![],
+            keymap: Keymap::default(), palette_query: String::new(), palette_idx: 0, palette_binding: false,
+            active_form: AppForm::default(), adhoc_input: String::new(),
+            pending_confirm: None,
+            undo_buffer: VecDeque::new(),
+            marked: BTreeSet::new(),
+            sequence: None,
+            chains: vec![], chain_idx: 0, chain_draft: None,
 
             // Search Defaults
             search_input: String::new(),
-            messages: vec![ChatMessage { 
-                role: "system".into(), 
-                content: "Welcome to bplus search.\n\n- Press **Tab** to cycle focus (Sidebar -> Chat -> Input).\n- Use **Up/Down/PgUp/PgDn** to scroll chat when focused.".into(),
-                sources: vec![]
-            }],
+            query_history: vec![], query_pos: None, query_draft: String::new(), query_search: None, launch_choice: None, snippet_choice: None, code_picker: None,
+            chat_focus: None, chat_focus_moved: false, edit_from: None, chat_lines: vec![], chat_visual: None, message_folds: BTreeMap::new(), sources_open: BTreeSet::new(), reasoning_open: BTreeSet::new(),
+            zen: false, sources_pane: false, sources_pane_idx: 0, sources_engine: None, sources_sort: SourceSort::default(),
+            messages: vec![ChatMessage::new("system",
+                "Welcome to bplus search.\n\n- Press **Tab** to cycle focus (Sidebar -> Chat -> Input).\n- Use **Up/Down/PgUp/PgDn** to scroll chat when focused."
+            )],
             is_searching: false,
+            stream_ticks: None,
             search_sidebar: SearchSidebarState::Hidden,
+            highlights: vec![],
+            highlight_idx: 0,
+            project_bindings: BTreeMap::new(),
+            summaries: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            pending_branch: None,
+            pending_project: None,
+            pending_context: vec![],
             chat_scroll: 0,
             chat_auto_scroll: true,
             
             current_convo_id: None,
             conversations: vec![],
             conversation_idx: 0,
+            conversations_loaded: false,
+            search_loaded: false,
+            ask_at: None,
+            terminal_focused: true,
+            dump_on_exit: false,
+            color_mode: ColorMode::default(),
+            a11y: false,
+            a11y_out: None,
+            updated_convos: HashSet::new(),
+            untitled: None,
+            convo_poll_ticks: 0,
+            tick_count: 0,
+            convo_backoff: Backoff::default(),
+            process_backoff: Backoff::default(),
             
-            llm_providers: vec!["lmstudio".into(), "openai".into(), "openrouter".into(), "google".into()],
+            llm_providers: BUILTIN_PROVIDERS.iter().map(|p| p.to_string()).collect(),
             selected_llm_provider: "lmstudio".into(),
             
             models: vec![],
             selected_model: "Loading...".into(),
+            model_prefs: ModelPrefs::default(),
             
             search_providers: vec![],
             settings_idx: 0,
+            config: Config::default(), settings_row: 0, settings_edit: None, settings_binding: false, settings_error: None, preset_idx: 0, source_form: None, file_browser: None,
+            history_query: String::new(), convo_search_idx: 0, convo_index: BTreeMap::new(), indexing: 0, pending_jump: None,
+
+            recorder: None,
+            replay: ReplayState::Off,
+
+            preview: None,
+            args_prompt: None,
+            todo_note: None,
+            summarizing: false,
+            arg_defaults: BTreeMap::new(),
+            preview_scroll: 0,
+            editor_request: None,
+            ref_cursor: 0,
 
             action_tx: tx,
             action_rx: rx,
@@ -200,318 +1307,4756 @@ impl App {
         self.apps.get(*self.filtered_apps.get(self.apps_idx)?)
     }
 
+    /// Rebuilds `filtered_apps`: text filter, tags and favorites-only applied,
+    /// favorites sorted to the top (otherwise backend order is kept).
     fn update_filter(&mut self) {
         let query = self.filter_input.to_lowercase();
         self.filtered_apps = self.apps.iter().enumerate()
-            .filter(|(_, app)| {
-                if query.is_empty() { return true; }
-                app.name.to_lowercase().contains(&query) || 
-                app.description.clone().unwrap_or_default().to_lowercase().contains(&query)
-            })
+            .filter(|(_, app)| !self.favorites_only || self.favorites.contains(&app.id))
+            .filter(|(_, app)| app_matches(app, &query))
+            .filter(|(_, app)| self.selected_tags.is_empty() || { let tags = app_tags(app); self.selected_tags.iter().all(|t| tags.contains(&t.as_str())) })
             .map(|(i, _)| i)
             .collect();
+        let stats = |i: usize| self.usage.get(&self.apps[i].id).cloned().unwrap_or_default();
+        match self.sort_mode {
+            SortMode::Server => {}
+            SortMode::Name => self.filtered_apps.sort_by_cached_key(|&i| self.apps[i].name.to_lowercase()),
+            SortMode::Recent => self.filtered_apps.sort_by_key(|&i| std::cmp::Reverse(stats(i).last_launched)),
+            SortMode::MostUsed => self.filtered_apps.sort_by_key(|&i| std::cmp::Reverse(stats(i).count)),
+            SortMode::Added => self.filtered_apps.reverse(),
+        }
+        self.filtered_apps.sort_by_key(|&i| !self.favorites.contains(&self.apps[i].id));
         self.apps_idx = 0;
     }
 
-    pub async fn update(&mut self, action: AppAction) {
-        match action {
-            AppAction::Tick => {},
-            AppAction::Quit => self.should_quit = true,
-            AppAction::SwitchTab => {
-                if self.input_mode == InputMode::Editing { return; }
-
-                self.current_screen = match self.current_screen {
-                    CurrentScreen::Launcher => {
-                        self.input_mode = InputMode::SearchInput; 
-                        if self.search_providers.is_empty() {
-                            let _ = self.action_tx.send(AppAction::LoadSearchState);
-                        }
-                        CurrentScreen::Search
-                    },
-                    CurrentScreen::Search => {
-                        self.input_mode = InputMode::Normal;
-                        CurrentScreen::Launcher
-                    },
+    /// Replaces the conversation list, flagging entries that are new or changed
+    /// since the previous fetch (e.g. created from the web UI) and keeping the
+    /// sidebar selection on the same conversation.
+    fn merge_conversations(&mut self, convos: Vec<Conversation>) {
+        if self.conversations_loaded {
+            for c in &convos {
+                let changed = match self.conversations.iter().find(|old| old.id == c.id) {
+                    Some(old) => old.title != c.title || old.updated_at != c.updated_at,
+                    None => true,
                 };
-            },
+                if changed && Some(c.id) != self.current_convo_id { self.updated_convos.insert(c.id); }
+                if changed { self.convo_index.remove(&c.id); }
+            }
+        }
+        let mut convos = convos;
+        convos.retain(|c| !self.is_pending_delete(c.id));
+        let selected_id = self.conversation_idx.checked_sub(1).and_then(|i| self.conversations.get(i)).map(|c| c.id);
+        self.updated_convos.retain(|id| convos.iter().any(|c| c.id == *id));
+        self.conversations = convos;
+        self.conversations_loaded = true;
+        if let Some(id) = selected_id {
+            self.conversation_idx = self.conversations.iter().position(|c| c.id == id).map(|i| i + 1).unwrap_or(0);
+        } else if self.conversation_idx > self.conversations.len() {
+            self.conversation_idx = 0;
+        }
+    }
 
-            // --- LAUNCHER LOGIC ---
-            AppAction::SelectNext => { if !self.filtered_apps.is_empty() { self.apps_idx = (self.apps_idx + 1) % self.filtered_apps.len(); } },
-            AppAction::SelectPrev => { if !self.filtered_apps.is_empty() { if self.apps_idx == 0 { self.apps_idx = self.filtered_apps.len() - 1; } else { self.apps_idx -= 1; } } },
-            AppAction::LoadApps => {
-                self.is_loading_apps = true;
-                let tx = self.action_tx.clone();
-                tokio::spawn(async move {
-                    match api::fetch_apps().await {
-                        Ok(apps) => tx.send(AppAction::AppsLoaded(apps)).unwrap(),
-                        Err(e) => tx.send(AppAction::LaunchResult(format!("Error fetching apps: {}", e))).unwrap(),
-                    }
-                });
-            },
-            AppAction::AppsLoaded(apps) => { self.apps = apps; self.is_loading_apps = false; self.update_filter(); },
-            AppAction::ToggleFilter => {
-                self.input_mode = match self.input_mode {
-                    InputMode::Filtering => InputMode::Normal,
-                    _ => { self.filter_input.clear(); self.update_filter(); InputMode::Filtering },
-                };
-            },
-            AppAction::EnterFilterChar(c) => { self.filter_input.push(c); self.update_filter(); },
-            AppAction::BackspaceFilter => { self.filter_input.pop(); self.update_filter(); },
-            
-            AppAction::OpenAddModal => { self.active_form = AppForm::default(); self.input_mode = InputMode::Editing; },
-            AppAction::OpenEditModal => { if let Some(app) = self.get_selected_app() { self.active_form = AppForm { id: app.id.clone(), name: app.name.clone(), desc: app.description.clone().unwrap_or_default(), cmd: app.command.clone(), url: app.url.clone(), focus_idx: 0 }; self.input_mode = InputMode::Editing; } },
-            AppAction::CloseModal => { self.input_mode = InputMode::Normal; },
-            AppAction::CycleFormFocus => { self.active_form.focus_idx = (self.active_form.focus_idx + 1) % 4; },
-            AppAction::FormChar(c) => match self.active_form.focus_idx { 0=>self.active_form.name.push(c),1=>self.active_form.desc.push(c),2=>self.active_form.cmd.push(c),3=>self.active_form.url.push(c),_=>{} },
-            AppAction::FormBackspace => match self.active_form.focus_idx { 0=>{self.active_form.name.pop();},1=>{self.active_form.desc.pop();},2=>{self.active_form.cmd.pop();},3=>{self.active_form.url.pop();},_=>{} },
-            AppAction::SubmitForm => {
-                let form = self.active_form.clone();
-                let model = AppModel { id: form.id.clone(), name: form.name, description: Some(form.desc), command: form.cmd, url: form.url };
-                let tx = self.action_tx.clone();
-                self.input_mode = InputMode::Normal;
-                tokio::spawn(async move {
-                    let res = if form.id.is_empty() { api::create_app(&model).await } else { api::update_app(&model).await.map(|_| model) };
-                    match res { Ok(_) => { tx.send(AppAction::LoadApps).unwrap(); }, Err(e) => tx.send(AppAction::LaunchResult(format!("Error: {}", e))).unwrap() }
-                });
-            },
-            AppAction::ConfirmDelete => { if let Some(app) = self.get_selected_app() { let id = app.id.clone(); let tx = self.action_tx.clone(); tokio::spawn(async move { let _ = api::delete_app(&id).await; tx.send(AppAction::LoadApps).unwrap(); }); } },
-            
-            AppAction::LaunchSelected => {
-                if let Some(app) = self.get_selected_app() {
-                    let id = app.id.clone();
-                    let name = app.name.clone();
-                    let tx = self.action_tx.clone();
-                    
-                    self.launcher_logs.push(format!("Executing '{}'...", name));
-                    
-                    tokio::spawn(async move {
-                        match api::launch_app(id).await {
-                            Ok(res) => {
-                                let output = if res.success {
-                                    format!("Success:\n{}", res.stdout)
-                                } else {
-                                    format!("Failed:\n{}\n{}", res.message, res.stderr)
-                                };
-                                tx.send(AppAction::LaunchResult(output)).unwrap();
-                            },
-                            Err(e) => {
-                                tx.send(AppAction::LaunchResult(format!("API Error: {}", e))).unwrap();
-                            }
-                        }
-                    });
-                }
-            },
-            
-            AppAction::LaunchResult(msg) => { for line in msg.lines() { self.launcher_logs.push(line.to_string()); } if self.launcher_logs.len() > 100 { let r = self.launcher_logs.len()-100; self.launcher_logs.drain(0..r); } },
-            
-            AppAction::OpenAdHocModal => { self.adhoc_input.clear(); self.input_mode = InputMode::AdHocCmd; },
-            
-            AppAction::SubmitAdHoc(cmd) => {
-                self.input_mode = InputMode::Normal;
-                let tx = self.action_tx.clone();
-                self.launcher_logs.push(format!("Running ad-hoc: {}", cmd));
-                
-                tokio::spawn(async move {
-                    let temp_app = AppModel {
-                        id: String::new(),
-                        name: "__TEMP_CMD__".into(),
-                        description: Some("Ad-hoc".into()),
-                        command: cmd,
-                        url: "http://localhost".into(),
-                    };
-                    
-                    match api::create_app(&temp_app).await {
-                        Ok(created) => {
-                            let launch_res = api::launch_app(created.id.clone()).await;
-                            let _ = api::delete_app(&created.id).await;
-                            
-                            match launch_res {
-                                Ok(res) => {
-                                     let output = if res.success {
-                                        format!("{}\n{}", res.stdout, res.stderr)
-                                    } else {
-                                        format!("Failed: {}\n{}", res.message, res.stderr)
-                                    };
-                                    tx.send(AppAction::LaunchResult(output)).unwrap();
-                                },
-                                Err(e) => tx.send(AppAction::LaunchResult(format!("Exec Error: {}", e))).unwrap(),
-                            }
-                        },
-                        Err(e) => tx.send(AppAction::LaunchResult(format!("AdHoc Error: {}", e))).unwrap(),
-                    }
-                });
-            },
+    /// The only way `input_mode` changes. Refuses transitions the mode
+    /// machine doesn't allow and remembers where a modal was opened from.
+    fn set_mode(&mut self, to: InputMode) -> bool {
+        if !self.input_mode.allows(&to) { return false; }
+        if to.is_modal() && !self.input_mode.is_modal() { self.modal_return = self.input_mode.clone(); }
+        self.input_mode = to;
+        true
+    }
 
-            // --- SEARCH LOGIC ---
-            AppAction::LoadSearchState => {
-                let tx = self.action_tx.clone();
-                tokio::spawn(async move {
-                    if let Ok(convos) = api::fetch_conversations().await { tx.send(AppAction::ConversationsLoaded(convos)).unwrap(); }
-                    if let Ok(provs) = api::fetch_providers_list().await { tx.send(AppAction::ProvidersLoaded(provs)).unwrap(); }
-                });
-                let tx2 = self.action_tx.clone();
-                let prov = self.selected_llm_provider.clone();
-                tokio::spawn(async move {
-                    if let Ok(models) = api::fetch_models(&prov).await { tx2.send(AppAction::ModelsLoaded(models)).unwrap(); }
-                });
-            },
-            AppAction::ConversationsLoaded(convos) => { self.conversations = convos; },
-            AppAction::ProvidersLoaded(provs) => { self.search_providers = provs; },
-            AppAction::ModelsLoaded(models) => { 
-                self.models = models;
-                if let Some(first) = self.models.first() { self.selected_model = first.id.clone(); }
-                else { self.selected_model = "default".into(); }
+    fn push_logs(&mut self, output: &str) {
+        for line in output.lines() { self.launcher_logs.push(line.to_string()); }
+    }
+
+    /// Appends a history record and returns its sequence number.
+    fn record_launch(&mut self, app_id: Option<String>, name: String, fx: &mut Vec<Effect>) -> u64 {
+        let seq = self.launch_history.last().map(|r| r.seq + 1).unwrap_or(1);
+        self.launch_history.push(LaunchRecord { seq, app_id, name, started: store::now_secs(), ..Default::default() });
+        if self.launch_history.len() > HISTORY_LIMIT { let r = self.launch_history.len() - HISTORY_LIMIT; self.launch_history.drain(0..r); }
+        // Older launches keep their status but not their output, so store::HISTORY stays small.
+        let old = self.launch_history.len().saturating_sub(RECORDS_WITH_OUTPUT);
+        for r in &mut self.launch_history[..old] { r.output = vec![]; }
+        fx.push(Effect::WriteStore { name: store::HISTORY, data: serde_json::json!(self.launch_history) });
+        self.running_launches.insert(seq);
+        seq
+    }
+
+    /// Drops launch history older than `days`; true if anything was removed.
+    pub fn apply_retention(&mut self, now: u64, days: u64) -> bool {
+        let cutoff = now.saturating_sub(days * 86_400);
+        let before = self.launch_history.len();
+        self.launch_history.retain(|r| r.started >= cutoff);
+        self.launch_history.len() != before
+    }
+
+    /// Launches from this session that are still running, for when the
+    /// backend cannot list processes itself.
+    fn local_processes(&self) -> Vec<ProcessInfo> {
+        self.launch_history.iter().filter(|r| self.running_launches.contains(&r.seq)).map(|r| ProcessInfo {
+            id: format!("local-{}", r.seq), app_id: r.app_id.clone(), name: r.name.clone(), pid: None, started_at: Some(r.started), status: "running".into(),
+        }).collect()
+    }
+
+    /// A status line: into the chat on the Search tab, the Output pane elsewhere.
+    fn notice(&mut self, text: &str) {
+        match self.current_screen {
+            CurrentScreen::Search => self.messages.push(ChatMessage::new("system", text)),
+            CurrentScreen::Launcher | CurrentScreen::Processes | CurrentScreen::Settings => self.launcher_logs.push(text.to_string()),
+        }
+    }
+
+    /// Commands plus one "Launch <app>" entry per app, filtered by the palette query.
+    pub fn palette_entries(&self) -> Vec<PaletteEntry> {
+        let query = self.palette_query.to_lowercase();
+        let commands = keymap::COMMANDS.iter().map(|c| PaletteEntry { id: c.id.to_string(), title: c.title.to_string(), keys: self.keymap.keys(c.id) });
+        let apps = self.apps.iter().map(|a| {
+            let id = format!("{}{}", keymap::LAUNCH_PREFIX, a.id);
+            PaletteEntry { keys: self.keymap.keys(&id), id, title: format!("Launch {}", a.name) }
+        });
+        commands.chain(apps)
+            .filter(|e| { let title = e.title.to_lowercase(); query.split_whitespace().all(|w| title.contains(w)) })
+            .collect()
+    }
+
+    /// Inserts pasted text into the focused input. Line breaks survive in the
+    /// command editor and the chat message; single-line inputs get spaces.
+    fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let one_line = || text.trim_end_matches('\n').split('\n').map(str::trim_end).collect::<Vec<_>>().join(" ");
+        match self.input_mode {
+            InputMode::Editing => {
+                match self.active_form.focused_mut() { Some(field) => field.push_str(&one_line()), None => self.active_form.cmd.insert_str(&text) }
+                self.revalidate_form();
             },
-            AppAction::ToggleSearchSidebar => {
-                self.search_sidebar = match self.search_sidebar {
-                    SearchSidebarState::Hidden => SearchSidebarState::History,
-                    SearchSidebarState::History => SearchSidebarState::Settings,
-                    SearchSidebarState::Settings => SearchSidebarState::Hidden,
-                };
-                if self.search_sidebar != SearchSidebarState::Hidden {
-                    self.input_mode = InputMode::SearchSidebar;
-                } else {
-                    self.input_mode = InputMode::SearchInput;
-                }
+            InputMode::AdHocCmd => self.adhoc_input.push_str(&one_line()),
+            InputMode::Filtering => { self.filter_input.push_str(&one_line()); self.update_filter(); },
+            InputMode::Output if self.output_searching => self.output_query.push_str(&one_line()),
+            InputMode::Palette if !self.palette_binding => { self.palette_query.push_str(&one_line()); self.palette_idx = 0; },
+            InputMode::HistorySearch => { self.history_query.push_str(&one_line()); self.convo_search_idx = 0; },
+            InputMode::SourceEditor => { if let Some(f) = self.source_form.as_mut() { f.focused_mut().push_str(&one_line()); } },
+            InputMode::Settings => { if let Some(v) = self.settings_edit.as_mut() { v.push_str(&one_line()); } },
+            InputMode::Args => { if let Some(p) = self.args_prompt.as_mut() { if let Some(f) = p.fields.get_mut(p.focus) { f.1.push_str(&one_line()); } } },
+            InputMode::SearchInput if self.query_search.is_some() => {
+                if let Some(h) = self.query_search.as_mut() { h.query.push_str(&one_line()); }
+                self.find_query(None);
             },
-            AppAction::CycleSearchFocus => {
-                self.input_mode = match self.input_mode {
-                    InputMode::SearchInput => {
-                         if self.search_sidebar != SearchSidebarState::Hidden {
-                             InputMode::SearchSidebar
-                         } else {
-                             InputMode::ChatHistory
-                         }
-                    },
-                    InputMode::SearchSidebar => InputMode::ChatHistory,
-                    InputMode::ChatHistory => InputMode::SearchInput,
-                    _ => InputMode::SearchInput,
-                };
+            InputMode::SearchInput => { self.search_input.push_str(text.trim_end_matches('\n')); self.query_pos = None; },
+            _ => {},
+        }
+    }
+
+    /// Once a submit has flagged problems, markers follow the edits.
+    fn revalidate_form(&mut self) {
+        if !self.active_form.errors.is_empty() { self.active_form.errors = self.active_form.validate(); }
+    }
+
+    fn persist_keymap(&self) -> Effect {
+        Effect::WriteStore { name: store::KEYMAP, data: serde_json::json!(self.keymap.overrides) }
+    }
+
+    fn persist_config(&self) -> Effect {
+        Effect::WriteStore { name: store::CONFIG, data: serde_json::json!(self.config) }
+    }
+
+    /// Copies `config` into the settings it drives. Backend URLs are set
+    /// separately (api::set_backends), being process-wide.
+    pub fn apply_config(&mut self) {
+        self.config.launcher_split = config::resize(self.config.launcher_split, 0, config::LAUNCHER_SPLIT);
+        self.config.sidebar_split = config::resize(self.config.sidebar_split, 0, config::SIDEBAR_SPLIT);
+        self.llm_providers = BUILTIN_PROVIDERS.iter().map(|p| p.to_string()).collect();
+        for ep in &self.config.endpoints {
+            if !ep.name.is_empty() && !self.llm_providers.contains(&ep.name) { self.llm_providers.push(ep.name.clone()); }
+        }
+        if let Some(p) = self.config.provider.as_ref().filter(|p| self.llm_providers.contains(p)) { self.selected_llm_provider = p.clone(); }
+        self.convo_poll_ticks = config::ticks(self.config.convo_poll_secs);
+        self.health_poll_ticks = config::ticks(self.config.health_poll_secs);
+        self.ansi_colors = self.config.ansi_colors;
+        self.auto_open_all = self.config.auto_open_all;
+    }
+
+    /// Shows `screen` with its default focus, loading what it lists.
+    fn enter_screen(&mut self, screen: CurrentScreen, fx: &mut Vec<Effect>) {
+        let mode = match screen {
+            CurrentScreen::Search => InputMode::SearchInput,
+            CurrentScreen::Settings => InputMode::Settings,
+            CurrentScreen::Launcher | CurrentScreen::Processes => InputMode::Normal,
+        };
+        if !self.set_mode(mode) { return; }
+        // Settings lists the search sources and models too.
+        if matches!(screen, CurrentScreen::Search | CurrentScreen::Settings) && !self.search_loaded {
+            self.search_loaded = true;
+            fx.push(Effect::Dispatch(AppAction::LoadSearchState));
+        }
+        if screen == CurrentScreen::Processes { fx.push(Effect::FetchProcesses); }
+        if screen == CurrentScreen::Settings { self.settings_edit = None; self.settings_binding = false; self.settings_error = None; }
+        self.current_screen = screen;
+    }
+
+    /// What RestoreSession brings back on the next start.
+    pub fn session(&self) -> UiSession {
+        UiSession { screen: self.current_screen.clone(), sidebar: self.search_sidebar.clone(), convo_id: self.current_convo_id }
+    }
+
+    /// Notifies about something that finished for `screen`, unless that tab
+    /// is in front in a focused terminal.
+    fn notify(&self, screen: CurrentScreen, title: &str, body: &str, fx: &mut Vec<Effect>) {
+        if self.config.notifications == Notifications::Off || (self.terminal_focused && self.current_screen == screen) { return; }
+        let body: String = body.chars().take(120).collect();
+        fx.push(Effect::Notify { how: self.config.notifications, title: title.to_string(), body, in_tmux: self.in_tmux });
+    }
+
+    /// What the `on_search_done` hook gets: the question, the answer and
+    /// where it came from.
+    fn search_done_payload(&self, duration_ms: Option<u64>) -> Value {
+        let answer = self.messages.iter().rev().find(|m| m.role == "assistant");
+        let question = self.messages.iter().rev().find(|m| m.role == "user").map(|m| m.question().trim().to_string());
+        let title = self.current_convo_id.and_then(|id| self.conversations.iter().find(|c| c.id == id)).map(|c| c.title.clone());
+        serde_json::json!({
+            "event": Event::SearchDone.name(),
+            "conversation_id": self.current_convo_id,
+            "title": title,
+            "query": question,
+            "answer": answer.map(|m| m.content.clone()),
+            "provider": answer.and_then(|m| m.provider.clone()),
+            "model": answer.and_then(|m| m.model.clone()),
+            "sources": answer.map(|m| m.sources.iter().map(|s| s.url.clone()).collect::<Vec<_>>()).unwrap_or_default(),
+            "usage": answer.and_then(|m| m.usage),
+            "duration_ms": duration_ms,
+        })
+    }
+
+    /// The open conversation as Markdown, for `--dump-on-exit` and `/dump`.
+    pub fn transcript(&self) -> String {
+        let title = self.current_convo_id.and_then(|id| self.conversations.iter().find(|c| c.id == id)).map(|c| c.title.as_str());
+        transcript::markdown(title, &self.messages)
+    }
+
+    /// Every line of the Settings tab, in display order.
+    pub fn settings_rows(&self) -> Vec<SettingRow> {
+        let row = |section, setting, label: &str, value: String| SettingRow { section, setting, label: label.to_string(), value };
+        let on = |b: bool| if b { "on" } else { "off" }.to_string();
+        let mut rows = vec![
+            row("Backends", Setting::LauncherUrl, "Launcher URL", self.config.launcher_url.clone()),
+            row("Backends", Setting::SearchUrl, "Search URL", self.config.search_url.clone()),
+            row("LLM", Setting::Provider, "Provider", self.selected_llm_provider.clone()),
+            row("LLM", Setting::Model, "Model", format!("{}{}", if self.is_starred(&self.selected_model) { "★ " } else { "" }, self.model_label())),
+        ];
+        rows.extend(self.search_providers.iter().enumerate().map(|(i, p)| row("Search sources", Setting::SearchSource(i), &p.name, on(p.is_enabled))));
+        rows.extend(self.config.presets.iter().enumerate().map(|(i, p)| {
+            let active = self.config.preset.as_ref() == Some(&p.name);
+            row("Presets", Setting::Preset(i), &p.name, format!("{}{}", if active { "● " } else { "" }, p.summary()))
+        }));
+        rows.push(row("Theme", Setting::Theme, "Accent color", self.config.theme.label().to_string()));
+        rows.extend([
+            row("General", Setting::ConvoPoll, "Conversation refresh (s, 0 = off)", self.config.convo_poll_secs.to_string()),
+            row("General", Setting::HealthPoll, "URL health checks (s, 0 = off)", self.config.health_poll_secs.to_string()),
+            row("General", Setting::AnsiColors, "ANSI colors in output", on(self.config.ansi_colors)),
+            row("General", Setting::AutoOpenAll, "Open every app's URL after launch", on(self.config.auto_open_all)),
+            row("General", Setting::FollowUps, "Suggest follow-up questions", on(self.config.follow_ups)),
+            row("General", Setting::AutoTitles, "Name new conversations automatically", on(self.config.auto_titles)),
+            row("General", Setting::Notifications, "Notify when answers or launches end", self.config.notifications.label().to_string()),
+            row("Direct chat", Setting::DirectChat, "Chat without the search backend", on(self.config.direct_chat)),
+            row("Direct chat", Setting::DirectUrl, "OpenAI-compatible URL", self.config.direct_url.clone()),
+            row("Direct chat", Setting::OllamaUrl, "Ollama URL (ollama provider)", self.config.ollama_url.clone()),
+            row("Direct chat", Setting::SearxngUrl, "SearxNG URL (web results, empty = off)", self.config.searxng_url.clone()),
+        ]);
+        rows.extend(keymap::COMMANDS.iter().map(|c| {
+            let keys = self.keymap.keys(c.id);
+            row("Keybindings", Setting::Binding(c.id), c.title, if keys.is_empty() { "-".into() } else { keys.join(", ") })
+        }));
+        rows
+    }
+
+    pub fn selected_setting(&self) -> Option<Setting> { self.settings_rows().get(self.settings_row).map(|r| r.setting) }
+
+    /// The configured endpoint the selected provider names, if it is one.
+    pub fn custom_endpoint(&self) -> Option<&Endpoint> {
+        self.config.endpoints.iter().find(|e| e.name == self.selected_llm_provider)
+    }
+
+    /// Whether queries skip the search backend: in direct chat, or with a configured endpoint.
+    pub fn is_direct(&self) -> bool {
+        self.config.direct_chat || self.custom_endpoint().is_some()
+    }
+
+    /// Whether queries go to Ollama's own API (the `ollama` provider in direct chat).
+    pub fn native_ollama(&self) -> bool {
+        self.config.direct_chat && self.selected_llm_provider == ollama::PROVIDER && self.custom_endpoint().is_none()
+    }
+
+    /// Loads the model list: the provider's from the backend, or in direct
+    /// chat the endpoint's (or Ollama's) own. A configured endpoint's own
+    /// list, if it has one, is used as is.
+    pub fn fetch_models(&self) -> Effect {
+        if let Some(ep) = self.custom_endpoint() {
+            return match &ep.models {
+                Some(ids) => Effect::Dispatch(AppAction::ModelsLoaded(ids.iter().map(|id| Model { id: id.clone(), name: id.clone(), params: None }).collect())),
+                None => Effect::FetchDirectModels { url: ep.url.clone(), api_key: ep.key() },
+            };
+        }
+        if self.native_ollama() { Effect::FetchOllamaModels(self.config.ollama_url.clone()) }
+        else if self.config.direct_chat { Effect::FetchDirectModels { url: self.config.direct_url.clone(), api_key: None } }
+        else { Effect::FetchModels(self.selected_llm_provider.clone()) }
+    }
+
+    /// The selected model with its parameter size, if known: `llama3.1:8b · 8.0B`.
+    pub fn model_label(&self) -> String {
+        match self.models.iter().find(|m| m.id == self.selected_model).and_then(|m| m.params.as_ref()) {
+            Some(params) => format!("{} · {}", self.selected_model, params),
+            None => self.selected_model.clone(),
+        }
+    }
+
+    fn cycle_provider(&mut self, forward: bool, fx: &mut Vec<Effect>) {
+        let n = self.llm_providers.len();
+        if n == 0 { return; }
+        let i = self.llm_providers.iter().position(|p| p == &self.selected_llm_provider).unwrap_or(0);
+        self.selected_llm_provider = self.llm_providers[if forward { (i + 1) % n } else { (i + n - 1) % n }].clone();
+        self.config.provider = Some(self.selected_llm_provider.clone());
+        self.config.model = None;
+        fx.push(self.fetch_models());
+        fx.push(self.persist_config());
+    }
+
+    /// Sends `query` with the current provider, model and preset into a new,
+    /// empty assistant message.
+    fn start_search(&mut self, query: String, title: String, fx: &mut Vec<Effect>) {
+        // The question itself is the last message and goes as `query`.
+        let history = (self.context_limited() || self.is_branch()).then(|| {
+            self.context_turns(self.messages.len().saturating_sub(1)).into_iter().map(|i| (self.messages[i].role.clone(), self.messages[i].query())).collect()
+        });
+        self.messages.push(ChatMessage {
+            time: Some(store::now_secs()),
+            provider: Some(if self.config.direct_chat && !self.native_ollama() && self.custom_endpoint().is_none() { "direct".to_string() } else { self.selected_llm_provider.clone() }),
+            model: Some(self.selected_model.clone()),
+            ..ChatMessage::new("assistant", "")
+        });
+        self.is_searching = true;
+        self.stream_ticks = Some((self.tick_count, None));
+        self.chat_auto_scroll = true;
+        if self.models.iter().any(|m| m.id == self.selected_model) {
+            let recent = self.model_prefs.recent.entry(self.selected_llm_provider.clone()).or_default();
+            if recent.first() != Some(&self.selected_model) {
+                recent.retain(|m| m != &self.selected_model);
+                recent.insert(0, self.selected_model.clone());
+                recent.truncate(RECENT_MODELS);
+                fx.push(self.persist_model_prefs());
+            }
+        }
+        if self.is_direct() {
+            let system = self.active_preset().and_then(|p| p.system_prompt.clone()).unwrap_or_else(|| api::DEFAULT_SYSTEM_PROMPT.to_string());
+            let mut messages = vec![("system".to_string(), system)];
+            messages.extend(self.context_turns(self.messages.len().saturating_sub(2)).into_iter().map(|i| (self.messages[i].role.clone(), self.messages[i].query())));
+            messages.push(("user".to_string(), query));
+            let (url, api_key) = match self.custom_endpoint() {
+                Some(ep) => (ep.url.clone(), ep.key()),
+                None if self.native_ollama() => (self.config.ollama_url.clone(), None),
+                None => (self.config.direct_url.clone(), None),
+            };
+            let searxng = Some(self.config.searxng_url.clone()).filter(|u| !u.is_empty());
+            let req = direct::ChatRequest { url, model: self.selected_model.clone(), messages, temperature: self.active_preset().and_then(|p| p.temperature), api_key, searxng };
+            fx.push(if self.native_ollama() { Effect::StartOllamaChat(req) } else { Effect::StartDirectChat(req) });
+            return;
+        }
+        fx.push(Effect::StartSearch(SearchRequest {
+            query,
+            title,
+            convo_id: self.current_convo_id,
+            model: self.selected_model.clone(),
+            provider: self.selected_llm_provider.clone(),
+            active_providers: self.search_providers.iter().filter(|p| p.is_enabled).map(|p| p.id).collect(),
+            system_prompt: self.active_preset().and_then(|p| p.system_prompt.clone()),
+            temperature: self.active_preset().and_then(|p| p.temperature),
+            history,
+        }));
+    }
+
+    /// The question waiting for the backend to come back, if any.
+    pub fn queued_message(&self) -> Option<usize> {
+        self.messages.iter().position(|m| m.queued)
+    }
+
+    /// Sends the queued question at `i`, moved to the end of the chat so its
+    /// answer follows it.
+    fn send_queued(&mut self, i: usize, fx: &mut Vec<Effect>) {
+        let mut msg = self.messages.remove(i);
+        self.forget_message_views(i);
+        msg.queued = false;
+        msg.time = Some(store::now_secs());
+        let (query, title) = (msg.query(), msg.question().to_string());
+        self.notice("Backend reachable again: sending your queued message.");
+        self.messages.push(msg);
+        self.start_search(query, title, fx);
+    }
+
+    /// Re-sends the question behind the last answer. The answer and anything
+    /// after it are dropped; a running stream is left to finish first.
+    fn regenerate_last(&mut self, fx: &mut Vec<Effect>) {
+        if self.is_searching { return; }
+        self.chat_auto_scroll = true;
+        let asked = self.messages.iter().rposition(|m| m.role == "assistant")
+            .and_then(|answer| self.messages[..answer].iter().rposition(|m| m.role == "user"));
+        let Some(asked) = asked else { return self.messages.push(ChatMessage::new("system", "Nothing to regenerate yet: ask something first.")) };
+        let msg = &self.messages[asked];
+        let (query, title) = (msg.query(), msg.question().lines().next().unwrap_or_default().to_string());
+        self.messages.truncate(asked + 1);
+        self.forget_message_views(asked + 1);
+        self.start_search(query, title, fx);
+    }
+
+    /// Whether message `i` shows only its first FOLDED_LINES. Unless toggled
+    /// with `z`, messages over FOLD_OVER lines are, except the newest.
+    pub fn is_folded(&self, i: usize) -> bool {
+        self.message_folds.get(&i).copied().unwrap_or_else(|| {
+            i + 1 < self.messages.len() && self.messages.get(i).is_some_and(|m| m.content.lines().count() > FOLD_OVER)
+        })
+    }
+
+    /// Whether the history sent with a query is trimmed, by hand (`x`) or
+    /// by the sliding window (`/context <n>`).
+    pub fn context_limited(&self) -> bool {
+        self.config.context_window > 0 || self.messages.iter().any(|m| m.excluded)
+    }
+
+    /// Indices of the questions and answers among `messages[..upto]` that go
+    /// into the next query's history: those not excluded, and of them the
+    /// last `config.context_window` (all with 0).
+    pub fn context_turns(&self, upto: usize) -> Vec<usize> {
+        let mut turns: Vec<usize> = self.messages[..upto.min(self.messages.len())].iter().enumerate()
+            .filter(|(_, m)| matches!(m.role.as_str(), "user" | "assistant") && !m.excluded && !m.content.trim().is_empty())
+            .map(|(i, _)| i)
+            .collect();
+        if self.config.context_window > 0 { turns.drain(..turns.len().saturating_sub(self.config.context_window)); }
+        turns
+    }
+
+    /// About how many tokens the conversation takes if sent now: the last
+    /// reported usage plus estimates for everything after it (all of it when
+    /// the backend reports none), with the input and attached context. With
+    /// a trimmed history, estimates for just the turns that are sent.
+    pub fn context_tokens(&self) -> u64 {
+        let pending: u64 = self.pending_context.iter().map(|c| tokens::estimate(&c.content)).sum::<u64>() + tokens::estimate(&self.search_input);
+        if self.context_limited() {
+            let turns: u64 = self.context_turns(self.messages.len()).into_iter().map(|i| tokens::estimate(&self.messages[i].query())).sum();
+            return turns + pending;
+        }
+        let (base, from) = match self.messages.iter().rposition(|m| m.usage.is_some()) {
+            Some(i) => (self.messages[i].usage.map_or(0, |u| u.total()), i + 1),
+            None => (0, 0),
+        };
+        let sent: u64 = self.messages[from..].iter().map(|m| match m.role.as_str() {
+            "user" => tokens::estimate(&m.query()),
+            "assistant" => tokens::estimate(&m.content),
+            _ => 0,
+        }).sum();
+        base + sent + pending
+    }
+
+    /// For the streaming answer: milliseconds since it was asked, and its
+    /// rate in tokens per second once it has streamed for a tick or more.
+    pub fn stream_progress(&self) -> Option<(u64, Option<f64>)> {
+        let (asked, first) = self.stream_ticks.filter(|_| self.is_searching)?;
+        let elapsed = self.tick_count.saturating_sub(asked) * config::TICK_MS;
+        let rate = first.map(|f| self.tick_count.saturating_sub(f)).filter(|t| *t > 0).and_then(|ticks| {
+            let answer = self.messages.iter().rev().find(|m| m.role == "assistant")?;
+            Some(tokens::estimate(&answer.content) as f64 * 1000.0 / (ticks * config::TICK_MS) as f64)
+        });
+        Some((elapsed, rate))
+    }
+
+    /// Engines the conversation's sources came from, sorted.
+    pub fn source_engines(&self) -> Vec<String> {
+        let engines: BTreeSet<&str> = self.messages.iter().flat_map(|m| &m.sources).map(|s| s.engine.as_str()).filter(|e| !e.is_empty()).collect();
+        engines.into_iter().map(str::to_string).collect()
+    }
+
+    /// The Sources pane's rows: every source in the conversation once per
+    /// URL, narrowed to `sources_engine` and in `sources_sort` order.
+    pub fn pane_sources(&self) -> Vec<PaneSource> {
+        let mut rows: Vec<PaneSource> = vec![];
+        for source in self.messages.iter().flat_map(|m| &m.sources) {
+            match rows.iter_mut().find(|r| r.source.url == source.url) {
+                Some(row) => row.cited += 1,
+                None => rows.push(PaneSource { source: source.clone(), cited: 1 }),
+            }
+        }
+        if let Some(engine) = &self.sources_engine { rows.retain(|r| &r.source.engine == engine); }
+        match self.sources_sort {
+            SourceSort::FirstCited => {},
+            SourceSort::Title => rows.sort_by_cached_key(|r| r.source.title.to_lowercase()),
+            SourceSort::Engine => rows.sort_by(|a, b| a.source.engine.cmp(&b.source.engine)),
+            SourceSort::MostCited => rows.sort_by_key(|r| std::cmp::Reverse(r.cited)),
+        }
+        rows
+    }
+
+    /// Shows `url` in the preview modal, fetching it anew.
+    fn open_preview(&mut self, url: String, fx: &mut Vec<Effect>) {
+        if !self.set_mode(InputMode::Preview) { return; }
+        self.preview = Some(PagePreview { url: url.clone(), title: String::new(), body: String::new() });
+        self.preview_scroll = 0;
+        fx.push(Effect::FetchPreview(url));
+    }
+
+    fn selected_pane_url(&self) -> Option<String> {
+        self.pane_sources().get(self.sources_pane_idx).and_then(|r| http_url(&r.source.url))
+    }
+
+    /// Drops focus, folds, open Sources and reasoning from message `from` on, once
+    /// those messages are gone.
+    fn forget_message_views(&mut self, from: usize) {
+        if self.chat_focus.is_some_and(|i| i >= from) { self.chat_focus = None; }
+        self.message_folds.retain(|i, _| *i < from);
+        self.sources_open.retain(|i| *i < from);
+        self.reasoning_open.retain(|i| *i < from);
+    }
+
+    /// Steps the chat focus to the previous or next message (with `mine`,
+    /// the previous or next of the user's); stepping past the newest clears it.
+    fn focus_message(&mut self, forward: bool, mine: bool) {
+        let n = self.messages.len();
+        let wanted = |i: &usize| !mine || self.messages[*i].role == "user";
+        self.chat_focus = match (self.chat_focus.filter(|i| *i < n), forward) {
+            (None, false) => (0..n).rev().find(wanted),
+            (None, true) => None,
+            (Some(i), false) => (0..i).rev().find(wanted).or(Some(i)),
+            (Some(i), true) => (i + 1..n).find(wanted),
+        };
+        self.chat_focus_moved = self.chat_focus.is_some();
+        self.chat_auto_scroll = self.chat_focus.is_none();
+    }
+
+    /// Puts the user message at or before the focus (else the last one) and
+    /// its context back in the input. With `cut`, sending it replaces the chat
+    /// from that message on.
+    fn edit_message(&mut self, cut: bool) {
+        let upto = self.chat_focus.map_or(self.messages.len(), |i| (i + 1).min(self.messages.len()));
+        let mine = |m: &ChatMessage| m.role == "user";
+        let Some(i) = self.messages[..upto].iter().rposition(mine).or_else(|| self.messages.iter().rposition(mine)) else {
+            self.chat_auto_scroll = true;
+            return self.messages.push(ChatMessage::new("system", "No message of yours to edit yet."));
+        };
+        self.search_input = self.messages[i].question().to_string();
+        self.pending_context = self.messages[i].context.clone();
+        self.query_pos = None;
+        self.edit_from = cut.then_some(i);
+        self.set_mode(InputMode::SearchInput);
+    }
+
+    /// Moves the submitted input to the end of `query_history` and clears it.
+    fn remember_query(&mut self) {
+        let text = std::mem::take(&mut self.search_input);
+        let text = text.trim();
+        self.query_pos = None;
+        if text.is_empty() { return; }
+        self.query_history.retain(|q| q != text);
+        self.query_history.push(text.to_string());
+        let extra = self.query_history.len().saturating_sub(QUERY_HISTORY);
+        self.query_history.drain(..extra);
+    }
+
+    /// Points the Ctrl+r prompt at the newest entry before `before` (default:
+    /// all of them) containing its text, ignoring case. Stepping past the
+    /// oldest match stays on it.
+    fn find_query(&mut self, before: Option<usize>) {
+        let Some(search) = self.query_search.as_mut() else { return };
+        let needle = search.query.to_lowercase();
+        let end = before.unwrap_or(self.query_history.len());
+        if let Some(i) = self.query_history[..end].iter().rposition(|q| q.to_lowercase().contains(&needle)) { search.hit = Some(i); }
+        else if before.is_none() { search.hit = None; }
+    }
+
+    fn persist_model_prefs(&self) -> Effect {
+        Effect::WriteStore { name: store::MODELS, data: serde_json::json!(self.model_prefs) }
+    }
+
+    pub fn is_starred(&self, model: &str) -> bool {
+        self.model_prefs.starred.get(&self.selected_llm_provider).is_some_and(|s| s.contains(model))
+    }
+
+    /// The provider's models in cycling order: starred, then recently used,
+    /// then the rest as the backend listed them.
+    pub fn model_order(&self) -> Vec<&Model> {
+        let recent = self.model_prefs.recent.get(&self.selected_llm_provider).map(Vec::as_slice).unwrap_or_default();
+        let rank = |m: &Model| if self.is_starred(&m.id) { 0 } else { recent.iter().position(|r| r == &m.id).map_or(RECENT_MODELS + 1, |i| i + 1) };
+        let mut models: Vec<&Model> = self.models.iter().collect();
+        models.sort_by_key(|m| rank(m));
+        models
+    }
+
+    fn cycle_model(&mut self, forward: bool, fx: &mut Vec<Effect>) {
+        let models = self.model_order();
+        let n = models.len();
+        if n == 0 { return; }
+        let i = models.iter().position(|m| m.id == self.selected_model).unwrap_or(0);
+        self.selected_model = models[if forward { (i + 1) % n } else { (i + n - 1) % n }].id.clone();
+        self.config.model = Some(self.selected_model.clone());
+        fx.push(self.persist_config());
+    }
+
+    pub fn active_preset(&self) -> Option<&Preset> {
+        self.config.preset.as_ref().and_then(|name| self.config.presets.iter().find(|p| &p.name == name))
+    }
+
+    /// Switches sources on by name, the rest off.
+    fn enable_only_sources(&mut self, names: &BTreeSet<String>) {
+        for p in &mut self.search_providers {
+            p.is_enabled = names.contains(&p.name);
+            self.config.search_sources.insert(p.name.clone(), p.is_enabled);
+        }
+    }
+
+    /// Makes preset `name` (or, with `None`, no preset) the active one.
+    fn use_preset(&mut self, name: Option<&str>, fx: &mut Vec<Effect>) {
+        let Some(name) = name else {
+            if self.config.preset.take().is_some() { self.notice("Preset off: default prompt and temperature."); fx.push(self.persist_config()); }
+            return;
+        };
+        let Some(preset) = self.config.presets.iter().find(|p| p.name.eq_ignore_ascii_case(name)).cloned() else {
+            let names: Vec<&str> = self.config.presets.iter().map(|p| p.name.as_str()).collect();
+            self.notice(&format!("No preset '{}' (have: {}).", name, if names.is_empty() { "none yet, see /preset save".to_string() } else { names.join(", ") }));
+            return;
+        };
+        self.config.preset = Some(preset.name.clone());
+        if let Some(model) = &preset.model { self.config.model = Some(model.clone()); }
+        match preset.provider.as_ref().filter(|p| self.llm_providers.contains(p)) {
+            Some(p) if p != &self.selected_llm_provider => {
+                self.selected_llm_provider = p.clone();
+                self.config.provider = Some(p.clone());
+                if preset.model.is_none() { self.config.model = None; }
+                fx.push(self.fetch_models());
             },
-            AppAction::SidebarNext => {
-                match self.search_sidebar {
-                    SearchSidebarState::History => {
-                        let max = self.conversations.len() + 1; 
-                        self.conversation_idx = (self.conversation_idx + 1) % max;
-                    },
-                    SearchSidebarState::Settings => { self.settings_idx = (self.settings_idx + 1) % (2 + self.search_providers.len()); },
-                    _ => {}
+            _ => if let Some(m) = preset.model.as_ref().filter(|m| self.models.iter().any(|x| &x.id == *m)) { self.selected_model = m.clone(); },
+        }
+        if let Some(names) = &preset.search_sources { self.enable_only_sources(names); }
+        self.notice(&format!("Preset '{}': {}", preset.name, preset.summary()));
+        fx.push(self.persist_config());
+    }
+
+    /// Saves the current provider, model and sources as preset `name`,
+    /// keeping the prompt and temperature of the preset in use.
+    fn save_preset(&mut self, name: &str, fx: &mut Vec<Effect>) {
+        let base = self.active_preset().cloned().unwrap_or_default();
+        let preset = Preset {
+            name: name.to_string(),
+            provider: Some(self.selected_llm_provider.clone()),
+            model: self.models.iter().any(|m| m.id == self.selected_model).then(|| self.selected_model.clone()).or(base.model),
+            search_sources: (!self.search_providers.is_empty()).then(|| self.search_providers.iter().filter(|p| p.is_enabled).map(|p| p.name.clone()).collect()),
+            ..base
+        };
+        match self.config.presets.iter_mut().find(|p| p.name == name) {
+            Some(p) => *p = preset,
+            None => self.config.presets.push(preset),
+        }
+        self.config.preset = Some(name.to_string());
+        self.notice(&format!("Saved preset '{}'. Add `system_prompt` or `temperature` to it in {}.", name, store::CONFIG));
+        fx.push(self.persist_config());
+    }
+
+    fn toggle_search_source(&mut self, i: usize, fx: &mut Vec<Effect>) {
+        let Some(p) = self.search_providers.get_mut(i) else { return };
+        p.is_enabled = !p.is_enabled;
+        self.config.search_sources.insert(p.name.clone(), p.is_enabled);
+        fx.push(self.persist_config());
+    }
+
+    /// Stores a typed Settings value, or explains why it was refused.
+    fn commit_setting(&mut self, setting: Setting, value: &str, fx: &mut Vec<Effect>) -> Result<(), String> {
+        let value = value.trim();
+        match setting {
+            Setting::LauncherUrl | Setting::SearchUrl => {
+                match reqwest::Url::parse(value) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => {},
+                    _ => return Err("must be an http:// or https:// URL".into()),
                 }
+                let url = value.trim_end_matches('/').to_string();
+                if setting == Setting::LauncherUrl { self.config.launcher_url = url; } else { self.config.search_url = url; }
+                fx.push(Effect::SetBackends { launcher: self.config.launcher_url.clone(), search: self.config.search_url.clone() });
+                if setting == Setting::LauncherUrl { fx.push(Effect::Dispatch(AppAction::LoadApps)); }
+                else if self.search_loaded { fx.push(Effect::Dispatch(AppAction::LoadSearchState)); }
             },
-            AppAction::SidebarPrev => {
-                match self.search_sidebar {
-                    SearchSidebarState::History => {
-                        let max = self.conversations.len() + 1;
-                        if self.conversation_idx == 0 { self.conversation_idx = max - 1; } else { self.conversation_idx -= 1; }
-                    },
-                    SearchSidebarState::Settings => { if self.settings_idx == 0 { self.settings_idx = (2 + self.search_providers.len()) - 1; } else { self.settings_idx -= 1; } },
-                    _ => {}
+            Setting::SearxngUrl if value.is_empty() => self.config.searxng_url.clear(),
+            Setting::SearxngUrl => {
+                match reqwest::Url::parse(value) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => {},
+                    _ => return Err("must be an http:// or https:// URL, or empty".into()),
                 }
+                self.config.searxng_url = value.trim_end_matches('/').to_string();
             },
-            AppAction::SidebarSelect => {
-                match self.search_sidebar {
-                    SearchSidebarState::History => {
-                        if self.conversation_idx == 0 {
-                            self.action_tx.send(AppAction::NewConversation).unwrap();
-                        } else if let Some(c) = self.conversations.get(self.conversation_idx - 1) {
-                             self.action_tx.send(AppAction::LoadConversation(c.id)).unwrap();
-                        }
-                    },
-                    SearchSidebarState::Settings => {
-                        if self.settings_idx == 0 {
-                            let curr_pos = self.llm_providers.iter().position(|p| p == &self.selected_llm_provider).unwrap_or(0);
-                            let next_pos = (curr_pos + 1) % self.llm_providers.len();
-                            self.selected_llm_provider = self.llm_providers[next_pos].clone();
-                            let tx = self.action_tx.clone(); let p = self.selected_llm_provider.clone();
-                            tokio::spawn(async move { if let Ok(m) = api::fetch_models(&p).await { tx.send(AppAction::ModelsLoaded(m)).unwrap(); } });
-                        } else if self.settings_idx == 1 {
-                            if !self.models.is_empty() {
-                                let curr = self.models.iter().position(|m| m.id == self.selected_model).unwrap_or(0);
-                                let next = (curr + 1) % self.models.len();
-                                self.selected_model = self.models[next].id.clone();
-                            }
-                        } else {
-                            if let Some(p) = self.search_providers.get_mut(self.settings_idx - 2) { p.is_enabled = !p.is_enabled; }
-                        }
-                    },
-                    _ => {}
+            Setting::DirectUrl | Setting::OllamaUrl => {
+                match reqwest::Url::parse(value) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => {},
+                    _ => return Err("must be an http:// or https:// URL".into()),
                 }
+                let url = value.trim_end_matches('/').to_string();
+                if setting == Setting::DirectUrl { self.config.direct_url = url; } else { self.config.ollama_url = url; }
+                if self.config.direct_chat { fx.push(self.fetch_models()); }
             },
-            AppAction::NewConversation => {
-                self.current_convo_id = None;
-                self.messages.clear();
-                self.messages.push(ChatMessage { role: "system".into(), content: "New conversation started.".into(), sources: vec![] });
-                self.chat_auto_scroll = true;
-                self.search_sidebar = SearchSidebarState::Hidden;
-                self.input_mode = InputMode::SearchInput;
-            },
-            AppAction::ConversationCreated(id) => {
-                self.current_convo_id = Some(id);
-                let tx = self.action_tx.clone();
-                tokio::spawn(async move { if let Ok(c) = api::fetch_conversations().await { tx.send(AppAction::ConversationsLoaded(c)).unwrap(); } });
+            Setting::ConvoPoll | Setting::HealthPoll => {
+                let secs: u64 = value.parse().map_err(|_| "must be a whole number of seconds".to_string())?;
+                if setting == Setting::ConvoPoll { self.config.convo_poll_secs = secs; } else { self.config.health_poll_secs = secs; }
+                self.apply_config();
             },
-            AppAction::LoadConversation(id) => {
-                self.current_convo_id = Some(id);
-                self.messages.clear();
-                self.messages.push(ChatMessage { role: "system".into(), content: "Loading conversation...".into(), sources: vec![] });
-                self.chat_auto_scroll = true;
-                self.input_mode = InputMode::ChatHistory; // Focus chat so user can see it loading
-                let tx = self.action_tx.clone();
-                tokio::spawn(async move {
-                    if let Ok(json) = api::load_conversation(id).await { 
-                        tx.send(AppAction::ConversationLoaded(json)).unwrap(); 
-                    } else {
-                        tx.send(AppAction::SearchError("Failed to load chat".into())).unwrap();
-                    }
-                });
+            _ => return Ok(()),
+        }
+        fx.push(self.persist_config());
+        Ok(())
+    }
+
+    /// Left/Right (and Enter) on the selected Settings row.
+    fn change_setting(&mut self, forward: bool, fx: &mut Vec<Effect>) {
+        match self.selected_setting() {
+            Some(Setting::Provider) => self.cycle_provider(forward, fx),
+            Some(Setting::Model) => self.cycle_model(forward, fx),
+            Some(Setting::SearchSource(i)) => self.toggle_search_source(i, fx),
+            Some(Setting::Preset(i)) => {
+                let name = self.config.presets[i].name.clone();
+                let active = self.config.preset.as_ref() == Some(&name);
+                self.use_preset((!active).then_some(name.as_str()), fx);
             },
-            AppAction::ConversationLoaded(json) => {
-                self.messages.clear();
-                if let Some(msgs) = json["messages"].as_array() {
-                    for m in msgs {
-                        let role = m["role"].as_str().unwrap_or("unknown").to_string();
-                        let content = m["content"].as_str().unwrap_or("").to_string();
-                        let sources: Vec<SearchSource> = if let Some(s_str) = m["sources"].as_str() { serde_json::from_str(s_str).unwrap_or_default() } else { vec![] };
-                        self.messages.push(ChatMessage { role, content, sources });
-                    }
-                }
-                self.chat_auto_scroll = true;
+            Some(Setting::Theme) => { self.config.theme = self.config.theme.cycle(forward); fx.push(self.persist_config()); },
+            Some(Setting::AnsiColors) => { self.config.ansi_colors = !self.config.ansi_colors; self.apply_config(); fx.push(self.persist_config()); },
+            Some(Setting::AutoOpenAll) => { self.config.auto_open_all = !self.config.auto_open_all; self.apply_config(); fx.push(self.persist_config()); },
+            Some(Setting::FollowUps) => { self.config.follow_ups = !self.config.follow_ups; fx.push(self.persist_config()); },
+            Some(Setting::AutoTitles) => { self.config.auto_titles = !self.config.auto_titles; fx.push(self.persist_config()); },
+            Some(Setting::Notifications) => { self.config.notifications = self.config.notifications.cycle(forward); fx.push(self.persist_config()); },
+            Some(Setting::DirectChat) => {
+                self.config.direct_chat = !self.config.direct_chat;
+                self.config.model = None;
+                fx.push(self.fetch_models());
+                fx.push(self.persist_config());
             },
-            AppAction::ScrollChat(delta) => {
-                self.chat_auto_scroll = false;
-                if delta < 0 {
-                    self.chat_scroll = self.chat_scroll.saturating_sub(delta.abs() as u16);
-                } else {
-                    self.chat_scroll = self.chat_scroll.saturating_add(delta as u16);
-                }
+            _ => {},
+        }
+    }
+
+    /// Launches app `id`, first asking for its `{{placeholders}}` if it has any.
+    fn launch(&mut self, id: String, name: String, fx: &mut Vec<Effect>) {
+        let names = self.apps.iter().find(|a| a.id == id).map(|a| template::placeholders(&a.command)).unwrap_or_default();
+        if names.is_empty() { return self.start_launch(id, name, None, fx); }
+        if !self.set_mode(InputMode::Args) { return; }
+        let defaults = self.arg_defaults.get(&id);
+        let fields = names.into_iter().map(|n| { let v = defaults.and_then(|d| d.get(&n)).cloned().unwrap_or_default(); (n, v) }).collect();
+        self.args_prompt = Some(ArgsPrompt { app_id: id, name, fields, focus: 0 });
+    }
+
+    /// Marked app ids in list order; marks hidden by the filter come last.
+    fn marked_ids(&self) -> Vec<String> {
+        let visible = self.filtered_apps.iter().map(|&i| &self.apps[i]);
+        let hidden = self.apps.iter().enumerate().filter(|(i, _)| !self.filtered_apps.contains(i)).map(|(_, a)| a);
+        visible.chain(hidden).filter(|a| self.marked.contains(&a.id)).map(|a| a.id.clone()).collect()
+    }
+
+    /// Chain list (or editor) selection one step forward or back, wrapping.
+    fn chain_select(&mut self, forward: bool) {
+        let step = |idx: usize, len: usize| if len == 0 { 0 } else if forward { (idx + 1) % len } else { idx.checked_sub(1).unwrap_or(len - 1) };
+        match self.chain_draft.as_mut() {
+            None => self.chain_idx = step(self.chain_idx, self.chains.len()),
+            Some(d) => match d.focus {
+                ChainFocus::Name => {},
+                ChainFocus::Apps => d.app_idx = step(d.app_idx, self.apps.len()),
+                ChainFocus::Steps => d.step_idx = step(d.step_idx, d.steps.len()),
             },
-            AppAction::EnterSearchChar(c) => self.search_input.push(c),
-            AppAction::DeleteSearchChar => { self.search_input.pop(); },
-            AppAction::SubmitSearch => {
-                if !self.search_input.trim().is_empty() && !self.is_searching {
-                    let query = self.search_input.clone();
-                    self.messages.push(ChatMessage { role: "user".into(), content: query.clone(), sources: vec![] });
-                    self.messages.push(ChatMessage { role: "assistant".into(), content: String::new(), sources: vec![] });
-                    self.search_input.clear();
-                    self.is_searching = true;
-                    self.chat_auto_scroll = true;
-                    
-                    let tx = self.action_tx.clone();
-                    let convo_id = self.current_convo_id;
-                    let model = self.selected_model.clone();
-                    let prov = self.selected_llm_provider.clone();
-                    let active_prov_ids: Vec<i64> = self.search_providers.iter().filter(|p| p.is_enabled).map(|p| p.id).collect();
-                    tokio::spawn(async move {
-                        if let Err(e) = api::start_search_stream(query, convo_id, model, prov, active_prov_ids, tx.clone()).await {
-                            tx.send(AppAction::SearchError(e.to_string())).unwrap();
-                        }
-                    });
-                }
+        }
+    }
+
+    /// Replaces the catalog (backend apps and containers) and everything derived from it.
+    fn set_apps(&mut self, apps: Vec<AppModel>, fx: &mut Vec<Effect>) {
+        self.marked.retain(|id| apps.iter().any(|a| a.id == *id));
+        self.apps = apps;
+        self.rebuild_tag_index();
+        self.update_filter();
+        if self.health_poll_ticks > 0 { self.request_health(fx); }
+    }
+
+    /// Appends an output line to launch `seq`'s record.
+    fn record_output(&mut self, seq: u64, text: &str, stderr: bool) {
+        let Some(rec) = self.launch_history.iter_mut().rev().find(|r| r.seq == seq) else { return };
+        if rec.output.len() == RECORD_LINES { rec.output.remove(0); rec.dropped_lines += 1; }
+        rec.output.push(RecordLine { text: text.to_string(), stderr });
+    }
+
+    /// The launch selected in the history view.
+    pub fn selected_launch(&self) -> Option<&LaunchRecord> { self.launch_history.iter().rev().nth(self.history_idx) }
+
+    /// Session line numbers of the Output lines shown, oldest first.
+    pub fn output_lines(&self) -> Vec<u64> {
+        let (logs, first) = (&self.launcher_logs, self.launcher_logs.first());
+        (0..logs.len()).filter(|i| !self.output_errors_only || logs.severity(*i).is_error()).map(|i| first + i as u64).collect()
+    }
+
+    /// Session line numbers of shown Output lines containing the query, ignoring case.
+    pub fn output_matches(&self) -> Vec<u64> {
+        let query = self.output_query.to_lowercase();
+        if query.is_empty() { return vec![]; }
+        let logs = &self.launcher_logs;
+        self.output_lines().into_iter().filter(|n| logs.get(logs.index_of(*n)).is_some_and(|l| ansi::strip(l).to_lowercase().contains(&query))).collect()
+    }
+
+    /// Makes `line` the current match and scrolls it to the bottom row.
+    fn show_output_line(&mut self, line: Option<u64>) {
+        let Some(line) = line else { self.output_match = None; return };
+        self.output_match = Some(line);
+        self.output_end = Some(line + 1).filter(|e| *e < self.launcher_logs.total());
+    }
+
+    /// Starts a check of every app with an http(s) URL, unless one is running.
+    fn request_health(&mut self, fx: &mut Vec<Effect>) {
+        if self.health_pending { return; }
+        let targets: Vec<(String, String)> = self.apps.iter()
+            .map(|a| (a.id.clone(), a.url.trim().to_string()))
+            .filter(|(_, u)| u.starts_with("http://") || u.starts_with("https://"))
+            .collect();
+        if targets.is_empty() { return; }
+        self.health_pending = true;
+        fx.push(Effect::CheckUrls(targets));
+    }
+
+    /// Opens auto-open URLs that answer; gives up on failed launches and after a minute.
+    fn advance_auto_open(&mut self, fx: &mut Vec<Effect>) {
+        let tick = self.tick_count;
+        let mut keep = vec![];
+        for (name, mut w) in std::mem::take(&mut self.opening) {
+            let failed = !self.running_launches.contains(&w.seq) && self.launch_history.iter().rev().find(|r| r.seq == w.seq).and_then(|r| r.ok) == Some(false);
+            if failed { continue; }
+            if w.up {
+                self.launcher_logs.push(format!("'{}' is up, opening {}", name, w.url));
+                fx.push(Effect::OpenExternal(w.url));
+            } else if w.timed_out(tick) {
+                self.launcher_logs.push(format!("'{}' did not answer at {}; not opening it.", name, w.url));
+            } else {
+                fx.extend(w.poll(tick));
+                keep.push((name, w));
+            }
+        }
+        self.opening = keep;
+    }
+
+    fn persist_chains(&self) -> Effect {
+        Effect::WriteStore { name: store::CHAINS, data: serde_json::json!(self.chains) }
+    }
+
+    fn start_sequence(&mut self, label: String, ids: Vec<String>, health_check: bool, stop_on_failure: bool, fx: &mut Vec<Effect>) {
+        if let Some(old) = self.sequence.take() { self.launcher_logs.push(format!("{} cancelled.", old.label)); }
+        self.launcher_logs.push(format!("{}: launching {} apps one at a time...", label, ids.len()));
+        self.sequence = Some(LaunchSequence { label, queue: ids.into(), health_check, stop_on_failure, ..Default::default() });
+        self.advance_sequence(fx);
+    }
+
+    /// Waits on the sequence's current launch with `seq`, if it has none yet
+    /// (the launch was held back by a placeholder prompt).
+    fn track_sequence_launch(&mut self, app_id: &str, seq: u64) {
+        let Some(app) = self.apps.iter().find(|a| a.id == app_id) else { return };
+        let Some(run) = self.sequence.as_mut().filter(|r| r.step.is_none()) else { return };
+        let watch = http_url(&app.url).filter(|_| run.health_check).map(|url| UrlWatch::new(seq, url, self.tick_count));
+        run.step = Some(SequenceStep { seq, name: app.name.clone(), since: self.tick_count, watch });
+        run.started += 1;
+    }
+
+    /// Moves the sequence along: the next app starts once the previous launch
+    /// has answered (or kept running for LAUNCH_STAGGER_TICKS, since servers
+    /// rarely exit), or, with health checks, once its URL responds.
+    fn advance_sequence(&mut self, fx: &mut Vec<Effect>) {
+        let Some(mut run) = self.sequence.take() else { return };
+        if self.args_prompt.is_some() { self.sequence = Some(run); return; }
+        if let Some(step) = run.step.as_mut() {
+            let running = self.running_launches.contains(&step.seq);
+            let failed = !running && self.launch_history.iter().rev().find(|r| r.seq == step.seq).and_then(|r| r.ok) == Some(false);
+            if failed && run.stop_on_failure {
+                self.launcher_logs.push(format!("{} stopped: '{}' failed.", run.label, step.name));
+                return;
+            }
+            let waited = self.tick_count.saturating_sub(step.since);
+            let done = match step.watch.as_mut() {
+                Some(_) if failed => true,
+                Some(w) if !w.up => {
+                    if w.timed_out(self.tick_count) {
+                        self.launcher_logs.push(format!("{} stopped: '{}' did not answer at {}.", run.label, step.name, w.url));
+                        return;
+                    }
+                    fx.extend(w.poll(self.tick_count));
+                    false
+                },
+                Some(_) => true,
+                None => !running || waited >= LAUNCH_STAGGER_TICKS,
+            };
+            if !done { self.sequence = Some(run); return; }
+            run.step = None;
+        }
+        while let Some(id) = run.queue.pop_front() {
+            let Some(name) = self.apps.iter().find(|a| a.id == id).map(|a| a.name.clone()) else {
+                self.launcher_logs.push(format!("{}: skipping missing app {}.", run.label, id));
+                continue;
+            };
+            let before = self.launch_history.last().map(|r| r.seq);
+            self.sequence = Some(run);
+            self.launch(id.clone(), name, fx);
+            if let Some(seq) = self.launch_history.last().map(|r| r.seq).filter(|s| Some(*s) != before) { self.track_sequence_launch(&id, seq); }
+            return;
+        }
+        if run.started > 0 { self.launcher_logs.push(format!("{}: all {} apps started.", run.label, run.started)); }
+    }
+
+    fn start_launch(&mut self, id: String, name: String, cmd: Option<String>, fx: &mut Vec<Effect>) {
+        let app = self.apps.iter().find(|a| a.id == id);
+        let remote = match app.and_then(|a| a.target.as_deref()) {
+            None => None,
+            Some(t) => match self.ssh_hosts.iter().find(|h| h.name == t) {
+                Some(host) => Some(host.clone()),
+                None => return self.launcher_logs.push(format!("'{}' targets unknown host '{}'; add it to {}.", name, t, store::HOSTS)),
             },
-            AppAction::SearchSourcesReceived(sources) => { if let Some(last) = self.messages.last_mut() { if last.role == "assistant" { last.sources = sources; } } },
-            AppAction::SearchStreamToken(text) => { if let Some(last) = self.messages.last_mut() { if last.role == "assistant" { last.content.push_str(&text); } } },
-            AppAction::SearchError(err) => { self.messages.push(ChatMessage { role: "system".into(), content: format!("Error: {}", err), sources: vec![] }); self.is_searching = false; },
-            AppAction::SearchDone => { self.is_searching = false; },
+        };
+        self.launcher_logs.push(match &remote { Some(h) => format!("Executing '{}' on {}...", name, h.name), None => format!("Executing '{}'...", name) });
+        self.ref_cursor = 0;
+        let stats = self.usage.entry(id.clone()).or_default();
+        stats.count += 1;
+        stats.last_launched = store::now_secs();
+        fx.push(Effect::WriteStore { name: store::USAGE, data: serde_json::json!(self.usage) });
+        let env = self.apps.iter().find(|a| a.id == id).map(|a| a.launch.clone()).unwrap_or_default();
+        let opens = self.auto_open_all || self.auto_open.contains(&id);
+        let open_url = self.apps.iter().find(|a| a.id == id).and_then(|a| http_url(&a.url)).filter(|_| opens);
+        let seq = self.record_launch(Some(id.clone()), name.clone(), fx);
+        if let Some(url) = open_url { self.opening.push((name.clone(), UrlWatch::new(seq, url, self.tick_count))); }
+        let tmux_mode = self.tmux_modes.get(&id).copied();
+        if remote.is_some() || tmux_mode.is_some() {
+            let template = self.apps.iter().find(|a| a.id == id).map(|a| a.command.clone()).unwrap_or_default();
+            let cmd = cmd.unwrap_or(template);
+            let (cmd, env) = match &remote { Some(host) => (ssh::command(host, &cmd, &env, tmux_mode.is_some()), LaunchEnv::default()), None => (cmd, env) };
+            return fx.push(match tmux_mode {
+                Some(mode) => { let (args, what) = tmux::args(mode, self.in_tmux, &name, seq, &cmd, &env); Effect::LaunchTmux { seq, args, what } },
+                None => Effect::LaunchRemote { id, cmd, seq },
+            });
+        }
+        fx.push(match cmd { Some(cmd) => Effect::LaunchCommand { id, cmd, env, seq }, None => Effect::LaunchApp { id, env, seq } });
+    }
+
+    pub fn selected_process(&self) -> Option<&ProcessInfo> { self.processes.get(self.process_idx) }
+
+    /// Name of a launch that is still running, for the Output spinner.
+    pub fn running_launch_name(&self) -> Option<&str> {
+        let seq = self.running_launches.iter().next_back()?;
+        self.launch_history.iter().rev().find(|r| r.seq == *seq).map(|r| r.name.as_str())
+    }
+
+    fn close_modal(&mut self) {
+        if self.input_mode.is_modal() {
+            self.input_mode = std::mem::replace(&mut self.modal_return, InputMode::Normal);
+        }
+    }
+
+    fn ask_confirm(&mut self, action: ConfirmAction) {
+        if self.set_mode(InputMode::Confirm) { self.pending_confirm = Some(action); }
+    }
+
+    fn rebuild_tag_index(&mut self) {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for app in &self.apps {
+            for tag in app_tags(app).into_iter().collect::<BTreeSet<_>>() { *counts.entry(tag.to_string()).or_default() += 1; }
+        }
+        // Tags that vanished from every app can't stay selected.
+        self.selected_tags.retain(|t| counts.contains_key(t));
+        self.tag_index = counts.into_iter().collect();
+        if self.tag_idx >= self.tag_index.len() { self.tag_idx = 0; }
+    }
+
+    /// Project directory bound to the open conversation, if any.
+    pub fn current_project(&self) -> Option<&str> {
+        match self.current_convo_id {
+            Some(id) => self.project_bindings.get(&id).map(String::as_str),
+            None => self.pending_project.as_deref(),
+        }
+    }
+
+    fn persist_highlights(&self) -> Effect {
+        Effect::WriteStore { name: store::HIGHLIGHTS, data: serde_json::json!(self.highlights) }
+    }
+
+    pub fn selected_highlight(&self) -> Option<&Highlight> {
+        self.highlights.len().checked_sub(self.highlight_idx + 1).and_then(|i| self.highlights.get(i))
+    }
+
+    fn persist_projects(&self) -> Effect {
+        Effect::WriteStore { name: store::PROJECTS, data: serde_json::json!(self.project_bindings) }
+    }
+
+    fn persist_summaries(&self) -> Effect {
+        Effect::WriteStore { name: store::SUMMARIES, data: serde_json::json!(self.summaries) }
+    }
+
+    fn persist_branches(&self) -> Effect {
+        Effect::WriteStore { name: store::BRANCHES, data: serde_json::json!(self.branches) }
+    }
+
+    /// Ctrl+f matches for `history_query` among the fetched conversations.
+    pub fn history_hits(&self) -> Vec<Hit> {
+        convo_search::search(&self.conversations, &self.convo_index, &self.history_query)
+    }
+
+    /// Whether the chat is a branch, whose history the backend doesn't have.
+    pub fn is_branch(&self) -> bool {
+        self.pending_branch.is_some() || self.current_convo_id.is_some_and(|id| self.branches.contains_key(&id))
+    }
+
+    /// Starts a new conversation seeded with the questions and answers up to
+    /// the focused (else the last) message. It is created on the backend with
+    /// the next query, which carries the seed as its history.
+    fn branch_from_message(&mut self) {
+        if self.is_searching { return; }
+        let Some(upto) = self.chat_focus.or(self.messages.len().checked_sub(1)) else { return };
+        let seed: Vec<ChatMessage> = self.messages[..=upto].iter()
+            .filter(|m| matches!(m.role.as_str(), "user" | "assistant") && !m.content.trim().is_empty())
+            .cloned().collect();
+        if seed.is_empty() { return self.notice("Nothing to branch from yet: ask something first."); }
+        let from_title = self.current_convo_id.and_then(|id| self.conversations.iter().find(|c| c.id == id)).map(|c| c.title.clone())
+            .unwrap_or_else(|| "this conversation".to_string());
+        self.pending_project = self.current_project().map(str::to_string);
+        self.pending_branch = Some(Branch { from: self.current_convo_id, from_title: from_title.clone(), seed: seed.iter().map(|m| (m.role.clone(), m.query())).collect() });
+        self.current_convo_id = None;
+        self.pending_context.clear();
+        self.messages = seed;
+        self.forget_message_views(0);
+        self.edit_from = None;
+        self.messages.push(ChatMessage::new("system", format!("Branched from \"{}\". Your next message starts a new conversation with the history above.", from_title)));
+        self.chat_auto_scroll = true;
+        self.set_mode(InputMode::SearchInput);
+    }
+
+    /// Opens the file browser in the project (attachments) or the typed
+    /// Working dir, else the current directory.
+    fn open_file_browser(&mut self, pick: Pick, fx: &mut Vec<Effect>) {
+        let start = match pick {
+            Pick::Attachment => self.current_project().map(str::to_string),
+            Pick::WorkingDir => Some(self.active_form.cwd.trim().to_string()).filter(|d| !d.is_empty()),
+        };
+        self.file_browser = Some(FileBrowser::new(pick));
+        fx.push(Effect::ListDir(commands::resolve_path(None, start.as_deref().unwrap_or("."))));
+    }
+
+    /// Enter in the file browser: directories are opened, a file is attached.
+    fn file_browser_open(&mut self, fx: &mut Vec<Effect>) {
+        let Some(b) = &self.file_browser else { return };
+        let Some(entry) = b.selected() else { return };
+        let path = b.path_of(entry);
+        if entry.is_dir { return fx.push(Effect::ListDir(path)); }
+        if b.pick != Pick::Attachment { return; }
+        // Labelled relative to the project when it is inside it.
+        let label = self.current_project().and_then(|p| path.strip_prefix(p).ok()).unwrap_or(&path).display().to_string();
+        self.file_browser = None;
+        fx.push(Effect::ReadContextFile { path, label });
+    }
+
+    /// Apps matching the `@mention` at the end of the Search input.
+    pub fn mention_suggestions(&self) -> Vec<&AppModel> {
+        if self.query_search.is_some() { return vec![]; }
+        mentions::partial(&self.search_input).map(|p| mentions::suggestions(&self.apps, p)).unwrap_or_default()
+    }
+
+    /// Apps that message `i` recommends (mentions as `@app` or by name).
+    pub fn recommended_apps(&self, i: usize) -> Vec<&AppModel> {
+        self.messages.get(i).filter(|m| m.role == "assistant").map(|m| mentions::recommended(&self.apps, &m.content)).unwrap_or_default()
+    }
+
+    /// The answer `L` launches from: the focused one, else the last.
+    pub fn launch_answer(&self) -> Option<usize> {
+        self.chat_focus.filter(|&i| self.messages.get(i).is_some_and(|m| m.role == "assistant")).or_else(|| self.messages.iter().rposition(|m| m.role == "assistant"))
+    }
+
+    /// The shell commands in message `i`, when it is an answer.
+    pub fn answer_commands(&self, i: usize) -> Vec<CodeBlock> {
+        self.messages.get(i).filter(|m| m.role == "assistant").map(|m| snippets::commands(&m.content)).unwrap_or_default()
+    }
+
+    /// The blocks of answer `i` that `verb` picks from; saving takes any code.
+    pub fn snippet_blocks(&self, i: usize, verb: Verb) -> Vec<CodeBlock> {
+        match verb {
+            Verb::SaveApp => self.messages.get(i).filter(|m| m.role == "assistant").map(|m| snippets::saveable(&m.content)).unwrap_or_default(),
+            Verb::Copy | Verb::Run => self.answer_commands(i),
         }
     }
-}
\ No newline at end of file
+
+    /// The code blocks of message `i`, any language, for copying as they are.
+    pub fn code_blocks(&self, i: usize) -> Vec<CodeBlock> {
+        self.messages.get(i).map(|m| snippets::blocks(&m.content).into_iter().filter(|b| !b.code.trim().is_empty()).collect()).unwrap_or_default()
+    }
+
+    fn use_snippet(&mut self, verb: Verb, cmd: String, fx: &mut Vec<Effect>) {
+        match verb {
+            Verb::Copy => fx.push(Effect::CopyToClipboard(cmd)),
+            Verb::Run => self.ask_confirm(ConfirmAction::RunSnippet(cmd)),
+            Verb::SaveApp => {
+                let title = self.current_convo_id.and_then(|id| self.conversations.iter().find(|c| c.id == id)).map(|c| c.title.clone());
+                let desc = title.map(|t| format!("From chat: {}", t)).unwrap_or_else(|| "From chat".into());
+                self.enter_screen(CurrentScreen::Launcher, fx);
+                if self.set_mode(InputMode::Editing) { self.active_form = AppForm { name: snippets::app_name(&cmd), desc, cmd: TextArea::new(cmd), ..AppForm::default() }; }
+            },
+        }
+    }
+
+    /// Launches an app from the chat on the Launcher, where its output shows.
+    fn launch_from_chat(&mut self, id: String, fx: &mut Vec<Effect>) {
+        self.enter_screen(CurrentScreen::Launcher, fx);
+        if let Some(i) = self.filtered_apps.iter().position(|&i| self.apps[i].id == id) { self.apps_idx = i; }
+        fx.push(Effect::Dispatch(AppAction::LaunchApp(id)));
+    }
+
+    /// The call for `/name args` when a running plugin registered `name`.
+    fn plugin_command(&self, input: &str) -> Option<Effect> {
+        let rest = input.trim().strip_prefix('/')?;
+        let (name, args) = rest.split_once(char::is_whitespace).map(|(n, a)| (n, a.trim())).unwrap_or((rest, ""));
+        let plugin = self.plugins.iter().find(|(_, m)| m.commands.iter().any(|c| c.name == name))?.0;
+        let params = serde_json::json!({ "name": name, "args": args, "conversation_id": self.current_convo_id });
+        Some(Effect::PluginCall { plugin: plugin.clone(), method: "command", params, reply: Reply::Command })
+    }
+
+    fn run_command(&mut self, cmd: SlashCommand, fx: &mut Vec<Effect>) {
+        match cmd {
+            SlashCommand::Help => {
+                let mut help = commands::HELP.to_string();
+                for (plugin, m) in &self.plugins {
+                    for c in &m.commands { help.push_str(&format!("\n- `/{}` {} ({} plugin)", c.name, c.description, plugin)); }
+                }
+                self.messages.push(ChatMessage::new("system", help));
+            },
+            SlashCommand::Cd(dir) => fx.push(Effect::ResolveProjectDir(commands::resolve_path(self.current_project(), &dir))),
+            SlashCommand::Unbind => {
+                match self.current_convo_id {
+                    Some(id) => { self.project_bindings.remove(&id); fx.push(self.persist_projects()); },
+                    None => self.pending_project = None,
+                }
+                self.messages.push(ChatMessage::new("system", "Project binding removed."));
+            },
+            SlashCommand::BrowseFiles => self.open_file_browser(Pick::Attachment, fx),
+            SlashCommand::File(path) => {
+                let resolved = commands::resolve_path(self.current_project(), &path);
+                fx.push(Effect::ReadContextFile { path: resolved, label: path });
+            },
+            git @ (SlashCommand::GitDiff { .. } | SlashCommand::GitLog(_) | SlashCommand::GitShow(_)) => {
+                let Some(dir) = self.current_project() else {
+                    self.messages.push(ChatMessage::new("system", "Bind a project first with /cd <dir>."));
+                    return;
+                };
+                let args = git.git_args().unwrap_or_default();
+                fx.push(Effect::RunGit { dir: dir.to_string(), label: format!("git {}", args.join(" ")), args });
+            },
+            SlashCommand::Todo(file) => self.extract_todos(file, fx),
+            SlashCommand::Summarize { save } => self.summarize(save, fx),
+            SlashCommand::Context(window) => {
+                if let Some(n) = window {
+                    self.config.context_window = n;
+                    if n == 0 { for m in &mut self.messages { m.excluded = false; } }
+                    fx.push(self.persist_config());
+                }
+                let total = self.context_turns(self.messages.len()).len();
+                let all = self.messages.iter().filter(|m| matches!(m.role.as_str(), "user" | "assistant") && !m.content.trim().is_empty()).count();
+                let window = match self.config.context_window { 0 => "no window".to_string(), n => format!("window of {}", n) };
+                let excluded = self.messages.iter().filter(|m| m.excluded).count();
+                self.messages.push(ChatMessage::new("system", format!("Context: {} of {} messages sent ({}, {} excluded).", total, all, window, excluded)));
+            },
+            SlashCommand::Preset(None) => fx.push(Effect::Dispatch(AppAction::OpenPresets)),
+            SlashCommand::Preset(Some(name)) => self.use_preset(Some(&name), fx),
+            SlashCommand::PresetOff => self.use_preset(None, fx),
+            SlashCommand::SavePreset(name) => self.save_preset(&name, fx),
+            SlashCommand::Retry => fx.push(Effect::Dispatch(AppAction::RegenerateLast)),
+            SlashCommand::Dump(on) => {
+                self.dump_on_exit = on;
+                self.messages.push(ChatMessage::new("system", if on { "The conversation open when bpt exits will be printed to stdout as Markdown." } else { "Nothing will be printed on exit." }));
+            },
+            SlashCommand::Purge { all: true } => self.ask_confirm(ConfirmAction::PurgeAll),
+            SlashCommand::Purge { all: false } => match self.current_convo_id {
+                Some(id) => self.ask_confirm(ConfirmAction::PurgeConversation { id }),
+                None => { self.pending_project = None; self.pending_context.clear(); },
+            },
+        }
+    }
+
+    fn extract_todos(&mut self, file: Option<String>, fx: &mut Vec<Effect>) {
+        self.chat_auto_scroll = true;
+        if self.todo_note.is_some() { return self.messages.push(ChatMessage::new("system", "Already extracting action items.")); }
+        if !self.messages.iter().any(|m| m.role == "assistant" && !m.content.trim().is_empty()) {
+            return self.messages.push(ChatMessage::new("system", "Nothing to extract yet: ask something first."));
+        }
+        let title = self.current_convo_id.and_then(|id| self.conversations.iter().find(|c| c.id == id)).map(|c| c.title.clone())
+            .or_else(|| self.messages.iter().find(|m| m.role == "user").map(|m| m.content.lines().next().unwrap_or("").to_string()))
+            .unwrap_or_default();
+        fx.push(Effect::ExtractTodos {
+            prompt: todo::prompt(&self.messages),
+            title,
+            model: self.selected_model.clone(),
+            provider: self.selected_llm_provider.clone(),
+            path: file.map(|f| commands::resolve_path(self.current_project(), &f)),
+        });
+        self.messages.push(ChatMessage::new("system", "Extracting action items..."));
+        self.todo_note = Some(self.messages.len() - 1);
+    }
+
+    fn summarize(&mut self, save: bool, fx: &mut Vec<Effect>) {
+        self.chat_auto_scroll = true;
+        if self.summarizing { return self.messages.push(ChatMessage::new("system", "Already summarizing.")); }
+        let answered = self.messages.iter().any(|m| m.role == "assistant" && !m.content.trim().is_empty());
+        if !answered || (save && self.current_convo_id.is_none()) {
+            return self.messages.push(ChatMessage::new("system", "Nothing to summarize yet: ask something first."));
+        }
+        fx.push(Effect::Summarize {
+            id: self.current_convo_id,
+            save,
+            prompt: summary::prompt(&self.messages),
+            model: self.selected_model.clone(),
+            provider: self.selected_llm_provider.clone(),
+        });
+        self.messages.push(ChatMessage::new("system", "Summarizing the conversation..."));
+        self.summarizing = true;
+    }
+
+    /// The running extraction's chat note, unless the chat was replaced since.
+    fn todo_note_mut(&mut self) -> Option<&mut ChatMessage> {
+        self.todo_note.and_then(|i| self.messages.get_mut(i)).filter(|m| m.role == "system" && m.content.starts_with("Extracting action items"))
+    }
+
+    fn is_pending_delete(&self, id: i64) -> bool {
+        self.undo_buffer.iter().any(|e| matches!(e, UndoEntry::Conversation { id: cid, .. } if *cid == id))
+    }
+
+    /// Conversation deletes still held for undo; flushed to the backend on exit.
+    pub fn pending_conversation_deletes(&self) -> Vec<i64> {
+        self.undo_buffer.iter().filter_map(|e| match e { UndoEntry::Conversation { id, .. } => Some(*id), _ => None }).collect()
+    }
+
+    fn push_undo(&mut self, entry: UndoEntry, fx: &mut Vec<Effect>) {
+        self.undo_buffer.push_back(entry);
+        if self.undo_buffer.len() > UNDO_DEPTH {
+            if let Some(UndoEntry::Conversation { id, .. }) = self.undo_buffer.pop_front() { fx.push(Effect::DeleteConversation(id)); }
+        }
+    }
+
+    /// Runs the reducer and hands the resulting effects to the executor.
+    /// During replay effects are dropped, so nothing reaches the backends.
+    pub async fn update(&mut self, action: AppAction) {
+        if let Some(rec) = &mut self.recorder { rec.action(&action); }
+        let fx = self.reduce(action);
+        if self.replay != ReplayState::Off { return; }
+        for effect in fx {
+            effects::execute(effect, self.action_tx.clone());
+        }
+    }
+
+    /// Applies `action` to the state and returns the side effects it requires.
+    /// Never performs I/O itself, so every transition can be unit tested.
+    pub fn reduce(&mut self, action: AppAction) -> Vec<Effect> {
+        let logged = self.launcher_logs.total();
+        let mut fx = self.apply(action);
+        if let Some(path) = self.output_log.clone().filter(|_| self.launcher_logs.total() > logged) {
+            let lines = self.launcher_logs.iter().skip(self.launcher_logs.index_of(logged)).cloned().collect();
+            fx.push(Effect::TeeOutput { path, lines });
+        }
+        fx
+    }
+
+    fn apply(&mut self, action: AppAction) -> Vec<Effect> {
+        let mut fx = Vec::new();
+        match action {
+            AppAction::Tick => {
+                self.tick_count = self.tick_count.wrapping_add(1);
+                // A failing backend is retried on its backoff schedule instead of every period.
+                let tick = self.tick_count;
+                // A queued message keeps the retries going from any tab, polling or not.
+                let queued = self.queued_message().is_some();
+                if (queued || self.convo_poll_ticks > 0 && self.current_screen == CurrentScreen::Search) && !self.is_searching {
+                    let due = if self.convo_backoff.failing() { self.convo_backoff.take_retry(tick) } else { self.convo_poll_ticks > 0 && tick.is_multiple_of(self.convo_poll_ticks) };
+                    if due { fx.push(Effect::FetchConversations); }
+                }
+                if self.current_screen == CurrentScreen::Processes {
+                    let due = if self.process_backoff.failing() { self.process_backoff.take_retry(tick) } else { tick.is_multiple_of(PROCESS_POLL_TICKS) };
+                    if due { fx.push(Effect::FetchProcesses); }
+                }
+                if self.health_poll_ticks > 0 && self.current_screen == CurrentScreen::Launcher && tick.is_multiple_of(self.health_poll_ticks) { self.request_health(&mut fx); }
+                self.advance_sequence(&mut fx);
+                self.advance_auto_open(&mut fx);
+                if self.ask_at.is_some_and(|at| tick >= at) {
+                    self.ask_at = None;
+                    fx.push(Effect::Dispatch(AppAction::SubmitSearch));
+                }
+            },
+            AppAction::Quit => self.should_quit = true,
+            AppAction::ReplayFinished => {
+                self.replay = ReplayState::Finished;
+                self.launcher_logs.push("Replay finished (backend calls stay disabled).".into());
+            },
+            AppAction::SwitchTab => {
+                let screen = match self.current_screen {
+                    CurrentScreen::Launcher => CurrentScreen::Search,
+                    CurrentScreen::Search => CurrentScreen::Processes,
+                    CurrentScreen::Processes => CurrentScreen::Settings,
+                    CurrentScreen::Settings => CurrentScreen::Launcher,
+                };
+                self.enter_screen(screen, &mut fx);
+            },
+            AppAction::RestoreSession(session) => {
+                // Plugin panels aren't there yet at startup.
+                self.search_sidebar = if session.sidebar == SearchSidebarState::Plugins { SearchSidebarState::Hidden } else { session.sidebar };
+                if session.screen != self.current_screen { self.enter_screen(session.screen, &mut fx); }
+                if let Some(id) = session.convo_id {
+                    self.current_convo_id = Some(id);
+                    self.messages.push(ChatMessage::new("system", "Loading conversation..."));
+                    fx.push(Effect::LoadConversation(id));
+                }
+            },
+
+            // --- LAUNCHER LOGIC ---
+            AppAction::SelectNext => { if !self.filtered_apps.is_empty() { self.apps_idx = (self.apps_idx + 1) % self.filtered_apps.len(); } },
+            AppAction::SelectPrev => { if !self.filtered_apps.is_empty() { if self.apps_idx == 0 { self.apps_idx = self.filtered_apps.len() - 1; } else { self.apps_idx -= 1; } } },
+            AppAction::LoadApps => {
+                self.is_loading_apps = true;
+                fx.push(Effect::FetchApps);
+                fx.extend(self.sources.iter().map(|s| Effect::FetchSource(*s)));
+                fx.extend(self.plugins.iter().filter(|(_, m)| m.apps).map(|(name, _)| list_plugin_apps(name)));
+            },
+            AppAction::AppsLoaded(mut apps) => {
+                // Ad-hoc temp apps are an implementation detail, even if one was left behind.
+                apps.retain(|a| a.name != api::TEMP_APP_NAME && !managed_elsewhere(&a.id));
+                apps.extend(self.apps.iter().filter(|a| managed_elsewhere(&a.id)).cloned());
+                self.is_loading_apps = false;
+                self.set_apps(apps, &mut fx);
+            },
+            AppAction::SourceLoaded(source, listed) => {
+                let mut apps: Vec<AppModel> = self.apps.iter().filter(|a| Source::of(&a.id) != Some(source)).cloned().collect();
+                self.source_states.retain(|id, _| Source::of(id) != Some(source));
+                for (app, state) in listed {
+                    self.source_states.insert(app.id.clone(), state);
+                    apps.push(app);
+                }
+                self.set_apps(apps, &mut fx);
+            },
+            AppAction::ShowLogs => {
+                let Some((app, source)) = self.get_selected_app().and_then(|a| Some((a, Source::of(&a.id)?))) else { self.push_logs("Logs are only available for Docker containers and systemd units."); return fx };
+                let (cmd, name) = (source.command("logs", &app.id), app.name.clone());
+                self.launcher_logs.push(format!("Logs for '{}':", name));
+                let seq = self.record_launch(None, cmd.clone(), &mut fx);
+                fx.push(Effect::RunLocal { cmd, seq });
+            },
+            AppAction::RefreshHealth => {
+                if self.health_pending { return fx; }
+                self.request_health(&mut fx);
+                self.launcher_logs.push(if self.health_pending { "Checking app URLs..." } else { "No app has an http(s) URL to check." }.into());
+            },
+            AppAction::UrlHealth(results) => {
+                self.health_pending = false;
+                self.url_health = results.into_iter().collect();
+            },
+            AppAction::OpenTagBrowser => { if !self.tag_index.is_empty() { self.set_mode(InputMode::TagBrowser); } },
+            AppAction::TagNext => { if !self.tag_index.is_empty() { self.tag_idx = (self.tag_idx + 1) % self.tag_index.len(); } },
+            AppAction::TagPrev => { if !self.tag_index.is_empty() { self.tag_idx = self.tag_idx.checked_sub(1).unwrap_or(self.tag_index.len() - 1); } },
+            AppAction::ToggleTag => {
+                if let Some((tag, _)) = self.tag_index.get(self.tag_idx) {
+                    if !self.selected_tags.remove(tag) { self.selected_tags.insert(tag.clone()); }
+                    self.update_filter();
+                }
+            },
+            AppAction::ClearTags => { self.selected_tags.clear(); self.update_filter(); },
+            AppAction::ToggleFavorite => {
+                if let Some(app) = self.get_selected_app() {
+                    let id = app.id.clone();
+                    if !self.favorites.remove(&id) { self.favorites.insert(id.clone()); }
+                    self.update_filter();
+                    // Keep the cursor on the app that just moved.
+                    if let Some(pos) = self.filtered_apps.iter().position(|&i| self.apps[i].id == id) { self.apps_idx = pos; }
+                    fx.push(Effect::WriteStore { name: store::FAVORITES, data: serde_json::json!(self.favorites) });
+                }
+            },
+            AppAction::ToggleAutoOpen => {
+                let Some((id, name)) = self.get_selected_app().map(|a| (a.id.clone(), a.name.clone())) else { return fx };
+                let on = !self.auto_open.remove(&id);
+                if on { self.auto_open.insert(id); }
+                self.launcher_logs.push(format!("'{}' {} its URL after launching.", name, if on { "now opens" } else { "no longer opens" }));
+                fx.push(Effect::WriteStore { name: store::AUTO_OPEN, data: serde_json::json!(self.auto_open) });
+            },
+            AppAction::CycleTmuxMode => {
+                let Some((id, name)) = self.get_selected_app().filter(|a| !managed_elsewhere(&a.id)).map(|a| (a.id.clone(), a.name.clone())) else { return fx };
+                match TmuxMode::cycle(self.tmux_modes.get(&id).copied()) {
+                    Some(mode) => {
+                        let note = if mode != TmuxMode::Session && !self.in_tmux { " (a session, since bpt is not running inside tmux)" } else { "" };
+                        self.launcher_logs.push(format!("'{}' now launches in a tmux {}{}.", name, mode.label(), note));
+                        self.tmux_modes.insert(id, mode);
+                    },
+                    None => {
+                        self.launcher_logs.push(format!("'{}' launches normally again.", name));
+                        self.tmux_modes.remove(&id);
+                    },
+                }
+                fx.push(Effect::WriteStore { name: store::TMUX, data: serde_json::json!(self.tmux_modes) });
+            },
+            AppAction::ToggleFavoritesOnly => { self.favorites_only = !self.favorites_only; self.update_filter(); },
+            AppAction::CycleSort => { self.sort_mode = self.sort_mode.next(); self.update_filter(); },
+            AppAction::ToggleFilter => {
+                if self.input_mode == InputMode::Filtering { self.set_mode(InputMode::Normal); }
+                else if self.set_mode(InputMode::Filtering) { self.filter_input.clear(); self.update_filter(); }
+            },
+            AppAction::EnterFilterChar(c) => { self.filter_input.push(c); self.update_filter(); },
+            AppAction::BackspaceFilter => { textarea::pop_grapheme(&mut self.filter_input); self.update_filter(); },
+            
+            AppAction::OpenAddModal => { if self.set_mode(InputMode::Editing) { self.active_form = AppForm::default(); } },
+            AppAction::OpenEditModal if self.get_selected_app().is_some_and(|a| managed_elsewhere(&a.id)) => self.push_logs(SOURCE_READONLY),
+            AppAction::OpenEditModal => {
+                if let Some(app) = self.get_selected_app() {
+                    let form = AppForm::from_app(app);
+                    if self.set_mode(InputMode::Editing) { self.active_form = form; }
+                }
+            },
+            AppAction::Undo => {
+                let wanted: fn(&UndoEntry) -> bool = match self.current_screen {
+                    CurrentScreen::Launcher | CurrentScreen::Processes | CurrentScreen::Settings => |e| matches!(e, UndoEntry::App(_) | UndoEntry::Apps(_)),
+                    CurrentScreen::Search => |e| matches!(e, UndoEntry::Conversation { .. }),
+                };
+                match self.undo_buffer.iter().rposition(wanted).and_then(|i| self.undo_buffer.remove(i)) {
+                    Some(UndoEntry::App(model)) => {
+                        self.launcher_logs.push(format!("Restored '{}'.", model.name));
+                        fx.push(Effect::SaveApp(AppModel { id: String::new(), ..model }));
+                    },
+                    Some(UndoEntry::Apps(models)) => {
+                        self.launcher_logs.push(format!("Restored {} apps.", models.len()));
+                        fx.extend(models.into_iter().map(|model| Effect::SaveApp(AppModel { id: String::new(), ..model })));
+                    },
+                    Some(UndoEntry::Conversation { title, .. }) => {
+                        self.messages.push(ChatMessage::new("system", format!("Restored conversation '{}'.", title)));
+                        fx.push(Effect::FetchConversations);
+                    },
+                    None => {},
+                }
+            },
+            AppAction::CloseModal if self.chain_draft.is_some() => self.chain_draft = None,
+            AppAction::CloseModal if self.history_output.is_some() => self.history_output = None,
+            AppAction::CloseModal => { self.pending_confirm = None; self.preview = None; self.args_prompt = None; self.source_form = None; self.close_modal(); },
+            AppAction::CycleFormFocus => { self.active_form.focus_idx = (self.active_form.focus_idx + 1) % AppForm::FIELDS; },
+            AppAction::FormChar(c) => {
+                match self.active_form.focused_mut() { Some(field) => field.push(c), None => self.active_form.cmd.insert(c) }
+                self.revalidate_form();
+            },
+            AppAction::FormBackspace => {
+                match self.active_form.focused_mut() { Some(field) => textarea::pop_grapheme(field), None => self.active_form.cmd.backspace() }
+                self.revalidate_form();
+            },
+            AppAction::Paste(text) => self.paste(&text),
+            AppAction::FormNewline => { if self.active_form.focus_idx == AppForm::CMD { self.active_form.cmd.insert('\n'); } },
+            AppAction::FormCursor(m) => { if self.active_form.focus_idx == AppForm::CMD { self.active_form.cmd.move_cursor(m); } },
+            AppAction::SubmitForm => {
+                if self.input_mode != InputMode::Editing { return fx; }
+                let errors = self.active_form.validate();
+                if let Some(first) = errors.keys().next() {
+                    self.active_form.focus_idx = *first;
+                    self.active_form.errors = errors;
+                    return fx;
+                }
+                let model = self.active_form.to_model();
+                self.close_modal();
+                fx.push(Effect::SaveApp(model));
+            },
+            AppAction::RequestDelete if !self.marked.is_empty() => {
+                let ids: Vec<String> = self.marked_ids().into_iter().filter(|id| !managed_elsewhere(id)).collect();
+                if ids.is_empty() { self.push_logs(SOURCE_READONLY); } else { self.ask_confirm(ConfirmAction::DeleteApps(ids)); }
+            },
+            AppAction::RequestDelete if self.get_selected_app().is_some_and(|a| managed_elsewhere(&a.id)) => self.push_logs(SOURCE_READONLY),
+            AppAction::RequestDelete => {
+                if let Some(app) = self.get_selected_app() {
+                    let action = ConfirmAction::DeleteApp { id: app.id.clone(), name: app.name.clone() };
+                    self.ask_confirm(action);
+                }
+            },
+            AppAction::Confirm(yes) => {
+                if let Some(action) = self.pending_confirm.take() {
+                    self.close_modal();
+                    if yes {
+                        match action {
+                            ConfirmAction::DeleteApp { id, name } => {
+                                self.launcher_logs.push(format!("Deleted '{}'. Press 'u' to undo.", name));
+                                if let Some(model) = self.apps.iter().find(|a| a.id == id).cloned() { self.push_undo(UndoEntry::App(model), &mut fx); }
+                                fx.push(Effect::DeleteApp(id));
+                            },
+                            ConfirmAction::DeleteApps(ids) => {
+                                let models: Vec<AppModel> = ids.iter().filter_map(|id| self.apps.iter().find(|a| a.id == *id).cloned()).collect();
+                                self.launcher_logs.push(format!("Deleted {} apps. Press 'u' to undo.", models.len()));
+                                self.marked.clear();
+                                self.push_undo(UndoEntry::Apps(models), &mut fx);
+                                fx.extend(ids.into_iter().map(Effect::DeleteApp));
+                            },
+                            ConfirmAction::DeleteConversation { id, title } => {
+                                if self.current_convo_id == Some(id) { fx.push(Effect::Dispatch(AppAction::NewConversation)); }
+                                self.conversations.retain(|c| c.id != id);
+                                self.updated_convos.remove(&id);
+                                self.conversation_idx = 0;
+                                self.push_undo(UndoEntry::Conversation { id, title }, &mut fx);
+                            },
+                            ConfirmAction::DeleteSource { id, name } => {
+                                self.search_providers.retain(|p| p.id != id);
+                                if self.config.search_sources.remove(&name).is_some() { fx.push(self.persist_config()); }
+                                self.settings_idx = self.settings_idx.min(1 + self.search_providers.len());
+                                fx.push(Effect::DeleteSource(id));
+                            },
+                            ConfirmAction::PurgeConversation { id } => {
+                                self.project_bindings.remove(&id);
+                                self.updated_convos.remove(&id);
+                                self.pending_context.clear();
+                                fx.push(self.persist_projects());
+                                if self.summaries.remove(&id).is_some() { fx.push(self.persist_summaries()); }
+                                if self.branches.remove(&id).is_some() { fx.push(self.persist_branches()); }
+                                let pinned = self.highlights.len();
+                                self.highlights.retain(|h| h.convo_id != Some(id));
+                                if self.highlights.len() != pinned {
+                                    self.highlight_idx = self.highlight_idx.min(self.highlights.len().saturating_sub(1));
+                                    fx.push(self.persist_highlights());
+                                }
+                                self.messages.push(ChatMessage::new("system", "Local data for this conversation removed."));
+                            },
+                            ConfirmAction::PurgeAll => {
+                                self.favorites.clear();
+                                self.project_bindings.clear();
+                                self.summaries.clear();
+                                self.branches.clear();
+                                self.usage.clear();
+                                self.launch_history.clear();
+                                self.chains.clear();
+                                self.auto_open.clear();
+                                self.ssh_hosts.clear();
+                                self.tmux_modes.clear();
+                                self.model_prefs = ModelPrefs::default();
+                                self.query_history.clear();
+                                self.config = Config::default();
+                                self.apply_config();
+                                fx.push(Effect::SetBackends { launcher: self.config.launcher_url.clone(), search: self.config.search_url.clone() });
+                                self.highlights.clear();
+                                self.arg_defaults.clear();
+                                self.keymap.overrides.clear();
+                                self.pending_project = None;
+                                self.pending_context.clear();
+                                self.update_filter();
+                                fx.push(Effect::PurgeStore);
+                                self.notice("All local data removed.");
+                            },
+                            ConfirmAction::RunSnippet(cmd) => {
+                                self.enter_screen(CurrentScreen::Launcher, &mut fx);
+                                fx.push(Effect::Dispatch(AppAction::SubmitAdHoc(cmd)));
+                            },
+                        }
+                    }
+                }
+            },
+            
+            AppAction::LaunchSelected if !self.marked.is_empty() => {
+                let ids = self.marked_ids();
+                self.marked.clear();
+                self.start_sequence("Bulk launch".into(), ids, false, false, &mut fx);
+            },
+            AppAction::ToggleMark => {
+                if let Some(id) = self.get_selected_app().map(|a| a.id.clone()) {
+                    if !self.marked.remove(&id) { self.marked.insert(id); }
+                    if self.apps_idx + 1 < self.filtered_apps.len() { self.apps_idx += 1; }
+                }
+            },
+            AppAction::ClearMarks => self.marked.clear(),
+            AppAction::ExportApps => {
+                let ids = if self.marked.is_empty() { self.get_selected_app().map(|a| vec![a.id.clone()]).unwrap_or_default() } else { self.marked_ids() };
+                let apps: Vec<AppModel> = ids.iter().filter_map(|id| self.apps.iter().find(|a| a.id == *id).cloned()).collect();
+                if !apps.is_empty() { fx.push(Effect::ExportApps(apps)); }
+            },
+            AppAction::LaunchSelected => {
+                if let Some(app) = self.get_selected_app() {
+                    let (id, name) = (app.id.clone(), app.name.clone());
+                    self.launch(id, name, &mut fx);
+                }
+            },
+            AppAction::LaunchApp(id) => {
+                match self.apps.iter().find(|a| a.id == id).map(|a| a.name.clone()) {
+                    Some(name) => self.launch(id, name, &mut fx),
+                    None => self.launcher_logs.push(format!("No app with id {}.", id)),
+                }
+            },
+            
+            AppAction::SyncApps { prefer_local } => {
+                self.push_logs("Syncing local apps with the backend...");
+                fx.push(Effect::SyncApps { prefer_local });
+            },
+            AppAction::ArgsChar(c) => { if let Some(p) = self.args_prompt.as_mut() { if let Some(f) = p.fields.get_mut(p.focus) { f.1.push(c); } } },
+            AppAction::ArgsBackspace => { if let Some(p) = self.args_prompt.as_mut() { if let Some(f) = p.fields.get_mut(p.focus) { textarea::pop_grapheme(&mut f.1); } } },
+            AppAction::ArgsNext => { if let Some(p) = self.args_prompt.as_mut() { p.focus = (p.focus + 1) % p.fields.len().max(1); } },
+            AppAction::ArgsPrev => { if let Some(p) = self.args_prompt.as_mut() { p.focus = p.focus.checked_sub(1).unwrap_or(p.fields.len().saturating_sub(1)); } },
+            AppAction::SubmitArgs => {
+                let Some(prompt) = self.args_prompt.take() else { return fx };
+                self.close_modal();
+                let values: BTreeMap<String, String> = prompt.fields.into_iter().collect();
+                let Some(template) = self.apps.iter().find(|a| a.id == prompt.app_id).map(|a| a.command.clone()) else { return fx };
+                let cmd = template::fill(&template, &values);
+                self.arg_defaults.insert(prompt.app_id.clone(), values);
+                fx.push(Effect::WriteStore { name: store::ARGS, data: serde_json::json!(self.arg_defaults) });
+                let id = prompt.app_id.clone();
+                self.start_launch(prompt.app_id, prompt.name, Some(cmd), &mut fx);
+                if let Some(seq) = self.launch_history.last().map(|r| r.seq) { self.track_sequence_launch(&id, seq); }
+            },
+            AppAction::PreviewSelected => {
+                let Some(url) = self.get_selected_app().map(|a| a.url.trim().to_string()) else { return fx };
+                if !url.starts_with("http://") && !url.starts_with("https://") { self.push_logs("This app has no http(s) URL to preview."); return fx; }
+                self.open_preview(url, &mut fx);
+            },
+            AppAction::OpenSelectedUrl => {
+                let Some(url) = self.get_selected_app().map(|a| a.url.trim().to_string()) else { return fx };
+                if !url.starts_with("http://") && !url.starts_with("https://") { self.push_logs("This app has no http(s) URL to open."); return fx; }
+                self.launcher_logs.push(format!("Opening {} in the browser...", url));
+                fx.push(Effect::OpenExternal(url));
+            },
+            AppAction::PreviewLoaded { url, title, body } => {
+                // Ignore a late answer for a preview that was closed or replaced.
+                if let Some(p) = self.preview.as_mut().filter(|p| p.url == url) { p.title = title; p.body = body; }
+            },
+            AppAction::PreviewReload => {
+                if let Some(url) = self.preview.as_ref().map(|p| p.url.clone()) { self.open_preview(url, &mut fx); }
+            },
+            AppAction::PreviewScroll(delta) => {
+                self.preview_scroll = if delta < 0 { self.preview_scroll.saturating_sub(delta.unsigned_abs()) } else { self.preview_scroll.saturating_add(delta as u16) };
+            },
+            AppAction::StopSelected => {
+                if let Some(app) = self.get_selected_app() {
+                    let (id, name, remote) = (app.id.clone(), app.name.clone(), app.target.is_some());
+                    self.launcher_logs.push(format!("Stopping '{}'...", name));
+                    fx.push(if remote { Effect::StopRemote { id, name } } else { Effect::StopApp { id, name } });
+                }
+            },
+            AppAction::LaunchResult(msg) => self.push_logs(&msg),
+            AppAction::LaunchOutput { seq, line, stderr } => {
+                for l in line.lines() {
+                    self.record_output(seq, l, stderr);
+                    self.launcher_logs.push_from(l.to_string(), stderr);
+                }
+            },
+            AppAction::LaunchFinished { seq, ok, duration_ms, output } => {
+                self.running_launches.remove(&seq);
+                let mut lines = output.lines().filter(|l| !l.trim().is_empty());
+                let status = lines.next().unwrap_or(if ok { "Success" } else { "Failed" }).trim_end_matches(':').to_string();
+                // Backends without streaming return the whole output here.
+                for l in lines { self.record_output(seq, l, false); }
+                if let Some(rec) = self.launch_history.iter_mut().rev().find(|r| r.seq == seq) {
+                    rec.ok = Some(ok);
+                    rec.duration_ms = Some(duration_ms);
+                    rec.status = status.clone();
+                    fx.push(Effect::WriteStore { name: store::HISTORY, data: serde_json::json!(self.launch_history) });
+                }
+                self.push_logs(&output);
+                let name = self.launch_history.iter().rev().find(|r| r.seq == seq).map(|r| r.name.clone()).unwrap_or_default();
+                self.notify(CurrentScreen::Launcher, &format!("bpt: '{}' {}", name, if ok { "finished" } else { "failed" }), &status, &mut fx);
+                let event = if ok { Event::LaunchSuccess } else { Event::LaunchFailure };
+                if let Some(cmd) = self.config.hooks.command(event) {
+                    let rec = self.launch_history.iter().rev().find(|r| r.seq == seq);
+                    let payload = serde_json::json!({
+                        "event": event.name(),
+                        "app_id": rec.and_then(|r| r.app_id.clone()),
+                        "name": rec.map(|r| r.name.clone()),
+                        "ok": ok,
+                        "status": status,
+                        "duration_ms": duration_ms,
+                    });
+                    fx.push(Effect::RunHook { event: event.name(), cmd: cmd.to_string(), payload });
+                }
+                self.advance_sequence(&mut fx);
+                self.advance_auto_open(&mut fx);
+            },
+            AppAction::HealthChecked { seq, up } => {
+                if let Some(step) = self.sequence.as_mut().and_then(|r| r.step.as_mut()).filter(|s| s.seq == seq) {
+                    if let Some(w) = step.watch.as_mut() { w.answered(up); }
+                    if up {
+                        let msg = format!("'{}' is up.", step.name);
+                        self.launcher_logs.push(msg);
+                        self.advance_sequence(&mut fx);
+                    }
+                }
+                for (_, w) in self.opening.iter_mut().filter(|(_, w)| w.seq == seq) { w.answered(up); }
+                self.advance_auto_open(&mut fx);
+            },
+
+            AppAction::FocusOutput(on) => { self.set_mode(if on { InputMode::Output } else { InputMode::Normal }); },
+            AppAction::ScrollOutput(delta) => {
+                // Counted in shown lines, so hidden ones are skipped when filtering.
+                let shown = self.output_lines();
+                let end = self.output_end.unwrap_or(self.launcher_logs.total());
+                let at = shown.partition_point(|n| *n < end) as i64 + delta as i64;
+                // ui.rs keeps the view full when this points above the first screen.
+                self.output_end = shown.get(at.max(1) as usize - 1).filter(|_| at < shown.len() as i64).map(|n| n + 1);
+            },
+            AppAction::OutputTop => self.output_end = self.output_lines().first().map(|n| n + 1).filter(|e| *e < self.launcher_logs.total()),
+            AppAction::OutputBottom => self.output_end = None,
+            AppAction::ToggleErrorsOnly => {
+                self.output_errors_only = !self.output_errors_only;
+                self.output_end = None;
+                self.output_match = None;
+            },
+            AppAction::AskAboutOutput => {
+                let logs = &self.launcher_logs;
+                let shown = self.output_lines();
+                let end = shown.partition_point(|n| *n < self.output_end.unwrap_or(logs.total()));
+                let lines: Vec<String> = shown[end.saturating_sub(ASK_OUTPUT_LINES)..end].iter().filter_map(|n| logs.get(logs.index_of(*n))).map(|l| ansi::strip(l)).collect();
+                if lines.iter().all(|l| l.trim().is_empty()) { self.push_logs("The Output pane is empty; nothing to ask about."); return fx; }
+                let question = match self.launch_history.last() {
+                    Some(rec) if rec.ok == Some(false) => format!("Why did '{}' fail ({}), and how do I fix it?", rec.name, rec.status),
+                    _ => "Explain this output. Is anything wrong?".to_string(),
+                };
+                let label = if self.output_errors_only { "output errors" } else { "output" };
+                if self.input_mode == InputMode::Output { self.set_mode(InputMode::Normal); }
+                self.enter_screen(CurrentScreen::Search, &mut fx);
+                if self.current_screen != CurrentScreen::Search { return fx; }
+                fx.push(Effect::Dispatch(AppAction::ContextAttached { label: label.into(), content: lines.join("\n") }));
+                // Pre-filled, not sent: the question is the user's to edit.
+                if self.search_input.trim().is_empty() { self.search_input = question; self.query_pos = None; }
+            },
+            AppAction::ExportOutput => fx.push(Effect::ExportOutput(self.launcher_logs.iter().cloned().collect())),
+            AppAction::OutputSearchStart => { if self.input_mode == InputMode::Output { self.output_searching = true; self.output_query.clear(); self.output_match = None; } },
+            AppAction::OutputSearchChar(c) => { if self.output_searching { self.output_query.push(c); } },
+            AppAction::OutputSearchBackspace => { if self.output_searching { textarea::pop_grapheme(&mut self.output_query); } },
+            AppAction::OutputSearchDone(keep) => {
+                self.output_searching = false;
+                if !keep || self.output_query.is_empty() { self.output_query.clear(); self.output_match = None; return fx; }
+                let newest = self.output_matches().last().copied();
+                if newest.is_none() { self.launcher_logs.push(format!("No output matches '{}'.", self.output_query)); }
+                self.show_output_line(newest);
+            },
+            AppAction::OutputNextMatch(older) => {
+                let matches = self.output_matches();
+                let next = match self.output_match.filter(|m| matches.contains(m)) {
+                    None => matches.last(),
+                    Some(cur) if older => matches.iter().rev().find(|m| **m < cur).or(matches.last()),
+                    Some(cur) => matches.iter().find(|m| **m > cur).or(matches.first()),
+                };
+                self.show_output_line(next.copied());
+            },
+            AppAction::OpenChains => {
+                if self.set_mode(InputMode::Chains) {
+                    self.chain_draft = None;
+                    self.chain_idx = self.chain_idx.min(self.chains.len().saturating_sub(1));
+                }
+            },
+            AppAction::ChainNext => self.chain_select(true),
+            AppAction::ChainPrev => self.chain_select(false),
+            AppAction::RunChain => {
+                if self.chain_draft.is_some() { return fx; }
+                let Some(chain) = self.chains.get(self.chain_idx).cloned() else { return fx };
+                self.close_modal();
+                self.start_sequence(format!("Chain '{}'", chain.name), chain.app_ids, chain.health_check, true, &mut fx);
+            },
+            AppAction::NewChain => { if self.chain_draft.is_none() { self.chain_draft = Some(ChainDraft::default()); } },
+            AppAction::EditChain => {
+                if self.chain_draft.is_some() { return fx; }
+                if let Some(c) = self.chains.get(self.chain_idx) {
+                    self.chain_draft = Some(ChainDraft { index: Some(self.chain_idx), name: c.name.clone(), steps: c.app_ids.clone(), health_check: c.health_check, ..Default::default() });
+                }
+            },
+            AppAction::DeleteChain => {
+                if self.chain_draft.is_some() || self.chain_idx >= self.chains.len() { return fx; }
+                let chain = self.chains.remove(self.chain_idx);
+                self.chain_idx = self.chain_idx.min(self.chains.len().saturating_sub(1));
+                self.launcher_logs.push(format!("Deleted chain '{}'.", chain.name));
+                fx.push(self.persist_chains());
+            },
+            AppAction::ChainFocusNext => {
+                if let Some(d) = self.chain_draft.as_mut() {
+                    d.focus = match d.focus { ChainFocus::Name => ChainFocus::Apps, ChainFocus::Apps => ChainFocus::Steps, ChainFocus::Steps => ChainFocus::Name };
+                }
+            },
+            AppAction::ChainChar(c) => { if let Some(d) = self.chain_draft.as_mut().filter(|d| d.focus == ChainFocus::Name) { d.name.push(c); d.error = None; } },
+            AppAction::ChainBackspace => {
+                let Some(d) = self.chain_draft.as_mut() else { return fx };
+                match d.focus {
+                    ChainFocus::Name => textarea::pop_grapheme(&mut d.name),
+                    ChainFocus::Steps if d.step_idx < d.steps.len() => {
+                        d.steps.remove(d.step_idx);
+                        d.step_idx = d.step_idx.min(d.steps.len().saturating_sub(1));
+                    },
+                    _ => {},
+                }
+            },
+            AppAction::ChainAddStep => {
+                let Some(d) = self.chain_draft.as_mut().filter(|d| d.focus == ChainFocus::Apps) else { return fx };
+                let Some(app) = self.apps.get(d.app_idx) else { return fx };
+                if d.steps.contains(&app.id) { d.error = Some(format!("'{}' is already in the chain.", app.name)); return fx; }
+                d.steps.push(app.id.clone());
+                d.step_idx = d.steps.len() - 1;
+                d.error = None;
+            },
+            AppAction::ChainMoveStep(up) => {
+                let Some(d) = self.chain_draft.as_mut().filter(|d| d.focus == ChainFocus::Steps && d.step_idx < d.steps.len()) else { return fx };
+                let to = if up { d.step_idx.checked_sub(1) } else { Some(d.step_idx + 1).filter(|i| *i < d.steps.len()) };
+                if let Some(to) = to { d.steps.swap(d.step_idx, to); d.step_idx = to; }
+            },
+            AppAction::ChainToggleHealth => { if let Some(d) = self.chain_draft.as_mut() { d.health_check = !d.health_check; } },
+            AppAction::SaveChain => {
+                let Some(d) = self.chain_draft.as_mut() else { return fx };
+                let name = d.name.trim().to_string();
+                let taken = self.chains.iter().enumerate().any(|(i, c)| c.name == name && Some(i) != d.index);
+                d.error = if name.is_empty() { Some("Give the chain a name.".into()) }
+                    else if taken { Some(format!("There is already a chain called '{}'.", name)) }
+                    else if d.steps.is_empty() { Some("Add at least one app (Tab to the app list, Enter to add).".into()) }
+                    else { None };
+                if d.error.is_some() { return fx; }
+                let Some(d) = self.chain_draft.take() else { return fx };
+                let chain = Chain { name, app_ids: d.steps, health_check: d.health_check };
+                match d.index.filter(|i| *i < self.chains.len()) {
+                    Some(i) => { self.chains[i] = chain; self.chain_idx = i; },
+                    None => { self.chains.push(chain); self.chain_idx = self.chains.len() - 1; },
+                }
+                fx.push(self.persist_chains());
+            },
+            AppAction::LoadProcesses => fx.push(Effect::FetchProcesses),
+            AppAction::ProcessesFailed(e) => {
+                if self.process_backoff.fail(self.tick_count, PROCESS_POLL_TICKS) { self.push_logs(&format!("Error fetching processes: {} (retrying less often)", e)); }
+            },
+            AppAction::ProcessesLoaded(list) => {
+                if self.process_backoff.succeed() { self.push_logs("Process list reachable again."); }
+                let selected = self.selected_process().map(|p| p.id.clone());
+                self.processes_remote = list.is_some();
+                self.processes = list.unwrap_or_else(|| self.local_processes());
+                self.process_idx = selected.and_then(|id| self.processes.iter().position(|p| p.id == id)).unwrap_or(0);
+            },
+            AppAction::ProcessNext => { if !self.processes.is_empty() { self.process_idx = (self.process_idx + 1) % self.processes.len(); } },
+            AppAction::ProcessPrev => { if !self.processes.is_empty() { self.process_idx = self.process_idx.checked_sub(1).unwrap_or(self.processes.len() - 1); } },
+            AppAction::StopProcess => {
+                if let Some(p) = self.selected_process() {
+                    if self.processes_remote { fx.push(Effect::ProcessAction { id: p.id.clone(), action: "stop" }); }
+                    else if let Some(id) = &p.app_id { fx.push(Effect::StopApp { id: id.clone(), name: p.name.clone() }); }
+                }
+            },
+            AppAction::RestartProcess => {
+                if let Some(p) = self.selected_process().cloned() {
+                    if self.processes_remote { fx.push(Effect::ProcessAction { id: p.id, action: "restart" }); }
+                    // Locally tracked: launch the app again.
+                    else if let Some(id) = p.app_id { self.launch(id, p.name, &mut fx); }
+                }
+            },
+            AppAction::ShowProcessOutput => {
+                if let Some(p) = self.selected_process() {
+                    if self.processes_remote { fx.push(Effect::FetchProcessOutput(p.id.clone())); }
+                    else { self.process_output = self.launcher_logs.iter().cloned().collect(); }
+                }
+            },
+            AppAction::ProcessOutputLoaded(text) => self.process_output = text.lines().map(String::from).collect(),
+            AppAction::OpenPalette => {
+                if self.set_mode(InputMode::Palette) { self.palette_query.clear(); self.palette_idx = 0; self.palette_binding = false; }
+            },
+            AppAction::PaletteChar(c) => { self.palette_query.push(c); self.palette_idx = 0; },
+            AppAction::PaletteBackspace => { textarea::pop_grapheme(&mut self.palette_query); self.palette_idx = 0; },
+            AppAction::PaletteNext => { let n = self.palette_entries().len(); if n > 0 { self.palette_idx = (self.palette_idx + 1) % n; } },
+            AppAction::PalettePrev => { let n = self.palette_entries().len(); if n > 0 { self.palette_idx = self.palette_idx.checked_sub(1).unwrap_or(n - 1); } },
+            AppAction::PaletteRun => {
+                if let Some(entry) = self.palette_entries().get(self.palette_idx) {
+                    let action = match entry.id.strip_prefix(keymap::LAUNCH_PREFIX) {
+                        Some(app_id) => Some(AppAction::LaunchApp(app_id.to_string())),
+                        None => keymap::COMMANDS.iter().find(|c| c.id == entry.id).map(|c| (c.action)()),
+                    };
+                    self.close_modal();
+                    if let Some(action) = action { fx.push(Effect::Dispatch(action)); }
+                }
+            },
+            AppAction::PaletteStartBind => { if !self.palette_entries().is_empty() { self.palette_binding = true; } },
+            AppAction::PaletteBindKey(key) => {
+                self.palette_binding = false;
+                if let (Some(key), Some(entry)) = (key, self.palette_entries().get(self.palette_idx)) {
+                    self.keymap.bind(&entry.id, &key);
+                    fx.push(self.persist_keymap());
+                }
+            },
+            AppAction::PaletteUnbind => {
+                if let Some(entry) = self.palette_entries().get(self.palette_idx) {
+                    self.keymap.unbind(&entry.id);
+                    fx.push(self.persist_keymap());
+                }
+            },
+            AppAction::SettingsNext => { let n = self.settings_rows().len(); if n > 0 { self.settings_row = (self.settings_row + 1) % n; } self.settings_error = None; },
+            AppAction::SettingsPrev => { let n = self.settings_rows().len(); if n > 0 { self.settings_row = self.settings_row.checked_sub(1).unwrap_or(n - 1); } self.settings_error = None; },
+            AppAction::SettingsActivate => {
+                self.settings_error = None;
+                match self.settings_rows().into_iter().nth(self.settings_row) {
+                    Some(row) if row.setting.is_text() => self.settings_edit = Some(row.value),
+                    Some(SettingRow { setting: Setting::Binding(_), .. }) => self.settings_binding = true,
+                    Some(_) => self.change_setting(true, &mut fx),
+                    None => {},
+                }
+            },
+            AppAction::SettingsCycle(forward) => { if self.settings_edit.is_none() { self.change_setting(forward, &mut fx); } },
+            AppAction::SettingsChar(c) => { if let Some(v) = self.settings_edit.as_mut() { v.push(c); } },
+            AppAction::SettingsBackspace => { if let Some(v) = self.settings_edit.as_mut() { textarea::pop_grapheme(v); } },
+            AppAction::SettingsEditDone(keep) => {
+                let Some(value) = self.settings_edit.take() else { return fx };
+                if !keep { self.settings_error = None; return fx; }
+                let Some(setting) = self.selected_setting() else { return fx };
+                match self.commit_setting(setting, &value, &mut fx) {
+                    Ok(()) => self.settings_error = None,
+                    Err(e) => { self.settings_error = Some(e); self.settings_edit = Some(value); },
+                }
+            },
+            AppAction::SettingsBindKey(key) => {
+                self.settings_binding = false;
+                if let (Some(key), Some(Setting::Binding(id))) = (key, self.selected_setting()) {
+                    self.keymap.bind(id, &key);
+                    fx.push(self.persist_keymap());
+                }
+            },
+            AppAction::ToggleStarModel => {
+                if !self.models.iter().any(|m| m.id == self.selected_model) { return fx; }
+                let starred = self.model_prefs.starred.entry(self.selected_llm_provider.clone()).or_default();
+                if !starred.remove(&self.selected_model) { starred.insert(self.selected_model.clone()); }
+                fx.push(self.persist_model_prefs());
+            },
+            AppAction::OpenHistorySearch => {
+                if self.set_mode(InputMode::HistorySearch) {
+                    self.history_query.clear();
+                    self.convo_search_idx = 0;
+                    let missing: Vec<i64> = self.conversations.iter().map(|c| c.id).filter(|id| !self.convo_index.contains_key(id)).collect();
+                    if !missing.is_empty() && self.indexing == 0 {
+                        self.indexing = missing.len();
+                        fx.push(Effect::IndexConversations(missing));
+                    }
+                }
+            },
+            AppAction::HistoryChar(c) => { self.history_query.push(c); self.convo_search_idx = 0; },
+            AppAction::HistoryBackspace => { textarea::pop_grapheme(&mut self.history_query); self.convo_search_idx = 0; },
+            AppAction::ConvoSearchNext => { let n = self.history_hits().len(); if n > 0 { self.convo_search_idx = (self.convo_search_idx + 1) % n; } },
+            AppAction::ConvoSearchPrev => { let n = self.history_hits().len(); if n > 0 { self.convo_search_idx = self.convo_search_idx.checked_sub(1).unwrap_or(n - 1); } },
+            AppAction::HistoryOpen => {
+                let Some(hit) = self.history_hits().into_iter().nth(self.convo_search_idx) else { return fx };
+                self.close_modal();
+                // Reloaded even when already open, so message indices match the backend's.
+                self.pending_jump = hit.message.map(|i| (hit.convo_id, i));
+                fx.push(Effect::Dispatch(AppAction::LoadConversation(hit.convo_id)));
+            },
+            AppAction::ConversationIndexed { id, json } => {
+                self.indexing = self.indexing.saturating_sub(1);
+                self.convo_index.insert(id, convo_search::messages(&json));
+            },
+            AppAction::OpenPresets => {
+                if self.set_mode(InputMode::Presets) {
+                    self.preset_idx = self.active_preset().and_then(|a| self.config.presets.iter().position(|p| p.name == a.name)).map_or(0, |i| i + 1);
+                }
+            },
+            AppAction::PresetNext => { self.preset_idx = (self.preset_idx + 1) % (self.config.presets.len() + 1); },
+            AppAction::PresetPrev => { self.preset_idx = self.preset_idx.checked_sub(1).unwrap_or(self.config.presets.len()); },
+            AppAction::PresetSelect => {
+                let name = self.preset_idx.checked_sub(1).and_then(|i| self.config.presets.get(i)).map(|p| p.name.clone());
+                self.close_modal();
+                self.use_preset(name.as_deref(), &mut fx);
+            },
+            AppAction::PresetDelete => {
+                let Some(i) = self.preset_idx.checked_sub(1).filter(|i| *i < self.config.presets.len()) else { return fx };
+                let removed = self.config.presets.remove(i);
+                if self.config.preset.as_ref() == Some(&removed.name) { self.config.preset = None; }
+                self.preset_idx = self.preset_idx.min(self.config.presets.len());
+                fx.push(self.persist_config());
+            },
+            AppAction::SettingsUnbind => {
+                if let Some(Setting::Binding(id)) = self.selected_setting() {
+                    self.keymap.unbind(id);
+                    fx.push(self.persist_keymap());
+                }
+            },
+            AppAction::OpenLaunchHistory => { if self.set_mode(InputMode::LaunchHistory) { self.history_idx = 0; self.history_output = None; } },
+            AppAction::ShowLaunchOutput => { if self.selected_launch().is_some() { self.history_output = Some(0); } },
+            AppAction::ScrollLaunchOutput(delta) => {
+                let len = self.selected_launch().map_or(0, |r| r.output.len());
+                if let Some(top) = self.history_output.as_mut() { *top = (*top as i64 + delta as i64).clamp(0, len.saturating_sub(1) as i64) as u16; }
+            },
+            AppAction::HistoryNext => { if self.history_idx + 1 < self.launch_history.len() { self.history_idx += 1; } },
+            AppAction::HistoryPrev => { self.history_idx = self.history_idx.saturating_sub(1); },
+            
+            AppAction::OpenAdHocModal => { if self.set_mode(InputMode::AdHocCmd) { self.adhoc_input.clear(); } },
+            AppAction::AdHocChar(c) => self.adhoc_input.push(c),
+            AppAction::AdHocBackspace => { textarea::pop_grapheme(&mut self.adhoc_input); },
+            
+            AppAction::SubmitAdHoc(cmd) => {
+                self.close_modal();
+                self.launcher_logs.push(format!("Running ad-hoc: {}", cmd));
+                let seq = self.record_launch(None, cmd.clone(), &mut fx);
+                fx.push(Effect::RunAdHoc { cmd, seq });
+            },
+
+            // --- SEARCH LOGIC ---
+            AppAction::LoadSearchState => {
+                fx.push(Effect::FetchConversations);
+                fx.push(Effect::FetchProviders);
+                fx.push(self.fetch_models());
+            },
+            AppAction::ConversationsLoaded(convos) => {
+                if self.convo_backoff.succeed() { self.push_logs("Conversations reachable again."); }
+                self.merge_conversations(convos);
+                if let Some(i) = self.queued_message().filter(|_| !self.is_searching) { self.send_queued(i, &mut fx); }
+            },
+            AppAction::ConversationsFailed(e) => {
+                if self.convo_backoff.fail(self.tick_count, self.convo_poll_ticks) { self.push_logs(&format!("Error fetching conversations: {} (retrying less often)", e)); }
+            },
+            AppAction::ProvidersLoaded(provs) => {
+                self.search_providers = provs;
+                for p in &mut self.search_providers {
+                    if let Some(on) = self.config.search_sources.get(&p.name) { p.is_enabled = *on; }
+                }
+                if let Some(names) = self.active_preset().and_then(|p| p.search_sources.clone()) { self.enable_only_sources(&names); }
+            },
+            AppAction::ModelsLoaded(models) => { 
+                self.models = models;
+                let saved = self.config.model.as_ref().filter(|m| self.models.iter().any(|x| &x.id == *m));
+                if let Some(m) = saved { self.selected_model = m.clone(); }
+                else if let Some(first) = self.model_order().first() { self.selected_model = first.id.clone(); }
+                else { self.selected_model = "default".into(); }
+                if self.ask_at.take().is_some() { fx.push(Effect::Dispatch(AppAction::SubmitSearch)); }
+            },
+            AppAction::ToggleZen => {
+                if !self.input_mode.is_search() { return fx; }
+                self.zen = !self.zen;
+                if self.zen && matches!(self.input_mode, InputMode::SearchSidebar | InputMode::SourcesPane) { self.set_mode(InputMode::SearchInput); }
+            },
+            AppAction::ResizePane(delta) => {
+                let (pct, range) = match self.current_screen {
+                    CurrentScreen::Launcher => (&mut self.config.launcher_split, config::LAUNCHER_SPLIT),
+                    CurrentScreen::Search if self.search_sidebar != SearchSidebarState::Hidden && !self.zen => (&mut self.config.sidebar_split, config::SIDEBAR_SPLIT),
+                    _ => return fx,
+                };
+                let resized = config::resize(*pct, delta, range);
+                if resized == *pct { return fx; }
+                *pct = resized;
+                fx.push(self.persist_config());
+            },
+            AppAction::ToggleSearchSidebar => {
+                if !self.input_mode.is_search() { return fx; }
+                self.zen = false;
+                self.search_sidebar = match self.search_sidebar {
+                    SearchSidebarState::Hidden => SearchSidebarState::History,
+                    SearchSidebarState::History => SearchSidebarState::Highlights,
+                    SearchSidebarState::Highlights => SearchSidebarState::Settings,
+                    SearchSidebarState::Settings if !self.plugin_panels.is_empty() => SearchSidebarState::Plugins,
+                    SearchSidebarState::Settings | SearchSidebarState::Plugins => SearchSidebarState::Hidden,
+                };
+                if self.search_sidebar != SearchSidebarState::Hidden {
+                    self.set_mode(InputMode::SearchSidebar);
+                } else {
+                    self.set_mode(InputMode::SearchInput);
+                }
+            },
+            AppAction::CycleSearchFocus => {
+                if !self.input_mode.is_search() { return fx; }
+                let next = match self.input_mode {
+                    InputMode::SearchInput => {
+                         if self.search_sidebar != SearchSidebarState::Hidden && !self.zen {
+                             InputMode::SearchSidebar
+                         } else {
+                             InputMode::ChatHistory
+                         }
+                    },
+                    InputMode::SearchSidebar => InputMode::ChatHistory,
+                    InputMode::ChatHistory if self.sources_pane && !self.zen => InputMode::SourcesPane,
+                    _ => InputMode::SearchInput,
+                };
+                self.set_mode(next);
+            },
+            AppAction::SidebarNext => {
+                match self.search_sidebar {
+                    SearchSidebarState::History => {
+                        let max = self.conversations.len() + 1; 
+                        self.conversation_idx = (self.conversation_idx + 1) % max;
+                    },
+                    SearchSidebarState::Highlights if !self.highlights.is_empty() => { self.highlight_idx = (self.highlight_idx + 1) % self.highlights.len(); },
+                    SearchSidebarState::Settings => { self.settings_idx = (self.settings_idx + 1) % (2 + self.search_providers.len()); },
+                    SearchSidebarState::Plugins if !self.plugin_panels.is_empty() => { self.plugin_panel_idx = (self.plugin_panel_idx + 1) % self.plugin_panels.len(); },
+                    _ => {}
+                }
+            },
+            AppAction::SidebarPrev => {
+                match self.search_sidebar {
+                    SearchSidebarState::History => {
+                        let max = self.conversations.len() + 1;
+                        if self.conversation_idx == 0 { self.conversation_idx = max - 1; } else { self.conversation_idx -= 1; }
+                    },
+                    SearchSidebarState::Highlights if !self.highlights.is_empty() => { self.highlight_idx = self.highlight_idx.checked_sub(1).unwrap_or(self.highlights.len() - 1); },
+                    SearchSidebarState::Settings => { if self.settings_idx == 0 { self.settings_idx = (2 + self.search_providers.len()) - 1; } else { self.settings_idx -= 1; } },
+                    SearchSidebarState::Plugins if !self.plugin_panels.is_empty() => { self.plugin_panel_idx = self.plugin_panel_idx.checked_sub(1).unwrap_or(self.plugin_panels.len() - 1); },
+                    _ => {}
+                }
+            },
+            AppAction::SidebarSelect => {
+                match self.search_sidebar {
+                    SearchSidebarState::History => {
+                        if self.conversation_idx == 0 {
+                            fx.push(Effect::Dispatch(AppAction::NewConversation));
+                        } else if let Some(c) = self.conversations.get(self.conversation_idx - 1) {
+                            fx.push(Effect::Dispatch(AppAction::LoadConversation(c.id)));
+                        }
+                    },
+                    SearchSidebarState::Highlights => match self.selected_highlight().cloned() {
+                        Some(Highlight { convo_id: Some(id), .. }) => fx.push(Effect::Dispatch(AppAction::LoadConversation(id))),
+                        // Never saved: the pinned text is all there is.
+                        Some(h) => { self.messages.push(ChatMessage::new("assistant", h.content)); self.chat_auto_scroll = true; },
+                        None => {}
+                    },
+                    SearchSidebarState::Settings => {
+                        match self.settings_idx {
+                            0 => self.cycle_provider(true, &mut fx),
+                            1 => self.cycle_model(true, &mut fx),
+                            i => self.toggle_search_source(i - 2, &mut fx),
+                        }
+                    },
+                    // Asks the plugin to draw the panel again.
+                    SearchSidebarState::Plugins => {
+                        if let Some(p) = self.plugin_panels.get(self.plugin_panel_idx) { fx.push(render_panel(&p.plugin, &p.id)); }
+                    },
+                    _ => {}
+                }
+            },
+            AppAction::RequestDeleteConversation => {
+                if self.search_sidebar == SearchSidebarState::Settings {
+                    if let Some(p) = self.settings_idx.checked_sub(2).and_then(|i| self.search_providers.get(i)) {
+                        let action = ConfirmAction::DeleteSource { id: p.id, name: p.name.clone() };
+                        self.ask_confirm(action);
+                    }
+                } else if self.search_sidebar == SearchSidebarState::Highlights {
+                    let Some(i) = self.highlights.len().checked_sub(self.highlight_idx + 1) else { return fx };
+                    self.highlights.remove(i);
+                    self.highlight_idx = self.highlight_idx.min(self.highlights.len().saturating_sub(1));
+                    fx.push(self.persist_highlights());
+                } else if self.search_sidebar == SearchSidebarState::History {
+                    if let Some(c) = self.conversation_idx.checked_sub(1).and_then(|i| self.conversations.get(i)) {
+                        let action = ConfirmAction::DeleteConversation { id: c.id, title: c.title.clone() };
+                        self.ask_confirm(action);
+                    }
+                }
+            },
+            AppAction::OpenFileBrowser(pick) => self.open_file_browser(pick, &mut fx),
+            AppAction::FilesListed { dir, entries } => {
+                let Some(b) = self.file_browser.as_mut() else { return fx };
+                match entries {
+                    Ok(entries) => b.show(dir, entries),
+                    Err(e) => b.error = Some(format!("{}: {}", dir.display(), e)),
+                }
+            },
+            AppAction::FileBrowserMove(delta) => { if let Some(b) = self.file_browser.as_mut() { b.step(delta); } },
+            AppAction::FileBrowserChar(c) => { if let Some(b) = self.file_browser.as_mut() { b.push_filter(c); } },
+            AppAction::FileBrowserBackspace => {
+                // With no filter left, Backspace goes up a directory.
+                let Some(b) = self.file_browser.as_mut() else { return fx };
+                if !b.pop_filter() { if let Some(parent) = b.dir.parent() { fx.push(Effect::ListDir(parent.to_path_buf())); } }
+            },
+            AppAction::FileBrowserOpen => self.file_browser_open(&mut fx),
+            AppAction::FileBrowserUseDir => {
+                let Some(b) = self.file_browser.take_if(|b| b.pick == Pick::WorkingDir && !b.dir.as_os_str().is_empty()) else { return fx };
+                self.active_form.cwd = b.dir.display().to_string();
+                self.revalidate_form();
+            },
+            AppAction::CloseFileBrowser => self.file_browser = None,
+            AppAction::OpenSourceEditor { edit } => {
+                let form = if edit {
+                    let Some(p) = self.settings_idx.checked_sub(2).and_then(|i| self.search_providers.get(i)) else { return fx };
+                    SourceForm::from_provider(p)
+                } else {
+                    SourceForm::new_source()
+                };
+                if self.set_mode(InputMode::SourceEditor) { self.source_form = Some(form); }
+            },
+            AppAction::SourceFormNext => { if let Some(f) = self.source_form.as_mut() { f.focus_idx = (f.focus_idx + 1) % SourceForm::FIELDS; } },
+            AppAction::SourceFormChar(c) => {
+                let Some(f) = self.source_form.as_mut() else { return fx };
+                f.focused_mut().push(c);
+                if !f.errors.is_empty() { f.errors = f.validate(); }
+            },
+            AppAction::SourceFormBackspace => {
+                let Some(f) = self.source_form.as_mut() else { return fx };
+                textarea::pop_grapheme(f.focused_mut());
+                if !f.errors.is_empty() { f.errors = f.validate(); }
+            },
+            AppAction::SubmitSourceForm => {
+                let Some(f) = self.source_form.as_mut() else { return fx };
+                let errors = f.validate();
+                if let Some(first) = errors.keys().next() {
+                    f.focus_idx = *first;
+                    f.errors = errors;
+                    return fx;
+                }
+                let source = f.to_provider();
+                // A renamed source keeps its on/off choice.
+                if let Some(old) = self.search_providers.iter().find(|p| p.id == source.id && source.id != 0 && p.name != source.name) {
+                    if let Some(on) = self.config.search_sources.remove(&old.name) {
+                        self.config.search_sources.insert(source.name.clone(), on);
+                        fx.push(self.persist_config());
+                    }
+                }
+                self.source_form = None;
+                self.close_modal();
+                fx.push(Effect::SaveSource(source));
+            },
+            AppAction::NewConversation => {
+                self.current_convo_id = None;
+                self.pending_project = None;
+                self.pending_branch = None;
+                self.pending_context.clear();
+                self.messages.clear();
+                self.forget_message_views(0);
+                self.edit_from = None;
+                self.messages.push(ChatMessage::new("system", "New conversation started."));
+                self.chat_auto_scroll = true;
+                self.search_sidebar = SearchSidebarState::Hidden;
+                self.set_mode(InputMode::SearchInput);
+            },
+            AppAction::ConversationCreated(id) => {
+                self.current_convo_id = Some(id);
+                self.untitled = Some(id);
+                if let Some(dir) = self.pending_project.take() {
+                    self.project_bindings.insert(id, dir);
+                    fx.push(self.persist_projects());
+                }
+                if let Some(branch) = self.pending_branch.take() {
+                    self.branches.insert(id, branch);
+                    fx.push(self.persist_branches());
+                }
+                fx.push(Effect::FetchConversations);
+            },
+            AppAction::ConversationRenamed { id, title } => {
+                if self.untitled == Some(id) { self.untitled = None; }
+                if let Some(c) = self.conversations.iter_mut().find(|c| c.id == id) { c.title = title; }
+            },
+            AppAction::LoadConversation(id) => {
+                self.current_convo_id = Some(id);
+                self.pending_project = None;
+                self.pending_branch = None;
+                self.pending_context.clear();
+                self.updated_convos.remove(&id);
+                self.messages.clear();
+                self.forget_message_views(0);
+                self.edit_from = None;
+                self.messages.push(ChatMessage::new("system", "Loading conversation..."));
+                self.chat_auto_scroll = true;
+                self.set_mode(InputMode::ChatHistory); // Focus chat so user can see it loading
+                fx.push(Effect::LoadConversation(id));
+            },
+            AppAction::ConversationLoaded(json) => {
+                self.messages.clear();
+                if let Some(msgs) = json["messages"].as_array() {
+                    for m in msgs {
+                        let role = m["role"].as_str().unwrap_or("unknown").to_string();
+                        let content = m["content"].as_str().unwrap_or("").to_string();
+                        let sources: Vec<SearchSource> = if let Some(s_str) = m["sources"].as_str() { serde_json::from_str(s_str).unwrap_or_default() } else { vec![] };
+                        let time = ["created_at", "createdAt", "timestamp"].iter().find_map(|k| clock::parse(&m[*k]));
+                        let text = |k: &str| m[k].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+                        let usage = tokens::parse_usage(&m["usage"]);
+                        let reasoning = text("reasoning").or_else(|| text("thinking")).unwrap_or_default();
+                        let tools = ["tools", "tool_calls", "toolCalls"].iter().find_map(|k| m[*k].as_array()).map(|calls| calls.iter().map(ToolCall::from_event).collect()).unwrap_or_default();
+                        self.messages.push(ChatMessage { role, content, sources, time, provider: text("provider"), model: text("model"), usage, reasoning, tools, ..Default::default() });
+                    }
+                }
+                if let Some(branch) = self.current_convo_id.and_then(|id| self.branches.get(&id)) {
+                    let mut seeded: Vec<ChatMessage> = branch.seed.iter().map(|(role, text)| ChatMessage::new(role, text.clone())).collect();
+                    seeded.push(ChatMessage::new("system", format!("Branched from \"{}\"; the history above is replayed with each query.", branch.from_title)));
+                    self.messages.splice(0..0, seeded);
+                }
+                self.chat_auto_scroll = true;
+                if let Some(id) = self.current_convo_id { self.convo_index.insert(id, convo_search::messages(&json)); }
+                if let Some((id, i)) = self.pending_jump.take().filter(|(id, _)| self.current_convo_id == Some(*id)) {
+                    let seeded = self.branches.get(&id).map_or(0, |b| b.seed.len() + 1);
+                    self.chat_focus = Some(seeded + i).filter(|i| *i < self.messages.len());
+                    self.chat_focus_moved = self.chat_focus.is_some();
+                    self.chat_auto_scroll = self.chat_focus.is_none();
+                }
+            },
+            AppAction::ScrollChat(delta) => {
+                self.chat_auto_scroll = false;
+                if delta < 0 {
+                    self.chat_scroll = self.chat_scroll.saturating_sub(delta.unsigned_abs());
+                } else {
+                    self.chat_scroll = self.chat_scroll.saturating_add(delta as u16);
+                }
+            },
+            AppAction::ChatTop => { self.chat_auto_scroll = false; self.chat_scroll = 0; },
+            AppAction::ChatBottom => self.chat_auto_scroll = true,
+            AppAction::ChatVisualStart => {
+                let top = (self.chat_scroll as usize).min(self.chat_lines.len().saturating_sub(1));
+                self.chat_visual = Some((top, top));
+                self.chat_auto_scroll = false;
+            },
+            AppAction::ChatVisualMove(delta) => {
+                let last = self.chat_lines.len().saturating_sub(1);
+                if let Some((_, cursor)) = self.chat_visual.as_mut() { *cursor = cursor.saturating_add_signed(delta as isize).min(last); }
+            },
+            AppAction::ChatYank => {
+                if let Some((anchor, cursor)) = self.chat_visual.take() {
+                    let lines = self.chat_lines.get(anchor.min(cursor)..=anchor.max(cursor)).unwrap_or_default();
+                    fx.push(Effect::CopyToClipboard(lines.join("\n")));
+                }
+            },
+            AppAction::ChatVisualCancel => self.chat_visual = None,
+            AppAction::ToggleFold => {
+                if let Some(i) = self.chat_focus.or(self.messages.len().checked_sub(1)) {
+                    let folded = self.is_folded(i);
+                    self.message_folds.insert(i, !folded);
+                }
+            },
+            AppAction::BranchFromMessage => self.branch_from_message(),
+            AppAction::ToggleExcluded => {
+                let turn = |m: &ChatMessage| matches!(m.role.as_str(), "user" | "assistant");
+                let target = match self.chat_focus { Some(i) => Some(i), None => self.messages.iter().rposition(turn) };
+                match target.and_then(|i| self.messages.get_mut(i)).filter(|m| turn(m)) {
+                    Some(m) => m.excluded = !m.excluded,
+                    None => self.notice("Only questions and answers can be left out of the context."),
+                }
+            },
+            AppAction::ToggleSources => {
+                let sourced = self.chat_focus.or_else(|| self.messages.iter().rposition(|m| !m.sources.is_empty()));
+                if let Some(i) = sourced { if !self.sources_open.remove(&i) { self.sources_open.insert(i); } }
+            },
+            AppAction::ToggleSourcesPane => {
+                if !self.input_mode.is_search() { return fx; }
+                self.zen = false;
+                self.sources_pane = !self.sources_pane;
+                if self.sources_pane { self.set_mode(InputMode::SourcesPane); }
+                else if self.input_mode == InputMode::SourcesPane { self.set_mode(InputMode::ChatHistory); }
+            },
+            AppAction::SourcesPaneNext => {
+                let n = self.pane_sources().len();
+                if n > 0 { self.sources_pane_idx = (self.sources_pane_idx + 1) % n; }
+            },
+            AppAction::SourcesPanePrev => {
+                let n = self.pane_sources().len();
+                if n > 0 { self.sources_pane_idx = (self.sources_pane_idx + n - 1) % n; }
+            },
+            AppAction::CycleSourceEngine => {
+                let engines = self.source_engines();
+                self.sources_engine = match &self.sources_engine {
+                    None => engines.first().cloned(),
+                    Some(e) => engines.iter().position(|x| x == e).and_then(|i| engines.get(i + 1)).cloned(),
+                };
+                self.sources_pane_idx = 0;
+            },
+            AppAction::CycleSourceSort => { self.sources_sort = self.sources_sort.next(); self.sources_pane_idx = 0; },
+            AppAction::OpenPaneSource => { if let Some(url) = self.selected_pane_url() { fx.push(Effect::OpenExternal(url)); } },
+            AppAction::CopyPaneSourceUrl => { if let Some(url) = self.selected_pane_url() { fx.push(Effect::CopyToClipboard(url)); } },
+            AppAction::PreviewPaneSource => { if let Some(url) = self.selected_pane_url() { self.open_preview(url, &mut fx); } },
+            AppAction::ToggleReasoning => {
+                let reasoned = self.chat_focus.or_else(|| self.messages.iter().rposition(|m| !m.reasoning.is_empty()));
+                if let Some(i) = reasoned { if !self.reasoning_open.remove(&i) { self.reasoning_open.insert(i); } }
+            },
+            AppAction::EnterSearchChar(c) => { self.search_input.push(c); self.query_pos = None; },
+            AppAction::DeleteSearchChar => { textarea::pop_grapheme(&mut self.search_input); self.query_pos = None; },
+            AppAction::QueryPrev => {
+                let pos = match self.query_pos {
+                    _ if self.query_history.is_empty() => return fx,
+                    None => { self.query_draft = self.search_input.clone(); self.query_history.len() - 1 },
+                    Some(i) => i.saturating_sub(1),
+                };
+                self.query_pos = Some(pos);
+                self.search_input = self.query_history[pos].clone();
+            },
+            AppAction::QueryNext => {
+                match self.query_pos {
+                    Some(i) if i + 1 < self.query_history.len() => { self.query_pos = Some(i + 1); self.search_input = self.query_history[i + 1].clone(); },
+                    Some(_) => { self.query_pos = None; self.search_input = std::mem::take(&mut self.query_draft); },
+                    None => {},
+                }
+            },
+            AppAction::QuerySearchStart => {
+                match &self.query_search {
+                    Some(h) => { let before = h.hit; self.find_query(before); },
+                    None => { self.query_search = Some(QuerySearch::default()); self.find_query(None); },
+                }
+            },
+            AppAction::QuerySearchChar(c) => { if let Some(h) = self.query_search.as_mut() { h.query.push(c); self.find_query(None); } },
+            AppAction::QuerySearchBackspace => { if let Some(h) = self.query_search.as_mut() { textarea::pop_grapheme(&mut h.query); self.find_query(None); } },
+            AppAction::QuerySearchDone(accept) => {
+                if let Some(QuerySearch { hit: Some(i), .. }) = self.query_search.take() {
+                    if accept { self.search_input = self.query_history[i].clone(); self.query_pos = None; }
+                }
+            },
+            AppAction::SubmitSearch => {
+                if let Some(parsed) = commands::parse(&self.search_input) {
+                    let input = self.search_input.clone();
+                    self.remember_query();
+                    self.chat_auto_scroll = true;
+                    match parsed {
+                        Ok(cmd) => self.run_command(cmd, &mut fx),
+                        // Built-in commands win; other names may belong to a plugin.
+                        Err(e) => match self.plugin_command(&input) {
+                            Some(call) => fx.push(call),
+                            None => self.messages.push(ChatMessage::new("system", e)),
+                        },
+                    }
+                    return fx;
+                }
+                // `= expr`: answered locally, no search round-trip.
+                if let Some(expr) = self.search_input.trim().strip_prefix('=') {
+                    let answer = match calc::eval(expr) { Ok(v) => format!("= **{}**", v), Err(e) => format!("Can't calculate that: {}", e) };
+                    self.messages.push(ChatMessage::new("user", self.search_input.trim().to_string()));
+                    self.messages.push(ChatMessage::new("system", answer));
+                    self.remember_query();
+                    self.chat_auto_scroll = true;
+                    return fx;
+                }
+                // One message waits at a time, unless it is about to be replaced (`c`).
+                let waiting = self.queued_message().is_some_and(|i| self.edit_from.is_none_or(|from| i < from));
+                if waiting && !self.search_input.trim().is_empty() {
+                    self.messages.push(ChatMessage::new("system", "A message is already queued; send this one once it has gone out."));
+                    self.chat_auto_scroll = true;
+                    return fx;
+                }
+                if !self.search_input.trim().is_empty() && !self.is_searching {
+                    let title = commands::unescape(self.search_input.trim()).to_string();
+                    self.ref_cursor = 0;
+                    let mut context: Vec<ContextItem> = self.pending_context.drain(..).collect();
+                    // `@app` mentions bring the app's details along.
+                    for app in mentions::mentioned(&self.apps, &title) {
+                        let label = format!("@{}", mentions::slug(&app.name));
+                        if !context.iter().any(|c| c.label == label) { context.push(ContextItem { label, content: mentions::context(app) }); }
+                    }
+                    let content = if context.is_empty() { title.clone() } else { format!("{}{}", title, CONTEXT_NOTE) };
+                    let message = ChatMessage { context, time: Some(store::now_secs()), ..ChatMessage::new("user", content) };
+                    let query = message.query();
+                    if let Some(from) = self.edit_from.take() { self.messages.truncate(from); self.forget_message_views(from); }
+                    self.chat_focus = None;
+                    self.messages.push(message);
+                    self.remember_query();
+                    self.start_search(query, title, &mut fx);
+                }
+            },
+            AppAction::RegenerateLast => self.regenerate_last(&mut fx),
+            AppAction::FocusMessage { forward, mine } => self.focus_message(forward, mine),
+            AppAction::FocusMessageEdge(last) => {
+                self.chat_focus = if last { self.messages.len().checked_sub(1) } else { (!self.messages.is_empty()).then_some(0) };
+                self.chat_focus_moved = self.chat_focus.is_some();
+                self.chat_auto_scroll = false;
+            },
+            AppAction::ClearMessageFocus => { self.chat_focus = None; self.chat_auto_scroll = true; },
+            AppAction::EditMessage { cut } => self.edit_message(cut),
+            AppAction::SearchSourcesReceived(sources) => { if let Some(last) = self.messages.last_mut() { if last.role == "assistant" { last.sources = sources; } } },
+            AppAction::SearchStreamToken(text) => {
+                if let Some(last) = self.messages.last_mut() { if last.role == "assistant" { last.content.push_str(&text); } }
+                if let Some((_, first @ None)) = self.stream_ticks.as_mut() { *first = Some(self.tick_count); }
+            },
+            AppAction::ToolCallStarted(call) => { if let Some(last) = self.messages.last_mut() { if last.role == "assistant" { last.tools.push(call); } } },
+            AppAction::ToolCallFinished(done) => {
+                let Some(last) = self.messages.last_mut().filter(|m| m.role == "assistant") else { return fx };
+                let running = |c: &ToolCall| c.result.is_none();
+                let at = done.id.as_ref().and_then(|id| last.tools.iter().position(|c| c.id.as_ref() == Some(id)))
+                    .or_else(|| last.tools.iter().rposition(|c| running(c) && c.name == done.name))
+                    .or_else(|| last.tools.iter().rposition(running));
+                match at {
+                    Some(i) => last.tools[i].result = Some(done.result.unwrap_or_default()),
+                    None => last.tools.push(ToolCall { result: Some(done.result.unwrap_or_default()), ..done }),
+                }
+            },
+            AppAction::SearchReasoningToken(text) => { if let Some(last) = self.messages.last_mut() { if last.role == "assistant" { last.reasoning.push_str(&text); } } },
+            AppAction::SearchUsage(usage) => { if let Some(last) = self.messages.iter_mut().rev().find(|m| m.role == "assistant") { last.usage = Some(usage); } },
+            AppAction::SearchError(err) => {
+                if let Some(sink) = self.a11y_out.clone() { fx.push(Effect::Speak { sink, text: format!("Error: {}", err) }); }
+                self.messages.push(ChatMessage::new("system", format!("Error: {}", err))); self.is_searching = false; self.stream_ticks = None;
+            },
+            AppAction::SearchOffline(err) => {
+                self.is_searching = false;
+                self.stream_ticks = None;
+                if self.messages.last().is_some_and(|m| m.role == "assistant" && m.content.is_empty()) { self.messages.pop(); }
+                match self.messages.iter().rposition(|m| m.role == "user") {
+                    Some(i) => self.messages[i].queued = true,
+                    None => return fx,
+                }
+                let base = if self.convo_poll_ticks > 0 { self.convo_poll_ticks } else { OFFLINE_RETRY_TICKS };
+                if self.convo_backoff.fail(self.tick_count, base) { self.push_logs(&format!("Search backend unreachable: {}", err)); }
+                self.messages.push(ChatMessage::new("system", "The backend can't be reached. Your message is queued and will be sent when it is back."));
+                self.chat_auto_scroll = true;
+            },
+            AppAction::SearchDone => {
+                self.is_searching = false;
+                let question = self.messages.iter().rev().find(|m| m.role == "user").map(|m| m.question().lines().next().unwrap_or_default().to_string());
+                self.notify(CurrentScreen::Search, "bpt: answer ready", question.as_deref().unwrap_or(""), &mut fx);
+                if let Some(sink) = self.a11y_out.clone() {
+                    let answer = self.messages.last().filter(|m| m.role == "assistant").map(|m| a11y::spoken(&m.content)).filter(|t| !t.is_empty());
+                    if let Some(text) = answer { fx.push(Effect::Speak { sink, text }); }
+                }
+                if let Some(cmd) = self.config.hooks.command(Event::SearchDone) {
+                    let duration_ms = self.stream_ticks.map(|(asked, _)| self.tick_count.saturating_sub(asked) * config::TICK_MS);
+                    let payload = self.search_done_payload(duration_ms);
+                    fx.push(Effect::RunHook { event: Event::SearchDone.name(), cmd: cmd.to_string(), payload });
+                }
+                self.stream_ticks = None;
+                if let Some(id) = self.current_convo_id { self.convo_index.remove(&id); }
+                let answered = self.messages.last().is_some_and(|m| m.role == "assistant" && m.follow_ups.is_empty());
+                if self.config.follow_ups && answered {
+                    if let Some(prompt) = followup::prompt(&self.messages) {
+                        fx.push(Effect::SuggestFollowUps { prompt, model: self.selected_model.clone(), provider: self.selected_llm_provider.clone() });
+                    }
+                }
+                // A new conversation is renamed once, after its first answer.
+                let fresh = self.untitled.take().filter(|id| self.config.auto_titles && self.current_convo_id == Some(*id));
+                if let Some((id, prompt)) = fresh.zip(title::prompt(&self.messages)) {
+                    fx.push(Effect::NameConversation { id, prompt, model: self.selected_model.clone(), provider: self.selected_llm_provider.clone() });
+                }
+            },
+            AppAction::FollowUpsReceived(questions) => {
+                // Only for the latest answer, and only once.
+                let Some(last) = self.messages.iter_mut().rev().find(|m| m.role == "assistant") else { return fx };
+                if last.follow_ups.is_empty() { last.follow_ups = questions.into_iter().take(followup::MAX).collect(); }
+            },
+            AppAction::UseFollowUp { n, send } => {
+                let pick = self.messages.iter().rev().find(|m| m.role == "assistant").and_then(|m| m.follow_ups.get(n.wrapping_sub(1))).cloned();
+                let Some(question) = pick else { return fx };
+                if self.is_searching && send { return fx; }
+                self.search_input = question;
+                self.query_pos = None;
+                self.set_mode(InputMode::SearchInput);
+                if send { fx.push(Effect::Dispatch(AppAction::SubmitSearch)); }
+            },
+            AppAction::AttachmentReceived(att) => fx.push(Effect::SaveAttachment(att)),
+            AppAction::AttachmentSaved(path) => {
+                match self.messages.iter_mut().rev().find(|m| m.role == "assistant") {
+                    Some(msg) => msg.attachments.push(path),
+                    None => self.messages.push(ChatMessage { attachments: vec![path], ..ChatMessage::new("system", "Attachment saved.") }),
+                }
+            },
+            AppAction::ExtractTodos(file) => self.extract_todos(file, &mut fx),
+            AppAction::SummaryDone { id, save, result } => {
+                self.summarizing = false;
+                // A summary of a conversation no longer on screen is only kept if saved.
+                let here = self.current_convo_id == id;
+                match (result, id) {
+                    (Ok(text), Some(id)) if save => {
+                        self.summaries.insert(id, text.clone());
+                        fx.push(self.persist_summaries());
+                        if here { self.messages.push(ChatMessage::new("system", format!("Saved as this conversation's description in History:\n\n{}", text))); }
+                    },
+                    (Ok(text), _) if here => self.messages.push(ChatMessage::new("system", format!("Summary:\n\n{}", text))),
+                    (Err(e), _) if here => self.messages.push(ChatMessage::new("system", format!("Summary failed: {}", e))),
+                    _ => {},
+                }
+                self.chat_auto_scroll = true;
+            },
+            AppAction::TodoItem(item) => { if let Some(note) = self.todo_note_mut() { note.content.push('\n'); note.content.push_str(&item); } },
+            AppAction::TodosDone { path, result } => {
+                let items: Vec<String> = self.todo_note_mut().map(|n| n.content.lines().skip(1).map(str::to_string).collect()).unwrap_or_default();
+                let head = match &result {
+                    Ok(0) => "No action items found.".to_string(),
+                    Ok(n) => format!("Added {} action item{} to `{}`:", n, if *n == 1 { "" } else { "s" }, path),
+                    Err(e) => format!("Action item extraction failed: {}", e),
+                };
+                let content = std::iter::once(head).chain(items).collect::<Vec<_>>().join("\n");
+                match self.todo_note_mut() {
+                    Some(note) => note.content = content,
+                    None => self.messages.push(ChatMessage::new("system", content)),
+                }
+                self.todo_note = None;
+                self.chat_auto_scroll = true;
+            },
+            AppAction::ChatNotice(text) => {
+                // Ahead of an answer still streaming, which takes tokens only while it is last.
+                let at = if self.is_searching { self.messages.len().saturating_sub(1) } else { self.messages.len() };
+                self.messages.insert(at, ChatMessage::new("system", text));
+                self.chat_auto_scroll = true;
+            },
+            AppAction::ProjectBound(dir) => {
+                self.messages.push(ChatMessage::new("system", format!("Conversation bound to `{}`.", dir)));
+                match self.current_convo_id {
+                    Some(id) => { self.project_bindings.insert(id, dir); fx.push(self.persist_projects()); },
+                    None => self.pending_project = Some(dir),
+                }
+            },
+            AppAction::ContextAttached { label, content } => {
+                self.messages.push(ChatMessage::new("system", format!("Attached `{}` ({} lines) to the next message.", label, content.lines().count())));
+                self.pending_context.push(ContextItem { label, content });
+            },
+            AppAction::TerminalFocus(focused) => self.terminal_focused = focused,
+            AppAction::AskOnStart { query, input } => {
+                self.enter_screen(CurrentScreen::Search, &mut fx);
+                if let Some(content) = input {
+                    self.messages.push(ChatMessage::new("system", format!("Attached `stdin` ({} lines) to the next message.", content.lines().count())));
+                    self.pending_context.push(ContextItem { label: "stdin".into(), content });
+                }
+                // The model list is still loading: asked once it arrives, or after a few seconds without.
+                if let Some(query) = query {
+                    self.search_input = query;
+                    self.ask_at = Some(self.tick_count + ASK_WAIT_TICKS);
+                }
+                self.chat_auto_scroll = true;
+            },
+            AppAction::StartPlugins => {
+                // Running plugins are looked up by name, so a second one would take over the first.
+                let mut names = BTreeSet::new();
+                for p in &self.config.plugins {
+                    if p.name.trim().is_empty() || p.name.contains(':') || p.command.trim().is_empty() {
+                        self.launcher_logs.push(format!("Skipping plugin '{}': it needs a name (without ':') and a command.", p.name));
+                    } else if !names.insert(p.name.as_str()) {
+                        self.launcher_logs.push(format!("Skipping plugin '{}': another plugin already has that name.", p.name));
+                    } else {
+                        fx.push(Effect::StartPlugin(p.clone()));
+                    }
+                }
+            },
+            AppAction::PluginReady { plugin, manifest } => {
+                let mut provides = vec![];
+                if !manifest.commands.is_empty() { provides.push(manifest.commands.iter().map(|c| format!("/{}", c.name)).collect::<Vec<_>>().join(" ")); }
+                if !manifest.panels.is_empty() { provides.push(format!("{} panel(s) in the Search sidebar", manifest.panels.len())); }
+                if manifest.apps { provides.push("apps".into()); fx.push(list_plugin_apps(&plugin)); }
+                self.launcher_logs.push(format!("Plugin '{}' ready: {}.", plugin, if provides.is_empty() { "nothing to add".into() } else { provides.join(", ") }));
+                self.plugin_panels.retain(|p| p.plugin != plugin);
+                for spec in &manifest.panels {
+                    fx.push(render_panel(&plugin, &spec.id));
+                    self.plugin_panels.push(Panel { plugin: plugin.clone(), id: spec.id.clone(), title: spec.title.clone(), lines: vec![] });
+                }
+                self.plugins.insert(plugin, manifest);
+            },
+            AppAction::PluginExited { plugin, error } => {
+                self.plugins.remove(&plugin);
+                self.plugin_panels.retain(|p| p.plugin != plugin);
+                self.plugin_panel_idx = self.plugin_panel_idx.min(self.plugin_panels.len().saturating_sub(1));
+                if self.plugin_panels.is_empty() && self.search_sidebar == SearchSidebarState::Plugins {
+                    self.search_sidebar = SearchSidebarState::Hidden;
+                    if self.input_mode == InputMode::SearchSidebar { self.set_mode(InputMode::SearchInput); }
+                }
+                let apps: Vec<AppModel> = self.apps.iter().filter(|a| plugins::owner(&a.id).is_none_or(|(p, _)| p != plugin)).cloned().collect();
+                if apps.len() != self.apps.len() { self.set_apps(apps, &mut fx); }
+                self.launcher_logs.push(if error.is_empty() { format!("Plugin '{}' exited.", plugin) } else { format!("Plugin '{}' exited: {}", plugin, error) });
+            },
+            AppAction::PluginMessage { plugin, text } => {
+                self.messages.push(ChatMessage::new("system", format!("**{}**\n\n{}", plugin, text)));
+                self.chat_auto_scroll = true;
+            },
+            AppAction::PluginApps { plugin, apps: listed } => {
+                let mine = |id: &str| plugins::owner(id).is_some_and(|(p, _)| p == plugin);
+                let mut apps: Vec<AppModel> = self.apps.iter().filter(|a| !mine(&a.id)).cloned().collect();
+                self.source_states.retain(|id, _| !mine(id));
+                for (app, state) in listed {
+                    if !state.is_empty() { self.source_states.insert(app.id.clone(), state); }
+                    apps.push(app);
+                }
+                self.set_apps(apps, &mut fx);
+            },
+            AppAction::PluginAppsChanged(plugin) => {
+                if self.plugins.get(&plugin).is_some_and(|m| m.apps) { fx.push(list_plugin_apps(&plugin)); }
+            },
+            AppAction::PluginPanel { plugin, panel, lines } => {
+                if let Some(p) = self.plugin_panels.iter_mut().find(|p| p.plugin == plugin && p.id == panel) { p.lines = lines; }
+            },
+            AppAction::OpenFileRef => {
+                // Newest first: the latest answer (or output line) is the likely target.
+                let (refs, base) = match self.current_screen {
+                    CurrentScreen::Search => (self.messages.iter().rev().filter(|m| m.role == "assistant").flat_map(|m| editor::find_refs(&m.content)).collect::<Vec<_>>(), self.current_project().map(str::to_string)),
+                    CurrentScreen::Launcher | CurrentScreen::Settings => (self.launcher_logs.iter().rev().flat_map(|l| editor::find_refs(l)).collect(), None),
+                    CurrentScreen::Processes => (self.process_output.iter().rev().flat_map(|l| editor::find_refs(l)).collect(), None),
+                };
+                if refs.is_empty() {
+                    self.notice("No file:line references to open.");
+                } else {
+                    let r = refs[self.ref_cursor % refs.len()].clone();
+                    self.ref_cursor += 1;
+                    self.editor_request = Some((commands::resolve_path(base.as_deref(), &r.path).to_string_lossy().to_string(), r));
+                }
+            },
+            AppAction::CompleteMention => {
+                if let Some(name) = self.mention_suggestions().first().map(|a| a.name.clone()) {
+                    self.search_input = mentions::complete(&self.search_input, &name);
+                    self.query_pos = None;
+                }
+            },
+            AppAction::LaunchRecommended => {
+                let ids: Vec<String> = self.launch_answer().map(|i| self.recommended_apps(i).iter().map(|a| a.id.clone()).collect()).unwrap_or_default();
+                match ids.as_slice() {
+                    [] => self.messages.push(ChatMessage::new("system", "This answer doesn't mention any of your apps.")),
+                    [id] => { let id = id.clone(); self.launch_from_chat(id, &mut fx); },
+                    _ => self.launch_choice = Some(ids),
+                }
+                self.chat_auto_scroll = self.chat_focus.is_none();
+            },
+            AppAction::ChooseLaunch(n) => {
+                let Some(ids) = self.launch_choice.take() else { return fx };
+                if let Some(id) = n.and_then(|n| ids.get(n)) { self.launch_from_chat(id.clone(), &mut fx); }
+            },
+            AppAction::Snippet(verb) => {
+                if let Some((anchor, cursor)) = self.chat_visual.take() {
+                    let lines = self.chat_lines.get(anchor.min(cursor)..=anchor.max(cursor)).unwrap_or_default();
+                    let text = lines.join("\n").trim().to_string();
+                    if !text.is_empty() { self.use_snippet(verb, text, &mut fx); }
+                    return fx;
+                }
+                let target = self.launch_answer();
+                let cmds = target.map(|i| self.snippet_blocks(i, verb)).unwrap_or_default();
+                match cmds.as_slice() {
+                    // A focused answer without code is saved whole.
+                    [] if verb == Verb::SaveApp && self.chat_focus.is_some() => {
+                        let text = target.and_then(|i| self.messages.get(i)).map(|m| m.content.trim().to_string()).unwrap_or_default();
+                        self.use_snippet(verb, text, &mut fx);
+                    },
+                    [] => {
+                        let text = if verb == Verb::SaveApp { "This answer has no code blocks; focus it with { } to save it whole." } else { "This answer has no shell commands." };
+                        self.messages.push(ChatMessage::new("system", text));
+                        self.chat_auto_scroll = self.chat_focus.is_none();
+                    },
+                    [one] => { let cmd = one.command(); self.use_snippet(verb, cmd, &mut fx); },
+                    _ => self.snippet_choice = Some(verb),
+                }
+            },
+            AppAction::CopyCodeBlock => {
+                let Some(i) = self.chat_focus.filter(|&i| i < self.messages.len()).or_else(|| self.launch_answer()) else { return fx };
+                match self.code_blocks(i).as_slice() {
+                    [] => { self.messages.push(ChatMessage::new("system", "No code blocks in this message.")); self.chat_auto_scroll = self.chat_focus.is_none(); },
+                    [one] => fx.push(Effect::CopyToClipboard(one.code.clone())),
+                    _ => self.code_picker = Some((i, 0)),
+                }
+            },
+            AppAction::CodePickerMove(delta) => {
+                if let Some((i, sel)) = self.code_picker {
+                    let n = self.code_blocks(i).len();
+                    self.code_picker = Some((i, (sel as i32 + delta).clamp(0, n.saturating_sub(1) as i32) as usize));
+                }
+            },
+            AppAction::CodePickerCopy(n) => {
+                let Some((i, sel)) = self.code_picker else { return fx };
+                if let Some(block) = self.code_blocks(i).get(n.unwrap_or(sel)) {
+                    fx.push(Effect::CopyToClipboard(block.code.clone()));
+                    self.code_picker = None;
+                }
+            },
+            AppAction::CodePickerCancel => self.code_picker = None,
+            AppAction::ChooseSnippet(n) => {
+                let Some(verb) = self.snippet_choice.take() else { return fx };
+                let cmds = self.launch_answer().map(|i| self.snippet_blocks(i, verb)).unwrap_or_default();
+                if let Some(block) = n.and_then(|n| cmds.get(n)) { self.use_snippet(verb, block.command(), &mut fx); }
+            },
+            AppAction::PinLastAnswer => {
+                let Some(content) = self.messages.iter().rev().find(|m| m.role == "assistant" && !m.content.trim().is_empty()).map(|m| m.content.clone()) else { return fx };
+                let convo_id = self.current_convo_id;
+                if let Some(i) = self.highlights.iter().position(|h| h.convo_id == convo_id && h.content == content) {
+                    self.highlights.remove(i);
+                    self.messages.push(ChatMessage::new("system", "Unpinned from highlights."));
+                } else {
+                    let convo_title = convo_id.and_then(|id| self.conversations.iter().find(|c| c.id == id)).map(|c| c.title.clone()).unwrap_or_else(|| "Untitled".into());
+                    self.highlights.push(Highlight { convo_id, convo_title, content, pinned_at: store::now_secs() });
+                    self.messages.push(ChatMessage::new("system", "Pinned to highlights (Ctrl+s to browse)."));
+                }
+                self.chat_auto_scroll = true;
+                fx.push(self.persist_highlights());
+            },
+            AppAction::OpenLastAttachment => {
+                if let Some(path) = self.messages.iter().rev().find_map(|m| m.attachments.last()) { fx.push(Effect::OpenExternal(path.clone())); }
+            },
+        }
+        fx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logbuf::Severity;
+
+    fn app_model(id: &str, name: &str, desc: &str) -> AppModel {
+        AppModel { id: id.into(), name: name.into(), description: Some(desc.into()), command: format!("run {}", name), url: "http://localhost:8080".into(), ..Default::default() }
+    }
+
+    fn convo(id: i64, title: &str) -> Conversation {
+        Conversation { id, title: title.into(), updated_at: None }
+    }
+
+    #[test]
+    fn switch_tab_to_search_loads_state_once() {
+        let mut app = App::new();
+        let fx = app.reduce(AppAction::SwitchTab);
+        assert_eq!(app.current_screen, CurrentScreen::Search);
+        assert_eq!(app.input_mode, InputMode::SearchInput);
+        assert_eq!(fx, vec![Effect::Dispatch(AppAction::LoadSearchState)]);
+
+        assert_eq!(app.reduce(AppAction::SwitchTab), vec![Effect::FetchProcesses]);
+        assert_eq!(app.current_screen, CurrentScreen::Processes);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.reduce(AppAction::SwitchTab).is_empty());
+        assert_eq!((app.current_screen.clone(), app.input_mode.clone()), (CurrentScreen::Settings, InputMode::Settings));
+        app.reduce(AppAction::SwitchTab);
+        assert_eq!(app.current_screen, CurrentScreen::Launcher);
+
+        assert!(app.search_providers.is_empty());
+        assert!(app.reduce(AppAction::SwitchTab).is_empty(), "fetched once, even if providers came back empty");
+    }
+
+    #[test]
+    fn processes_fall_back_to_local_launches() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        app.reduce(AppAction::LaunchSelected);
+        app.reduce(AppAction::ProcessesLoaded(None));
+        assert_eq!(app.processes.iter().map(|p| (p.id.as_str(), p.app_id.as_deref())).collect::<Vec<_>>(), [("local-1", Some("9"))]);
+        assert_eq!(app.reduce(AppAction::StopProcess), vec![Effect::StopApp { id: "9".into(), name: "svc".into() }]);
+        assert_eq!(app.reduce(AppAction::RestartProcess).last(), Some(&Effect::LaunchApp { id: "9".into(), env: LaunchEnv::default(), seq: 2 }));
+
+        let remote = ProcessInfo { id: "p1".into(), name: "svc".into(), pid: Some(42), status: "running".into(), ..Default::default() };
+        app.reduce(AppAction::ProcessesLoaded(Some(vec![remote])));
+        assert_eq!(app.reduce(AppAction::StopProcess), vec![Effect::ProcessAction { id: "p1".into(), action: "stop" }]);
+        assert_eq!(app.reduce(AppAction::ShowProcessOutput), vec![Effect::FetchProcessOutput("p1".into())]);
+    }
+
+    #[test]
+    fn switch_tab_is_ignored_while_editing() {
+        let mut app = App::new();
+        app.reduce(AppAction::OpenAddModal);
+        assert!(app.reduce(AppAction::SwitchTab).is_empty());
+        assert_eq!(app.current_screen, CurrentScreen::Launcher);
+        assert_eq!(app.input_mode, InputMode::Editing);
+    }
+
+    #[test]
+    fn modals_and_search_modes_reject_launcher_transitions() {
+        let mut app = App::new();
+        app.reduce(AppAction::OpenAdHocModal);
+        app.reduce(AppAction::SwitchTab);
+        app.reduce(AppAction::ToggleFilter);
+        app.reduce(AppAction::OpenAddModal);
+        assert_eq!((app.current_screen.clone(), app.input_mode.clone()), (CurrentScreen::Launcher, InputMode::AdHocCmd));
+        app.reduce(AppAction::CloseModal);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.reduce(AppAction::SwitchTab);
+        app.reduce(AppAction::ToggleFilter);
+        app.reduce(AppAction::OpenAdHocModal);
+        assert!(app.reduce(AppAction::SubmitForm).is_empty());
+        assert_eq!(app.input_mode, InputMode::SearchInput);
+    }
+
+    #[test]
+    fn filter_matches_name_and_description() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "Grafana", "#ops"), app_model("2", "Jupyter", "notebooks #py")]));
+        assert_eq!(app.filtered_apps, vec![0, 1]);
+
+        app.reduce(AppAction::ToggleFilter);
+        assert_eq!(app.input_mode, InputMode::Filtering);
+        for c in "#py".chars() { app.reduce(AppAction::EnterFilterChar(c)); }
+        assert_eq!(app.filtered_apps, vec![1]);
+        assert_eq!(app.get_selected_app().map(|a| a.id.as_str()), Some("2"));
+
+        app.reduce(AppAction::BackspaceFilter);
+        app.reduce(AppAction::BackspaceFilter);
+        app.reduce(AppAction::BackspaceFilter);
+        assert_eq!(app.filtered_apps, vec![0, 1]);
+    }
+
+    #[test]
+    fn filter_prefixes_target_command_and_url() {
+        let mut app = App::new();
+        let mut notebook = app_model("1", "Notebook", "");
+        notebook.command = "python -m jupyter lab".into();
+        notebook.url = "http://localhost:8888".into();
+        let mut api = app_model("2", "API", "python backend");
+        api.url = "http://localhost:8080".into();
+        app.reduce(AppAction::AppsLoaded(vec![notebook, api]));
+        app.reduce(AppAction::ToggleFilter);
+
+        let matches = |app: &mut App, q: &str| {
+            app.filter_input = q.to_lowercase();
+            app.update_filter();
+            app.filtered_apps.clone()
+        };
+        assert_eq!(matches(&mut app, "url:8080"), vec![1]);
+        assert_eq!(matches(&mut app, "cmd:python"), vec![0]);
+        assert_eq!(matches(&mut app, "python"), vec![0, 1], "bare terms search every field");
+        assert_eq!(matches(&mut app, "python url:8888"), vec![0]);
+        assert_eq!(matches(&mut app, "name:api desc:backend"), vec![1]);
+        assert_eq!(matches(&mut app, "https://"), Vec::<usize>::new(), "unknown prefixes are plain text");
+    }
+
+    #[test]
+    fn tag_browser_combines_selected_tags() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "a", "#ops #web"), app_model("2", "b", "#web"), app_model("3", "c", "#ops #ops")]));
+        assert_eq!(app.tag_index, vec![("#ops".to_string(), 2), ("#web".to_string(), 2)]);
+
+        app.reduce(AppAction::OpenTagBrowser);
+        assert_eq!(app.input_mode, InputMode::TagBrowser);
+        app.reduce(AppAction::ToggleTag);
+        assert_eq!(app.filtered_apps, vec![0, 2]);
+        app.reduce(AppAction::TagNext);
+        app.reduce(AppAction::ToggleTag);
+        assert_eq!(app.filtered_apps, vec![0]);
+
+        app.reduce(AppAction::CloseModal);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "a", "#ops")]));
+        assert_eq!(app.selected_tags, BTreeSet::from(["#ops".to_string()]));
+        app.reduce(AppAction::ClearTags);
+        assert_eq!(app.filtered_apps, vec![0]);
+    }
+
+    #[test]
+    fn form_env_and_cwd_reach_the_launch() {
+        let mut app = App::new();
+        app.reduce(AppAction::OpenAddModal);
+        for (field, text) in [(0, "web"), (2, "npm start"), (4, "PORT=3000  MODE=dev"), (5, " ~/src/web ")] {
+            app.active_form.focus_idx = field;
+            for c in text.chars() { app.reduce(AppAction::FormChar(c)); }
+        }
+        let Some(Effect::SaveApp(mut model)) = app.reduce(AppAction::SubmitForm).pop() else { panic!("no save") };
+        assert_eq!(model.launch.env, BTreeMap::from([("MODE".to_string(), "dev".to_string()), ("PORT".to_string(), "3000".to_string())]));
+        assert_eq!(model.launch.cwd.as_deref(), Some("~/src/web"));
+
+        model.id = "3".into();
+        let env = model.launch.clone();
+        app.reduce(AppAction::AppsLoaded(vec![model]));
+        app.reduce(AppAction::OpenEditModal);
+        assert_eq!(app.active_form.env, "MODE=dev PORT=3000");
+        app.reduce(AppAction::CloseModal);
+        assert_eq!(app.reduce(AppAction::LaunchSelected).last(), Some(&Effect::LaunchApp { id: "3".into(), env, seq: 1 }));
+    }
+
+    #[test]
+    fn env_values_with_spaces_round_trip() {
+        let env = BTreeMap::from([
+            ("GREETING".to_string(), "hello world".to_string()),
+            ("QUOTED".to_string(), r#"say "hi" \ bye"#.to_string()),
+            ("EMPTY".to_string(), String::new()),
+            ("PATHS".to_string(), r"C:\tools".to_string()),
+        ]);
+        let app = AppModel { id: "1".into(), name: "web".into(), command: "run".into(), url: "http://localhost".into(), launch: LaunchEnv { env: env.clone(), cwd: None }, ..Default::default() };
+        let form = AppForm::from_app(&app);
+        assert_eq!(form.env, r#"EMPTY= GREETING="hello world" PATHS=C:\tools QUOTED="say \"hi\" \\ bye""#);
+        assert!(form.validate().is_empty());
+        assert_eq!(form.to_model().launch.env, env);
+
+        let form = AppForm { env: "A='x  y' B=\"1 2\"".into(), ..form };
+        assert_eq!(form.to_model().launch.env, BTreeMap::from([("A".to_string(), "x  y".to_string()), ("B".to_string(), "1 2".to_string())]));
+        let form = AppForm { env: "A=\"open".into(), ..form };
+        assert_eq!(form.validate().get(&4).map(String::as_str), Some("unclosed \""));
+    }
+
+    #[test]
+    fn todo_extraction_streams_into_a_chat_note() {
+        let mut app = App::new();
+        app.reduce(AppAction::ExtractTodos(None));
+        assert!(app.messages.last().unwrap().content.starts_with("Nothing to extract"));
+
+        app.messages = vec![ChatMessage::new("user", "plan the release"), ChatMessage::new("assistant", "Tag it, then email QA.")];
+        let Some(Effect::ExtractTodos { prompt, title, path, .. }) = app.reduce(AppAction::ExtractTodos(Some("/tmp/t.md".into()))).pop() else { panic!("no extraction") };
+        assert!(prompt.contains("Assistant: Tag it, then email QA."));
+        assert_eq!((title.as_str(), path), ("plan the release", Some(std::path::PathBuf::from("/tmp/t.md"))));
+        assert!(app.reduce(AppAction::ExtractTodos(None)).is_empty(), "one extraction at a time");
+
+        app.reduce(AppAction::TodoItem("- [ ] Tag it".into()));
+        app.reduce(AppAction::TodoItem("- [ ] Email QA".into()));
+        app.reduce(AppAction::TodosDone { path: "/tmp/t.md".into(), result: Ok(2) });
+        assert_eq!(app.messages[2].content, "Added 2 action items to `/tmp/t.md`:\n- [ ] Tag it\n- [ ] Email QA");
+        assert_eq!(app.todo_note, None);
+        assert_eq!(app.messages.len(), 4, "the busy notice stays, nothing else added");
+    }
+
+    #[test]
+    fn placeholders_are_asked_for_and_remembered() {
+        let mut app = App::new();
+        let mut tpl = app_model("4", "deploy", "");
+        tpl.command = "deploy {{env}} --tag {{tag}}".into();
+        app.reduce(AppAction::AppsLoaded(vec![tpl]));
+        assert!(app.reduce(AppAction::LaunchSelected).is_empty());
+        assert_eq!(app.input_mode, InputMode::Args);
+        for c in "prod".chars() { app.reduce(AppAction::ArgsChar(c)); }
+        app.reduce(AppAction::ArgsNext);
+        app.reduce(AppAction::ArgsChar('7'));
+        let fx = app.reduce(AppAction::SubmitArgs);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(fx.last(), Some(&Effect::LaunchCommand { id: "4".into(), cmd: "deploy prod --tag 7".into(), env: LaunchEnv::default(), seq: 1 }));
+        assert!(fx.iter().any(|e| matches!(e, Effect::WriteStore { name: store::ARGS, .. })));
+
+        app.reduce(AppAction::LaunchSelected);
+        let fields = &app.args_prompt.as_ref().unwrap().fields;
+        assert_eq!(fields[0], ("env".to_string(), "prod".to_string()), "last values are the defaults");
+    }
+
+    #[test]
+    fn equals_prefix_is_answered_locally() {
+        let mut app = App::new();
+        app.search_input = "= 2 GiB in MiB".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty());
+        assert!(!app.is_searching);
+        assert_eq!(app.messages.last().map(|m| m.content.as_str()), Some("= **2048 MiB**"));
+    }
+
+    #[test]
+    fn leftover_temp_apps_are_hidden() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "a", ""), app_model("2", api::TEMP_APP_NAME, "Ad-hoc")]));
+        assert_eq!(app.apps.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["1"]);
+    }
+
+    #[test]
+    fn favorites_sort_first_and_persist() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "a", ""), app_model("2", "b", ""), app_model("3", "c", "")]));
+        app.reduce(AppAction::SelectNext);
+        app.reduce(AppAction::SelectNext);
+        let fx = app.reduce(AppAction::ToggleFavorite);
+        assert_eq!(fx, vec![Effect::WriteStore { name: store::FAVORITES, data: serde_json::json!(["3"]) }]);
+        assert_eq!(app.filtered_apps, vec![2, 0, 1]);
+        assert_eq!(app.get_selected_app().map(|a| a.id.as_str()), Some("3"));
+
+        app.reduce(AppAction::ToggleFavoritesOnly);
+        assert_eq!(app.filtered_apps, vec![2]);
+        app.reduce(AppAction::ToggleFavorite);
+        assert!(app.filtered_apps.is_empty());
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "a", ""), app_model("2", "b", "")]));
+        app.reduce(AppAction::SelectPrev);
+        assert_eq!(app.apps_idx, 1);
+        app.reduce(AppAction::SelectNext);
+        assert_eq!(app.apps_idx, 0);
+    }
+
+    #[test]
+    fn command_editor_keeps_newlines() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("7", "api", "backend")]));
+        app.reduce(AppAction::OpenEditModal);
+        app.reduce(AppAction::FormNewline);
+        assert_eq!(app.active_form.name, "api", "Enter only breaks lines in Cmd");
+        app.active_form.focus_idx = AppForm::CMD;
+        app.reduce(AppAction::FormCursor(CursorMove::Home));
+        for c in "cd srv".chars() { app.reduce(AppAction::FormChar(c)); }
+        app.reduce(AppAction::FormNewline);
+        let Some(Effect::SaveApp(model)) = app.reduce(AppAction::SubmitForm).pop() else { panic!("no save") };
+        assert_eq!(model.command, "cd srv\nrun api");
+    }
+
+    #[test]
+    fn paste_goes_to_the_focused_input_in_one_action() {
+        let mut app = App::new();
+        app.reduce(AppAction::OpenAddModal);
+        app.reduce(AppAction::Paste("my\r\napp\n".into()));
+        assert_eq!(app.active_form.name, "my app", "single-line fields get spaces");
+        app.active_form.focus_idx = AppForm::CMD;
+        app.reduce(AppAction::Paste("cat <<EOF\nhi\nEOF".into()));
+        app.reduce(AppAction::FormChar('!'));
+        assert_eq!(app.active_form.cmd.text, "cat <<EOF\nhi\nEOF!");
+        app.reduce(AppAction::CloseModal);
+
+        app.reduce(AppAction::SwitchTab);
+        app.input_mode = InputMode::SearchInput;
+        app.reduce(AppAction::Paste("why does\nthis fail?\n".into()));
+        assert_eq!(app.search_input, "why does\nthis fail?");
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::Paste("ignored".into()));
+        assert_eq!(app.search_input, "why does\nthis fail?");
+    }
+
+    #[test]
+    fn marked_apps_launch_one_at_a_time_and_delete_together() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "api", ""), app_model("2", "worker", ""), app_model("3", "web", "")]));
+        app.reduce(AppAction::ToggleMark);
+        app.reduce(AppAction::ToggleMark);
+        assert_eq!(app.apps_idx, 2, "marking moves down");
+        app.apps_idx = 0;
+        app.reduce(AppAction::ToggleMark);
+        assert_eq!(app.marked_ids(), ["2"]);
+        app.apps_idx = 2;
+        app.reduce(AppAction::ToggleMark);
+        assert_eq!(app.marked_ids(), ["2", "3"]);
+
+        let launched = |fx: &[Effect]| fx.iter().filter_map(|e| match e { Effect::LaunchApp { id, .. } => Some(id.clone()), _ => None }).collect::<Vec<_>>();
+        assert_eq!(launched(&app.reduce(AppAction::LaunchSelected)), ["2"]);
+        assert!(app.marked.is_empty());
+        assert!(launched(&app.reduce(AppAction::Tick)).is_empty(), "waits for the first launch");
+        assert_eq!(launched(&app.reduce(AppAction::LaunchFinished { seq: 1, ok: true, duration_ms: 5, output: String::new() })), ["3"]);
+        let stagger: Vec<String> = (0..LAUNCH_STAGGER_TICKS).flat_map(|_| launched(&app.reduce(AppAction::Tick))).collect();
+        assert!(stagger.is_empty() && app.sequence.is_none());
+
+        app.marked = ["1".to_string(), "3".to_string()].into();
+        app.reduce(AppAction::RequestDelete);
+        let fx = app.reduce(AppAction::Confirm(true));
+        assert_eq!(fx.iter().filter(|e| matches!(e, Effect::DeleteApp(_))).count(), 2);
+        let restored = app.reduce(AppAction::Undo);
+        assert_eq!(restored.iter().filter(|e| matches!(e, Effect::SaveApp(_))).count(), 2, "one undo brings both back");
+    }
+
+    #[test]
+    fn open_url_needs_an_http_url() {
+        let mut app = App::new();
+        let mut bare = app_model("2", "cli", "");
+        bare.url = "  ".into();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "api", ""), bare]));
+        assert!(matches!(app.reduce(AppAction::OpenSelectedUrl).as_slice(), [Effect::OpenExternal(u)] if u == "http://localhost:8080"));
+        app.reduce(AppAction::SelectNext);
+        assert!(app.reduce(AppAction::OpenSelectedUrl).is_empty());
+        assert_eq!(app.launcher_logs.last().unwrap(), "This app has no http(s) URL to open.");
+    }
+
+    #[test]
+    fn url_health_is_polled_one_check_at_a_time() {
+        let mut app = App::new();
+        app.health_poll_ticks = 2;
+        let mut cli = app_model("2", "cli", "");
+        cli.url = String::new();
+        let fx = app.reduce(AppAction::AppsLoaded(vec![app_model("1", "api", ""), cli]));
+        assert!(matches!(fx.as_slice(), [Effect::CheckUrls(t)] if t == &[("1".to_string(), "http://localhost:8080".to_string())]), "apps without a URL are skipped");
+        app.reduce(AppAction::Tick);
+        assert!(app.reduce(AppAction::Tick).is_empty(), "previous check still running");
+        app.reduce(AppAction::UrlHealth(vec![("1".into(), true)]));
+        assert_eq!(app.url_health.get("1"), Some(&true));
+        app.reduce(AppAction::Tick);
+        assert!(matches!(app.reduce(AppAction::Tick).as_slice(), [Effect::CheckUrls(_)]));
+        app.reduce(AppAction::UrlHealth(vec![("1".into(), false)]));
+        assert!(matches!(app.reduce(AppAction::RefreshHealth).as_slice(), [Effect::CheckUrls(_)]));
+    }
+
+    #[test]
+    fn auto_open_waits_for_the_url_to_answer() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "web", ""), app_model("2", "api", "")]));
+        assert!(matches!(app.reduce(AppAction::ToggleAutoOpen).as_slice(), [Effect::WriteStore { name: store::AUTO_OPEN, .. }]));
+        app.reduce(AppAction::LaunchSelected);
+        let fx = app.reduce(AppAction::LaunchFinished { seq: 1, ok: true, duration_ms: 5, output: String::new() });
+        assert!(fx.iter().any(|e| matches!(e, Effect::CheckHealth { seq: 1, .. })));
+        assert!(app.reduce(AppAction::HealthChecked { seq: 1, up: false }).is_empty(), "not again until the next probe is due");
+        assert!(matches!(app.reduce(AppAction::HealthChecked { seq: 1, up: true }).as_slice(), [Effect::OpenExternal(u)] if u == "http://localhost:8080"));
+        assert!(app.opening.is_empty());
+
+        // Other apps, and failed launches, open nothing.
+        app.reduce(AppAction::SelectNext);
+        app.reduce(AppAction::LaunchSelected);
+        assert!(app.opening.is_empty());
+        app.reduce(AppAction::SelectPrev);
+        app.reduce(AppAction::LaunchSelected);
+        app.reduce(AppAction::LaunchFinished { seq: 3, ok: false, duration_ms: 5, output: String::new() });
+        assert!(app.opening.is_empty());
+    }
+
+    #[test]
+    fn chain_editor_builds_and_saves_a_chain() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "web", ""), app_model("2", "api", ""), app_model("3", "worker", "")]));
+        app.reduce(AppAction::OpenChains);
+        app.reduce(AppAction::NewChain);
+        for c in "dev".chars() { app.reduce(AppAction::ChainChar(c)); }
+        app.reduce(AppAction::ChainFocusNext);
+        app.reduce(AppAction::ChainAddStep);
+        app.reduce(AppAction::ChainAddStep);
+        assert!(app.chain_draft.as_ref().unwrap().error.is_some(), "an app goes in once");
+        app.reduce(AppAction::ChainNext);
+        app.reduce(AppAction::ChainAddStep);
+        app.reduce(AppAction::ChainFocusNext);
+        app.reduce(AppAction::ChainMoveStep(true));
+        app.reduce(AppAction::ChainToggleHealth);
+        let fx = app.reduce(AppAction::SaveChain);
+        assert!(fx.iter().any(|e| matches!(e, Effect::WriteStore { name: store::CHAINS, .. })));
+        assert_eq!(app.chains, [Chain { name: "dev".into(), app_ids: vec!["2".into(), "1".into()], health_check: true }]);
+        assert_eq!(app.input_mode, InputMode::Chains, "back to the list");
+
+        app.reduce(AppAction::EditChain);
+        app.reduce(AppAction::ChainBackspace);
+        app.reduce(AppAction::ChainBackspace);
+        app.reduce(AppAction::ChainBackspace);
+        app.reduce(AppAction::SaveChain);
+        assert!(app.chain_draft.as_ref().is_some_and(|d| d.error.is_some()), "a name is required");
+        app.reduce(AppAction::CloseModal);
+        assert!(app.chain_draft.is_none() && app.input_mode == InputMode::Chains);
+        app.reduce(AppAction::CloseModal);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn chain_waits_for_each_app_and_stops_on_failure() {
+        let mut app = App::new();
+        let mut api = app_model("1", "api", "");
+        api.url = "http://localhost:8000".into();
+        app.reduce(AppAction::AppsLoaded(vec![api, app_model("2", "worker", ""), app_model("3", "web", "")]));
+        app.chains = vec![Chain { name: "dev".into(), app_ids: vec!["1".into(), "2".into(), "3".into()], health_check: true }];
+        app.reduce(AppAction::OpenChains);
+        let launched = |fx: &[Effect]| fx.iter().filter_map(|e| match e { Effect::LaunchApp { id, .. } => Some(id.clone()), _ => None }).collect::<Vec<_>>();
+        assert_eq!(launched(&app.reduce(AppAction::RunChain)), ["1"]);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // The launch answering is not enough: the URL has to respond.
+        let fx = app.reduce(AppAction::LaunchFinished { seq: 1, ok: true, duration_ms: 5, output: String::new() });
+        assert!(matches!(fx.as_slice(), [Effect::WriteStore { .. }, Effect::CheckHealth { seq: 1, url }] if url == "http://localhost:8000"));
+        assert!(app.reduce(AppAction::Tick).is_empty(), "one probe at a time");
+        app.reduce(AppAction::HealthChecked { seq: 1, up: false });
+        assert!((0..HEALTH_PROBE_TICKS).flat_map(|_| app.reduce(AppAction::Tick)).any(|e| matches!(e, Effect::CheckHealth { .. })));
+        assert_eq!(launched(&app.reduce(AppAction::HealthChecked { seq: 1, up: true })), ["2"]);
+
+        // No URL: falls back to waiting for the launch, and a failure ends the chain.
+        assert!(launched(&app.reduce(AppAction::LaunchFinished { seq: 2, ok: false, duration_ms: 5, output: String::new() })).is_empty());
+        assert!(app.sequence.is_none());
+        assert!(app.launcher_logs.last().unwrap().contains("stopped: 'worker' failed"));
+    }
+
+    #[test]
+    fn invalid_form_is_not_submitted() {
+        let mut app = App::new();
+        app.reduce(AppAction::OpenAddModal);
+        app.active_form.url = "localhost:3000".into();
+        app.active_form.env = "PORT=1 2X=y".into();
+        assert!(app.reduce(AppAction::SubmitForm).is_empty());
+        assert_eq!(app.input_mode, InputMode::Editing);
+        assert_eq!(app.active_form.errors.keys().copied().collect::<Vec<_>>(), [0, 2, 3, 4]);
+        assert_eq!(app.active_form.focus_idx, 0, "focus jumps to the first problem");
+
+        app.reduce(AppAction::FormChar('w'));
+        assert!(!app.active_form.errors.contains_key(&0), "marker clears once fixed");
+        app.active_form.cmd = "run".into();
+        app.active_form.url = "https://example.com".into();
+        app.active_form.env = "PORT=1".into();
+        assert!(matches!(app.reduce(AppAction::SubmitForm).as_slice(), [Effect::SaveApp(_)]));
+    }
+
+    #[test]
+    fn edit_form_round_trips_into_save_effect() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("7", "api", "backend")]));
+        app.reduce(AppAction::OpenEditModal);
+        app.reduce(AppAction::CycleFormFocus);
+        app.reduce(AppAction::FormBackspace);
+        app.reduce(AppAction::FormChar('X'));
+        let fx = app.reduce(AppAction::SubmitForm);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(fx, vec![Effect::SaveApp(AppModel { description: Some("backenX".into()), ..app_model("7", "api", "") })]);
+    }
+
+    #[test]
+    fn launch_and_delete_emit_effects_for_selected_app() {
+        let mut app = App::new();
+        assert!(app.reduce(AppAction::LaunchSelected).is_empty());
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        let fx = app.reduce(AppAction::LaunchSelected);
+        assert_eq!(fx.last(), Some(&Effect::LaunchApp { id: "9".into(), env: LaunchEnv::default(), seq: 1 }));
+        assert_eq!(app.usage["9"].count, 1);
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some("Executing 'svc'..."));
+    }
+
+    #[test]
+    fn launches_are_recorded_and_completed() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        app.reduce(AppAction::LaunchSelected);
+        app.reduce(AppAction::OpenAdHocModal);
+        let fx = app.reduce(AppAction::SubmitAdHoc("uptime".into()));
+        assert_eq!(fx.last(), Some(&Effect::RunAdHoc { cmd: "uptime".into(), seq: 2 }));
+        assert_eq!(app.launch_history.iter().map(|r| (r.app_id.as_deref(), r.ok)).collect::<Vec<_>>(), [(Some("9"), None), (None, None)]);
+
+        let fx = app.reduce(AppAction::LaunchFinished { seq: 1, ok: false, duration_ms: 1500, output: "Failed:\nboom".into() });
+        assert!(matches!(&fx[..], [Effect::WriteStore { name: store::HISTORY, .. }]));
+        assert_eq!((app.launch_history[0].ok, app.launch_history[0].duration_ms), (Some(false), Some(1500)));
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some("boom"));
+    }
+
+    #[test]
+    fn purge_all_needs_confirmation_and_clears_state() {
+        let mut app = App::new();
+        app.input_mode = InputMode::SearchInput;
+        app.favorites.insert("1".into());
+        app.project_bindings.insert(4, "/p".into());
+        app.search_input = "/purge all".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty());
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        app.config.theme = config::Theme::Gray;
+        assert!(matches!(app.reduce(AppAction::Confirm(true)).as_slice(), [Effect::SetBackends { .. }, Effect::PurgeStore]));
+        assert_eq!(app.input_mode, InputMode::SearchInput);
+        assert_eq!(app.config, Config::default());
+        assert!(app.favorites.is_empty() && app.project_bindings.is_empty());
+    }
+
+    #[test]
+    fn purging_a_conversation_drops_its_highlights() {
+        let mut app = App::new();
+        app.current_convo_id = Some(7);
+        let pin = |convo_id, content: &str| Highlight { convo_id, convo_title: "t".into(), content: content.into(), pinned_at: 1 };
+        app.highlights = vec![pin(Some(7), "mine"), pin(Some(8), "other"), pin(None, "unsaved")];
+        app.search_input = "/purge".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert_eq!(app.pending_confirm, Some(ConfirmAction::PurgeConversation { id: 7 }));
+        let fx = app.reduce(AppAction::Confirm(true));
+        assert_eq!(app.highlights.iter().map(|h| h.content.as_str()).collect::<Vec<_>>(), ["other", "unsaved"]);
+        assert!(fx.iter().any(|e| matches!(e, Effect::WriteStore { name: store::HIGHLIGHTS, .. })));
+    }
+
+    #[test]
+    fn purge_all_forgets_what_it_deleted() {
+        let mut app = App::new();
+        app.highlights.push(Highlight { convo_id: Some(1), convo_title: "rust".into(), content: "pinned".into(), pinned_at: 1 });
+        app.arg_defaults.insert("1".into(), BTreeMap::from([("tag".to_string(), "v2".to_string())]));
+        app.keymap.overrides.insert("launcher.launch".into(), vec!["F5".into()]);
+        app.search_input = "/purge all".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert_eq!(app.pending_confirm, Some(ConfirmAction::PurgeAll));
+        assert!(app.reduce(AppAction::Confirm(true)).contains(&Effect::PurgeStore));
+        assert!(app.highlights.is_empty() && app.arg_defaults.is_empty() && app.keymap.overrides.is_empty(), "the next save must not bring them back");
+    }
+
+    #[test]
+    fn retention_drops_old_launches() {
+        let mut app = App::new();
+        app.launch_history = [1, 2].map(|seq| LaunchRecord { seq, started: seq * 86_400, ..Default::default() }).to_vec();
+        assert!(app.apply_retention(3 * 86_400 + 10, 2));
+        assert_eq!(app.launch_history.iter().map(|r| r.seq).collect::<Vec<_>>(), [2]);
+        assert!(!app.apply_retention(3 * 86_400 + 10, 2));
+    }
+
+    #[test]
+    fn streamed_output_lands_in_logs_while_running() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        app.reduce(AppAction::LaunchSelected);
+        assert_eq!(app.running_launch_name(), Some("svc"));
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "listening on :80".into(), stderr: false });
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some("listening on :80"));
+        app.reduce(AppAction::LaunchFinished { seq: 1, ok: true, duration_ms: 10, output: "Success".into() });
+        assert_eq!(app.running_launch_name(), None);
+    }
+
+    #[test]
+    fn sort_modes_order_below_favorites() {
+        let mut app = App::new();
+        app.usage.insert("1".into(), LaunchStats { count: 1, last_launched: 300 });
+        app.usage.insert("3".into(), LaunchStats { count: 5, last_launched: 100 });
+        app.favorites.insert("2".into());
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "beta", ""), app_model("2", "zeta", ""), app_model("3", "Alpha", "")]));
+        let names = |app: &App| app.filtered_apps.iter().map(|&i| app.apps[i].name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&app), ["zeta", "beta", "Alpha"]);
+        app.reduce(AppAction::CycleSort);
+        assert_eq!(names(&app), ["zeta", "Alpha", "beta"]);
+        app.reduce(AppAction::CycleSort);
+        assert_eq!(names(&app), ["zeta", "beta", "Alpha"]);
+        app.reduce(AppAction::CycleSort);
+        assert_eq!(names(&app), ["zeta", "Alpha", "beta"]);
+        app.reduce(AppAction::CycleSort);
+        assert_eq!((app.sort_mode, names(&app)), (SortMode::Added, vec!["zeta".to_string(), "Alpha".into(), "beta".into()]));
+    }
+
+    #[test]
+    fn palette_runs_and_rebinds_commands() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        app.reduce(AppAction::OpenPalette);
+        assert_eq!(app.input_mode, InputMode::Palette);
+        for c in "launch svc".chars() { app.reduce(AppAction::PaletteChar(c)); }
+        assert_eq!(app.palette_entries().iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["launch:9"]);
+
+        app.reduce(AppAction::PaletteStartBind);
+        assert!(app.palette_binding);
+        let fx = app.reduce(AppAction::PaletteBindKey(Some("g".into())));
+        assert_eq!(fx, vec![Effect::WriteStore { name: store::KEYMAP, data: serde_json::json!({"launch:9": ["g"]}) }]);
+        assert_eq!(app.palette_entries()[0].keys, ["g"]);
+        assert_eq!(app.keymap.lookup(keymap::Context::Launcher, "g"), Some(AppAction::LaunchApp("9".into())));
+
+        assert_eq!(app.reduce(AppAction::PaletteRun), vec![Effect::Dispatch(AppAction::LaunchApp("9".into()))]);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.reduce(AppAction::LaunchApp("9".into())).last(), Some(&Effect::LaunchApp { id: "9".into(), env: LaunchEnv::default(), seq: 1 }));
+    }
+
+    #[test]
+    fn stop_selected_app() {
+        let mut app = App::new();
+        assert!(app.reduce(AppAction::StopSelected).is_empty());
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        assert_eq!(app.reduce(AppAction::StopSelected), vec![Effect::StopApp { id: "9".into(), name: "svc".into() }]);
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some("Stopping 'svc'..."));
+    }
+
+    #[test]
+    fn delete_requires_confirmation() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "")]));
+        assert!(app.reduce(AppAction::RequestDelete).is_empty());
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        assert!(app.reduce(AppAction::Confirm(false)).is_empty());
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.pending_confirm.is_none());
+
+        app.reduce(AppAction::RequestDelete);
+        assert_eq!(app.reduce(AppAction::Confirm(true)), vec![Effect::DeleteApp("9".into())]);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn deleting_open_conversation_resets_chat() {
+        let mut app = App::new();
+        app.input_mode = InputMode::SearchSidebar;
+        app.search_sidebar = SearchSidebarState::History;
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(4, "old")]));
+        app.current_convo_id = Some(4);
+        assert!(app.reduce(AppAction::RequestDeleteConversation).is_empty(), "[+] New Chat row is not deletable");
+
+        app.reduce(AppAction::SidebarNext);
+        app.reduce(AppAction::RequestDeleteConversation);
+        assert_eq!(app.pending_confirm.as_ref().map(|p| p.prompt()), Some("Delete conversation 'old'?".into()));
+        let fx = app.reduce(AppAction::Confirm(true));
+        assert_eq!(fx, vec![Effect::Dispatch(AppAction::NewConversation)]);
+        assert_eq!(app.input_mode, InputMode::SearchSidebar);
+        assert!(app.conversations.is_empty());
+        assert_eq!(app.pending_conversation_deletes(), vec![4]);
+    }
+
+    #[test]
+    fn undo_restores_deleted_app_by_reposting() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("9", "svc", "keep me")]));
+        app.reduce(AppAction::RequestDelete);
+        app.reduce(AppAction::Confirm(true));
+        app.reduce(AppAction::AppsLoaded(vec![]));
+
+        assert_eq!(app.reduce(AppAction::Undo), vec![Effect::SaveApp(app_model("", "svc", "keep me"))]);
+        assert!(app.reduce(AppAction::Undo).is_empty());
+    }
+
+    #[test]
+    fn conversation_deletes_are_deferred_until_evicted() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Search;
+        app.input_mode = InputMode::SearchSidebar;
+        app.search_sidebar = SearchSidebarState::History;
+        let all: Vec<_> = (1..=UNDO_DEPTH as i64 + 1).map(|i| convo(i, "c")).collect();
+        app.reduce(AppAction::ConversationsLoaded(all.clone()));
+
+        let mut evicted = vec![];
+        for _ in 0..all.len() {
+            app.reduce(AppAction::SidebarNext);
+            app.reduce(AppAction::RequestDeleteConversation);
+            evicted.extend(app.reduce(AppAction::Confirm(true)));
+        }
+        assert_eq!(evicted, vec![Effect::DeleteConversation(1)]);
+
+        app.reduce(AppAction::ConversationsLoaded(all[1..].to_vec()));
+        assert!(app.conversations.is_empty(), "polling doesn't resurrect hidden conversations");
+
+        assert_eq!(app.reduce(AppAction::Undo), vec![Effect::FetchConversations]);
+        app.reduce(AppAction::ConversationsLoaded(all[1..].to_vec()));
+        assert_eq!(app.conversations.iter().map(|c| c.id).collect::<Vec<_>>(), vec![6]);
+    }
+
+    #[test]
+    fn launch_log_is_capped() {
+        let mut app = App::new();
+        let output = (0..crate::logbuf::LIMIT + 50).map(|i| i.to_string()).collect::<Vec<_>>().join("\n");
+        app.reduce(AppAction::LaunchResult(output));
+        assert_eq!(app.launcher_logs.len(), crate::logbuf::LIMIT);
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some("5049"));
+    }
+
+    #[test]
+    fn output_search_jumps_between_matches() {
+        let mut app = App::new();
+        app.reduce(AppAction::LaunchResult("GET / 200\nerror: disk full\nGET /a 200\nERROR: timeout\nGET /b 200".into()));
+        app.reduce(AppAction::FocusOutput(true));
+        app.reduce(AppAction::OutputSearchStart);
+        for c in "error".chars() { app.reduce(AppAction::OutputSearchChar(c)); }
+        assert_eq!(app.output_matches(), [2, 4], "ignores case");
+        app.reduce(AppAction::OutputSearchDone(true));
+        assert_eq!((app.output_match, app.output_end), (Some(4), Some(5)), "newest match first");
+        app.reduce(AppAction::OutputNextMatch(true));
+        assert_eq!(app.output_match, Some(2));
+        app.reduce(AppAction::OutputNextMatch(true));
+        assert_eq!(app.output_match, Some(4), "wraps around");
+        app.reduce(AppAction::OutputNextMatch(false));
+        assert_eq!(app.output_match, Some(2));
+        app.reduce(AppAction::OutputSearchStart);
+        app.reduce(AppAction::OutputSearchDone(false));
+        assert!(app.output_query.is_empty() && app.output_match.is_none());
+    }
+
+    #[test]
+    fn targeted_apps_launch_over_ssh() {
+        let mut app = App::new();
+        let mut nas = app_model("1", "media", "");
+        nas.target = Some("nas".into());
+        app.reduce(AppAction::AppsLoaded(vec![nas]));
+        assert!(!app.reduce(AppAction::LaunchSelected).iter().any(|e| matches!(e, Effect::LaunchRemote { .. } | Effect::LaunchApp { .. })));
+        assert!(app.launcher_logs.last().unwrap().contains("unknown host 'nas'"));
+        app.ssh_hosts = vec![SshHost { name: "nas".into(), destination: "me@nas".into(), ..Default::default() }];
+        let fx = app.reduce(AppAction::LaunchSelected);
+        assert!(matches!(fx.last(), Some(Effect::LaunchRemote { id, cmd, seq: 1 }) if id == "1" && cmd.starts_with("ssh -o BatchMode=yes me@nas ")), "{:?}", fx);
+        assert_eq!(app.reduce(AppAction::StopSelected), vec![Effect::StopRemote { id: "1".into(), name: "media".into() }]);
+    }
+
+    #[test]
+    fn tmux_apps_start_in_tmux() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "htop", "")]));
+        app.reduce(AppAction::CycleTmuxMode);
+        assert_eq!(app.tmux_modes.get("1"), Some(&TmuxMode::Window));
+        assert!(app.launcher_logs.last().unwrap().contains("a session, since bpt is not running inside tmux"));
+        app.in_tmux = true;
+        let fx = app.reduce(AppAction::LaunchSelected);
+        assert!(matches!(fx.last(), Some(Effect::LaunchTmux { seq: 1, args, what: "window" }) if args[0] == "new-window"), "{:?}", fx);
+        for _ in 0..3 { app.reduce(AppAction::CycleTmuxMode); }
+        assert!(app.tmux_modes.is_empty(), "back to a normal launch");
+        assert!(matches!(app.reduce(AppAction::LaunchSelected).last(), Some(Effect::LaunchApp { .. })));
+    }
+
+    #[test]
+    fn source_apps_are_listed_with_the_apps() {
+        let mut app = App::new();
+        app.sources = BTreeSet::from([Source::Docker, Source::Systemd]);
+        assert_eq!(app.reduce(AppAction::LoadApps), vec![Effect::FetchApps, Effect::FetchSource(Source::Docker), Effect::FetchSource(Source::Systemd)]);
+        app.reduce(AppAction::SourceLoaded(Source::Docker, vec![(app_model("docker:web", "web", "nginx #docker"), "running".into())]));
+        app.reduce(AppAction::SourceLoaded(Source::Systemd, vec![(app_model("systemd:sync.service", "sync", ""), "failed".into())]));
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "api", "")]));
+        let ids = |app: &App| app.apps.iter().map(|a| a.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&app), ["1", "docker:web", "systemd:sync.service"], "a backend refresh keeps the sources");
+        app.reduce(AppAction::SourceLoaded(Source::Docker, vec![(app_model("docker:db", "db", ""), "exited".into())]));
+        assert_eq!(ids(&app), ["1", "systemd:sync.service", "docker:db"]);
+        assert_eq!(app.source_states.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<Vec<_>>(), [("docker:db", "exited"), ("systemd:sync.service", "failed")]);
+        app.apps_idx = app.filtered_apps.iter().position(|i| app.apps[*i].id == "docker:db").unwrap();
+        app.reduce(AppAction::OpenEditModal);
+        assert_eq!(app.input_mode, InputMode::Normal, "source apps are read-only");
+        let fx = app.reduce(AppAction::ShowLogs);
+        assert!(matches!(fx.last(), Some(Effect::RunLocal { cmd, .. }) if cmd == "docker logs --tail 200 db"));
+        assert_eq!(app.launch_history.last().map(|r| r.name.as_str()), Some("docker logs --tail 200 db"));
+    }
+
+    #[test]
+    fn launches_keep_their_own_output() {
+        let mut app = App::new();
+        app.reduce(AppAction::SubmitAdHoc("make".into()));
+        app.reduce(AppAction::SubmitAdHoc("uptime".into()));
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "cc main.c".into(), stderr: false });
+        app.reduce(AppAction::LaunchOutput { seq: 2, line: "up 3 days".into(), stderr: false });
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "main.c:1: error".into(), stderr: true });
+        app.reduce(AppAction::LaunchFinished { seq: 1, ok: false, duration_ms: 5, output: "Failed: exit status: 2".into() });
+        app.reduce(AppAction::LaunchFinished { seq: 2, ok: true, duration_ms: 5, output: "Success:\nload 0.1".into() });
+        let [make, uptime] = &app.launch_history[..] else { panic!() };
+        assert_eq!((make.status.as_str(), make.output.iter().map(|l| (l.text.as_str(), l.stderr)).collect::<Vec<_>>()),
+            ("Failed: exit status: 2", vec![("cc main.c", false), ("main.c:1: error", true)]));
+        assert_eq!((uptime.status.as_str(), uptime.output.len()), ("Success", 2), "non-streamed output goes into the record");
+        app.reduce(AppAction::OpenLaunchHistory);
+        app.reduce(AppAction::HistoryNext);
+        app.reduce(AppAction::ShowLaunchOutput);
+        assert_eq!(app.selected_launch().map(|r| r.seq), Some(1));
+        app.reduce(AppAction::ScrollLaunchOutput(i16::MAX));
+        assert_eq!(app.history_output, Some(1), "clamped to the last line");
+        app.reduce(AppAction::CloseModal);
+        assert!(app.history_output.is_none() && app.input_mode == InputMode::LaunchHistory, "back to the list first");
+        for i in 1..RECORDS_WITH_OUTPUT { app.reduce(AppAction::SubmitAdHoc(i.to_string())); }
+        assert!(app.launch_history[0].output.is_empty() && app.launch_history[1].output.len() == 2, "only recent launches keep output");
+    }
+
+    #[test]
+    fn errors_only_hides_normal_output() {
+        let mut app = App::new();
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "Compiling\nwarning: unused".into(), stderr: true });
+        app.reduce(AppAction::LaunchResult("Error: port in use\nready".into()));
+        assert_eq!(app.launcher_logs.severity(1), Severity::Stderr, "stderr is kept per line");
+        app.reduce(AppAction::ToggleErrorsOnly);
+        assert_eq!(app.output_lines(), [1, 3], "stderr and errors; warnings are not errors");
+        app.reduce(AppAction::ScrollOutput(-1));
+        assert_eq!(app.output_end, Some(2), "scrolls by shown lines");
+        app.output_query = "ready".into();
+        assert!(app.output_matches().is_empty(), "hidden lines don't match");
+        app.reduce(AppAction::ToggleErrorsOnly);
+        assert_eq!((app.output_lines().len(), app.output_end), (5, None));
+    }
+
+    #[test]
+    fn output_lines_are_asked_about_in_search() {
+        let mut app = App::new();
+        app.launcher_logs = LogBuffer::new(100);
+        assert!(app.reduce(AppAction::AskAboutOutput).is_empty(), "nothing to ask about");
+        app.reduce(AppAction::SubmitAdHoc("make".into()));
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "cc main.c\n\x1b[31mmain.c:1: error\x1b[0m".into(), stderr: true });
+        app.reduce(AppAction::LaunchFinished { seq: 1, ok: false, duration_ms: 5, output: "Failed: exit status: 2".into() });
+        app.reduce(AppAction::FocusOutput(true));
+        let fx = app.reduce(AppAction::AskAboutOutput);
+        assert_eq!((app.current_screen.clone(), app.input_mode.clone()), (CurrentScreen::Search, InputMode::SearchInput));
+        let Some(Effect::Dispatch(AppAction::ContextAttached { label, content })) = fx.last() else { panic!("{:?}", fx) };
+        assert_eq!(label, "output");
+        assert!(content.contains("cc main.c\nmain.c:1: error") && !content.contains('\x1b'), "{}", content);
+        assert!(app.search_input.starts_with("Why did '") && app.search_input.contains("exit status: 2"), "{}", app.search_input);
+    }
+
+    #[test]
+    fn output_is_teed_to_the_session_log() {
+        let mut app = App::new();
+        assert!(app.reduce(AppAction::LaunchResult("quiet".into())).is_empty());
+        app.output_log = Some("session.log".into());
+        let fx = app.reduce(AppAction::LaunchFinished { seq: 9, ok: false, duration_ms: 1, output: "panic: x\nexit 101".into() });
+        assert!(matches!(fx.as_slice(), [Effect::TeeOutput { lines, .. }] if lines == &["panic: x", "exit 101"]));
+        assert!(app.reduce(AppAction::OutputTop).is_empty(), "nothing new, nothing written");
+        assert!(matches!(app.reduce(AppAction::ExportOutput).as_slice(), [Effect::ExportOutput(l)] if l.len() == 4));
+    }
+
+    #[test]
+    fn scrolled_output_stays_put_while_lines_arrive() {
+        let mut app = App::new();
+        app.reduce(AppAction::FocusOutput(true));
+        assert_eq!(app.input_mode, InputMode::Output);
+        app.reduce(AppAction::LaunchResult("a\nb\nc".into()));
+        app.reduce(AppAction::ScrollOutput(-2));
+        assert_eq!(app.output_end, Some(2));
+        app.reduce(AppAction::LaunchResult("d".into()));
+        assert_eq!(app.output_end, Some(2), "anchored to the same lines");
+        app.reduce(AppAction::ScrollOutput(-10));
+        assert_eq!(app.output_end, Some(1));
+        app.reduce(AppAction::ScrollOutput(10));
+        assert_eq!(app.output_end, None, "back at the bottom follows new output");
+        app.reduce(AppAction::OutputTop);
+        assert_eq!(app.output_end, Some(1));
+        app.reduce(AppAction::OutputBottom);
+        app.reduce(AppAction::FocusOutput(false));
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn submit_search_streams_into_assistant_message() {
+        let mut app = App::new();
+        app.current_convo_id = Some(3);
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty());
+
+        for c in "hi".chars() { app.reduce(AppAction::EnterSearchChar(c)); }
+        let fx = app.reduce(AppAction::SubmitSearch);
+        assert!(app.is_searching);
+        assert!(app.search_input.is_empty());
+        assert_eq!(fx, vec![Effect::StartSearch(SearchRequest {
+            query: "hi".into(), title: "hi".into(), convo_id: Some(3), model: "Loading...".into(), provider: "lmstudio".into(), active_providers: vec![], system_prompt: None, temperature: None, history: None,
+        })]);
+
+        app.search_input = "again".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty(), "no second search while streaming");
+
+        app.reduce(AppAction::SearchStreamToken("Hel".into()));
+        app.reduce(AppAction::SearchStreamToken("lo".into()));
+        app.reduce(AppAction::SearchDone);
+        assert!(!app.is_searching);
+        assert_eq!(app.messages.last().map(|m| m.content.as_str()), Some("Hello"));
+    }
+
+    #[test]
+    fn queues_a_message_while_the_backend_is_unreachable() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.convo_poll_ticks = 0;
+        app.search_input = "still there?".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchOffline("error sending request: connection refused".into()));
+        assert!(!app.is_searching);
+        assert_eq!(app.queued_message(), Some(1), "after the welcome note");
+        assert!(app.messages.iter().all(|m| m.role != "assistant"), "the empty answer is dropped");
+
+        app.search_input = "hello?".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty());
+        assert_eq!(app.search_input, "hello?", "kept for later");
+
+        // Retries follow the backoff, even with polling off.
+        let polls = (0..40).filter(|_| app.reduce(AppAction::Tick).contains(&Effect::FetchConversations)).count();
+        assert_eq!(polls, 1);
+        let fx = app.reduce(AppAction::ConversationsLoaded(vec![]));
+        let Some(Effect::StartSearch(req)) = fx.last() else { panic!("not resent") };
+        assert_eq!(req.query, "still there?");
+        assert_eq!(app.queued_message(), None);
+        let last: Vec<&str> = app.messages.iter().rev().take(2).map(|m| m.role.as_str()).collect();
+        assert_eq!(last, ["assistant", "user"]);
+    }
+
+    #[test]
+    fn counts_context_from_reported_usage_and_estimates() {
+        let mut app = App::new();
+        app.search_input = "what is rust".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchStreamToken("A language.".into()));
+        assert_eq!(app.context_tokens(), 3 + 3, "estimated while nothing is reported");
+        app.reduce(AppAction::ChatNotice("Copied.".into()));
+        app.reduce(AppAction::SearchUsage(Usage { prompt: 900, completion: 40 }));
+        app.reduce(AppAction::SearchDone);
+        assert_eq!(app.messages.last().and_then(|m| m.usage), Some(Usage { prompt: 900, completion: 40 }));
+        app.search_input = "and then".into();
+        assert_eq!(app.context_tokens(), 940 + 2, "the report covers everything before it");
+    }
+
+    #[test]
+    fn times_the_streaming_answer() {
+        let mut app = App::new();
+        app.search_input = "hi".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::Tick);
+        assert_eq!(app.stream_progress(), Some((250, None)), "no rate before the first token");
+        app.reduce(AppAction::SearchStreamToken("x".repeat(40)));
+        app.reduce(AppAction::Tick);
+        app.reduce(AppAction::Tick);
+        app.reduce(AppAction::SearchStreamToken("y".repeat(40)));
+        assert_eq!(app.stream_progress(), Some((750, Some(40.0))));
+        app.reduce(AppAction::SearchDone);
+        assert_eq!(app.stream_progress(), None);
+    }
+
+    #[test]
+    fn chat_top_and_bottom() {
+        let mut app = App::new();
+        app.chat_scroll = 30;
+        app.reduce(AppAction::ScrollChat(-3));
+        assert_eq!((app.chat_scroll, app.chat_auto_scroll), (27, false));
+        app.reduce(AppAction::ChatTop);
+        assert_eq!((app.chat_scroll, app.chat_auto_scroll), (0, false));
+        app.reduce(AppAction::ChatBottom);
+        assert!(app.chat_auto_scroll, "follows the stream again");
+    }
+
+    #[test]
+    fn yanks_a_visual_selection_of_chat_lines() {
+        let mut app = App::new();
+        app.chat_lines = ["ASSISTANT:", "one", "two", "three", ""].map(String::from).to_vec();
+        app.chat_scroll = 1;
+        app.reduce(AppAction::ChatVisualStart);
+        app.reduce(AppAction::ChatVisualMove(10));
+        app.reduce(AppAction::ChatVisualMove(-2));
+        assert_eq!(app.chat_visual, Some((1, 2)));
+        assert_eq!(app.reduce(AppAction::ChatYank), vec![Effect::CopyToClipboard("one\ntwo".into())]);
+        assert_eq!(app.chat_visual, None);
+        assert!(app.reduce(AppAction::ChatYank).is_empty());
+
+        app.is_searching = true;
+        app.messages.push(ChatMessage::new("assistant", "strea"));
+        app.reduce(AppAction::ChatNotice("Copied 2 lines (xclip).".into()));
+        app.reduce(AppAction::SearchStreamToken("m".into()));
+        assert_eq!(app.messages.last().unwrap().content, "stream", "notices go ahead of a streaming answer");
+    }
+
+    #[test]
+    fn folds_long_messages_and_sources() {
+        let mut app = App::new();
+        let long = (1..=30).map(|n| n.to_string()).collect::<Vec<_>>().join("\n");
+        app.messages.push(ChatMessage { sources: vec![SearchSource { title: "a".into(), url: "https://a".into(), content: String::new(), engine: "ddg".into() }], ..ChatMessage::new("assistant", long) });
+        assert!(!app.is_folded(1), "the newest message stays open");
+        app.messages.push(ChatMessage::new("user", "short"));
+        assert!(app.is_folded(1) && !app.is_folded(2));
+        app.chat_focus = Some(1);
+        app.reduce(AppAction::ToggleFold);
+        assert!(!app.is_folded(1));
+
+        app.chat_focus = None;
+        app.reduce(AppAction::ToggleSources);
+        assert!(app.sources_open.contains(&1), "the last message with sources");
+        app.reduce(AppAction::NewConversation);
+        assert!(app.message_folds.is_empty() && app.sources_open.is_empty());
+    }
+
+    #[test]
+    fn keeps_reasoning_apart_from_the_answer() {
+        let mut app = App::new();
+        app.search_input = "why".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchReasoningToken("The user asks why.".into()));
+        app.reduce(AppAction::SearchStreamToken("Because.".into()));
+        app.reduce(AppAction::SearchDone);
+        let last = app.messages.last().unwrap();
+        assert_eq!((last.reasoning.as_str(), last.content.as_str()), ("The user asks why.", "Because."));
+        app.reduce(AppAction::ToggleReasoning);
+        assert!(app.reasoning_open.contains(&2));
+        app.reduce(AppAction::NewConversation);
+        assert!(app.reasoning_open.is_empty());
+    }
+
+    #[test]
+    fn pairs_tool_results_with_their_calls() {
+        let mut app = App::new();
+        app.search_input = "2^10 in KiB".into();
+        app.reduce(AppAction::SubmitSearch);
+        let call = |v: serde_json::Value| ToolCall::from_event(&v);
+        app.reduce(AppAction::ToolCallStarted(call(serde_json::json!({"id": "a", "name": "scrape", "arguments": {"url": "https://x"}}))));
+        app.reduce(AppAction::ToolCallStarted(call(serde_json::json!({"function": {"name": "calc", "arguments": "2^10"}}))));
+        app.reduce(AppAction::ToolCallFinished(call(serde_json::json!({"name": "calc", "result": 1024}))));
+        app.reduce(AppAction::ToolCallFinished(call(serde_json::json!({"id": "a", "output": "ok"}))));
+        let tools = &app.messages.last().unwrap().tools;
+        assert_eq!(tools.iter().map(|c| (c.name.as_str(), c.args.as_str(), c.result.as_deref())).collect::<Vec<_>>(),
+            [("scrape", r#"{"url":"https://x"}"#, Some("ok")), ("calc", "2^10", Some("1024"))]);
+    }
+
+    #[test]
+    fn sources_pane_lists_each_url_once() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        let src = |title: &str, url: &str, engine: &str| SearchSource { title: title.into(), url: url.into(), content: String::new(), engine: engine.into() };
+        app.messages.push(ChatMessage { sources: vec![src("Rust", "https://rust-lang.org", "google"), src("Book", "https://doc.rust-lang.org/book", "duckduckgo")], ..ChatMessage::new("assistant", "a") });
+        app.messages.push(ChatMessage { sources: vec![src("Book", "https://doc.rust-lang.org/book", "duckduckgo")], ..ChatMessage::new("assistant", "b") });
+        app.reduce(AppAction::ToggleSourcesPane);
+        assert_eq!(app.input_mode, InputMode::SourcesPane);
+        let cited: Vec<(String, usize)> = app.pane_sources().into_iter().map(|r| (r.source.title, r.cited)).collect();
+        assert_eq!(cited, [("Rust".to_string(), 1), ("Book".to_string(), 2)]);
+
+        app.reduce(AppAction::CycleSourceSort);
+        app.reduce(AppAction::CycleSourceSort);
+        app.reduce(AppAction::CycleSourceSort);
+        assert_eq!(app.pane_sources()[0].source.title, "Book", "most cited first");
+        app.reduce(AppAction::CycleSourceEngine);
+        assert_eq!((app.sources_engine.as_deref(), app.pane_sources().len()), (Some("duckduckgo"), 1));
+        assert_eq!(app.reduce(AppAction::CopyPaneSourceUrl), vec![Effect::CopyToClipboard("https://doc.rust-lang.org/book".into())]);
+        assert_eq!(app.reduce(AppAction::PreviewPaneSource), vec![Effect::FetchPreview("https://doc.rust-lang.org/book".into())]);
+        app.reduce(AppAction::CloseModal);
+        assert_eq!(app.input_mode, InputMode::SourcesPane, "the preview returns to the pane");
+        app.reduce(AppAction::CycleSourceEngine);
+        app.reduce(AppAction::CycleSourceEngine);
+        assert_eq!(app.sources_engine, None, "past the last engine: all of them");
+        app.reduce(AppAction::ToggleSourcesPane);
+        assert_eq!((app.sources_pane, app.input_mode.clone()), (false, InputMode::ChatHistory));
+    }
+
+    #[test]
+    fn trims_the_history_sent_with_a_query() {
+        let mut app = App::new();
+        for (role, text) in [("user", "q1"), ("assistant", "a1"), ("system", "note"), ("user", "q2"), ("assistant", "a2")] {
+            app.messages.push(ChatMessage::new(role, text));
+        }
+        let request = |app: &mut App| {
+            app.search_input = "q3".into();
+            let fx = app.reduce(AppAction::SubmitSearch);
+            app.reduce(AppAction::SearchDone);
+            app.messages.truncate(6);
+            match &fx[..] { [Effect::StartSearch(req)] => req.history.clone(), _ => panic!("no search") }
+        };
+        assert_eq!(request(&mut app), None, "untrimmed: the backend's stored history");
+
+        app.chat_focus = Some(2);
+        app.reduce(AppAction::ToggleExcluded);
+        assert!(app.messages[2].excluded);
+        let turns = |h: Option<Vec<(String, String)>>| h.unwrap().into_iter().map(|(_, t)| t).collect::<Vec<_>>();
+        assert_eq!(turns(request(&mut app)), ["q1", "q2", "a2"]);
+
+        app.search_input = "/context 2".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert_eq!(app.config.context_window, 2);
+        assert_eq!(app.messages.pop().unwrap().content, "Context: 2 of 4 messages sent (window of 2, 1 excluded).");
+        assert_eq!(turns(request(&mut app)), ["q2", "a2"]);
+        assert_eq!(app.context_tokens(), 2);
+
+        app.search_input = "/context all".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert!(!app.context_limited());
+        app.chat_focus = Some(3);
+        app.reduce(AppAction::ToggleExcluded);
+        assert!(!app.messages[3].excluded, "system notes are never sent anyway");
+    }
+
+    #[test]
+    fn branches_carry_their_history_into_a_new_conversation() {
+        let mut app = App::new();
+        app.current_convo_id = Some(3);
+        app.conversations = vec![convo(3, "rust")];
+        for (role, text) in [("user", "q1"), ("assistant", "a1"), ("user", "q2"), ("assistant", "a2")] {
+            app.messages.push(ChatMessage::new(role, text));
+        }
+        // a1, after the welcome note at index 0.
+        app.chat_focus = Some(2);
+        app.reduce(AppAction::BranchFromMessage);
+        assert_eq!((app.current_convo_id, app.messages.len(), app.input_mode.clone()), (None, 3, InputMode::SearchInput));
+        assert_eq!(app.messages[..2].iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["q1", "a1"], "the welcome note isn't carried over");
+        assert!(app.messages[2].content.starts_with("Branched from \"rust\""));
+
+        app.search_input = "q2 instead".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        let Some(Effect::StartSearch(req)) = fx.last() else { panic!("no search") };
+        assert_eq!(req.convo_id, None);
+        assert_eq!(req.history, Some(vec![("user".to_string(), "q1".to_string()), ("assistant".to_string(), "a1".to_string())]));
+        let fx = app.reduce(AppAction::ConversationCreated(9));
+        assert!(fx.contains(&Effect::WriteStore { name: store::BRANCHES, data: serde_json::json!({"9": {"from": 3, "from_title": "rust", "seed": [["user", "q1"], ["assistant", "a1"]]}}) }));
+
+        // The backend only has what came after; the seed is put back in front.
+        app.reduce(AppAction::LoadConversation(9));
+        app.reduce(AppAction::ConversationLoaded(serde_json::json!({"messages": [{"role": "user", "content": "q2 instead"}, {"role": "assistant", "content": "b2"}]})));
+        let chat: Vec<&str> = app.messages.iter().filter(|m| m.role != "system").map(|m| m.content.as_str()).collect();
+        assert_eq!(chat, ["q1", "a1", "q2 instead", "b2"]);
+        assert!(app.is_branch());
+    }
+
+    #[test]
+    fn direct_chat_sends_the_whole_history_to_the_endpoint() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Settings;
+        select_setting(&mut app, Setting::DirectChat);
+        let fx = app.reduce(AppAction::SettingsActivate);
+        assert!(matches!(fx.as_slice(), [Effect::FetchDirectModels { url, api_key: None }, Effect::WriteStore { name: store::CONFIG, .. }] if url == direct::DEFAULT_URL));
+
+        app.selected_model = "qwen3".into();
+        for (role, text) in [("user", "q1"), ("assistant", "a1"), ("system", "Pinned.")] {
+            app.messages.push(ChatMessage::new(role, text));
+        }
+        app.search_input = "q2".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        let Some(Effect::StartDirectChat(req)) = fx.last() else { panic!("no direct chat") };
+        let roles: Vec<&str> = req.messages.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+        assert_eq!((req.messages[0].1.as_str(), req.messages[3].1.as_str(), req.model.as_str()), (api::DEFAULT_SYSTEM_PROMPT, "q2", "qwen3"));
+        assert!(!fx.iter().any(|e| matches!(e, Effect::StartSearch(_))));
+        assert_eq!((app.messages.last().unwrap().provider.as_deref(), req.searxng.as_ref()), (Some("direct"), None));
+
+        app.reduce(AppAction::SearchDone);
+        select_setting(&mut app, Setting::SearxngUrl);
+        app.settings_edit = Some("http://searx:8888/".into());
+        app.reduce(AppAction::SettingsEditDone(true));
+        app.search_input = "q3".into();
+        let Some(Effect::StartDirectChat(req)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("no direct chat") };
+        assert_eq!(req.searxng.as_deref(), Some("http://searx:8888"), "web results are fetched first");
+    }
+
+    #[test]
+    fn configured_endpoints_are_providers_of_their_own() {
+        let mut app = App::new();
+        app.config.endpoints = vec![
+            Endpoint { name: "groq".into(), url: "https://api.groq.com/openai/v1".into(), api_key: Some("gsk_1".into()), models: Some(vec!["llama-3.3-70b".into()]) },
+            Endpoint { name: "vllm".into(), url: "http://gpu:8000/v1".into(), ..Default::default() },
+        ];
+        app.config.provider = Some("vllm".into());
+        app.apply_config();
+        assert_eq!(app.llm_providers[BUILTIN_PROVIDERS.len()..], ["groq", "vllm"]);
+        assert_eq!(app.reduce(AppAction::LoadSearchState)[2], Effect::FetchDirectModels { url: "http://gpu:8000/v1".into(), api_key: None });
+
+        app.current_screen = CurrentScreen::Settings;
+        select_setting(&mut app, Setting::Provider);
+        let fx = app.reduce(AppAction::SettingsCycle(false));
+        let Effect::Dispatch(AppAction::ModelsLoaded(models)) = &fx[0] else { panic!("the listed models are used as is") };
+        app.reduce(AppAction::ModelsLoaded(models.clone()));
+        assert_eq!((app.selected_llm_provider.as_str(), app.selected_model.as_str()), ("groq", "llama-3.3-70b"));
+
+        app.search_input = "hi".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        let Some(Effect::StartDirectChat(req)) = fx.last() else { panic!("not sent to the endpoint") };
+        assert_eq!((req.url.as_str(), req.api_key.as_deref()), ("https://api.groq.com/openai/v1", Some("gsk_1")));
+        assert_eq!(app.messages.last().unwrap().provider.as_deref(), Some("groq"));
+    }
+
+    #[test]
+    fn ollama_uses_its_own_api_in_direct_chat() {
+        let mut app = App::new();
+        app.selected_llm_provider = ollama::PROVIDER.into();
+        assert_eq!(app.reduce(AppAction::LoadSearchState)[2], Effect::FetchModels("ollama".into()), "passed through to the backend");
+        app.config.direct_chat = true;
+        assert_eq!(app.reduce(AppAction::LoadSearchState)[2], Effect::FetchOllamaModels(ollama::DEFAULT_URL.into()));
+
+        app.reduce(AppAction::ModelsLoaded(vec![Model { id: "llama3.1:8b".into(), name: "llama3.1:8b".into(), params: Some("8.0B".into()) }]));
+        assert_eq!(app.model_label(), "llama3.1:8b · 8.0B");
+        app.search_input = "hi".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        let Some(Effect::StartOllamaChat(req)) = fx.last() else { panic!("no ollama chat") };
+        assert_eq!((req.url.as_str(), req.model.as_str()), (ollama::DEFAULT_URL, "llama3.1:8b"));
+        assert_eq!(app.messages.last().unwrap().provider.as_deref(), Some("ollama"));
+    }
+
+    #[test]
+    fn searches_every_conversation_and_jumps_to_the_message() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(2, "Lifetimes"), convo(1, "Lunch")]));
+        let fx = app.reduce(AppAction::OpenHistorySearch);
+        assert_eq!(fx, vec![Effect::IndexConversations(vec![2, 1])]);
+        assert_eq!((app.input_mode.clone(), app.indexing), (InputMode::HistorySearch, 2));
+        let msgs = serde_json::json!({"messages": [{"role": "user", "content": "what is 'a"}, {"role": "assistant", "content": "A lifetime parameter."}]});
+        app.reduce(AppAction::ConversationIndexed { id: 2, json: msgs.clone() });
+        app.reduce(AppAction::ConversationIndexed { id: 1, json: serde_json::json!({"messages": []}) });
+        assert_eq!(app.indexing, 0);
+
+        for c in "lifetime".chars() { app.reduce(AppAction::HistoryChar(c)); }
+        assert_eq!(app.history_hits().iter().map(|h| h.message).collect::<Vec<_>>(), [None, Some(1)]);
+        app.reduce(AppAction::ConvoSearchNext);
+        assert_eq!(app.reduce(AppAction::HistoryOpen), vec![Effect::Dispatch(AppAction::LoadConversation(2))]);
+        app.reduce(AppAction::LoadConversation(2));
+        app.reduce(AppAction::ConversationLoaded(msgs));
+        assert_eq!(app.chat_focus, Some(1));
+        assert!(app.chat_focus_moved && !app.chat_auto_scroll);
+
+        assert!(app.reduce(AppAction::OpenHistorySearch).is_empty(), "already indexed");
+    }
+
+    #[test]
+    fn summarizes_into_the_chat_or_the_history_description() {
+        let mut app = App::new();
+        app.search_input = "/summarize".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert!(app.messages.last().unwrap().content.starts_with("Nothing to summarize"));
+        app.current_convo_id = Some(4);
+        app.messages.push(ChatMessage::new("user", "deploy?"));
+        app.messages.push(ChatMessage::new("assistant", "Run make deploy"));
+
+        app.search_input = "/summarize save".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        assert!(matches!(&fx[..], [Effect::Summarize { id: Some(4), save: true, prompt, .. }] if prompt.ends_with("Assistant: Run make deploy")));
+        app.search_input = "/summarize".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty(), "one at a time");
+        let fx = app.reduce(AppAction::SummaryDone { id: Some(4), save: true, result: Ok("Deploys use make.".into()) });
+        assert_eq!(fx, vec![Effect::WriteStore { name: store::SUMMARIES, data: serde_json::json!({"4": "Deploys use make."}) }]);
+        assert!(!app.summarizing);
+
+        app.reduce(AppAction::SummaryDone { id: Some(4), save: false, result: Ok("Short.".into()) });
+        assert_eq!(app.messages.last().unwrap().content, "Summary:\n\nShort.");
+        app.reduce(AppAction::SummaryDone { id: Some(9), save: false, result: Err("timeout".into()) });
+        assert_eq!(app.messages.last().unwrap().content, "Summary:\n\nShort.", "another conversation's result is dropped");
+    }
+
+    #[test]
+    fn names_a_new_conversation_after_its_first_answer() {
+        let named = |fx: &[Effect]| fx.iter().find_map(|e| match e { Effect::NameConversation { id, prompt, .. } => Some((*id, prompt.clone())), _ => None });
+        let mut app = App::new();
+        app.search_input = "how do i borrow twice".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::ConversationCreated(7));
+        app.reduce(AppAction::SearchStreamToken("You can't, mutably.".into()));
+        let (id, prompt) = named(&app.reduce(AppAction::SearchDone)).expect("a title request");
+        assert_eq!(id, 7);
+        assert!(prompt.contains("User: how do i borrow twice"));
+        app.search_input = "and immutably?".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert_eq!(named(&app.reduce(AppAction::SearchDone)), None, "only once");
+
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(7, "how do i borrow twice")]));
+        app.reduce(AppAction::ConversationRenamed { id: 7, title: "Borrowing twice".into() });
+        assert_eq!(app.conversations[0].title, "Borrowing twice");
+
+        // Opted out, or named by the backend while streaming: no request.
+        for by_backend in [false, true] {
+            app.reduce(AppAction::NewConversation);
+            app.config.auto_titles = by_backend;
+            app.search_input = "q".into();
+            app.reduce(AppAction::SubmitSearch);
+            app.reduce(AppAction::ConversationCreated(8));
+            if by_backend { app.reduce(AppAction::ConversationRenamed { id: 8, title: "Named".into() }); }
+            app.reduce(AppAction::SearchStreamToken("a".into()));
+            assert_eq!(named(&app.reduce(AppAction::SearchDone)), None);
+        }
+    }
+
+    #[test]
+    fn offers_follow_ups_under_the_latest_answer() {
+        let mut app = App::new();
+        app.config.follow_ups = true;
+        app.search_input = "what is rust".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchStreamToken("A language.".into()));
+        let fx = app.reduce(AppAction::SearchDone);
+        assert!(fx.iter().any(|e| matches!(e, Effect::SuggestFollowUps { prompt, .. } if prompt.contains("User: what is rust"))));
+        app.reduce(AppAction::FollowUpsReceived(vec!["Is it fast?".into(), "Who made it?".into()]));
+        app.reduce(AppAction::FollowUpsReceived(vec!["Late?".into()]));
+        assert_eq!(app.messages.last().unwrap().follow_ups, ["Is it fast?", "Who made it?"], "the first list sticks");
+        assert!(app.reduce(AppAction::UseFollowUp { n: 3, send: true }).is_empty());
+        assert_eq!(app.reduce(AppAction::UseFollowUp { n: 2, send: true }), vec![Effect::Dispatch(AppAction::SubmitSearch)]);
+        assert_eq!((app.search_input.as_str(), app.input_mode.clone()), ("Who made it?", InputMode::SearchInput));
+    }
+
+    #[test]
+    fn regenerates_the_last_answer_with_current_settings() {
+        let mut app = App::new();
+        app.current_convo_id = Some(3);
+        app.reduce(AppAction::RegenerateLast);
+        assert!(app.messages.last().unwrap().content.starts_with("Nothing to regenerate"));
+
+        app.pending_context.push(ContextItem { label: "a.rs".into(), content: "fn a() {}".into() });
+        app.search_input = "explain".into();
+        let Some(Effect::StartSearch(first)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("expected search") };
+        app.reduce(AppAction::SearchStreamToken("partial".into()));
+        assert!(app.reduce(AppAction::RegenerateLast).is_empty(), "waits for the stream");
+        app.reduce(AppAction::SearchError("timeout".into()));
+
+        app.selected_model = "bigger".into();
+        let Some(Effect::StartSearch(again)) = app.reduce(AppAction::RegenerateLast).pop() else { panic!("expected search") };
+        assert_eq!((again.query, again.model.as_str()), (first.query, "bigger"), "context is sent again");
+        let roles: Vec<&str> = app.messages.iter().skip(2).map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"], "old answer and error are replaced");
+        assert!(app.messages.last().unwrap().content.is_empty());
+        assert!(app.is_searching);
+    }
+
+    #[test]
+    fn edits_an_earlier_message_and_replaces_the_rest() {
+        let mut app = App::new();
+        app.reduce(AppAction::EditMessage { cut: false });
+        assert!(app.messages.last().unwrap().content.starts_with("No message of yours"));
+        app.pending_context.push(ContextItem { label: "a.rs".into(), content: "fn a() {}".into() });
+        for q in ["explain tihs", "and then?"] {
+            app.search_input = q.into();
+            app.reduce(AppAction::SubmitSearch);
+            app.reduce(AppAction::SearchDone);
+        }
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::FocusMessage { forward: false, mine: true });
+        app.reduce(AppAction::FocusMessage { forward: false, mine: true });
+        app.reduce(AppAction::FocusMessage { forward: false, mine: true });
+        assert_eq!((app.chat_focus, app.chat_focus_moved), (Some(2), true), "stays on the oldest");
+        app.reduce(AppAction::FocusMessage { forward: true, mine: false });
+        app.reduce(AppAction::EditMessage { cut: false });
+        assert_eq!(app.search_input, "explain tihs", "an answer edits the question before it");
+        app.reduce(AppAction::FocusMessageEdge(false));
+        app.reduce(AppAction::EditMessage { cut: false });
+        assert_eq!(app.search_input, "and then?", "nothing before the first question: the last one");
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::FocusMessageEdge(true));
+        app.reduce(AppAction::FocusMessage { forward: false, mine: true });
+        app.reduce(AppAction::FocusMessage { forward: false, mine: true });
+        app.reduce(AppAction::EditMessage { cut: true });
+        assert_eq!((app.input_mode.clone(), app.search_input.as_str(), app.pending_context.len()), (InputMode::SearchInput, "explain tihs", 1));
+
+        app.search_input = "explain this".into();
+        let Some(Effect::StartSearch(req)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("expected search") };
+        assert!(req.query.starts_with("Context from `a.rs`"));
+        let asked: Vec<&str> = app.messages.iter().filter(|m| m.role == "user").map(ChatMessage::question).collect();
+        assert_eq!(asked, ["explain this"]);
+        assert_eq!((app.chat_focus, app.edit_from), (None, None));
+    }
+
+    #[test]
+    fn recalls_and_searches_submitted_input() {
+        let mut app = App::new();
+        for q in ["why is the build slow", "= 2 + 2", "why is the build slow "] {
+            app.search_input = q.into();
+            app.reduce(AppAction::SubmitSearch);
+            app.reduce(AppAction::SearchDone);
+        }
+        assert_eq!(app.query_history, ["= 2 + 2", "why is the build slow"], "repeats move to the end");
+
+        app.search_input = "draft".into();
+        app.reduce(AppAction::QueryPrev);
+        app.reduce(AppAction::QueryPrev);
+        app.reduce(AppAction::QueryPrev);
+        assert_eq!((app.search_input.as_str(), app.query_pos), ("= 2 + 2", Some(0)));
+        app.reduce(AppAction::QueryNext);
+        app.reduce(AppAction::QueryNext);
+        assert_eq!((app.search_input.as_str(), app.query_pos), ("draft", None), "past the newest the draft comes back");
+
+        app.reduce(AppAction::QuerySearchStart);
+        for c in "BUILD".chars() { app.reduce(AppAction::QuerySearchChar(c)); }
+        assert_eq!(app.query_search.as_ref().and_then(|h| h.hit), Some(1));
+        app.reduce(AppAction::QuerySearchStart);
+        assert_eq!(app.query_search.as_ref().and_then(|h| h.hit), Some(1), "no older match stays put");
+        app.reduce(AppAction::QuerySearchChar('!'));
+        assert_eq!(app.query_search.as_ref().and_then(|h| h.hit), None);
+        app.reduce(AppAction::QuerySearchBackspace);
+        app.reduce(AppAction::QuerySearchDone(true));
+        assert_eq!((app.search_input.as_str(), app.query_search.clone()), ("why is the build slow", None));
+    }
+
+    #[test]
+    fn attachments_are_saved_and_openable() {
+        let mut app = App::new();
+        let att = Attachment { filename: Some("chart.png".into()), mime: Some("image/png".into()), data: "AA==".into() };
+        assert_eq!(app.reduce(AppAction::AttachmentReceived(att.clone())), vec![Effect::SaveAttachment(att)]);
+
+        app.messages.push(ChatMessage::new("assistant", "here is a chart"));
+        app.messages.push(ChatMessage::new("system", "note"));
+        app.reduce(AppAction::AttachmentSaved("/dl/chart.png".into()));
+        assert_eq!(app.messages[app.messages.len() - 2].attachments, vec!["/dl/chart.png".to_string()]);
+        assert_eq!(app.reduce(AppAction::OpenLastAttachment), vec![Effect::OpenExternal("/dl/chart.png".into())]);
+    }
+
+    #[test]
+    fn project_binding_follows_conversation() {
+        let mut app = App::new();
+        app.search_input = "/cd /work/app".into();
+        assert_eq!(app.reduce(AppAction::SubmitSearch), vec![Effect::ResolveProjectDir("/work/app".into())]);
+        assert!(app.search_input.is_empty());
+
+        assert!(app.reduce(AppAction::ProjectBound("/work/app".into())).is_empty(), "pending until the conversation exists");
+        assert_eq!(app.current_project(), Some("/work/app"));
+        let fx = app.reduce(AppAction::ConversationCreated(8));
+        assert_eq!(fx[0], Effect::WriteStore { name: store::PROJECTS, data: serde_json::json!({"8": "/work/app"}) });
+
+        app.search_input = "/file src/lib.rs".into();
+        assert_eq!(app.reduce(AppAction::SubmitSearch), vec![Effect::ReadContextFile { path: "/work/app/src/lib.rs".into(), label: "src/lib.rs".into() }]);
+
+        app.reduce(AppAction::NewConversation);
+        assert_eq!(app.current_project(), None);
+        app.reduce(AppAction::LoadConversation(8));
+        assert_eq!(app.current_project(), Some("/work/app"));
+    }
+
+    #[test]
+    fn git_commands_need_a_bound_project() {
+        let mut app = App::new();
+        app.search_input = "/gitdiff".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty());
+        assert!(app.messages.last().unwrap().content.contains("/cd"));
+
+        app.pending_project = Some("/work/app".into());
+        app.search_input = "/gitdiff staged".into();
+        assert_eq!(app.reduce(AppAction::SubmitSearch), vec![Effect::RunGit {
+            dir: "/work/app".into(), args: vec!["diff".into(), "--cached".into()], label: "git diff --cached".into(),
+        }]);
+    }
+
+    #[test]
+    fn attached_context_is_sent_with_next_query_only() {
+        let mut app = App::new();
+        app.reduce(AppAction::ContextAttached { label: "a.rs".into(), content: "fn a() {}\n".into() });
+        app.search_input = "explain".into();
+        let Some(Effect::StartSearch(req)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("expected search") };
+        assert_eq!(req.query, "Context from `a.rs`:\n```\nfn a() {}\n```\n\nexplain");
+        assert_eq!(req.title, "explain");
+        assert!(app.pending_context.is_empty());
+    }
+
+    #[test]
+    fn hooks_run_on_answers_and_launches() {
+        let mut app = App::new();
+        app.config.hooks = crate::hooks::Hooks { on_search_done: Some("notify-send bpt".into()), on_launch_failure: Some("logger".into()), ..Default::default() };
+        app.search_input = "why".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchStreamToken("Because.".into()));
+        let fx = app.reduce(AppAction::SearchDone);
+        let Some(Effect::RunHook { event, cmd, payload }) = fx.into_iter().find(|e| matches!(e, Effect::RunHook { .. })) else { panic!("expected the search hook") };
+        assert_eq!((event, cmd.as_str()), ("search_done", "notify-send bpt"));
+        assert_eq!((payload["query"].as_str(), payload["answer"].as_str(), payload["duration_ms"].as_u64()), (Some("why"), Some("Because."), Some(0)));
+
+        app.apps = vec![app_model("3", "web", "")];
+        app.reduce(AppAction::LaunchApp("3".into()));
+        let fx = app.reduce(AppAction::LaunchFinished { seq: 1, ok: true, duration_ms: 5, output: "Success".into() });
+        assert!(!fx.iter().any(|e| matches!(e, Effect::RunHook { .. })), "no success hook configured");
+        app.reduce(AppAction::LaunchApp("3".into()));
+        let fx = app.reduce(AppAction::LaunchFinished { seq: 2, ok: false, duration_ms: 9, output: "Failed: exit status: 1".into() });
+        let Some(Effect::RunHook { event, payload, .. }) = fx.into_iter().find(|e| matches!(e, Effect::RunHook { .. })) else { panic!("expected the failure hook") };
+        assert_eq!(event, "launch_failure");
+        assert_eq!(payload, serde_json::json!({ "event": "launch_failure", "app_id": "3", "name": "web", "ok": false, "status": "Failed: exit status: 1", "duration_ms": 9 }));
+    }
+
+    #[test]
+    fn answers_and_selections_are_saved_as_apps() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.search_input = "serve this folder".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchStreamToken("Run it with:\n```python\nimport http.server\n```".into()));
+        app.reduce(AppAction::SearchDone);
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::Snippet(Verb::SaveApp));
+        assert_eq!((app.input_mode.clone(), app.active_form.cmd.text.as_str(), app.active_form.desc.as_str()), (InputMode::Editing, "import http.server", "From chat"));
+
+        app.reduce(AppAction::CloseModal);
+        app.reduce(AppAction::SwitchTab);
+        app.input_mode = InputMode::ChatHistory;
+        app.chat_lines = vec!["Assistant:".into(), "  python3 -m http.server 8000".into()];
+        app.chat_visual = Some((1, 1));
+        app.reduce(AppAction::Snippet(Verb::SaveApp));
+        assert_eq!((app.active_form.name.as_str(), app.active_form.cmd.text.as_str(), app.chat_visual), ("python3", "python3 -m http.server 8000", None));
+    }
+
+    #[test]
+    fn code_blocks_are_picked_and_copied_verbatim() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.search_input = "print something".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchStreamToken("Two ways:\n```rust\nfn main() {\n    println!(\"{}\", \"a very long line that the chat pane would wrap\");\n}\n```\n```py\nprint(1)\n```".into()));
+        app.reduce(AppAction::SearchDone);
+        app.input_mode = InputMode::ChatHistory;
+        assert!(app.reduce(AppAction::CopyCodeBlock).is_empty());
+        assert_eq!(app.code_picker, Some((2, 0)), "the answer, after the welcome note and question");
+        app.reduce(AppAction::CodePickerMove(5));
+        assert_eq!(app.code_picker, Some((2, 1)));
+        let fx = app.reduce(AppAction::CodePickerCopy(Some(0)));
+        assert_eq!(fx, vec![Effect::CopyToClipboard("fn main() {\n    println!(\"{}\", \"a very long line that the chat pane would wrap\");\n}".into())]);
+        assert_eq!(app.code_picker, None);
+        app.reduce(AppAction::CopyCodeBlock);
+        assert!(app.reduce(AppAction::CodePickerCopy(Some(7))).is_empty(), "out of range keeps the picker open");
+        app.reduce(AppAction::CodePickerMove(1));
+        assert_eq!(app.reduce(AppAction::CodePickerCopy(None)), vec![Effect::CopyToClipboard("print(1)".into())]);
+    }
+
+    #[test]
+    fn zen_mode_hides_the_side_panes() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.reduce(AppAction::ToggleSearchSidebar);
+        assert_eq!(app.input_mode, InputMode::SearchSidebar);
+        app.reduce(AppAction::ToggleZen);
+        assert_eq!((app.zen, app.input_mode.clone()), (true, InputMode::SearchInput));
+        app.reduce(AppAction::CycleSearchFocus);
+        assert_eq!(app.input_mode, InputMode::ChatHistory, "the hidden sidebar is skipped");
+        app.reduce(AppAction::ToggleSearchSidebar);
+        assert!(!app.zen, "opening a pane leaves zen mode");
+        app.input_mode = InputMode::Normal;
+        app.reduce(AppAction::ToggleZen);
+        assert!(!app.zen);
+    }
+
+    #[test]
+    fn finished_answers_are_handed_to_a_screen_reader() {
+        let mut app = App::new();
+        app.messages.push(ChatMessage::new("user", "how?"));
+        app.messages.push(ChatMessage::new("assistant", "Run **this**:\n\n```sh\nmake\n```"));
+        app.is_searching = true;
+        assert!(!app.reduce(AppAction::SearchDone).iter().any(|e| matches!(e, Effect::Speak { .. })), "only with --a11y-out");
+
+        app.a11y_out = Some(Sink::File("reader.fifo".into()));
+        let speak = |text: &str| Effect::Speak { sink: Sink::File("reader.fifo".into()), text: text.into() };
+        assert!(app.reduce(AppAction::SearchDone).contains(&speak("Run this:\n\nmake")));
+        assert!(app.reduce(AppAction::SearchError("timed out".into())).contains(&speak("Error: timed out")));
+    }
+
+    #[test]
+    fn pane_splits_resize_and_persist() {
+        let mut app = App::new();
+        for _ in 0..10 { app.reduce(AppAction::ResizePane(config::SPLIT_STEP)); }
+        assert_eq!(app.config.launcher_split, 80);
+        assert!(app.reduce(AppAction::ResizePane(config::SPLIT_STEP)).is_empty(), "nothing to save at the limit");
+        app.reduce(AppAction::SwitchTab);
+        assert!(app.reduce(AppAction::ResizePane(-config::SPLIT_STEP)).is_empty(), "no sidebar shown");
+        app.reduce(AppAction::ToggleSearchSidebar);
+        let fx = app.reduce(AppAction::ResizePane(-config::SPLIT_STEP));
+        assert_eq!(app.config.sidebar_split, 20);
+        assert!(matches!(fx.as_slice(), [Effect::WriteStore { name: store::CONFIG, .. }]));
+    }
+
+    #[test]
+    fn app_mentions_go_along_and_answers_launch_apps() {
+        let mut app = App::new();
+        app.apps = vec![app_model("1", "LM Studio", "local models"), app_model("2", "Jupyter", "notebooks")];
+        app.update_filter();
+        app.reduce(AppAction::SwitchTab);
+        app.search_input = "does @lm".into();
+        assert_eq!(app.mention_suggestions().len(), 1);
+        app.reduce(AppAction::CompleteMention);
+        assert_eq!(app.search_input, "does @LM-Studio ");
+        app.search_input.push_str("need a GPU?");
+        let Some(Effect::StartSearch(req)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("expected search") };
+        assert!(req.query.starts_with("Context from `@LM-Studio`:\n```\nApp \"LM Studio\""), "{}", req.query);
+        assert!(req.query.ends_with("does @LM-Studio need a GPU?"));
+
+        app.reduce(AppAction::SearchStreamToken("Yes; or run it in @Jupyter with LM Studio's server.".into()));
+        app.reduce(AppAction::SearchDone);
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::LaunchRecommended);
+        assert_eq!(app.launch_choice, Some(vec!["2".to_string(), "1".to_string()]));
+        let fx = app.reduce(AppAction::ChooseLaunch(Some(1)));
+        assert_eq!((app.current_screen.clone(), app.launch_choice.clone()), (CurrentScreen::Launcher, None));
+        assert_eq!(fx.last(), Some(&Effect::Dispatch(AppAction::LaunchApp("1".into()))));
+        assert_eq!(app.get_selected_app().map(|a| a.id.as_str()), Some("1"));
+    }
+
+    #[test]
+    fn shell_blocks_in_answers_are_copied_run_or_saved() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.search_input = "check the load".into();
+        app.reduce(AppAction::SubmitSearch);
+        app.reduce(AppAction::SearchStreamToken("Try:\n```bash\n$ uptime\n```\nor\n```sh\nfree -h\n```".into()));
+        app.reduce(AppAction::SearchDone);
+        app.input_mode = InputMode::ChatHistory;
+        assert!(app.reduce(AppAction::Snippet(Verb::Copy)).is_empty());
+        assert_eq!(app.snippet_choice, Some(Verb::Copy));
+        assert_eq!(app.reduce(AppAction::ChooseSnippet(Some(0))), vec![Effect::CopyToClipboard("uptime".into())]);
+
+        app.reduce(AppAction::Snippet(Verb::Run));
+        app.reduce(AppAction::ChooseSnippet(Some(1)));
+        assert_eq!(app.pending_confirm, Some(ConfirmAction::RunSnippet("free -h".into())));
+        let fx = app.reduce(AppAction::Confirm(true));
+        assert_eq!(app.current_screen, CurrentScreen::Launcher);
+        assert_eq!(fx.last(), Some(&Effect::Dispatch(AppAction::SubmitAdHoc("free -h".into()))));
+
+        app.reduce(AppAction::SwitchTab);
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::Snippet(Verb::SaveApp));
+        assert!(app.reduce(AppAction::ChooseSnippet(None)).is_empty());
+        assert_eq!((app.snippet_choice, app.input_mode.clone()), (None, InputMode::ChatHistory));
+        app.reduce(AppAction::Snippet(Verb::SaveApp));
+        app.reduce(AppAction::ChooseSnippet(Some(0)));
+        assert_eq!((app.current_screen.clone(), app.input_mode.clone(), app.active_form.cmd.text.as_str()), (CurrentScreen::Launcher, InputMode::Editing, "uptime"));
+    }
+
+    #[test]
+    fn plugins_add_commands_panels_and_apps() {
+        let mut app = App::new();
+        app.config.plugins = vec![
+            crate::plugins::PluginConfig { name: "jira".into(), command: "bpt-jira".into() },
+            crate::plugins::PluginConfig { name: "a:b".into(), command: "x".into() },
+            crate::plugins::PluginConfig { name: "jira".into(), command: "bpt-jira --cloud".into() },
+        ];
+        assert_eq!(app.reduce(AppAction::StartPlugins), vec![Effect::StartPlugin(app.config.plugins[0].clone())]);
+        assert!(app.launcher_logs.iter().any(|l| l == "Skipping plugin 'jira': another plugin already has that name."));
+
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({"commands": [{"name": "issue"}], "panels": [{"id": "mine", "title": "My issues"}], "apps": true})).unwrap();
+        let fx = app.reduce(AppAction::PluginReady { plugin: "jira".into(), manifest });
+        assert_eq!(fx, vec![list_plugin_apps("jira"), render_panel("jira", "mine")]);
+
+        // Built-in commands keep their names; the rest go to the plugin that registered them.
+        app.search_input = "/issue BPT-1".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        assert_eq!(fx, vec![Effect::PluginCall { plugin: "jira".into(), method: "command", params: serde_json::json!({"name": "issue", "args": "BPT-1", "conversation_id": null}), reply: Reply::Command }]);
+        app.search_input = "/nope".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert!(app.messages.last().unwrap().content.starts_with("Unknown command /nope"));
+
+        app.apps = vec![app_model("1", "web", "")];
+        app.reduce(AppAction::PluginApps { plugin: "jira".into(), apps: vec![(app_model("plugin:jira:BPT-1", "BPT-1", ""), "open".into())] });
+        app.reduce(AppAction::AppsLoaded(vec![app_model("1", "web", "")]));
+        assert_eq!(app.apps.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["1", "plugin:jira:BPT-1"], "backend reloads keep plugin apps");
+        app.apps_idx = app.filtered_apps.iter().position(|&i| app.apps[i].id.starts_with("plugin:")).unwrap();
+        app.reduce(AppAction::RequestDelete);
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some(SOURCE_READONLY));
+
+        app.reduce(AppAction::PluginPanel { plugin: "jira".into(), panel: "mine".into(), lines: vec!["BPT-1 Crash".into()] });
+        app.current_screen = CurrentScreen::Search;
+        app.input_mode = InputMode::SearchInput;
+        app.search_sidebar = SearchSidebarState::Settings;
+        app.reduce(AppAction::ToggleSearchSidebar);
+        assert_eq!((app.search_sidebar.clone(), app.plugin_panels[0].lines.len()), (SearchSidebarState::Plugins, 1));
+
+        app.reduce(AppAction::PluginExited { plugin: "jira".into(), error: String::new() });
+        assert_eq!((app.apps.len(), app.plugin_panels.len(), app.search_sidebar.clone()), (1, 0, SearchSidebarState::Hidden));
+    }
+
+    #[test]
+    fn piped_input_is_asked_about_once_models_load() {
+        let mut app = App::new();
+        let fx = app.reduce(AppAction::AskOnStart { query: Some("why is this failing".into()), input: Some("error: disk full\n".into()) });
+        assert_eq!(fx, vec![Effect::Dispatch(AppAction::LoadSearchState)]);
+        assert_eq!((app.current_screen.clone(), app.search_input.as_str(), app.pending_context.len()), (CurrentScreen::Search, "why is this failing", 1));
+        assert_eq!(app.reduce(AppAction::ModelsLoaded(vec![Model { id: "m".into(), name: "M".into(), params: None }])), vec![Effect::Dispatch(AppAction::SubmitSearch)]);
+        let Some(Effect::StartSearch(req)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("expected search") };
+        assert_eq!((req.query.as_str(), req.model.as_str()), ("Context from `stdin`:\n```\nerror: disk full\n```\n\nwhy is this failing", "m"));
+
+        // Without models (say, the backend is down) it goes after a few seconds anyway.
+        let mut app = App::new();
+        app.reduce(AppAction::AskOnStart { query: Some("why".into()), input: None });
+        let asked = (0..ASK_WAIT_TICKS).map(|_| app.reduce(AppAction::Tick)).filter(|fx| fx.contains(&Effect::Dispatch(AppAction::SubmitSearch))).count();
+        assert_eq!((asked, app.ask_at), (1, None));
+    }
+
+    #[test]
+    fn file_browser_attaches_files_and_picks_working_dirs() {
+        let mut app = App::new();
+        app.pending_project = Some("/work/app".into());
+        app.search_input = "/file".into();
+        assert_eq!(app.reduce(AppAction::SubmitSearch), vec![Effect::ListDir("/work/app".into())]);
+        let listed = |dir: &str, names: &[(&str, bool)]| AppAction::FilesListed { dir: dir.into(), entries: Ok(names.iter().map(|(n, d)| files::Entry { name: n.to_string(), is_dir: *d }).collect()) };
+        app.reduce(listed("/work/app", &[("README.md", false), ("src", true)]));
+        app.reduce(AppAction::FileBrowserMove(1));
+        assert_eq!(app.reduce(AppAction::FileBrowserOpen), vec![Effect::ListDir("/work/app/src".into())]);
+        app.reduce(listed("/work/app/src", &[("main.rs", false), ("lib.rs", false)]));
+        for c in "main".chars() { app.reduce(AppAction::FileBrowserChar(c)); }
+        assert_eq!(app.reduce(AppAction::FileBrowserOpen), vec![Effect::ReadContextFile { path: "/work/app/src/main.rs".into(), label: "src/main.rs".into() }]);
+        assert!(app.file_browser.is_none());
+
+        app.current_screen = CurrentScreen::Launcher;
+        app.input_mode = InputMode::Normal;
+        app.reduce(AppAction::OpenAddModal);
+        app.active_form.cwd = "/srv".into();
+        assert_eq!(app.reduce(AppAction::OpenFileBrowser(Pick::WorkingDir)), vec![Effect::ListDir("/srv".into())]);
+        app.reduce(AppAction::FilesListed { dir: "/srv".into(), entries: Err("permission denied".into()) });
+        assert_eq!(app.file_browser.as_ref().unwrap().error.as_deref(), Some("/srv: permission denied"));
+        app.reduce(listed("/srv", &[("api", true)]));
+        assert_eq!(app.reduce(AppAction::FileBrowserBackspace), vec![Effect::ListDir("/".into())], "Backspace on an empty filter goes up");
+        app.reduce(AppAction::FileBrowserUseDir);
+        assert_eq!((app.active_form.cwd.as_str(), app.input_mode.clone()), ("/srv", InputMode::Editing));
+        assert!(app.file_browser.is_none());
+    }
+
+    #[test]
+    fn unknown_slash_command_is_reported_not_sent() {
+        let mut app = App::new();
+        app.search_input = "/frobnicate".into();
+        assert!(app.reduce(AppAction::SubmitSearch).is_empty());
+        assert!(app.messages.last().unwrap().content.starts_with("Unknown command /frobnicate"));
+        assert!(!app.is_searching);
+    }
+
+    #[test]
+    fn file_refs_resolve_against_project_and_cycle() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Search;
+        app.pending_project = Some("/work/app".into());
+        app.messages.push(ChatMessage::new("assistant", "Old: main.rs:1"));
+        app.messages.push(ChatMessage::new("assistant", "Change `src/app.rs:42` and /etc/x.conf:3"));
+        app.reduce(AppAction::OpenFileRef);
+        assert_eq!(app.editor_request.take().map(|(p, r)| (p, r.line)), Some(("/work/app/src/app.rs".into(), 42)));
+        app.reduce(AppAction::OpenFileRef);
+        assert_eq!(app.editor_request.take().map(|(p, _)| p), Some("/etc/x.conf".into()));
+        app.reduce(AppAction::OpenFileRef);
+        assert_eq!(app.editor_request.take().map(|(p, _)| p), Some("/work/app/main.rs".into()));
+
+        app.current_screen = CurrentScreen::Launcher;
+        app.reduce(AppAction::OpenFileRef);
+        assert!(app.editor_request.is_none());
+    }
+
+    #[test]
+    fn search_error_appends_system_message() {
+        let mut app = App::new();
+        app.is_searching = true;
+        app.reduce(AppAction::SearchError("boom".into()));
+        assert!(!app.is_searching);
+        let last = app.messages.last().unwrap();
+        assert_eq!((last.role.as_str(), last.content.as_str()), ("system", "Error: boom"));
+    }
+
+    #[test]
+    fn sidebar_cycles_and_focus_follows() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.reduce(AppAction::ToggleSearchSidebar);
+        assert_eq!((app.search_sidebar.clone(), app.input_mode.clone()), (SearchSidebarState::History, InputMode::SearchSidebar));
+        app.reduce(AppAction::CycleSearchFocus);
+        assert_eq!(app.input_mode, InputMode::ChatHistory);
+        app.reduce(AppAction::CycleSearchFocus);
+        assert_eq!(app.input_mode, InputMode::SearchInput);
+        app.reduce(AppAction::ToggleSearchSidebar);
+        app.reduce(AppAction::ToggleSearchSidebar);
+        assert_eq!(app.search_sidebar, SearchSidebarState::Settings);
+        app.reduce(AppAction::ToggleSearchSidebar);
+        assert_eq!((app.search_sidebar.clone(), app.input_mode.clone()), (SearchSidebarState::Hidden, InputMode::SearchInput));
+    }
+
+    #[test]
+    fn pinned_answers_link_back_and_unpin() {
+        let mut app = App::new();
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(5, "rust lifetimes")]));
+        app.current_convo_id = Some(5);
+        app.messages.push(ChatMessage::new("assistant", "Use `'a` here."));
+        let fx = app.reduce(AppAction::PinLastAnswer);
+        assert_eq!(app.highlights.iter().map(|h| (h.convo_id, h.convo_title.as_str())).collect::<Vec<_>>(), [(Some(5), "rust lifetimes")]);
+        assert!(matches!(&fx[..], [Effect::WriteStore { name: store::HIGHLIGHTS, .. }]));
+
+        app.current_convo_id = None;
+        app.search_sidebar = SearchSidebarState::Highlights;
+        assert_eq!(app.reduce(AppAction::SidebarSelect), vec![Effect::Dispatch(AppAction::LoadConversation(5))]);
+        app.reduce(AppAction::RequestDeleteConversation);
+        assert!(app.highlights.is_empty());
+
+        app.reduce(AppAction::PinLastAnswer);
+        app.reduce(AppAction::PinLastAnswer);
+        assert!(app.highlights.is_empty(), "pinning the same answer again unpins it");
+    }
+
+    #[test]
+    fn history_select_dispatches_new_or_load() {
+        let mut app = App::new();
+        app.search_sidebar = SearchSidebarState::History;
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(5, "first")]));
+        assert_eq!(app.reduce(AppAction::SidebarSelect), vec![Effect::Dispatch(AppAction::NewConversation)]);
+        app.reduce(AppAction::SidebarNext);
+        assert_eq!(app.reduce(AppAction::SidebarSelect), vec![Effect::Dispatch(AppAction::LoadConversation(5))]);
+        assert_eq!(app.reduce(AppAction::LoadConversation(5)), vec![Effect::LoadConversation(5)]);
+        assert_eq!(app.input_mode, InputMode::ChatHistory);
+    }
+
+    #[test]
+    fn settings_cycle_provider_and_toggle_source() {
+        let mut app = App::new();
+        app.search_sidebar = SearchSidebarState::Settings;
+        assert!(matches!(app.reduce(AppAction::SidebarSelect).as_slice(), [Effect::FetchModels(p), Effect::WriteStore { name: store::CONFIG, .. }] if p == "openai"));
+        assert_eq!(app.selected_llm_provider, "openai");
+
+        app.reduce(AppAction::ProvidersLoaded(vec![ProviderConfig { id: 1, name: "web".into(), type_: "searxng".into(), is_enabled: true, config: Value::Null }]));
+        app.reduce(AppAction::SidebarNext);
+        app.reduce(AppAction::SidebarNext);
+        app.reduce(AppAction::SidebarSelect);
+        assert!(!app.search_providers[0].is_enabled);
+        app.reduce(AppAction::SidebarNext);
+        assert_eq!(app.settings_idx, 0);
+
+        // Saved choices come back when the lists are loaded again.
+        assert_eq!(app.config.search_sources.get("web"), Some(&false));
+        app.reduce(AppAction::ProvidersLoaded(vec![ProviderConfig { id: 1, name: "web".into(), type_: "searxng".into(), is_enabled: true, config: Value::Null }]));
+        assert!(!app.search_providers[0].is_enabled);
+        app.config.model = Some("b".into());
+        app.reduce(AppAction::ModelsLoaded(vec![Model { id: "a".into(), name: "A".into(), params: None }, Model { id: "b".into(), name: "B".into(), params: None }]));
+        assert_eq!(app.selected_model, "b");
+    }
+
+    #[test]
+    fn search_sources_are_added_edited_and_removed() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Search;
+        app.input_mode = InputMode::SearchSidebar;
+        app.search_sidebar = SearchSidebarState::Settings;
+        let searx = ProviderConfig { id: 4, name: "web".into(), type_: "searxng".into(), is_enabled: true, config: serde_json::json!({"url": "http://searx:8080", "limit": 5, "headers": {"X": "1"}}) };
+        app.reduce(AppAction::ProvidersLoaded(vec![searx]));
+
+        app.settings_idx = 2;
+        app.reduce(AppAction::OpenSourceEditor { edit: true });
+        assert_eq!(app.input_mode, InputMode::SourceEditor);
+        assert_eq!(app.source_form.as_ref().unwrap().settings, "limit=5 url=http://searx:8080");
+        for c in " local".chars() { app.reduce(AppAction::SourceFormChar(c)); }
+        app.reduce(AppAction::SourceFormNext);
+        app.reduce(AppAction::SourceFormNext);
+        for c in " lang=en".chars() { app.reduce(AppAction::SourceFormChar(c)); }
+        let Some(Effect::SaveSource(saved)) = app.reduce(AppAction::SubmitSourceForm).pop() else { panic!("no save") };
+        assert_eq!((saved.id, saved.name.as_str()), (4, "web local"));
+        assert_eq!(saved.config, serde_json::json!({"url": "http://searx:8080", "limit": 5, "lang": "en", "headers": {"X": "1"}}), "nested settings are kept");
+        assert_eq!(app.input_mode, InputMode::SearchSidebar);
+
+        app.reduce(AppAction::OpenSourceEditor { edit: false });
+        app.reduce(AppAction::SourceFormNext);
+        app.reduce(AppAction::SourceFormNext);
+        for c in "oops".chars() { app.reduce(AppAction::SourceFormChar(c)); }
+        assert!(app.reduce(AppAction::SubmitSourceForm).is_empty());
+        let form = app.source_form.as_ref().unwrap();
+        assert_eq!((form.focus_idx, form.errors.len()), (0, 3), "name, type and settings are refused");
+        app.reduce(AppAction::CloseModal);
+
+        app.reduce(AppAction::RequestDeleteConversation);
+        assert_eq!(app.pending_confirm, Some(ConfirmAction::DeleteSource { id: 4, name: "web".into() }));
+        assert_eq!(app.reduce(AppAction::Confirm(true)), vec![Effect::DeleteSource(4)]);
+        assert!(app.search_providers.is_empty());
+    }
+
+    #[test]
+    fn source_settings_with_spaces_round_trip() {
+        let source = ProviderConfig { id: 2, name: "brave".into(), type_: "brave".into(), is_enabled: true, config: serde_json::json!({"query_suffix": "site:docs.rs OR site:rust-lang.org", "count": 3}) };
+        let form = SourceForm::from_provider(&source);
+        assert_eq!(form.settings, r#"count=3 query_suffix="site:docs.rs OR site:rust-lang.org""#);
+        assert!(form.validate().is_empty());
+        assert_eq!(form.to_provider(), source);
+
+        let form = SourceForm { settings: "region='en US'".into(), ..form };
+        assert_eq!(form.to_provider().config, serde_json::json!({"region": "en US"}));
+        let form = SourceForm { settings: "region='en US".into(), ..form };
+        assert_eq!(form.validate().get(&2).map(String::as_str), Some("unclosed '"));
+    }
+
+    #[test]
+    fn starred_and_recent_models_cycle_first() {
+        let mut app = App::new();
+        app.selected_llm_provider = "openai".into();
+        let models = ["a", "b", "c", "d"].map(|id| Model { id: id.into(), name: id.to_uppercase(), params: None }).to_vec();
+        app.reduce(AppAction::ModelsLoaded(models.clone()));
+        assert_eq!(app.selected_model, "a");
+
+        app.selected_model = "c".into();
+        assert!(matches!(app.reduce(AppAction::ToggleStarModel).as_slice(), [Effect::WriteStore { name: store::MODELS, .. }]));
+        app.selected_model = "d".into();
+        app.search_input = "hi".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        assert!(matches!(fx.first(), Some(Effect::WriteStore { name: store::MODELS, .. })));
+        app.reduce(AppAction::SearchDone);
+        app.search_input = "again".into();
+        assert_eq!(app.reduce(AppAction::SubmitSearch).len(), 1, "already the most recent");
+
+        let order: Vec<&str> = app.model_order().iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(order, ["c", "d", "a", "b"]);
+        app.reduce(AppAction::ModelsLoaded(models));
+        assert_eq!(app.selected_model, "c", "a starred model is picked over the first listed");
+        app.selected_llm_provider = "google".into();
+        assert!(!app.is_starred("c"), "stars are per provider");
+    }
+
+    #[test]
+    fn presets_switch_provider_model_and_sources() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Search;
+        app.input_mode = InputMode::SearchInput;
+        app.reduce(AppAction::ProvidersLoaded(vec![
+            ProviderConfig { id: 1, name: "web".into(), type_: "searxng".into(), is_enabled: true, config: Value::Null },
+            ProviderConfig { id: 2, name: "arxiv".into(), type_: "arxiv".into(), is_enabled: false, config: Value::Null },
+        ]));
+        app.config.presets.push(Preset {
+            name: "deep research".into(), provider: Some("openai".into()), model: Some("o3".into()),
+            system_prompt: Some("Cite sources.".into()), temperature: Some(0.2), search_sources: Some(["arxiv".to_string()].into()),
+        });
+        app.search_input = "/preset Deep Research".into();
+        let fx = app.reduce(AppAction::SubmitSearch);
+        assert!(matches!(fx.as_slice(), [Effect::FetchModels(p), Effect::WriteStore { name: store::CONFIG, .. }] if p == "openai"));
+        assert_eq!((app.config.preset.as_deref(), app.config.model.as_deref()), (Some("deep research"), Some("o3")));
+        assert_eq!(app.search_providers.iter().map(|p| p.is_enabled).collect::<Vec<_>>(), [false, true]);
+        app.reduce(AppAction::ModelsLoaded(vec![Model { id: "gpt".into(), name: "GPT".into(), params: None }, Model { id: "o3".into(), name: "o3".into(), params: None }]));
+        assert_eq!(app.selected_model, "o3");
+
+        app.search_input = "why".into();
+        let Some(Effect::StartSearch(req)) = app.reduce(AppAction::SubmitSearch).pop() else { panic!("no search") };
+        assert_eq!((req.system_prompt.as_deref(), req.temperature, req.active_providers), (Some("Cite sources."), Some(0.2), vec![2]));
+        app.reduce(AppAction::SearchDone);
+
+        app.search_input = "/preset save mine".into();
+        app.reduce(AppAction::SubmitSearch);
+        let mine = app.active_preset().unwrap();
+        assert_eq!((mine.name.as_str(), mine.model.as_deref(), mine.temperature), ("mine", Some("o3"), Some(0.2)), "prompt and temperature carry over");
+
+        app.reduce(AppAction::OpenPresets);
+        assert_eq!((app.input_mode.clone(), app.preset_idx), (InputMode::Presets, 2));
+        app.reduce(AppAction::PresetNext);
+        app.reduce(AppAction::PresetSelect);
+        assert_eq!((app.input_mode.clone(), app.active_preset()), (InputMode::SearchInput, None), "the first entry turns presets off");
+        app.search_input = "/preset nope".into();
+        app.reduce(AppAction::SubmitSearch);
+        assert!(app.messages.last().unwrap().content.starts_with("No preset 'nope'"));
+    }
+
+    fn select_setting(app: &mut App, setting: Setting) {
+        app.settings_row = app.settings_rows().iter().position(|r| r.setting == setting).unwrap();
+    }
+
+    #[test]
+    fn session_is_restored() {
+        let mut app = App::new();
+        let saved = UiSession { screen: CurrentScreen::Search, sidebar: SearchSidebarState::History, convo_id: Some(9) };
+        let fx = app.reduce(AppAction::RestoreSession(saved.clone()));
+        assert_eq!(fx, vec![Effect::Dispatch(AppAction::LoadSearchState), Effect::LoadConversation(9)]);
+        assert_eq!((app.input_mode.clone(), app.session()), (InputMode::SearchInput, saved));
+
+        let mut app = App::new();
+        assert!(app.reduce(AppAction::RestoreSession(UiSession::default())).is_empty(), "a first run stays on the Launcher");
+        assert_eq!(app.current_screen, CurrentScreen::Launcher);
+    }
+
+    #[test]
+    fn settings_tab_edits_values_and_keys() {
+        let mut app = App::new();
+        app.search_loaded = true;
+        app.current_screen = CurrentScreen::Processes;
+        app.reduce(AppAction::SwitchTab);
+        assert_eq!(app.input_mode, InputMode::Settings);
+
+        select_setting(&mut app, Setting::LauncherUrl);
+        app.reduce(AppAction::SettingsActivate);
+        assert_eq!(app.settings_edit.as_deref(), Some(api::DEFAULT_LAUNCHER_URL));
+        app.settings_edit = Some("box:5660".into());
+        assert!(app.reduce(AppAction::SettingsEditDone(true)).is_empty());
+        assert!(app.settings_error.is_some() && app.settings_edit.is_some(), "a bad value stays open for fixing");
+        app.settings_edit = Some("http://box:5660/".into());
+        let fx = app.reduce(AppAction::SettingsEditDone(true));
+        assert!(matches!(fx.as_slice(), [Effect::SetBackends { launcher, .. }, Effect::Dispatch(AppAction::LoadApps), Effect::WriteStore { name: store::CONFIG, .. }] if launcher == "http://box:5660"));
+        assert_eq!((app.config.launcher_url.as_str(), app.settings_error.as_ref()), ("http://box:5660", None));
+
+        select_setting(&mut app, Setting::HealthPoll);
+        app.reduce(AppAction::SettingsActivate);
+        app.reduce(AppAction::SettingsBackspace);
+        app.reduce(AppAction::SettingsBackspace);
+        app.reduce(AppAction::SettingsChar('3'));
+        app.reduce(AppAction::SettingsEditDone(true));
+        assert_eq!((app.config.health_poll_secs, app.health_poll_ticks), (3, config::ticks(3)));
+
+        select_setting(&mut app, Setting::Theme);
+        app.reduce(AppAction::SettingsCycle(false));
+        assert_eq!(app.config.theme, config::Theme::Gray);
+        select_setting(&mut app, Setting::AnsiColors);
+        app.reduce(AppAction::SettingsActivate);
+        assert!(!app.ansi_colors);
+
+        select_setting(&mut app, Setting::Binding("launcher.sort"));
+        app.reduce(AppAction::SettingsActivate);
+        assert!(app.settings_binding);
+        let fx = app.reduce(AppAction::SettingsBindKey(Some("z".into())));
+        assert!(matches!(fx.as_slice(), [Effect::WriteStore { name: store::KEYMAP, .. }]));
+        assert_eq!(app.keymap.keys("launcher.sort"), ["s", "z"]);
+        app.reduce(AppAction::SettingsUnbind);
+        assert!(app.keymap.keys("launcher.sort").is_empty());
+    }
+
+    #[test]
+    fn polled_conversations_mark_external_changes() {
+        let mut app = App::new();
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(1, "a"), convo(2, "b")]));
+        assert!(app.updated_convos.is_empty(), "first load marks nothing");
+
+        app.current_convo_id = Some(2);
+        app.conversation_idx = 1;
+        app.reduce(AppAction::ConversationsLoaded(vec![convo(3, "new"), convo(1, "a"), convo(2, "b renamed")]));
+        assert_eq!(app.updated_convos, HashSet::from([3]));
+        assert_eq!(app.conversation_idx, 2, "selection follows conversation 1");
+
+        app.reduce(AppAction::LoadConversation(3));
+        assert!(app.updated_convos.is_empty());
+    }
+
+    #[test]
+    fn tick_polls_only_on_search_screen() {
+        let mut app = App::new();
+        app.convo_poll_ticks = 2;
+        app.reduce(AppAction::Tick);
+        assert!(app.reduce(AppAction::Tick).is_empty());
+        app.current_screen = CurrentScreen::Search;
+        app.reduce(AppAction::Tick);
+        assert_eq!(app.reduce(AppAction::Tick), vec![Effect::FetchConversations]);
+    }
+
+    #[test]
+    fn failed_polls_back_off_and_recover() {
+        let mut app = App::new();
+        app.convo_poll_ticks = 2;
+        app.current_screen = CurrentScreen::Search;
+        app.reduce(AppAction::ConversationsFailed("connection refused".into()));
+        app.reduce(AppAction::ConversationsFailed("connection refused".into()));
+        assert_eq!(app.launcher_logs.iter().filter(|l| l.contains("connection refused")).count(), 1, "reported once");
+
+        let polls: Vec<u64> = (1..=30).filter(|_| !app.reduce(AppAction::Tick).is_empty()).collect();
+        assert_eq!(polls.len(), 1, "one retry after the backoff, not every 2 ticks");
+        app.reduce(AppAction::ConversationsLoaded(vec![]));
+        assert_eq!(app.launcher_logs.last().map(String::as_str), Some("Conversations reachable again."));
+        app.reduce(AppAction::Tick);
+        assert_eq!(app.reduce(AppAction::Tick), vec![Effect::FetchConversations], "regular schedule again");
+    }
+}
@@ -0,0 +1,116 @@
+// ================================================
+// FILE: src/attachments.rs
+// ================================================
+// Binary attachments streamed with an answer (generated charts, files) are
+// decoded and written to the downloads directory instead of being dropped.
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use base64::Engine;
+use crate::api::Attachment;
+
+/// `$BPT_DOWNLOAD_DIR`, else `~/Downloads` when it exists, else the current directory.
+pub fn download_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("BPT_DOWNLOAD_DIR") { return PathBuf::from(dir); }
+    if let Some(home) = std::env::var_os("HOME") {
+        let dl = PathBuf::from(home).join("Downloads");
+        if dl.is_dir() { return dl; }
+    }
+    PathBuf::from(".")
+}
+
+/// Payload bytes, accepting either plain base64 or a `data:<mime>;base64,` URI.
+pub fn decode(att: &Attachment) -> Result<Vec<u8>> {
+    let raw = att.data.split_once(";base64,").map(|(_, b)| b).unwrap_or(&att.data);
+    let cleaned: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD.decode(cleaned).context("attachment is not valid base64")
+}
+
+fn mime_of(att: &Attachment) -> Option<&str> {
+    att.mime.as_deref().or_else(|| att.data.strip_prefix("data:").and_then(|d| d.split(';').next()))
+}
+
+fn extension_for(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "application/pdf" => "pdf",
+        "application/json" => "json",
+        "text/csv" => "csv",
+        "text/plain" => "txt",
+        _ => "bin",
+    }
+}
+
+/// A safe file name: the basename of what the backend suggested (never a
+/// path), or `attachment.<ext>` derived from the MIME type.
+pub fn file_name(att: &Attachment) -> String {
+    let suggested = att.filename.as_deref()
+        .and_then(|f| Path::new(f).file_name())
+        .map(|f| f.to_string_lossy().trim().to_string())
+        .filter(|f| !f.is_empty() && f != "." && f != "..");
+    suggested.unwrap_or_else(|| format!("attachment.{}", extension_for(mime_of(att).unwrap_or(""))))
+}
+
+/// `dir/name`, or `dir/stem-N.ext` for the first N that doesn't exist yet.
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() { return candidate; }
+    let p = Path::new(name);
+    let stem = p.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| name.to_string());
+    let ext = p.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..).map(|n| dir.join(format!("{}-{}{}", stem, n, ext))).find(|p| !p.exists()).unwrap_or(candidate)
+}
+
+pub async fn save(att: &Attachment) -> Result<PathBuf> {
+    let bytes = decode(att)?;
+    let dir = download_dir();
+    tokio::fs::create_dir_all(&dir).await.with_context(|| format!("creating {}", dir.display()))?;
+    let path = unique_path(&dir, &file_name(att));
+    tokio::fs::write(&path, bytes).await.with_context(|| format!("writing {}", path.display()))?;
+    Ok(path)
+}
+
+/// Shortens paths under `$HOME` to `~/...` for display.
+pub fn display_path(path: &str) -> String {
+    match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() && path.starts_with(&home) => format!("~{}", &path[home.len()..]),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn att(filename: Option<&str>, mime: Option<&str>, data: &str) -> Attachment {
+        Attachment { filename: filename.map(Into::into), mime: mime.map(Into::into), data: data.into() }
+    }
+
+    #[test]
+    fn decodes_plain_and_data_uri() {
+        assert_eq!(decode(&att(None, None, "aGVs\nbG8=")).unwrap(), b"hello");
+        assert_eq!(decode(&att(None, None, "data:text/plain;base64,aGVsbG8=")).unwrap(), b"hello");
+        assert!(decode(&att(None, None, "!!!")).is_err());
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        assert_eq!(file_name(&att(Some("../../etc/passwd"), None, "")), "passwd");
+        assert_eq!(file_name(&att(Some("chart.png"), None, "")), "chart.png");
+        assert_eq!(file_name(&att(None, Some("image/png"), "")), "attachment.png");
+        assert_eq!(file_name(&att(Some(".."), None, "data:application/pdf;base64,AA==")), "attachment.pdf");
+    }
+
+    #[test]
+    fn unique_path_never_overwrites() {
+        let dir = std::env::temp_dir().join(format!("bpt-attach-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("chart.png"), b"x").unwrap();
+        assert_eq!(unique_path(&dir, "chart.png"), dir.join("chart-1.png"));
+        assert_eq!(unique_path(&dir, "other.png"), dir.join("other.png"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
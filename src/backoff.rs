@@ -0,0 +1,81 @@
+// ================================================
+// FILE: src/backoff.rs
+// ================================================
+// Retry schedule for background polls while a backend is unreachable.
+// Counted in reducer ticks so app.rs stays free of clocks; jitter comes from
+// the tick number, which keeps it deterministic for tests and replays.
+
+/// Longest wait between retries: two minutes at the 250ms tick.
+pub const MAX_DELAY_TICKS: u64 = 480;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Backoff {
+    pub failures: u32,
+    retry_at: u64,
+}
+
+// splitmix64: cheap, well-spread bits from a counter.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl Backoff {
+    pub fn failing(&self) -> bool { self.failures > 0 }
+
+    /// While failing: whether the next retry is due at `tick`. A due retry is
+    /// taken, so no further retries go out until its result is recorded.
+    pub fn take_retry(&mut self, tick: u64) -> bool {
+        if !self.failing() || tick < self.retry_at { return false; }
+        self.retry_at = u64::MAX;
+        true
+    }
+
+    /// Records a failed poll. The wait doubles from `base_ticks` up to
+    /// `MAX_DELAY_TICKS`, plus up to 25% jitter. True on the first failure
+    /// of a streak, so callers can report the outage once.
+    pub fn fail(&mut self, tick: u64, base_ticks: u64) -> bool {
+        self.failures = self.failures.saturating_add(1);
+        let delay = base_ticks.max(1).saturating_mul(1 << (self.failures - 1).min(16)).min(MAX_DELAY_TICKS);
+        let jitter = mix(tick ^ u64::from(self.failures)) % (delay / 4 + 1);
+        self.retry_at = tick + delay + jitter;
+        self.failures == 1
+    }
+
+    /// Records a successful call; true if that ends a failure streak.
+    pub fn succeed(&mut self) -> bool {
+        let recovered = self.failing();
+        *self = Backoff::default();
+        recovered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait(b: &mut Backoff, from: u64) -> u64 {
+        (from..).find(|t| b.take_retry(*t)).unwrap() - from
+    }
+
+    #[test]
+    fn doubles_with_jitter_and_caps() {
+        let mut b = Backoff::default();
+        assert!(!b.take_retry(0), "healthy: regular schedule only");
+        assert!(b.fail(10, 8));
+        let first = wait(&mut b, 10);
+        assert!((8..=10).contains(&first), "{}", first);
+        assert!(!b.take_retry(1000), "one retry in flight at a time");
+
+        assert!(!b.fail(20, 8));
+        assert!((16..=20).contains(&wait(&mut b, 20)));
+        for t in 0..20 { b.fail(100 + t, 8); }
+        let capped = wait(&mut b, 119);
+        assert!((MAX_DELAY_TICKS..=MAX_DELAY_TICKS * 5 / 4).contains(&capped), "{}", capped);
+
+        assert!(b.succeed());
+        assert!(!b.failing() && !b.succeed());
+    }
+}
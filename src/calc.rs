@@ -0,0 +1,258 @@
+// ================================================
+// FILE: src/calc.rs
+// ================================================
+// `= <expr>` in the Search input: arithmetic and unit conversion answered
+// locally, e.g. `= 3 * 1.5 GiB in MB` or `= 72 F to C`. Values are kept in
+// the base unit of their dimension (bytes, metres, ...) while evaluating.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dim { Data, Length, Mass, Time, Temp }
+
+struct Unit {
+    names: &'static [&'static str],
+    dim: Dim,
+    factor: f64,
+    /// Added before scaling; only temperatures have one (to kelvin).
+    offset: f64,
+}
+
+const fn unit(names: &'static [&'static str], dim: Dim, factor: f64) -> Unit { Unit { names, dim, factor, offset: 0.0 } }
+
+const KI: f64 = 1024.0;
+const UNITS: &[Unit] = &[
+    unit(&["B", "byte", "bytes"], Dim::Data, 1.0),
+    unit(&["bit", "bits"], Dim::Data, 0.125),
+    unit(&["KB", "kB", "kb"], Dim::Data, 1e3),
+    unit(&["MB", "mb"], Dim::Data, 1e6),
+    unit(&["GB", "gb"], Dim::Data, 1e9),
+    unit(&["TB", "tb"], Dim::Data, 1e12),
+    unit(&["KiB", "kib"], Dim::Data, KI),
+    unit(&["MiB", "mib"], Dim::Data, KI * KI),
+    unit(&["GiB", "gib"], Dim::Data, KI * KI * KI),
+    unit(&["TiB", "tib"], Dim::Data, KI * KI * KI * KI),
+    unit(&["mm"], Dim::Length, 1e-3),
+    unit(&["cm"], Dim::Length, 1e-2),
+    unit(&["m"], Dim::Length, 1.0),
+    unit(&["km"], Dim::Length, 1e3),
+    unit(&["in", "inch", "inches"], Dim::Length, 0.0254),
+    unit(&["ft", "foot", "feet"], Dim::Length, 0.3048),
+    unit(&["yd"], Dim::Length, 0.9144),
+    unit(&["mi", "mile", "miles"], Dim::Length, 1609.344),
+    unit(&["mg"], Dim::Mass, 1e-6),
+    unit(&["g"], Dim::Mass, 1e-3),
+    unit(&["kg"], Dim::Mass, 1.0),
+    unit(&["oz"], Dim::Mass, 0.028_349_523_125),
+    unit(&["lb", "lbs"], Dim::Mass, 0.453_592_37),
+    unit(&["ms"], Dim::Time, 1e-3),
+    unit(&["s", "sec"], Dim::Time, 1.0),
+    unit(&["min"], Dim::Time, 60.0),
+    unit(&["h", "hr", "hour", "hours"], Dim::Time, 3600.0),
+    unit(&["day", "days"], Dim::Time, 86400.0),
+    unit(&["week", "weeks"], Dim::Time, 604800.0),
+    Unit { names: &["C", "°C", "celsius"], dim: Dim::Temp, factor: 1.0, offset: 273.15 },
+    Unit { names: &["F", "°F", "fahrenheit"], dim: Dim::Temp, factor: 5.0 / 9.0, offset: 459.67 },
+    Unit { names: &["K", "kelvin"], dim: Dim::Temp, factor: 1.0, offset: 0.0 },
+];
+
+fn find_unit(name: &str) -> Option<&'static Unit> { UNITS.iter().find(|u| u.names.contains(&name)) }
+
+/// A value in base units, with the unit it was first written in (for display).
+#[derive(Clone, Copy)]
+struct Quantity { value: f64, unit: Option<&'static Unit> }
+
+impl Quantity {
+    fn scalar(value: f64) -> Self { Quantity { value, unit: None } }
+    fn dim(&self) -> Option<Dim> { self.unit.map(|u| u.dim) }
+
+    fn with_unit(self, name: &str) -> Result<Quantity, String> {
+        match name {
+            "pi" => return Ok(Quantity::scalar(self.value * std::f64::consts::PI)),
+            "e" => return Ok(Quantity::scalar(self.value * std::f64::consts::E)),
+            _ => {}
+        }
+        let u = find_unit(name).ok_or_else(|| format!("unknown unit `{}`", name))?;
+        Ok(Quantity { value: (self.value + u.offset) * u.factor, unit: Some(u) })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok { Num(f64), Word(String), Op(char) }
+
+fn lex(input: &str) -> Result<Vec<Tok>, String> {
+    let mut toks = vec![];
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == '_' { i += 1; continue; }
+        if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_') { i += 1; }
+            // Exponent, but not the start of a unit name like `1 EB`.
+            if i + 1 < chars.len() && (chars[i] == 'e' || chars[i] == 'E') && (chars[i + 1].is_ascii_digit() || (matches!(chars[i + 1], '+' | '-') && chars.get(i + 2).is_some_and(|c| c.is_ascii_digit()))) {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_digit() { i += 1; }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            toks.push(Tok::Num(text.parse().map_err(|_| format!("bad number `{}`", text))?));
+        } else if c.is_alphabetic() || c == '°' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphabetic() || chars[i] == '°') { i += 1; }
+            toks.push(Tok::Word(chars[start..i].iter().collect()));
+        } else if "+-*/^%()×".contains(c) {
+            toks.push(Tok::Op(if c == '×' { '*' } else { c }));
+            i += 1;
+        } else {
+            return Err(format!("unexpected `{}`", c));
+        }
+    }
+    Ok(toks)
+}
+
+/// Nesting allowed for parentheses, signs and `^`, so a pasted `((((...`
+/// gets an error instead of overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser { toks: Vec<Tok>, pos: usize, depth: usize }
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> { self.toks.get(self.pos) }
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Tok::Op(op)) { self.pos += 1; true } else { false }
+    }
+
+    fn expr(&mut self) -> Result<Quantity, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let sign = if self.eat('+') { 1.0 } else if self.eat('-') { -1.0 } else { return Ok(lhs) };
+            let rhs = self.term()?;
+            if lhs.dim() != rhs.dim() { return Err("can't add or subtract different kinds of units".into()); }
+            lhs = Quantity { value: lhs.value + sign * rhs.value, unit: lhs.unit.or(rhs.unit) };
+        }
+    }
+
+    fn term(&mut self) -> Result<Quantity, String> {
+        let mut lhs = self.power()?;
+        loop {
+            let op = if self.eat('*') { '*' } else if self.eat('/') { '/' } else if self.eat('%') { '%' } else { return Ok(lhs) };
+            let rhs = self.power()?;
+            lhs = match (op, lhs.unit, rhs.unit) {
+                ('*', _, None) | ('*', None, _) => Quantity { value: lhs.value * rhs.value, unit: lhs.unit.or(rhs.unit) },
+                ('/', _, None) => Quantity { value: lhs.value / rhs.value, unit: lhs.unit },
+                // A ratio of like quantities is a plain number.
+                ('/', Some(a), Some(b)) if a.dim == b.dim => Quantity::scalar(lhs.value / rhs.value),
+                ('%', _, None) => Quantity { value: lhs.value % rhs.value, unit: lhs.unit },
+                _ => return Err("unsupported combination of units".into()),
+            };
+        }
+    }
+
+    /// Every recursion passes through here, so this is where depth is counted.
+    fn power(&mut self) -> Result<Quantity, String> {
+        if self.depth == MAX_DEPTH { return Err("expression is nested too deeply".into()); }
+        self.depth += 1;
+        let q = self.raise();
+        self.depth -= 1;
+        q
+    }
+
+    fn raise(&mut self) -> Result<Quantity, String> {
+        let base = self.unary()?;
+        if !self.eat('^') { return Ok(base); }
+        let exp = self.power()?;
+        if base.unit.is_some() || exp.unit.is_some() { return Err("can't raise units to a power".into()); }
+        Ok(Quantity::scalar(base.value.powf(exp.value)))
+    }
+
+    fn unary(&mut self) -> Result<Quantity, String> {
+        // `-2^2` is -(2^2).
+        if self.eat('-') { let q = self.power()?; return Ok(Quantity { value: -q.value, ..q }); }
+        if self.eat('+') { return self.power(); }
+        let q = match self.toks.get(self.pos).cloned() {
+            Some(Tok::Num(n)) => { self.pos += 1; Quantity::scalar(n) },
+            Some(Tok::Op('(')) => {
+                self.pos += 1;
+                let q = self.expr()?;
+                if !self.eat(')') { return Err("missing `)`".into()); }
+                q
+            },
+            Some(Tok::Word(w)) => { self.pos += 1; Quantity { value: 1.0, unit: None }.with_unit(&w)? },
+            Some(t) => return Err(format!("unexpected {:?}", t)),
+            None => return Err("expression ends too early".into()),
+        };
+        // `1.5 GiB`: a unit right after a number applies to it.
+        match self.peek().cloned() {
+            Some(Tok::Word(w)) if q.unit.is_none() => { self.pos += 1; q.with_unit(&w) },
+            _ => Ok(q),
+        }
+    }
+}
+
+/// Compact decimal form: up to 10 significant decimals, no trailing zeros.
+pub fn format_number(v: f64) -> String {
+    if v == 0.0 { return "0".into(); }
+    if v.abs() >= 1e15 || v.abs() < 1e-6 { return format!("{:e}", v); }
+    let s = format!("{:.10}", v);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Evaluates `expr`, optionally ending in `in <unit>` / `to <unit>`, and
+/// returns the result for display (`4831.838208 MB`).
+pub fn eval(expr: &str) -> Result<String, String> {
+    let expr = expr.trim();
+    // The last ` in `/` to ` followed by a unit is the conversion (so `3 in in cm` works).
+    let split = [" in ", " to "].iter()
+        .filter_map(|kw| expr.rfind(kw).map(|i| (i, kw.len())))
+        .filter(|(i, len)| find_unit(expr[i + len..].trim()).is_some())
+        .max();
+    let (lhs, target) = match split {
+        Some((i, len)) => (&expr[..i], find_unit(expr[i + len..].trim())),
+        None => (expr, None),
+    };
+    let mut p = Parser { toks: lex(lhs)?, pos: 0, depth: 0 };
+    let q = p.expr()?;
+    if p.pos < p.toks.len() { return Err(format!("unexpected {:?}", p.toks[p.pos])); }
+    if !q.value.is_finite() { return Err("result is not a finite number".into()); }
+    let Some(unit) = target.or(q.unit) else { return Ok(format_number(q.value)) };
+    if q.dim() != Some(unit.dim) { return Err(format!("can't convert to {}", unit.names[0])); }
+    Ok(format!("{} {}", format_number(q.value / unit.factor - unit.offset), unit.names[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(eval("1 + 2 * 3").unwrap(), "7");
+        assert_eq!(eval("(1 + 2) * 3 ^ 2").unwrap(), "27");
+        assert_eq!(eval("-2^2").unwrap(), "-4");
+        assert_eq!(eval("10 / 4").unwrap(), "2.5");
+        assert_eq!(eval("7 % 3").unwrap(), "1");
+        assert_eq!(eval("1_000 * 1.5e3").unwrap(), "1500000");
+        assert!(eval("2 *").is_err());
+        assert!(eval("1 / 0").is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        assert_eq!(eval(&format!("{}1{}", "(".repeat(20), ")".repeat(20))).unwrap(), "1");
+        assert_eq!(eval(&format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000))).unwrap_err(), "expression is nested too deeply");
+        assert!(eval(&"-".repeat(100_000)).is_err());
+        assert!(eval(&format!("2{}", "^2".repeat(100_000))).is_err());
+    }
+
+    #[test]
+    fn units_and_conversion() {
+        assert_eq!(eval("3 * 1.5 GiB in MB").unwrap(), "4831.838208 MB");
+        assert_eq!(eval("1 GiB + 512 MiB").unwrap(), "1.5 GiB");
+        assert_eq!(eval("3 in in cm").unwrap(), "7.62 cm");
+        assert_eq!(eval("5 ft to m").unwrap(), "1.524 m");
+        assert_eq!(eval("212 F to C").unwrap(), "100 C");
+        assert_eq!(eval("90 min in h").unwrap(), "1.5 h");
+        assert_eq!(eval("1 GB / 1 MB").unwrap(), "1000");
+        assert!(eval("1 kg in m").is_err());
+        assert!(eval("1 kg + 1 m").is_err());
+        assert!(eval("3 parsecs").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+// ================================================
+// FILE: src/cli.rs
+// ================================================
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use anyhow::{anyhow, bail, Result};
+use crate::a11y::Sink;
+use crate::colors::ColorMode;
+
+pub const USAGE: &str = "Usage: bpt [--local] [--docker] [--systemd] [--serve-chat ADDR] [--record FILE | --replay FILE] [--ask QUESTION] [--dump-on-exit] [--color MODE] [--a11y] [--a11y-out FILE]
+       bpt [--local] [--docker] [--systemd] ask [--json] QUESTION | list [--json] | launch NAME|ID [PLACEHOLDER=VALUE...]
+
+Options:
+  --local             Run app commands directly, keeping apps in a local file (no launcher backend)
+  --docker            Also list local Docker containers as apps (start, stop, logs)
+  --systemd           Also list systemd user services as apps (restart, stop, journal)
+  --serve-chat ADDR   Serve the current conversation read-only at http://ADDR (e.g. 127.0.0.1:7878)
+  --record FILE       Log key events and actions to FILE (JSON lines) for bug reports
+  --replay FILE       Play back a recorded session with backend calls disabled
+  --ask QUESTION      Open the Search tab asking QUESTION; piped stdin is attached as context
+  --dump-on-exit      Print the open conversation as Markdown to stdout when bpt exits
+  --color MODE        Colors to use: 16, 256, truecolor or never (default: detected from TERM, COLORTERM, NO_COLOR)
+  --a11y              Accessibility mode: mark focus, selection and state in text, no spinners or ticking timers
+  --a11y-out FILE     Also write each finished answer as plain text to FILE (e.g. a FIFO a screen reader follows); `-` is stdout
+  -h, --help          Show this help
+
+Commands:
+  ask QUESTION        Answer QUESTION on stdout without the TUI, using the saved provider, model and sources;
+                      piped stdin is attached as context. --json prints the answer, sources and usage as JSON
+  list                Print the Apps catalog, one `id<TAB>name<TAB>description` line per app (--json for all fields)
+  launch NAME|ID      Launch an app and stream its output; placeholders take PLACEHOLDER=VALUE, else their last values";
+
+/// Work done without the TUI.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Ask { query: String, json: bool },
+    List { json: bool },
+    Launch { target: String, args: BTreeMap<String, String> },
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Cli {
+    pub local: bool,
+    pub docker: bool,
+    pub systemd: bool,
+    pub serve_chat: Option<SocketAddr>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub ask: Option<String>,
+    pub dump_on_exit: bool,
+    pub color: Option<ColorMode>,
+    pub a11y: bool,
+    pub a11y_out: Option<Sink>,
+    pub command: Option<Command>,
+    pub help: bool,
+}
+
+impl Cli {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // A subcommand takes the rest of the arguments; flags before it still apply.
+            if matches!(arg.as_str(), "ask" | "list" | "launch") {
+                cli.command = Some(parse_command(&arg, args.by_ref())?);
+                break;
+            }
+            // Accept both `--flag value` and `--flag=value`.
+            let (flag, inline) = match arg.split_once('=') {
+                Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| inline.clone().or_else(|| args.next()).ok_or_else(|| anyhow!("{} needs a value", name));
+            match flag.as_str() {
+                "--serve-chat" => {
+                    let v = value("--serve-chat")?;
+                    cli.serve_chat = Some(v.parse().map_err(|_| anyhow!("invalid address for --serve-chat: {}", v))?);
+                },
+                "--record" => cli.record = Some(value("--record")?.into()),
+                "--replay" => cli.replay = Some(value("--replay")?.into()),
+                "--ask" => cli.ask = Some(value("--ask")?).filter(|q| !q.trim().is_empty()),
+                "--color" => {
+                    let v = value("--color")?;
+                    cli.color = Some(ColorMode::parse(&v).ok_or_else(|| anyhow!("--color takes 16, 256, truecolor or never, not {}", v))?);
+                },
+                "--a11y-out" => { cli.a11y_out = Some(Sink::parse(&value("--a11y-out")?)); cli.a11y = true; },
+                "--a11y" => cli.a11y = true,
+                "--local" => cli.local = true,
+                "--dump-on-exit" => cli.dump_on_exit = true,
+                "--docker" => cli.docker = true,
+                "--systemd" => cli.systemd = true,
+                "-h" | "--help" => cli.help = true,
+                other => bail!("unknown argument: {}", other),
+            }
+        }
+        if cli.record.is_some() && cli.replay.is_some() { bail!("--record and --replay are mutually exclusive"); }
+        if cli.ask.is_some() && cli.replay.is_some() { bail!("--ask and --replay are mutually exclusive"); }
+        Ok(cli)
+    }
+}
+
+/// A subcommand's arguments. `ask` takes its question in one argument or
+/// several words; `launch` an app name (or id) and `name=value` pairs.
+fn parse_command<I: Iterator<Item = String>>(name: &str, args: I) -> Result<Command> {
+    let (mut words, mut json) = (Vec::new(), false);
+    for arg in args {
+        match arg.as_str() {
+            "--json" if name != "launch" => json = true,
+            flag if flag.starts_with("--") => bail!("unknown argument for {}: {}", name, flag),
+            _ => words.push(arg),
+        }
+    }
+    Ok(match name {
+        "ask" => {
+            let query = words.join(" ");
+            if query.trim().is_empty() { bail!("ask needs a question"); }
+            Command::Ask { query, json }
+        },
+        "list" => {
+            if let Some(extra) = words.first() { bail!("unexpected argument for list: {}", extra); }
+            Command::List { json }
+        },
+        _ => {
+            let mut words = words.into_iter();
+            let Some(target) = words.next() else { bail!("launch needs an app name or id") };
+            let args = words.map(|w| match w.split_once('=') {
+                Some((k, v)) if !k.is_empty() => Ok((k.to_string(), v.to_string())),
+                _ => Err(anyhow!("expected PLACEHOLDER=VALUE, got {}", w)),
+            }).collect::<Result<_>>()?;
+            Command::Launch { target, args }
+        },
+    })
+}
+
+/// Largest piped input kept; the rest is cut off.
+pub const MAX_STDIN_BYTES: usize = 256 * 1024;
+
+/// Everything piped into stdin (`journalctl -e | bpt --ask ...`), read before
+/// the TUI starts; `None` when stdin is a terminal or the pipe was empty.
+/// Keys still reach the TUI, which reads them from the controlling terminal.
+pub fn read_stdin() -> Option<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() { return None; }
+    let mut bytes = Vec::new();
+    stdin.lock().take(MAX_STDIN_BYTES as u64 + 1).read_to_end(&mut bytes).ok()?;
+    Some(clip_input(&String::from_utf8_lossy(&bytes))).filter(|s| !s.trim().is_empty())
+}
+
+/// `text` within MAX_STDIN_BYTES, marked when something was cut.
+fn clip_input(text: &str) -> String {
+    if text.len() <= MAX_STDIN_BYTES { return text.to_string(); }
+    let mut cut = MAX_STDIN_BYTES;
+    while !text.is_char_boundary(cut) { cut -= 1; }
+    format!("{}\n[... truncated]", &text[..cut])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli> { Cli::parse(args.iter().map(|s| s.to_string())) }
+
+    #[test]
+    fn parses_serve_chat_in_both_forms() {
+        let addr: SocketAddr = "127.0.0.1:7878".parse().unwrap();
+        assert_eq!(parse(&["--serve-chat", "127.0.0.1:7878"]).unwrap().serve_chat, Some(addr));
+        assert_eq!(parse(&["--serve-chat=127.0.0.1:7878"]).unwrap().serve_chat, Some(addr));
+    }
+
+    #[test]
+    fn parses_local_flag() {
+        assert!(parse(&["--local"]).unwrap().local);
+        assert!(!parse(&[]).unwrap().local);
+        assert!(parse(&["--docker", "--local"]).unwrap().docker);
+        assert!(parse(&["--systemd"]).unwrap().systemd);
+        assert!(parse(&["--dump-on-exit"]).unwrap().dump_on_exit);
+        assert_eq!(parse(&["--color=16"]).unwrap().color, Some(ColorMode::Basic));
+        assert_eq!(parse(&["--color", "never"]).unwrap().color, Some(ColorMode::Never));
+        assert!(parse(&["--color", "8"]).is_err());
+        let cli = parse(&["--a11y-out=-"]).unwrap();
+        assert!(cli.a11y && cli.a11y_out == Some(Sink::Stdout));
+        assert!(parse(&["--a11y"]).unwrap().a11y_out.is_none());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse(&["--serve-chat"]).is_err());
+        assert!(parse(&["--serve-chat", "localhost"]).is_err());
+        assert!(parse(&["--nope"]).is_err());
+        assert!(parse(&["--record", "a.jsonl", "--replay", "b.jsonl"]).is_err());
+        assert!(parse(&["--ask"]).is_err());
+        assert!(parse(&["--ask", "why", "--replay", "b.jsonl"]).is_err());
+    }
+
+    #[test]
+    fn parses_ask_command() {
+        assert_eq!(parse(&["ask", "what is rust"]).unwrap().command, Some(Command::Ask { query: "what is rust".into(), json: false }));
+        assert_eq!(parse(&["ask", "--json", "what", "is", "rust"]).unwrap().command, Some(Command::Ask { query: "what is rust".into(), json: true }));
+        assert!(parse(&["ask"]).is_err());
+        assert!(parse(&["ask", "--nope", "q"]).is_err());
+        assert_eq!(parse(&["--local"]).unwrap().command, None);
+    }
+
+    #[test]
+    fn parses_list_and_launch_commands() {
+        let cli = parse(&["--local", "list", "--json"]).unwrap();
+        assert!(cli.local);
+        assert_eq!(cli.command, Some(Command::List { json: true }));
+        assert!(parse(&["list", "extra"]).is_err());
+
+        let args = BTreeMap::from([("tag".to_string(), "v2".to_string())]);
+        assert_eq!(parse(&["launch", "LM Studio", "tag=v2"]).unwrap().command, Some(Command::Launch { target: "LM Studio".into(), args }));
+        assert!(parse(&["launch"]).is_err());
+        assert!(parse(&["launch", "web", "v2"]).is_err());
+        assert!(parse(&["launch", "web", "--json"]).is_err());
+    }
+
+    #[test]
+    fn parses_ask_and_clips_piped_input() {
+        assert_eq!(parse(&["--ask", "why is this failing"]).unwrap().ask.as_deref(), Some("why is this failing"));
+        assert_eq!(parse(&["--ask=  "]).unwrap().ask, None);
+        assert_eq!(clip_input("short"), "short");
+        let long = "é".repeat(MAX_STDIN_BYTES);
+        let clipped = clip_input(&long);
+        assert!(clipped.ends_with("[... truncated]") && clipped.len() < MAX_STDIN_BYTES + 20);
+    }
+}
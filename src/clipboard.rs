@@ -0,0 +1,51 @@
+// ================================================
+// FILE: src/clipboard.rs
+// ================================================
+// Copying text to the system clipboard. A platform tool is used when one is
+// installed (pbcopy, wl-copy, xclip, xsel, clip); otherwise the text is sent
+// to the terminal as an OSC 52 escape, which most terminals honour and which
+// also works over SSH. Inside tmux that needs `set -g set-clipboard on`.
+use std::io::Write;
+use std::process::{Command, Stdio};
+use base64::Engine;
+
+/// Copy commands to try, in order, for this platform and session.
+fn tools() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") { return vec![("pbcopy", &[])]; }
+    if cfg!(windows) { return vec![("clip", &[])]; }
+    let mut tools: Vec<(&'static str, &'static [&'static str])> = vec![];
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() { tools.push(("wl-copy", &[])); }
+    if std::env::var_os("DISPLAY").is_some() {
+        tools.push(("xclip", &["-selection", "clipboard"]));
+        tools.push(("xsel", &["--clipboard", "--input"]));
+    }
+    tools
+}
+
+/// The OSC 52 sequence that sets the clipboard to `text`.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text))
+}
+
+/// Copies `text`, returning the tool that took it (`"terminal"` for OSC 52).
+pub fn copy(text: &str) -> std::io::Result<&'static str> {
+    for (tool, args) in tools() {
+        let Ok(mut child) = Command::new(tool).args(args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() else { continue };
+        if let Some(mut stdin) = child.stdin.take() { stdin.write_all(text.as_bytes())?; }
+        if child.wait()?.success() { return Ok(tool); }
+    }
+    let mut out = std::io::stdout();
+    out.write_all(osc52(text).as_bytes())?;
+    out.flush()?;
+    Ok("terminal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_encodes_the_text() {
+        assert_eq!(osc52("hi\n"), "\x1b]52;c;aGkK\x07");
+    }
+}
@@ -0,0 +1,58 @@
+// ================================================
+// FILE: src/clock.rs
+// ================================================
+// Wall-clock times for the chat: `14:32` in the local time zone for message
+// headers, and Unix seconds from the timestamps the search backend stores
+// with messages (numbers, or `2024-05-01 14:32:07` / RFC 3339 strings in UTC).
+use serde_json::Value;
+
+/// `14:32` for a Unix timestamp, in local time where the platform says what that is.
+pub fn hm(secs: u64) -> String {
+    let (h, m) = local_hm(secs).unwrap_or(((secs / 3600) % 24, (secs / 60) % 60));
+    format!("{:02}:{:02}", h, m)
+}
+
+#[cfg(unix)]
+fn local_hm(secs: u64) -> Option<(u64, u64)> {
+    let t = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the call; localtime_r is the thread-safe variant.
+    if unsafe { libc::localtime_r(&t, &mut tm) }.is_null() { return None; }
+    Some((tm.tm_hour as u64, tm.tm_min as u64))
+}
+
+#[cfg(not(unix))]
+fn local_hm(_secs: u64) -> Option<(u64, u64)> { None }
+
+/// Unix seconds from a backend timestamp: seconds or milliseconds since the
+/// epoch, or `YYYY-MM-DD[T ]HH:MM[:SS]` read as UTC.
+pub fn parse(v: &Value) -> Option<u64> {
+    if let Some(n) = v.as_u64() { return Some(if n > 100_000_000_000 { n / 1000 } else { n }); }
+    let s = v.as_str()?;
+    let num = |r: std::ops::Range<usize>| s.get(r)?.parse::<i64>().ok();
+    let (y, mo, d, h, mi) = (num(0..4)?, num(5..7)?, num(8..10)?, num(11..13)?, num(14..16)?);
+    let sec = num(17..19).unwrap_or(0);
+    // Civil-to-days, after Howard Hinnant's algorithm (the inverse of todo::date).
+    let y = if mo <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if mo > 2 { mo - 3 } else { mo + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400 + h * 3600 + mi * 60 + sec).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_backend_timestamps() {
+        assert_eq!(parse(&json!("2024-05-01 14:32:07")), Some(1_714_573_927));
+        assert_eq!(parse(&json!("2024-05-01T14:32:07.123Z")), Some(1_714_573_927));
+        assert_eq!(parse(&json!(1_714_573_927_000u64)), Some(1_714_573_927));
+        assert_eq!(parse(&json!("yesterday")), None);
+        assert_eq!(crate::todo::date(parse(&json!("2000-02-29 00:00")).unwrap()), "2000-02-29");
+    }
+}
@@ -0,0 +1,143 @@
+// ================================================
+// FILE: src/colors.rs
+// ================================================
+// What the terminal can show: true color, the 256-color palette, the 16
+// basic colors or none at all (`NO_COLOR`, `TERM=dumb`). `detect` guesses
+// from the environment and `--color` overrides it. The UI is drawn with its
+// usual colors and `adapt` then rewrites the frame: RGB and palette colors
+// become their nearest neighbour, and without color, backgrounds (selections,
+// the footer bar) turn into reverse video and grays into dim text so state
+// stays visible.
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode { Never, Basic, Ansi256, #[default] TrueColor }
+
+impl ColorMode {
+    /// The `--color` value: `16`, `256`, `truecolor` or `never`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "never" | "none" | "0" => Some(ColorMode::Never),
+            "16" => Some(ColorMode::Basic),
+            "256" => Some(ColorMode::Ansi256),
+            "truecolor" | "24bit" => Some(ColorMode::TrueColor),
+            _ => None,
+        }
+    }
+}
+
+/// The mode for these `NO_COLOR`, `TERM` and `COLORTERM` values.
+pub fn detect_from(no_color: bool, term: &str, colorterm: &str) -> ColorMode {
+    if no_color || term == "dumb" { return ColorMode::Never; }
+    if matches!(colorterm, "truecolor" | "24bit") || term.ends_with("-direct") { return ColorMode::TrueColor; }
+    if term.contains("256color") { return ColorMode::Ansi256; }
+    ColorMode::Basic
+}
+
+/// The mode for this terminal; an empty `NO_COLOR` doesn't count.
+pub fn detect() -> ColorMode {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    detect_from(!var("NO_COLOR").is_empty(), &var("TERM"), &var("COLORTERM"))
+}
+
+/// Standard RGB values of the 16 basic colors, in palette order.
+const BASIC: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)), (Color::Red, (205, 0, 0)), (Color::Green, (0, 205, 0)), (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)), (Color::Magenta, (205, 0, 205)), (Color::Cyan, (0, 205, 205)), (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)), (Color::LightRed, (255, 0, 0)), (Color::LightGreen, (0, 255, 0)), (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)), (Color::LightMagenta, (255, 0, 255)), (Color::LightCyan, (0, 255, 255)), (Color::White, (255, 255, 255)),
+];
+
+/// Channel values of the 6×6×6 color cube.
+const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// RGB of a 256-palette entry.
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => BASIC[i as usize].1,
+        16..=231 => { let i = i - 16; (LEVELS[(i / 36) as usize], LEVELS[(i / 6 % 6) as usize], LEVELS[(i % 6) as usize]) },
+        _ => { let v = 8 + (i - 232) * 10; (v, v, v) },
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The palette entry closest to `rgb`, from the color cube or the gray ramp.
+fn to_256(rgb: (u8, u8, u8)) -> Color {
+    let level = |v: u8| (0..6).min_by_key(|&i| (LEVELS[i] as i16 - v as i16).abs()).unwrap_or(0) as u8;
+    let cube = 16 + 36 * level(rgb.0) + 6 * level(rgb.1) + level(rgb.2);
+    let avg = ((rgb.0 as u16 + rgb.1 as u16 + rgb.2 as u16) / 3) as u8;
+    let gray = 232 + (avg.saturating_sub(3) / 10).min(23);
+    Color::Indexed(if distance(indexed_rgb(gray), rgb) < distance(indexed_rgb(cube), rgb) { gray } else { cube })
+}
+
+fn to_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC.iter().min_by_key(|(_, c)| distance(*c, rgb)).map_or(Color::Reset, |(c, _)| *c)
+}
+
+/// `color` as `mode` can show it.
+pub fn map(color: Color, mode: ColorMode) -> Color {
+    match (color, mode) {
+        (_, ColorMode::Never) => Color::Reset,
+        (Color::Rgb(r, g, b), ColorMode::Ansi256) => to_256((r, g, b)),
+        (Color::Rgb(r, g, b), ColorMode::Basic) => to_basic((r, g, b)),
+        (Color::Indexed(i), ColorMode::Basic) => to_basic(indexed_rgb(i)),
+        _ => color,
+    }
+}
+
+/// Rewrites every cell of a drawn frame for `mode`.
+pub fn adapt(buf: &mut Buffer, mode: ColorMode) {
+    if mode == ColorMode::TrueColor { return; }
+    for cell in &mut buf.content {
+        if mode == ColorMode::Never {
+            // Black is the plain background of popups, not a highlight.
+            if !matches!(cell.bg, Color::Reset | Color::Black) { cell.modifier |= Modifier::REVERSED; }
+            match cell.fg {
+                Color::DarkGray | Color::Gray => cell.modifier |= Modifier::DIM,
+                Color::Yellow | Color::LightYellow | Color::Red | Color::LightRed => cell.modifier |= Modifier::BOLD,
+                _ => {},
+            }
+        }
+        cell.fg = map(cell.fg, mode);
+        cell.bg = map(cell.bg, mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn detects_and_parses_modes() {
+        assert_eq!(detect_from(true, "xterm-256color", "truecolor"), ColorMode::Never);
+        assert_eq!(detect_from(false, "dumb", ""), ColorMode::Never);
+        assert_eq!(detect_from(false, "xterm-256color", "truecolor"), ColorMode::TrueColor);
+        assert_eq!(detect_from(false, "tmux-256color", ""), ColorMode::Ansi256);
+        assert_eq!(detect_from(false, "linux", ""), ColorMode::Basic);
+        assert_eq!((ColorMode::parse("16"), ColorMode::parse("never"), ColorMode::parse("8")), (Some(ColorMode::Basic), Some(ColorMode::Never), None));
+    }
+
+    #[test]
+    fn maps_colors_down_to_what_the_terminal_has() {
+        assert_eq!(map(Color::Rgb(40, 40, 40), ColorMode::Ansi256), Color::Indexed(235));
+        assert_eq!(map(Color::Rgb(255, 135, 0), ColorMode::Ansi256), Color::Indexed(208));
+        assert_eq!(map(Color::Rgb(40, 40, 40), ColorMode::Basic), Color::Black);
+        assert_eq!(map(Color::Indexed(208), ColorMode::Basic), Color::Yellow);
+        assert_eq!(map(Color::Blue, ColorMode::Basic), Color::Blue);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buf.set_string(0, 0, "abc", Style::default().fg(Color::White).bg(Color::Blue));
+        buf.get_mut(1, 0).set_style(Style::default().fg(Color::DarkGray).bg(Color::Reset));
+        adapt(&mut buf, ColorMode::Never);
+        let cell = buf.get(0, 0);
+        assert_eq!((cell.fg, cell.bg, cell.modifier), (Color::Reset, Color::Reset, Modifier::REVERSED));
+        assert_eq!(buf.get(1, 0).modifier, Modifier::DIM);
+    }
+}
@@ -0,0 +1,199 @@
+// ================================================
+// FILE: src/commands.rs
+// ================================================
+// Slash commands typed into the Search input. Parsing is pure; `App::reduce`
+// decides what each command does.
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashCommand {
+    Help,
+    /// Bind the conversation to a project directory.
+    Cd(String),
+    Unbind,
+    /// Attach a file's contents as context for the next query.
+    File(String),
+    /// Pick the file to attach in the file browser (`/file` alone).
+    BrowseFiles,
+    /// Working tree changes (`staged` only the index).
+    GitDiff { staged: bool },
+    GitLog(usize),
+    GitShow(String),
+    /// Remove local data for this conversation, or (`all`) everything bpt stores.
+    Purge { all: bool },
+    /// Append the conversation's action items to a TODO file (default: todo::default_path).
+    Todo(Option<String>),
+    /// Switch to a named preset (config.rs), or pick one when `None`.
+    Preset(Option<String>),
+    /// Save the current provider, model and sources as a preset.
+    SavePreset(String),
+    PresetOff,
+    /// Ask the last question again (AppAction::RegenerateLast).
+    Retry,
+    /// Summarize the conversation into the chat, or (`save`) as its History description.
+    Summarize { save: bool },
+    /// Show what history is sent, or set the sliding window (`Some(0)`: send everything again).
+    Context(Option<usize>),
+    /// Print the conversation as Markdown to stdout when bpt exits (`off`: don't).
+    Dump(bool),
+}
+
+impl SlashCommand {
+    /// `git` arguments for the git commands; they run in the bound project.
+    pub fn git_args(&self) -> Option<Vec<String>> {
+        let args: Vec<&str> = match self {
+            SlashCommand::GitDiff { staged: true } => vec!["diff", "--cached"],
+            SlashCommand::GitDiff { staged: false } => vec!["diff", "HEAD"],
+            SlashCommand::GitLog(n) => return Some(vec!["log".into(), "--stat".into(), "-n".into(), n.to_string()]),
+            SlashCommand::GitShow(rev) => vec!["show", "--stat", "--patch", rev, "--"],
+            _ => return None,
+        };
+        Some(args.into_iter().map(String::from).collect())
+    }
+}
+
+pub const HELP: &str = "Commands:\n\
+- `/cd <dir>` bind this conversation to a project directory\n\
+- `/unbind` remove the project binding\n\
+- `/file [path]` attach a file (relative to the project) to the next message, or pick one in the file browser\n\
+- `/gitdiff [staged]` attach uncommitted (or only staged) changes\n\
+- `/gitlog [n]` attach the last n commits (default 10)\n\
+- `/gitshow <ref>` attach a commit\n\
+- `/purge [all]` forget local data for this conversation (or all local data)\n\
+- `/retry` answer the last question again with the current model and settings (also `r` in the chat)\n\
+- `/context [n|all]` show how much history is sent, keep only the last n messages, or send all again (`x` in the chat leaves one out)\n\
+- `/summarize [save]` summarize this conversation in the chat, or save it as its description in History\n\
+- `/todo [file]` append this conversation's action items to a TODO checklist (also `t` in the chat)\n\
+- `/preset [name]` switch to a preset, or pick one (also Ctrl+t); `/preset save <name>`, `/preset off`\n\
+- `/dump [off]` print this conversation as Markdown to stdout when bpt exits (like `--dump-on-exit`)\n\
+- `//text` send a message that starts with `/`\n\
+- `= <expr>` calculate locally, e.g. `= 3 * 1.5 GiB in MB`";
+
+/// `None` when `input` is a normal message (including the `//` escape).
+pub fn parse(input: &str) -> Option<Result<SlashCommand, String>> {
+    let input = input.trim();
+    let rest = input.strip_prefix('/')?;
+    if rest.starts_with('/') { return None; }
+    let (name, arg) = rest.split_once(char::is_whitespace).map(|(n, a)| (n, a.trim())).unwrap_or((rest, ""));
+    let need_arg = |cmd: fn(String) -> SlashCommand| if arg.is_empty() { Err(format!("/{} needs an argument", name)) } else { Ok(cmd(arg.to_string())) };
+    Some(match name {
+        "help" | "?" => Ok(SlashCommand::Help),
+        "cd" | "bind" => need_arg(SlashCommand::Cd),
+        "unbind" => Ok(SlashCommand::Unbind),
+        "file" if arg.is_empty() => Ok(SlashCommand::BrowseFiles),
+        "file" => need_arg(SlashCommand::File),
+        "gitdiff" => match arg {
+            "" => Ok(SlashCommand::GitDiff { staged: false }),
+            "staged" | "--staged" | "--cached" => Ok(SlashCommand::GitDiff { staged: true }),
+            _ => Err(format!("/gitdiff takes no argument or `staged`, not `{}`", arg)),
+        },
+        "gitlog" => match arg {
+            "" => Ok(SlashCommand::GitLog(10)),
+            n => n.parse().ok().filter(|n| *n > 0).map(SlashCommand::GitLog).ok_or_else(|| format!("/gitlog expects a count, not `{}`", n)),
+        },
+        // A ref starting with `-` would be read as an option by git.
+        "gitshow" if arg.starts_with('-') => Err(format!("Not a git ref: {}", arg)),
+        "gitshow" => need_arg(SlashCommand::GitShow),
+        "purge" => match arg {
+            "" => Ok(SlashCommand::Purge { all: false }),
+            "all" => Ok(SlashCommand::Purge { all: true }),
+            _ => Err(format!("/purge takes no argument or `all`, not `{}`", arg)),
+        },
+        "retry" | "regen" => Ok(SlashCommand::Retry),
+        "context" => match arg {
+            "" => Ok(SlashCommand::Context(None)),
+            "all" => Ok(SlashCommand::Context(Some(0))),
+            n => n.parse().ok().map(|n| SlashCommand::Context(Some(n))).ok_or_else(|| format!("/context expects a message count or `all`, not `{}`", n)),
+        },
+        "summarize" | "summary" => match arg {
+            "" => Ok(SlashCommand::Summarize { save: false }),
+            "save" => Ok(SlashCommand::Summarize { save: true }),
+            _ => Err(format!("/summarize takes no argument or `save`, not `{}`", arg)),
+        },
+        "dump" => match arg {
+            "" | "on" => Ok(SlashCommand::Dump(true)),
+            "off" => Ok(SlashCommand::Dump(false)),
+            _ => Err("usage: /dump [off]".to_string()),
+        },
+        "todo" => Ok(SlashCommand::Todo(Some(arg).filter(|a| !a.is_empty()).map(str::to_string))),
+        "preset" => match arg.split_once(char::is_whitespace).map(|(v, n)| (v, n.trim())) {
+            _ if arg.is_empty() => Ok(SlashCommand::Preset(None)),
+            _ if arg == "off" => Ok(SlashCommand::PresetOff),
+            Some(("save", name)) => Ok(SlashCommand::SavePreset(name.to_string())),
+            _ if arg == "save" => Err("/preset save needs a name".to_string()),
+            _ => Ok(SlashCommand::Preset(Some(arg.to_string()))),
+        },
+        _ => Err(format!("Unknown command /{} (try /help, or // to send a message starting with /)", name)),
+    })
+}
+
+/// Strips the `//` escape from a message that really starts with `/`.
+pub fn unescape(input: &str) -> &str {
+    if input.starts_with("//") { &input[1..] } else { input }
+}
+
+/// Expands `~` and resolves relative paths against `base` (the bound
+/// project) or the current directory.
+pub fn resolve_path(base: Option<&str>, path: &str) -> PathBuf {
+    let expanded = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    };
+    if expanded.is_absolute() { return expanded; }
+    match base {
+        Some(b) => Path::new(b).join(expanded),
+        None => expanded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_escapes() {
+        assert_eq!(parse("what is rust"), None);
+        assert_eq!(parse("//etc is a dir"), None);
+        assert_eq!(unescape("//etc is a dir"), "/etc is a dir");
+        assert_eq!(parse("/help"), Some(Ok(SlashCommand::Help)));
+        assert_eq!(parse("  /cd   ~/code/app "), Some(Ok(SlashCommand::Cd("~/code/app".into()))));
+        assert_eq!(parse("/file src/main.rs"), Some(Ok(SlashCommand::File("src/main.rs".into()))));
+        assert_eq!(parse("/file"), Some(Ok(SlashCommand::BrowseFiles)));
+        assert_eq!(parse("/todo"), Some(Ok(SlashCommand::Todo(None))));
+        assert_eq!(parse("/todo ~/notes/todo.md"), Some(Ok(SlashCommand::Todo(Some("~/notes/todo.md".into())))));
+        assert!(matches!(parse("/nope x"), Some(Err(_))));
+        assert_eq!(parse("/preset"), Some(Ok(SlashCommand::Preset(None))));
+        assert_eq!(parse("/preset deep research"), Some(Ok(SlashCommand::Preset(Some("deep research".into())))));
+        assert_eq!(parse("/preset save  code helper"), Some(Ok(SlashCommand::SavePreset("code helper".into()))));
+        assert_eq!(parse("/preset off"), Some(Ok(SlashCommand::PresetOff)));
+        assert!(matches!(parse("/preset save"), Some(Err(_))));
+        assert_eq!(parse("/retry"), Some(Ok(SlashCommand::Retry)));
+        assert_eq!(parse("/summarize save"), Some(Ok(SlashCommand::Summarize { save: true })));
+        assert!(matches!(parse("/summarize all"), Some(Err(_))));
+        assert_eq!(parse("/context 6"), Some(Ok(SlashCommand::Context(Some(6)))));
+        assert_eq!(parse("/context all"), Some(Ok(SlashCommand::Context(Some(0)))));
+        assert_eq!(parse("/dump"), Some(Ok(SlashCommand::Dump(true))));
+        assert_eq!(parse("/dump off"), Some(Ok(SlashCommand::Dump(false))));
+    }
+
+    #[test]
+    fn git_commands_build_safe_args() {
+        assert_eq!(parse("/gitdiff staged"), Some(Ok(SlashCommand::GitDiff { staged: true })));
+        assert_eq!(parse("/gitlog"), Some(Ok(SlashCommand::GitLog(10))));
+        assert!(matches!(parse("/gitlog lots"), Some(Err(_))));
+        assert!(matches!(parse("/gitshow --output=/tmp/x"), Some(Err(_))));
+        let show = SlashCommand::GitShow("HEAD~1".into()).git_args().unwrap();
+        assert_eq!(show.last().map(String::as_str), Some("--"), "ref is never followed by paths");
+        assert_eq!(SlashCommand::Help.git_args(), None);
+    }
+
+    #[test]
+    fn resolves_relative_to_project() {
+        assert_eq!(resolve_path(Some("/work/app"), "src/lib.rs"), PathBuf::from("/work/app/src/lib.rs"));
+        assert_eq!(resolve_path(Some("/work/app"), "/etc/hosts"), PathBuf::from("/etc/hosts"));
+        assert_eq!(resolve_path(None, "notes.md"), PathBuf::from("notes.md"));
+    }
+}
@@ -0,0 +1,262 @@
+// ================================================
+// FILE: src/config.rs
+// ================================================
+// User settings edited on the Settings tab and saved as store::CONFIG:
+// backend URLs, the default LLM provider and model, which search sources are
+// on, the color theme and a few behaviour switches. Environment variables
+// (BPT_CONVO_POLL_SECS, ...) still win at startup, so a one-off run can
+// override a setting without rewriting the file. Keys live in store::KEYMAP.
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeInclusive;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use crate::{api, direct, ollama};
+use crate::hooks::Hooks;
+use crate::notify::Notifications;
+use crate::plugins::PluginConfig;
+
+/// Length of one `AppAction::Tick`; poll intervals are kept in ticks.
+pub const TICK_MS: u64 = 250;
+
+/// How far the pane splits go, in percent of the screen width, and one Ctrl+Left/Right step.
+pub const LAUNCHER_SPLIT: RangeInclusive<u16> = 20..=80;
+pub const SIDEBAR_SPLIT: RangeInclusive<u16> = 10..=60;
+pub const SPLIT_STEP: i16 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub launcher_url: String,
+    pub search_url: String,
+    /// Provider and model picked for new queries; `None` takes the first offered.
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Search sources switched on or off, by name. Unlisted ones keep the backend's setting.
+    pub search_sources: BTreeMap<String, bool>,
+    pub theme: Theme,
+    pub convo_poll_secs: u64,
+    pub health_poll_secs: u64,
+    pub ansi_colors: bool,
+    pub auto_open_all: bool,
+    /// Ask the model for follow-up questions after each answer the backend sent none for.
+    pub follow_ups: bool,
+    /// Rename new conversations after their first answer, from their first query to a short title.
+    pub auto_titles: bool,
+    /// Notify when an answer or launch finishes out of sight (notify.rs).
+    pub notifications: Notifications,
+    /// Questions and answers sent as history with a query, newest first; 0 sends all (`/context`).
+    pub context_window: usize,
+    /// Send queries straight to an OpenAI-compatible endpoint instead of the search backend.
+    pub direct_chat: bool,
+    /// Base URL of that endpoint, up to and including `/v1`.
+    pub direct_url: String,
+    /// Ollama server used for the `ollama` provider in direct chat.
+    pub ollama_url: String,
+    /// SearxNG instance searched before each direct chat query; empty searches nothing.
+    pub searxng_url: String,
+    /// Extra OpenAI-compatible providers (Groq, vLLM, llama.cpp, ...), offered after the backend's.
+    pub endpoints: Vec<Endpoint>,
+    /// Commands run on events, with the event as JSON on stdin (hooks.rs).
+    pub hooks: Hooks,
+    /// External processes adding commands, panels and apps (plugins.rs).
+    pub plugins: Vec<PluginConfig>,
+    pub presets: Vec<Preset>,
+    /// Name of the preset in use, if any.
+    pub preset: Option<String>,
+    /// Width of the Launcher's app list and of the Search sidebar, in percent.
+    pub launcher_split: u16,
+    pub sidebar_split: u16,
+}
+
+/// A provider reached directly rather than through the search backend.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Endpoint {
+    pub name: String,
+    /// Base URL, up to and including `/v1`.
+    pub url: String,
+    /// The key, or `$NAME` to read it from that environment variable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Models offered instead of what the endpoint's `/models` lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub models: Option<Vec<String>>,
+}
+
+impl Endpoint {
+    /// The key with any `$NAME` looked up; `None` if unset or empty.
+    pub fn key(&self) -> Option<String> {
+        let key = self.api_key.as_deref()?.trim();
+        let key = match key.strip_prefix('$') { Some(var) => std::env::var(var).ok()?, None => key.to_string() };
+        (!key.is_empty()).then_some(key)
+    }
+}
+
+/// A named bundle of query settings, switched with `/preset` or Ctrl+t.
+/// Fields left out keep whatever is current when the preset is applied.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Search sources switched on, by name; every other one is switched off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_sources: Option<BTreeSet<String>>,
+}
+
+impl Preset {
+    /// `openai · gpt-4o · temp 0.2 · 2 sources`, for the picker.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = [&self.provider, &self.model].into_iter().flatten().cloned().collect();
+        if let Some(t) = self.temperature { parts.push(format!("temp {}", t)); }
+        if let Some(s) = &self.search_sources { parts.push(format!("{} source{}", s.len(), if s.len() == 1 { "" } else { "s" })); }
+        if self.system_prompt.is_some() { parts.push("own prompt".into()); }
+        parts.join(" · ")
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            launcher_url: api::DEFAULT_LAUNCHER_URL.into(),
+            search_url: api::DEFAULT_SEARCH_URL.into(),
+            provider: None,
+            model: None,
+            search_sources: BTreeMap::new(),
+            theme: Theme::default(),
+            convo_poll_secs: 15,
+            health_poll_secs: 10,
+            ansi_colors: true,
+            auto_open_all: false,
+            follow_ups: false,
+            auto_titles: true,
+            notifications: Notifications::Off,
+            context_window: 0,
+            direct_chat: false,
+            direct_url: direct::DEFAULT_URL.into(),
+            ollama_url: ollama::DEFAULT_URL.into(),
+            searxng_url: String::new(),
+            endpoints: vec![],
+            hooks: Hooks::default(),
+            plugins: vec![],
+            presets: vec![],
+            preset: None,
+            launcher_split: 40,
+            sidebar_split: 25,
+        }
+    }
+}
+
+/// A split `delta` percent wider (or narrower), kept within `range`.
+pub fn resize(pct: u16, delta: i16, range: RangeInclusive<u16>) -> u16 {
+    (pct as i16 + delta).clamp(*range.start() as i16, *range.end() as i16) as u16
+}
+
+/// Seconds as a number of ticks, for the poll settings.
+pub fn ticks(secs: u64) -> u64 { secs * 1000 / TICK_MS }
+
+/// Accent color of selections and the footer bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme { #[default] Blue, Green, Magenta, Gray }
+
+impl Theme {
+    const ALL: [Theme; 4] = [Theme::Blue, Theme::Green, Theme::Magenta, Theme::Gray];
+
+    pub fn cycle(self, forward: bool) -> Self {
+        let i = Theme::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        let n = Theme::ALL.len();
+        Theme::ALL[if forward { (i + 1) % n } else { (i + n - 1) % n }]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self { Theme::Blue => "blue", Theme::Green => "green", Theme::Magenta => "magenta", Theme::Gray => "gray" }
+    }
+
+    pub fn accent(self) -> Color {
+        match self { Theme::Blue => Color::Blue, Theme::Green => Color::Green, Theme::Magenta => Color::Magenta, Theme::Gray => Color::DarkGray }
+    }
+}
+
+/// One line of the Settings tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    LauncherUrl,
+    SearchUrl,
+    Provider,
+    Model,
+    SearchSource(usize),  // Index into App::search_providers
+    Theme,
+    ConvoPoll,
+    HealthPoll,
+    AnsiColors,
+    AutoOpenAll,
+    FollowUps,
+    AutoTitles,
+    Notifications,
+    DirectChat,
+    DirectUrl,
+    OllamaUrl,
+    SearxngUrl,
+    Binding(&'static str),  // keymap command id
+    Preset(usize),          // Index into Config::presets
+}
+
+impl Setting {
+    /// Typed in rather than toggled or cycled.
+    pub fn is_text(self) -> bool {
+        matches!(self, Setting::LauncherUrl | Setting::SearchUrl | Setting::DirectUrl | Setting::OllamaUrl | Setting::SearxngUrl | Setting::ConvoPoll | Setting::HealthPoll)
+    }
+}
+
+/// A Settings row: its section heading, what it changes, label and current value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingRow {
+    pub section: &'static str,
+    pub setting: Setting,
+    pub label: String,
+    pub value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_take_defaults() {
+        let c: Config = serde_json::from_str(r#"{"theme":"gray","health_poll_secs":0}"#).unwrap();
+        assert_eq!((c.theme, c.health_poll_secs, c.convo_poll_secs), (Theme::Gray, 0, 15));
+        assert_eq!(c.launcher_url, api::DEFAULT_LAUNCHER_URL);
+        assert_eq!((c.direct_chat, c.direct_url.as_str()), (false, direct::DEFAULT_URL));
+        assert_eq!(Theme::Blue.cycle(false), Theme::Gray);
+        assert_eq!(Theme::Gray.cycle(true), Theme::Blue);
+        assert_eq!(ticks(10), 40);
+        let c: Config = serde_json::from_str(r#"{"notifications":"desktop"}"#).unwrap();
+        assert_eq!(c.notifications, Notifications::Desktop);
+        assert_eq!((c.launcher_split, c.sidebar_split), (40, 25));
+        assert_eq!((resize(78, SPLIT_STEP, LAUNCHER_SPLIT), resize(12, -SPLIT_STEP, SIDEBAR_SPLIT)), (80, 10));
+    }
+
+    #[test]
+    fn presets_load_and_summarize() {
+        let c: Config = serde_json::from_str(r#"{"presets":[{"name":"fast local","provider":"lmstudio","temperature":0.2,"search_sources":["web"]}]}"#).unwrap();
+        assert_eq!(c.presets[0].summary(), "lmstudio · temp 0.2 · 1 source");
+        assert_eq!(c.presets[0].model, None);
+    }
+
+    #[test]
+    fn endpoints_load_with_keys_from_the_environment() {
+        let c: Config = serde_json::from_str(r#"{"endpoints":[{"name":"groq","url":"https://api.groq.com/openai/v1","api_key":"$BPT_TEST_GROQ_KEY","models":["llama-3.3-70b"]},{"name":"vllm","url":"http://gpu:8000/v1"}]}"#).unwrap();
+        std::env::set_var("BPT_TEST_GROQ_KEY", "gsk_1");
+        assert_eq!(c.endpoints[0].key().as_deref(), Some("gsk_1"));
+        assert_eq!((c.endpoints[1].key(), c.endpoints[1].models.as_ref()), (None, None));
+        assert_eq!(Endpoint { api_key: Some("sk-2".into()), ..Default::default() }.key().as_deref(), Some("sk-2"));
+    }
+}
@@ -0,0 +1,96 @@
+// ================================================
+// FILE: src/convo_search.rs
+// ================================================
+// Search across every conversation (Ctrl+f on the Search tab). The backend
+// has no search endpoint, so conversations are fetched once into a local
+// index of their messages; matching titles and messages are listed and
+// opening one jumps to the conversation, focused on the message.
+use std::collections::BTreeMap;
+use serde_json::Value;
+use crate::api::Conversation;
+
+/// Hits listed at most, newest conversations first.
+pub const MAX_HITS: usize = 200;
+
+/// Characters of message shown on either side of the match.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Role and text of each message of a conversation, in backend order.
+pub type Messages = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub convo_id: i64,
+    pub title: String,
+    /// Index of the matching message in the conversation, `None` for a title match.
+    pub message: Option<usize>,
+    pub snippet: String,
+}
+
+/// Messages of a conversation as the backend returns it.
+pub fn messages(json: &Value) -> Messages {
+    json["messages"].as_array().into_iter().flatten()
+        .map(|m| (m["role"].as_str().unwrap_or("unknown").to_string(), m["content"].as_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+/// Titles and messages containing `query`, ignoring case, in the order of
+/// `conversations`: a conversation's title first, then its messages.
+pub fn search(conversations: &[Conversation], index: &BTreeMap<i64, Messages>, query: &str) -> Vec<Hit> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() { return vec![]; }
+    let mut hits = Vec::new();
+    for c in conversations {
+        if c.title.to_lowercase().contains(&needle) {
+            hits.push(Hit { convo_id: c.id, title: c.title.clone(), message: None, snippet: String::new() });
+        }
+        for (i, (role, text)) in index.get(&c.id).into_iter().flatten().enumerate() {
+            if role == "system" { continue; }
+            if let Some(snippet) = snippet(text, &needle) {
+                hits.push(Hit { convo_id: c.id, title: c.title.clone(), message: Some(i), snippet });
+            }
+        }
+        if hits.len() >= MAX_HITS { hits.truncate(MAX_HITS); break; }
+    }
+    hits
+}
+
+/// The part of `text` around the first match of `needle` (lowercase), on
+/// one line, or `None` if it doesn't match.
+fn snippet(text: &str, needle: &str) -> Option<String> {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let chars: Vec<char> = flat.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let needle: Vec<char> = needle.chars().collect();
+    let at = lower.windows(needle.len()).position(|w| w == needle.as_slice())?;
+    let start = at.saturating_sub(SNIPPET_CONTEXT);
+    let end = (at + needle.len() + SNIPPET_CONTEXT).min(chars.len());
+    Some(format!("{}{}{}", if start > 0 { "…" } else { "" }, chars[start..end].iter().collect::<String>(), if end < chars.len() { "…" } else { "" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn finds_titles_and_messages() {
+        let convos = [Conversation { id: 2, title: "Borrow checker".into(), updated_at: None }, Conversation { id: 1, title: "Lunch".into(), updated_at: None }];
+        let mut index = BTreeMap::new();
+        index.insert(2, messages(&json!({"messages": [{"role": "user", "content": "why does the\nborrow fail"}, {"role": "assistant", "content": "Two mutable borrows."}]})));
+        index.insert(1, vec![("system".to_string(), "borrow".to_string())]);
+        let hits = search(&convos, &index, " BORROW ");
+        assert_eq!(hits.iter().map(|h| (h.convo_id, h.message)).collect::<Vec<_>>(), [(2, None), (2, Some(0)), (2, Some(1))]);
+        assert_eq!(hits[1].snippet, "why does the borrow fail");
+        assert!(search(&convos, &index, "  ").is_empty());
+    }
+
+    #[test]
+    fn snippets_are_cut_around_the_match() {
+        let text = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let s = snippet(&text, "needle").unwrap();
+        assert!(s.starts_with('…') && s.ends_with('…'));
+        assert_eq!(s.chars().count(), 2 + 40 + 6 + 40);
+        assert_eq!(snippet("hay", "needle"), None);
+    }
+}
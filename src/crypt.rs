@@ -0,0 +1,148 @@
+// ================================================
+// FILE: src/crypt.rs
+// ================================================
+// Optional encryption at rest for the files in store.rs. Enabled by
+// `BPT_PASSPHRASE`, or `BPT_PASSPHRASE_CMD` (a command printing the
+// passphrase, e.g. a keyring lookup). The passphrase is stretched once per
+// run with argon2id and a salt kept in `store.key`; every file is sealed with
+// XChaCha20-Poly1305 under a fresh nonce.
+use std::sync::OnceLock;
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use crate::store;
+
+const MAGIC: &[u8] = b"BPTENC1\n";
+const NONCE_LEN: usize = 24;
+const KEY_FILE: &str = "store.key";
+const CHECK: &[u8] = b"bplus-tui";
+
+pub struct Key(XChaCha20Poly1305);
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    salt: String,
+    /// `CHECK` sealed with the key, so a wrong passphrase is caught before anything is overwritten.
+    check: String,
+}
+
+pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut bytes = [0u8; 32];
+    Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut bytes).map_err(|e| anyhow!("deriving key: {}", e))?;
+    Ok(Key(XChaCha20Poly1305::new(&bytes.into())))
+}
+
+pub fn is_sealed(data: &[u8]) -> bool { data.starts_with(MAGIC) }
+
+pub fn seal(key: &Key, plain: &[u8]) -> Vec<u8> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = key.0.encrypt(&nonce, plain).expect("in-memory encryption cannot fail");
+    [MAGIC, nonce.as_slice(), &sealed].concat()
+}
+
+pub fn open(key: &Key, data: &[u8]) -> Result<Vec<u8>> {
+    let body = data.strip_prefix(MAGIC).context("not an encrypted file")?;
+    if body.len() < NONCE_LEN { bail!("encrypted file is truncated"); }
+    let (nonce, sealed) = body.split_at(NONCE_LEN);
+    key.0.decrypt(XNonce::from_slice(nonce), sealed).map_err(|_| anyhow!("wrong passphrase or corrupted file"))
+}
+
+fn passphrase() -> Result<Option<String>> {
+    if let Ok(cmd) = std::env::var("BPT_PASSPHRASE_CMD") {
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        let out = std::process::Command::new(shell).arg(flag).arg(&cmd).output().context("running BPT_PASSPHRASE_CMD")?;
+        if !out.status.success() { bail!("BPT_PASSPHRASE_CMD failed: {}", String::from_utf8_lossy(&out.stderr).trim()); }
+        let pass = String::from_utf8_lossy(&out.stdout).trim_end_matches(['\r', '\n']).to_string();
+        if pass.is_empty() { bail!("BPT_PASSPHRASE_CMD printed nothing"); }
+        return Ok(Some(pass));
+    }
+    Ok(std::env::var("BPT_PASSPHRASE").ok().filter(|p| !p.is_empty()))
+}
+
+/// Whether `dir` holds encrypted state: a `store.key` or a sealed file.
+fn encrypted(dir: &std::path::Path) -> bool {
+    if dir.join(KEY_FILE).exists() { return true; }
+    let Ok(entries) = std::fs::read_dir(dir) else { return false };
+    entries.flatten().any(|e| {
+        let mut head = [0u8; MAGIC.len()];
+        std::fs::File::open(e.path()).and_then(|mut f| std::io::Read::read_exact(&mut f, &mut head)).is_ok() && is_sealed(&head)
+    })
+}
+
+fn load_key() -> Result<Option<Key>> {
+    // Without a passphrase, encrypted files would read as empty and the next save would replace them.
+    let Some(pass) = passphrase()? else {
+        if encrypted(&store::data_dir()) { bail!("{} is encrypted: set BPT_PASSPHRASE or BPT_PASSPHRASE_CMD", store::data_dir().display()); }
+        return Ok(None);
+    };
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let path = store::path(KEY_FILE);
+    match std::fs::read(&path) {
+        Ok(bytes) => {
+            let file: KeyFile = serde_json::from_slice(&bytes).with_context(|| format!("reading {}", path.display()))?;
+            let key = derive(&pass, &b64.decode(file.salt).context("bad salt in store.key")?)?;
+            let check = b64.decode(file.check).context("bad check in store.key")?;
+            if open(&key, &check).ok().as_deref() != Some(CHECK) { bail!("wrong passphrase for {}", store::data_dir().display()); }
+            Ok(Some(key))
+        },
+        Err(_) => {
+            let mut salt = [0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            let key = derive(&pass, &salt)?;
+            let file = KeyFile { salt: b64.encode(salt), check: b64.encode(seal(&key, CHECK)) };
+            std::fs::create_dir_all(store::data_dir())?;
+            std::fs::write(&path, serde_json::to_vec_pretty(&file)?).with_context(|| format!("writing {}", path.display()))?;
+            Ok(Some(key))
+        },
+    }
+}
+
+static ACTIVE: OnceLock<Result<Option<Key>, String>> = OnceLock::new();
+
+/// The key for this run, or `None` when encryption is off. Resolved once;
+/// main.rs calls this at startup so a bad passphrase stops before the TUI opens.
+pub fn key() -> Result<Option<&'static Key>> {
+    match ACTIVE.get_or_init(|| load_key().map_err(|e| format!("{:#}", e))) {
+        Ok(key) => Ok(key.as_ref()),
+        Err(e) => Err(anyhow!("{}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_round_trips_and_rejects_wrong_key() {
+        let key = derive("correct horse", b"0123456789abcdef").unwrap();
+        let sealed = seal(&key, b"{\"a\":1}");
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(5).any(|w| w == b"\"a\":1"));
+        assert_eq!(open(&key, &sealed).unwrap(), b"{\"a\":1}");
+        assert_ne!(seal(&key, b"x"), seal(&key, b"x"), "fresh nonce per file");
+
+        let other = derive("battery staple", b"0123456789abcdef").unwrap();
+        assert!(open(&other, &sealed).is_err());
+        assert!(open(&key, &sealed[..MAGIC.len() + 3]).is_err());
+        assert!(!is_sealed(b"{}"));
+    }
+
+    #[test]
+    fn finds_encrypted_state() {
+        let dir = std::env::temp_dir().join(format!("bpt-crypt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("favorites.json"), b"[]").unwrap();
+        assert!(!encrypted(&dir));
+        let key = derive("correct horse", b"0123456789abcdef").unwrap();
+        std::fs::write(dir.join("session.json"), seal(&key, b"{}")).unwrap();
+        assert!(encrypted(&dir), "a sealed file without store.key");
+        std::fs::remove_file(dir.join("session.json")).unwrap();
+        std::fs::write(dir.join(KEY_FILE), b"{}").unwrap();
+        assert!(encrypted(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!encrypted(&dir), "no data dir yet");
+    }
+}
@@ -0,0 +1,146 @@
+// ================================================
+// FILE: src/direct.rs
+// ================================================
+// Direct chat: plain LLM chat against an OpenAI-compatible endpoint (LM
+// Studio, Ollama, OpenRouter, ...) without the search backend. The endpoint
+// keeps no state, so every request carries the whole history; its SSE chunks
+// are turned into the same actions the search stream sends.
+use anyhow::{bail, Result};
+use futures::stream::StreamExt;
+use eventsource_stream::Eventsource;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+use crate::api::{self, Model, ToolCall};
+use crate::app::AppAction;
+use crate::tokens;
+
+/// LM Studio's server, the most common local endpoint.
+pub const DEFAULT_URL: &str = "http://localhost:1234/v1";
+
+/// One chat completion request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatRequest {
+    /// Base URL, up to and including `/v1`.
+    pub url: String,
+    pub model: String,
+    /// Role and text of each message, system prompt first, the question last.
+    pub messages: Vec<(String, String)>,
+    pub temperature: Option<f32>,
+    /// Key of a configured endpoint; `None` falls back to the environment.
+    pub api_key: Option<String>,
+    /// SearxNG instance to search for the question first, if any.
+    pub searxng: Option<String>,
+}
+
+/// Searches SearxNG for the question, if the request asks to, and puts the
+/// results in front of it. The search shows as a `searxng` tool call and its
+/// results as the answer's sources; if it fails, the question goes as is.
+pub async fn ground(mut req: ChatRequest, tx: &UnboundedSender<AppAction>) -> ChatRequest {
+    let Some(base) = req.searxng.take() else { return req };
+    let Some((_, query)) = req.messages.last_mut() else { return req };
+    let call = ToolCall { id: Some("searxng".into()), name: "searxng".into(), args: query.clone(), result: None };
+    let _ = tx.send(AppAction::ToolCallStarted(call.clone()));
+    let result = match api::searxng_search(&base, query).await {
+        Ok(sources) => {
+            *query = api::grounded_prompt(query, &sources);
+            let n = sources.len();
+            let _ = tx.send(AppAction::SearchSourcesReceived(sources));
+            format!("{} result{}", n, if n == 1 { "" } else { "s" })
+        },
+        Err(e) => format!("failed, answering without web results: {}", e),
+    };
+    let _ = tx.send(AppAction::ToolCallFinished(ToolCall { result: Some(result), ..call }));
+    req
+}
+
+/// `$BPT_DIRECT_API_KEY`, else `$OPENAI_API_KEY`; local servers need none.
+fn env_key() -> Option<String> {
+    ["BPT_DIRECT_API_KEY", "OPENAI_API_KEY"].iter().find_map(|k| std::env::var(k).ok()).filter(|k| !k.is_empty())
+}
+
+fn authorized(req: reqwest::RequestBuilder, key: Option<String>) -> reqwest::RequestBuilder {
+    match key.or_else(env_key) {
+        Some(key) => req.bearer_auth(key),
+        None => req,
+    }
+}
+
+pub async fn fetch_models(url: &str, api_key: Option<String>) -> Result<Vec<Model>> {
+    let resp: Value = authorized(Client::new().get(format!("{}/models", url)), api_key).send().await?.error_for_status()?.json().await?;
+    Ok(resp["data"].as_array().into_iter().flatten()
+        .filter_map(|m| m["id"].as_str())
+        .map(|id| Model { id: id.to_string(), name: id.to_string(), params: None })
+        .collect())
+}
+
+pub async fn stream_chat(req: ChatRequest, tx: UnboundedSender<AppAction>) -> Result<()> {
+    let mut body = serde_json::json!({
+        "model": req.model,
+        "messages": req.messages.iter().map(|(role, content)| serde_json::json!({ "role": role, "content": content })).collect::<Vec<_>>(),
+        "stream": true,
+        "stream_options": { "include_usage": true },
+    });
+    if let Some(t) = req.temperature { body["temperature"] = serde_json::json!(t); }
+    let resp = authorized(Client::new().post(format!("{}/chat/completions", req.url)), req.api_key.clone()).json(&body).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        bail!("{}: {}", status, error_message(&text));
+    }
+    let mut stream = resp.bytes_stream().eventsource();
+    while let Some(event) = stream.next().await {
+        let evt = event?;
+        if evt.data.trim() == "[DONE]" { break; }
+        let Ok(chunk) = serde_json::from_str::<Value>(&evt.data) else { continue };
+        if chunk["error"].is_object() { bail!("{}", error_message(&evt.data)); }
+        for action in chunk_actions(&chunk) { let _ = tx.send(action); }
+    }
+    let _ = tx.send(AppAction::SearchDone);
+    Ok(())
+}
+
+/// What one streamed chunk adds: answer text, reasoning (`reasoning_content`
+/// from DeepSeek-style servers, `reasoning` from OpenRouter) and, in the
+/// last chunk, usage.
+pub fn chunk_actions(chunk: &Value) -> Vec<AppAction> {
+    let mut actions = Vec::new();
+    let delta = &chunk["choices"][0]["delta"];
+    for key in ["reasoning_content", "reasoning"] {
+        if let Some(text) = delta[key].as_str().filter(|t| !t.is_empty()) { actions.push(AppAction::SearchReasoningToken(text.to_string())); }
+    }
+    if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) { actions.push(AppAction::SearchStreamToken(text.to_string())); }
+    if chunk["usage"].is_object() {
+        if let Some(usage) = tokens::parse_usage(chunk) { actions.push(AppAction::SearchUsage(usage)); }
+    }
+    actions
+}
+
+/// The `error.message` of an OpenAI-style error body, else the body itself.
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body).ok()
+        .and_then(|v| v["error"]["message"].as_str().or_else(|| v["error"].as_str()).map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::tokens::Usage;
+
+    #[test]
+    fn turns_chunks_into_stream_actions() {
+        assert_eq!(chunk_actions(&json!({"choices": [{"delta": {"content": "Hel"}}]})), [AppAction::SearchStreamToken("Hel".into())]);
+        assert_eq!(chunk_actions(&json!({"choices": [{"delta": {"reasoning_content": "hmm", "content": ""}}]})), [AppAction::SearchReasoningToken("hmm".into())]);
+        assert_eq!(chunk_actions(&json!({"choices": [], "usage": {"prompt_tokens": 9, "completion_tokens": 3}})), [AppAction::SearchUsage(Usage { prompt: 9, completion: 3 })]);
+        assert!(chunk_actions(&json!({"choices": [{"delta": {"role": "assistant"}}]})).is_empty());
+    }
+
+    #[test]
+    fn reads_error_bodies() {
+        assert_eq!(error_message(r#"{"error": {"message": "model not found"}}"#), "model not found");
+        assert_eq!(error_message(r#"{"error": "bad key"}"#), "bad key");
+        assert_eq!(error_message(" upstream down \n"), "upstream down");
+    }
+}
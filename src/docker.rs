@@ -0,0 +1,101 @@
+// ================================================
+// FILE: src/docker.rs
+// ================================================
+// Local Docker containers as launcher apps (`--docker`). `docker ps -a` is
+// listed next to the backend's apps under ids like `docker:web`: launching
+// one starts the container, `x` stops it and `L` streams its recent logs.
+// The container's state (`running`, `exited`, ...) is shown in the list.
+// Everything goes through the `docker` CLI, so contexts, remote hosts and
+// Podman's docker shim work the same way they do in a shell.
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use crate::api::AppModel;
+
+pub const PREFIX: &str = "docker:";
+/// Log lines fetched by `L`.
+pub const LOG_TAIL: u32 = 200;
+
+/// The container name behind an app id.
+pub fn container(id: &str) -> &str { id.strip_prefix(PREFIX).unwrap_or(id) }
+
+/// One line of `docker ps --format '{{json .}}'`.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PsLine {
+    names: String,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    ports: String,
+    #[serde(default)]
+    labels: String,
+}
+
+/// `docker ps` output as apps and their states. Compose services get their
+/// project as a tag.
+pub fn parse_ps(out: &str) -> Vec<(AppModel, String)> {
+    out.lines().filter_map(|l| serde_json::from_str::<PsLine>(l.trim()).ok()).filter_map(|c| {
+        let name = c.names.split(',').next()?.trim().to_string();
+        if name.is_empty() { return None; }
+        let project = c.labels.split(',').find_map(|l| l.strip_prefix("com.docker.compose.project="));
+        let mut description = format!("{} · {} #docker", c.image, c.status);
+        if let Some(p) = project.filter(|p| !p.is_empty()) { description.push_str(&format!(" #{}", p)); }
+        let app = AppModel {
+            id: format!("{}{}", PREFIX, name),
+            command: format!("docker start {}", name),
+            url: host_url(&c.ports),
+            description: Some(description),
+            name,
+            ..Default::default()
+        };
+        Some((app, c.state))
+    }).collect()
+}
+
+/// `http://localhost:8080` for the first published port (`0.0.0.0:8080->80/tcp`).
+fn host_url(ports: &str) -> String {
+    ports.split(',').find_map(|p| {
+        let (host, _) = p.trim().split_once("->")?;
+        let port: u16 = host.rsplit(':').next()?.parse().ok()?;
+        Some(format!("http://localhost:{}", port))
+    }).unwrap_or_default()
+}
+
+/// The containers on this machine, running or not.
+pub async fn list() -> Result<Vec<(AppModel, String)>> {
+    let out = Command::new("docker").args(["ps", "-a", "--format", "{{json .}}"]).output().await
+        .map_err(|e| anyhow!("could not run docker: {}", e))?;
+    if !out.status.success() { return Err(anyhow!("{}", String::from_utf8_lossy(&out.stderr).trim())); }
+    Ok(parse_ps(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Shell command for `docker <verb>` on the container behind `id`.
+pub fn command(verb: &str, id: &str) -> String {
+    match verb {
+        "logs" => format!("docker logs --tail {} {}", LOG_TAIL, container(id)),
+        verb => format!("docker {} {}", verb, container(id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ps_lines() {
+        let out = r#"{"Names":"shop-web-1","Image":"nginx:1.25","State":"running","Status":"Up 2 hours","Ports":"0.0.0.0:8080->80/tcp, :::8080->80/tcp","Labels":"com.docker.compose.project=shop,com.docker.compose.service=web"}
+{"Names":"scratch","Image":"alpine","Status":"Exited (0) 3 days ago","Ports":"","Labels":""}
+not json"#;
+        let (apps, states): (Vec<_>, Vec<_>) = parse_ps(out).into_iter().unzip();
+        assert_eq!(states, ["running", ""]);
+        assert_eq!((apps[0].id.as_str(), apps[0].url.as_str()), ("docker:shop-web-1", "http://localhost:8080"));
+        assert_eq!(apps[0].description.as_deref(), Some("nginx:1.25 · Up 2 hours #docker #shop"));
+        assert_eq!((apps[1].url.as_str(), apps[1].command.as_str()), ("", "docker start scratch"));
+        assert_eq!(command("logs", &apps[1].id), "docker logs --tail 200 scratch");
+    }
+}
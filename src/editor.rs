@@ -0,0 +1,120 @@
+// ================================================
+// FILE: src/editor.rs
+// ================================================
+// `path/to/file.rs:123` references in answers and launcher output, and the
+// editor command that opens them. Terminal editors take over the screen, so
+// main.rs suspends the TUI around them; GUI editors are just spawned.
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRef {
+    pub path: String,
+    pub line: u32,
+    pub col: Option<u32>,
+}
+
+impl std::fmt::Display for FileRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.col {
+            Some(c) => write!(f, "{}:{}:{}", self.path, self.line, c),
+            None => write!(f, "{}:{}", self.path, self.line),
+        }
+    }
+}
+
+fn parse_ref(token: &str) -> Option<FileRef> {
+    let token = token.trim_end_matches(['.', ',', ';', ':', ')', ']']);
+    if token.contains("://") { return None; }
+    let mut parts = token.split(':');
+    let path = parts.next()?;
+    let line = parts.next()?.parse().ok().filter(|l| *l > 0)?;
+    let col = match parts.next() {
+        Some(c) => Some(c.parse().ok()?),
+        None => None,
+    };
+    if parts.next().is_some() { return None; }
+    // Something that looks like a file, not `localhost:8080` or `12:30`.
+    let name = Path::new(path).file_name()?.to_str()?;
+    let looks_like_file = name.contains('.') && !name.ends_with('.') && name.chars().any(|c| c.is_alphabetic()) && !name.starts_with("localhost");
+    if !looks_like_file || path.chars().any(|c| c.is_whitespace()) { return None; }
+    Some(FileRef { path: path.to_string(), line, col })
+}
+
+/// All references in `text`, in order of appearance, without duplicates.
+pub fn find_refs(text: &str) -> Vec<FileRef> {
+    let mut found: Vec<FileRef> = vec![];
+    for token in text.split(|c: char| c.is_whitespace() || matches!(c, '`' | '\'' | '"' | '(' | '[' | '<' | '>')) {
+        if let Some(r) = parse_ref(token) {
+            if !found.contains(&r) { found.push(r); }
+        }
+    }
+    found
+}
+
+/// A ready-to-run editor invocation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditorCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// GUI editors run in the background instead of taking over the terminal.
+    pub detached: bool,
+}
+
+const GUI_EDITORS: [&str; 8] = ["code", "codium", "cursor", "subl", "zed", "idea", "gedit", "kate"];
+
+/// `template` is `$BPT_EDITOR` (e.g. `code -g {file}:{line}`); without one,
+/// `$VISUAL`/`$EDITOR` (default `vi`) is called as `<editor> +{line} {file}`.
+pub fn command(template: Option<&str>, fallback: Option<&str>, file: &str, r: &FileRef) -> EditorCommand {
+    let template = match template.filter(|t| !t.trim().is_empty()) {
+        Some(t) => t.to_string(),
+        None => format!("{} +{{line}} {{file}}", fallback.filter(|e| !e.trim().is_empty()).unwrap_or("vi")),
+    };
+    let mut words: Vec<String> = template.split_whitespace().map(|w| {
+        w.replace("{file}", file).replace("{line}", &r.line.to_string()).replace("{col}", &r.col.unwrap_or(1).to_string())
+    }).collect();
+    let program = words.remove(0);
+    let base = Path::new(&program).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    EditorCommand { detached: GUI_EDITORS.contains(&base.as_str()), program, args: words }
+}
+
+pub fn command_from_env(file: &str, r: &FileRef) -> EditorCommand {
+    let fallback = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).ok();
+    command(std::env::var("BPT_EDITOR").ok().as_deref(), fallback.as_deref(), file, r)
+}
+
+/// Runs the editor; for terminal editors this blocks until it exits.
+pub fn run(cmd: &EditorCommand) -> std::io::Result<()> {
+    let mut c = Command::new(&cmd.program);
+    c.args(&cmd.args);
+    if cmd.detached {
+        c.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().map(|_| ())
+    } else {
+        let status = c.status()?;
+        if status.success() { Ok(()) } else { Err(std::io::Error::other(format!("{} exited with {}", cmd.program, status))) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_file_line_refs() {
+        let text = "See `src/app.rs:42` and (lib/util.py:7:3). Server at localhost:8080, meet 12:30, https://x.io:443/a.rs:9 and src/app.rs:42 again.";
+        assert_eq!(find_refs(text), vec![
+            FileRef { path: "src/app.rs".into(), line: 42, col: None },
+            FileRef { path: "lib/util.py".into(), line: 7, col: Some(3) },
+        ]);
+        assert_eq!(find_refs("error at main.rs:0"), vec![]);
+    }
+
+    #[test]
+    fn builds_editor_commands() {
+        let r = FileRef { path: "a.rs".into(), line: 12, col: Some(4) };
+        assert_eq!(command(None, Some("nvim"), "/p/a.rs", &r), EditorCommand { program: "nvim".into(), args: vec!["+12".into(), "/p/a.rs".into()], detached: false });
+        assert_eq!(command(None, None, "a.rs", &r).program, "vi");
+        let code = command(Some("code -g {file}:{line}:{col}"), Some("nvim"), "/p/a.rs", &r);
+        assert_eq!((code.args, code.detached), (vec!["-g".to_string(), "/p/a.rs:12:4".into()], true));
+    }
+}
@@ -0,0 +1,767 @@
+// ================================================
+// FILE: src/effects.rs
+// ================================================
+use tokio::sync::mpsc::UnboundedSender;
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::a11y::{self, Sink};
+use crate::api::{self, AppModel, Attachment, LaunchEnv, ProviderConfig, SearchRequest};
+use crate::attachments;
+use crate::clipboard;
+use crate::direct;
+use crate::files;
+use crate::followup;
+use crate::hooks;
+use crate::notify::{self, Notifications};
+use crate::summary;
+use crate::title;
+use crate::sources::{self, Source};
+use crate::plugins::{self, PluginConfig, Reply};
+use crate::local;
+use crate::ollama;
+use crate::preview;
+use crate::sync;
+use crate::todo;
+use crate::store;
+use serde_json::Value;
+use crate::app::AppAction;
+
+/// Side effects requested by `App::reduce`. The reducer only describes work;
+/// `execute` is the single place that spawns tasks and talks to the backends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Feed another action back through the event loop.
+    Dispatch(AppAction),
+
+    // Launcher
+    FetchApps,
+    SaveApp(AppModel),
+    DeleteApp(String),
+    /// `seq` identifies the launch history record the result belongs to.
+    LaunchApp { id: String, env: LaunchEnv, seq: u64 },
+    /// App `id` run with a filled-in command instead of its stored one.
+    LaunchCommand { id: String, cmd: String, env: LaunchEnv, seq: u64 },
+    RunAdHoc { cmd: String, seq: u64 },
+    StopApp { id: String, name: String },
+    /// List a source's apps; answers with SourceLoaded.
+    FetchSource(Source),
+    /// Spawn a configured plugin; it answers with PluginReady (plugins.rs).
+    StartPlugin(PluginConfig),
+    /// A JSON-RPC call to a running plugin; `reply` says what its result becomes.
+    PluginCall { plugin: String, method: &'static str, params: Value, reply: Reply },
+    /// Run `cmd` on this machine as launch `seq`, whatever the backend.
+    RunLocal { cmd: String, seq: u64 },
+    /// App `id` launched over SSH: `cmd` is the local `ssh ...` command.
+    LaunchRemote { id: String, cmd: String, seq: u64 },
+    /// Start launch `seq` in tmux with these `tmux` arguments; `what` is
+    /// the kind of target (window, pane, session) for the log.
+    LaunchTmux { seq: u64, args: Vec<String>, what: &'static str },
+    /// Close the SSH connection of a remote launch.
+    StopRemote { id: String, name: String },
+    SyncApps { prefer_local: bool },
+    FetchPreview(String),
+    /// Probe a chained app's URL; answers with HealthChecked.
+    CheckHealth { seq: u64, url: String },
+    /// Probe every (app id, url) at once; answers with UrlHealth.
+    CheckUrls(Vec<(String, String)>),
+    /// Write app definitions to a JSON file in the download directory.
+    ExportApps(Vec<AppModel>),
+    /// Write the Output pane's lines to a file in the download directory.
+    ExportOutput(Vec<String>),
+    /// Append new Output lines to the session log ($BPT_OUTPUT_LOG).
+    TeeOutput { path: PathBuf, lines: Vec<String> },
+    FetchProcesses,
+    /// `action` is `stop` or `restart`.
+    ProcessAction { id: String, action: &'static str },
+    FetchProcessOutput(String),
+
+    // Search
+    FetchConversations,
+    FetchProviders,
+    FetchModels(String),
+    /// Models of an OpenAI-compatible endpoint (direct chat or a configured one).
+    FetchDirectModels { url: String, api_key: Option<String> },
+    /// Models of the Ollama server at this URL.
+    FetchOllamaModels(String),
+    LoadConversation(i64),
+    DeleteConversation(i64),
+    /// Creates (id 0) or updates a search source on the backend.
+    SaveSource(ProviderConfig),
+    DeleteSource(i64),
+    StartSearch(SearchRequest),
+    StartDirectChat(direct::ChatRequest),
+    StartOllamaChat(direct::ChatRequest),
+    SaveAttachment(Attachment),
+    ResolveProjectDir(PathBuf),
+    /// Lists a directory for the file browser.
+    ListDir(PathBuf),
+    ReadContextFile { path: PathBuf, label: String },
+    RunGit { dir: String, args: Vec<String>, label: String },
+    /// Ask for the action items in `prompt` and append them to `path`
+    /// (todo::default_path when unset) under a heading for `title`.
+    ExtractTodos { prompt: String, title: String, model: String, provider: String, path: Option<PathBuf> },
+
+    /// Ask for follow-up questions to the latest answer; answers with FollowUpsReceived.
+    SuggestFollowUps { prompt: String, model: String, provider: String },
+    /// Fetch conversations for the Ctrl+f search, one ConversationIndexed each.
+    IndexConversations(Vec<i64>),
+    /// Ask for a summary of conversation `id`; answers with SummaryDone.
+    Summarize { id: Option<i64>, save: bool, prompt: String, model: String, provider: String },
+    /// Ask for a short title for conversation `id` and rename it; answers with ConversationRenamed.
+    NameConversation { id: i64, prompt: String, model: String, provider: String },
+
+    /// Hand a path or URL to the platform opener.
+    OpenExternal(String),
+    /// Put text on the system clipboard (clipboard.rs).
+    CopyToClipboard(String),
+    /// Tell the user something finished (notify.rs).
+    Notify { how: Notifications, title: String, body: String, in_tmux: bool },
+    /// Hand a finished answer to a screen reader (`--a11y-out`, a11y.rs).
+    Speak { sink: Sink, text: String },
+    /// Run a configured hook command with `payload` on stdin (hooks.rs).
+    RunHook { event: &'static str, cmd: String, payload: Value },
+
+    /// Delete every local data file.
+    PurgeStore,
+    /// Point API calls at other backend base URLs.
+    SetBackends { launcher: String, search: String },
+
+    /// Persist local state as `store::path(name)`.
+    WriteStore { name: &'static str, data: Value },
+}
+
+/// Upper bound for files attached as context with `/file`.
+const MAX_CONTEXT_BYTES: usize = 256 * 1024;
+
+/// Ad-hoc fallback for backends without `/api/exec`: a throwaway app that is
+/// launched and then deleted, whatever happened in between.
+async fn run_via_temp_app(cmd: String, env: LaunchEnv, seq: u64, tx: &UnboundedSender<AppAction>) -> (bool, String) {
+    let temp_app = AppModel {
+        id: String::new(),
+        name: api::TEMP_APP_NAME.into(),
+        description: Some("Ad-hoc".into()),
+        command: cmd.clone(),
+        url: "http://localhost".into(),
+        launch: env.clone(),
+        target: None,
+    };
+    let created = match api::create_app(&temp_app).await {
+        Ok(created) => created,
+        // The app may have been created even if the reply was unusable.
+        Err(e) => { sweep_temp_apps(&cmd).await; return (false, format!("AdHoc Error: {}", e)); },
+    };
+    let result = match api::launch_app_stream(&created.id, &env, seq, tx).await {
+        Ok(Some(ok)) => (ok, if ok { String::new() } else { "Failed".to_string() }),
+        Ok(None) => match api::launch_app(created.id.clone(), &env).await {
+            Ok(res) if res.success => (true, format!("{}\n{}", res.stdout, res.stderr)),
+            Ok(res) => (false, format!("Failed: {}\n{}", res.message, res.stderr)),
+            Err(e) => (false, format!("Exec Error: {}", e)),
+        },
+        Err(e) => (false, format!("Exec Error: {}", e)),
+    };
+    if api::delete_app(&created.id).await.is_err() { sweep_temp_apps(&cmd).await; }
+    result
+}
+
+/// Deletes leftover temp apps for `cmd`. Matching on the command leaves
+/// other ad-hoc runs that are still in flight alone.
+async fn sweep_temp_apps(cmd: &str) {
+    let Ok(apps) = api::fetch_apps().await else { return };
+    for app in apps.iter().filter(|a| a.name == api::TEMP_APP_NAME && a.command == cmd) { let _ = api::delete_app(&app.id).await; }
+}
+
+async fn send_source(source: Source, tx: &UnboundedSender<AppAction>) {
+    let action = match source.list().await {
+        Ok(apps) => AppAction::SourceLoaded(source, apps),
+        Err(e) => AppAction::LaunchResult(format!("Error listing {}: {:#}", source.label(), e)),
+    };
+    let _ = tx.send(action);
+}
+
+/// A plugin that isn't running fails the call right away.
+fn call_plugin(plugin: &str, method: &str, params: Value, reply: Reply, tx: &UnboundedSender<AppAction>) {
+    if let Err(e) = plugins::call(plugin, method, params, reply.clone()) {
+        for action in reply.actions(plugin, Err(e.to_string()), 0) { let _ = tx.send(action); }
+    }
+}
+
+/// Runs `cmd` through `/api/exec`, or a temp app where that is missing.
+fn exec(cmd: String, env: LaunchEnv, seq: u64, tx: UnboundedSender<AppAction>) {
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let (ok, output) = match api::exec_stream(&cmd, &env, seq, &tx).await {
+            Ok(Some(ok)) => (ok, if ok { String::new() } else { "Failed".to_string() }),
+            Ok(None) => run_via_temp_app(cmd, env, seq, &tx).await,
+            Err(e) => (false, format!("Exec Error: {}", e)),
+        };
+        let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+    });
+}
+
+pub fn execute(effect: Effect, tx: UnboundedSender<AppAction>) {
+    match effect {
+        Effect::Dispatch(action) => { let _ = tx.send(action); },
+
+        // --docker/--systemd: source apps start, stop and show logs through their own CLI.
+        Effect::FetchSource(source) => { tokio::spawn(async move { send_source(source, &tx).await; }); },
+        Effect::LaunchApp { id, env, seq } | Effect::LaunchCommand { id, env, seq, .. } if Source::of(&id).is_some() => {
+            let Some(source) = Source::of(&id) else { return };
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = local::run(id.clone(), &source.command("start", &id), &env, seq, &tx).await.unwrap_or_else(|e| (false, format!("Exec Error: {}", e)));
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+                send_source(source, &tx).await;
+            });
+        },
+        // Plugin apps start and stop through their plugin.
+        Effect::StartPlugin(plugin) => plugins::start(plugin, tx),
+        Effect::PluginCall { plugin, method, params, reply } => call_plugin(&plugin, method, params, reply, &tx),
+        Effect::LaunchApp { id, env, seq } | Effect::LaunchCommand { id, env, seq, .. } if plugins::owner(&id).is_some() => {
+            let Some((plugin, app)) = plugins::owner(&id) else { return };
+            call_plugin(plugin, "apps/launch", serde_json::json!({ "id": app, "env": env, "seq": seq }), Reply::Launch { seq }, &tx);
+        },
+        Effect::StopApp { id, .. } if plugins::owner(&id).is_some() => {
+            let Some((plugin, app)) = plugins::owner(&id) else { return };
+            call_plugin(plugin, "apps/stop", serde_json::json!({ "id": app }), Reply::Log, &tx);
+        },
+        Effect::StopApp { id, name } if Source::of(&id).is_some() => {
+            let Some(source) = Source::of(&id) else { return };
+            // Ends a journal follow started by the launch, if any.
+            local::stop(&id);
+            tokio::spawn(async move {
+                let msg = match sources::exec(&source.command("stop", &id)).await {
+                    Ok(()) => format!("Stopped '{}'.", name),
+                    Err(e) => format!("Stop failed for '{}': {}", name, e),
+                };
+                let _ = tx.send(AppAction::LaunchResult(msg));
+                send_source(source, &tx).await;
+            });
+        },
+        Effect::RunLocal { cmd, seq } => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = local::run(format!("local-{}", seq), &cmd, &LaunchEnv::default(), seq, &tx).await.unwrap_or_else(|e| (false, format!("Exec Error: {}", e)));
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+
+        Effect::LaunchRemote { id, cmd, seq } => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = local::run(id, &cmd, &LaunchEnv::default(), seq, &tx).await.unwrap_or_else(|e| (false, format!("Exec Error: {}", e)));
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+        Effect::LaunchTmux { seq, args, what } => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = match tokio::process::Command::new("tmux").args(&args).output().await {
+                    Ok(out) if out.status.success() => (true, format!("Started in tmux {} {}", what, String::from_utf8_lossy(&out.stdout).trim())),
+                    Ok(out) => (false, format!("tmux failed: {}", String::from_utf8_lossy(&out.stderr).trim())),
+                    Err(e) => (false, format!("Could not run tmux: {}", e)),
+                };
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+        Effect::StopRemote { id, name } => {
+            let msg = if local::stop(&id) { format!("Disconnected from '{}'.", name) } else { format!("'{}' is not running.", name) };
+            let _ = tx.send(AppAction::LaunchResult(msg));
+        },
+
+        // --local: the same effects, served from the apps file and child processes.
+        Effect::FetchApps if local::enabled() => {
+            tokio::task::spawn_blocking(move || { let _ = tx.send(AppAction::AppsLoaded(local::load_apps())); });
+        },
+        Effect::SaveApp(model) if local::enabled() => {
+            tokio::task::spawn_blocking(move || {
+                match local::save_app(model) { Ok(()) => { let _ = tx.send(AppAction::LoadApps); }, Err(e) => { let _ = tx.send(AppAction::LaunchResult(format!("Error: {:#}", e))); } }
+            });
+        },
+        Effect::DeleteApp(id) if local::enabled() => {
+            tokio::task::spawn_blocking(move || { let _ = local::delete_app(&id); let _ = tx.send(AppAction::LoadApps); });
+        },
+        Effect::LaunchApp { id, env, seq } if local::enabled() => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = match local::load_apps().into_iter().find(|a| a.id == id) {
+                    Some(app) => local::run(id, &app.command, &env, seq, &tx).await.unwrap_or_else(|e| (false, format!("Exec Error: {}", e))),
+                    None => (false, format!("No local app with id {}", id)),
+                };
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+        Effect::LaunchCommand { id, cmd, env, seq } if local::enabled() => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = local::run(id, &cmd, &env, seq, &tx).await.unwrap_or_else(|e| (false, format!("Exec Error: {}", e)));
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+        Effect::RunAdHoc { cmd, seq } if local::enabled() => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = local::run(format!("adhoc-{}", seq), &cmd, &LaunchEnv::default(), seq, &tx).await.unwrap_or_else(|e| (false, format!("Exec Error: {}", e)));
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+        Effect::StopApp { id, name } if local::enabled() => {
+            let msg = if local::stop(&id) { format!("Stopped '{}'.", name) } else { format!("'{}' is not running.", name) };
+            let _ = tx.send(AppAction::LaunchResult(msg));
+        },
+        // No process API to ask; the Processes tab shows this session's launches.
+        Effect::FetchProcesses if local::enabled() => { let _ = tx.send(AppAction::ProcessesLoaded(None)); },
+
+        Effect::FetchApps => {
+            tokio::spawn(async move {
+                match api::fetch_apps().await {
+                    Ok(apps) => { let _ = tx.send(AppAction::AppsLoaded(apps)); },
+                    Err(e) => { let _ = tx.send(AppAction::LaunchResult(format!("Error fetching apps: {}", e))); },
+                }
+            });
+        },
+        Effect::SaveApp(model) => {
+            tokio::spawn(async move {
+                let res = if model.id.is_empty() { api::create_app(&model).await } else { api::update_app(&model).await.map(|_| model) };
+                match res { Ok(_) => { let _ = tx.send(AppAction::LoadApps); }, Err(e) => { let _ = tx.send(AppAction::LaunchResult(format!("Error: {}", e))); } }
+            });
+        },
+        Effect::DeleteApp(id) => {
+            tokio::spawn(async move { let _ = api::delete_app(&id).await; let _ = tx.send(AppAction::LoadApps); });
+        },
+        Effect::LaunchApp { id, env, seq } => {
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (ok, output) = match api::launch_app_stream(&id, &env, seq, &tx).await {
+                    Ok(Some(ok)) => (ok, if ok { "Success".to_string() } else { "Failed".to_string() }),
+                    Ok(None) => match api::launch_app(id, &env).await {
+                        Ok(res) if res.success => (true, format!("Success:\n{}", res.stdout)),
+                        Ok(res) => (false, format!("Failed:\n{}\n{}", res.message, res.stderr)),
+                        Err(e) => (false, format!("API Error: {}", e)),
+                    },
+                    Err(e) => (false, format!("API Error: {}", e)),
+                };
+                let _ = tx.send(AppAction::LaunchFinished { seq, ok, duration_ms: started.elapsed().as_millis() as u64, output });
+            });
+        },
+        // The backend only launches stored commands, so a filled-in one runs like an ad-hoc command.
+        Effect::LaunchCommand { cmd, env, seq, .. } => exec(cmd, env, seq, tx),
+        Effect::RunAdHoc { cmd, seq } => exec(cmd, LaunchEnv::default(), seq, tx),
+
+        Effect::ExportApps(apps) => {
+            tokio::task::spawn_blocking(move || {
+                let path = attachments::download_dir().join(format!("bpt-apps-{}.json", store::now_secs()));
+                let res = serde_json::to_vec_pretty(&apps).map_err(anyhow::Error::from).and_then(|json| std::fs::write(&path, json).map_err(Into::into));
+                let msg = match res {
+                    Ok(()) => format!("Exported {} app{} to {}", apps.len(), if apps.len() == 1 { "" } else { "s" }, attachments::display_path(&path.to_string_lossy())),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+                let _ = tx.send(AppAction::LaunchResult(msg));
+            });
+        },
+        Effect::ExportOutput(lines) => {
+            tokio::task::spawn_blocking(move || {
+                let path = attachments::download_dir().join(format!("bpt-output-{}.log", store::now_secs()));
+                let mut text = lines.join("\n");
+                text.push('\n');
+                let msg = match std::fs::write(&path, text) {
+                    Ok(()) => format!("Saved {} output lines to {}", lines.len(), attachments::display_path(&path.to_string_lossy())),
+                    Err(e) => format!("Saving output failed: {}", e),
+                };
+                let _ = tx.send(AppAction::LaunchResult(msg));
+            });
+        },
+        Effect::TeeOutput { path, lines } => tee(path, lines, tx),
+        Effect::FetchPreview(url) => {
+            tokio::spawn(async move {
+                let (title, body) = match api::fetch_page(&url).await {
+                    Ok((kind, text)) if kind.contains("html") || text.trim_start().starts_with('<') => preview::extract(&text),
+                    Ok((_, text)) => (String::new(), format!("```\n{}\n```", text.trim_end())),
+                    Err(e) => (String::new(), format!("**Could not load {}**\n\n{}", url, e)),
+                };
+                let _ = tx.send(AppAction::PreviewLoaded { url, title, body });
+            });
+        },
+        Effect::CheckHealth { seq, url } => {
+            tokio::spawn(async move {
+                let up = api::check_health(&url).await;
+                let _ = tx.send(AppAction::HealthChecked { seq, up });
+            });
+        },
+        Effect::CheckUrls(targets) => {
+            tokio::spawn(async move {
+                let checks = targets.iter().map(|(_, url)| api::check_health(url));
+                let up = futures::future::join_all(checks).await;
+                let _ = tx.send(AppAction::UrlHealth(targets.into_iter().map(|(id, _)| id).zip(up).collect()));
+            });
+        },
+        Effect::SyncApps { prefer_local } => {
+            tokio::spawn(async move {
+                let msg = sync::run(prefer_local).await.unwrap_or_else(|e| format!("Sync failed: {:#}", e));
+                let _ = tx.send(AppAction::LaunchResult(msg));
+                let _ = tx.send(AppAction::LoadApps);
+            });
+        },
+
+        Effect::StopApp { id, name } => {
+            tokio::spawn(async move {
+                let msg = match api::stop_app(&id).await {
+                    Ok(m) if m.is_empty() => format!("Stopped '{}'.", name),
+                    Ok(m) => format!("Stopped '{}': {}", name, m),
+                    Err(e) => format!("Stop failed for '{}': {}", name, e),
+                };
+                let _ = tx.send(AppAction::LaunchResult(msg));
+            });
+        },
+        Effect::FetchProcesses => {
+            tokio::spawn(async move {
+                match api::fetch_processes().await {
+                    Ok(list) => { let _ = tx.send(AppAction::ProcessesLoaded(list)); },
+                    Err(e) => { let _ = tx.send(AppAction::ProcessesFailed(e.to_string())); },
+                }
+            });
+        },
+        Effect::ProcessAction { id, action } => {
+            tokio::spawn(async move {
+                let msg = match api::process_action(&id, action).await {
+                    Ok(m) if m.is_empty() => format!("{} {}: ok", action, id),
+                    Ok(m) => format!("{} {}: {}", action, id, m),
+                    Err(e) => format!("{} {} failed: {}", action, id, e),
+                };
+                let _ = tx.send(AppAction::LaunchResult(msg));
+                let _ = tx.send(AppAction::LoadProcesses);
+            });
+        },
+        Effect::FetchProcessOutput(id) => {
+            tokio::spawn(async move {
+                let text = api::process_output(&id).await.unwrap_or_else(|e| format!("Could not fetch output: {}", e));
+                let _ = tx.send(AppAction::ProcessOutputLoaded(text));
+            });
+        },
+        Effect::FetchConversations => {
+            tokio::spawn(async move {
+                match api::fetch_conversations().await {
+                    Ok(c) => { let _ = tx.send(AppAction::ConversationsLoaded(c)); },
+                    Err(e) => { let _ = tx.send(AppAction::ConversationsFailed(e.to_string())); },
+                }
+            });
+        },
+        Effect::FetchProviders => {
+            tokio::spawn(async move { if let Ok(p) = api::fetch_providers_list().await { let _ = tx.send(AppAction::ProvidersLoaded(p)); } });
+        },
+        Effect::FetchModels(provider) => {
+            tokio::spawn(async move { if let Ok(m) = api::fetch_models(&provider).await { let _ = tx.send(AppAction::ModelsLoaded(m)); } });
+        },
+        Effect::FetchDirectModels { url, api_key } => {
+            tokio::spawn(async move {
+                match direct::fetch_models(&url, api_key).await {
+                    Ok(m) => { let _ = tx.send(AppAction::ModelsLoaded(m)); },
+                    Err(e) => { let _ = tx.send(AppAction::SearchError(format!("No models from {}: {}", url, e))); },
+                }
+            });
+        },
+        Effect::FetchOllamaModels(url) => {
+            tokio::spawn(async move {
+                match ollama::fetch_models(&url).await {
+                    Ok(m) => { let _ = tx.send(AppAction::ModelsLoaded(m)); },
+                    Err(e) => { let _ = tx.send(AppAction::SearchError(format!("No models from Ollama at {}: {}", url, e))); },
+                }
+            });
+        },
+        Effect::LoadConversation(id) => {
+            tokio::spawn(async move {
+                match api::load_conversation(id).await {
+                    Ok(json) => { let _ = tx.send(AppAction::ConversationLoaded(json)); },
+                    Err(_) => { let _ = tx.send(AppAction::SearchError("Failed to load chat".into())); },
+                }
+            });
+        },
+        Effect::SaveSource(source) => {
+            tokio::spawn(async move {
+                if let Err(e) = api::save_provider(&source).await { let _ = tx.send(AppAction::SearchError(format!("Saving search source '{}' failed: {}", source.name, e))); }
+                if let Ok(p) = api::fetch_providers_list().await { let _ = tx.send(AppAction::ProvidersLoaded(p)); }
+            });
+        },
+        Effect::DeleteSource(id) => {
+            tokio::spawn(async move {
+                if let Err(e) = api::delete_provider(id).await { let _ = tx.send(AppAction::SearchError(format!("Removing search source failed: {}", e))); }
+                if let Ok(p) = api::fetch_providers_list().await { let _ = tx.send(AppAction::ProvidersLoaded(p)); }
+            });
+        },
+        Effect::DeleteConversation(id) => {
+            tokio::spawn(async move {
+                if let Err(e) = api::delete_conversation(id).await { let _ = tx.send(AppAction::SearchError(format!("Delete failed: {}", e))); }
+                if let Ok(c) = api::fetch_conversations().await { let _ = tx.send(AppAction::ConversationsLoaded(c)); }
+            });
+        },
+        Effect::SaveAttachment(att) => {
+            tokio::spawn(async move {
+                match attachments::save(&att).await {
+                    Ok(path) => { let _ = tx.send(AppAction::AttachmentSaved(path.to_string_lossy().to_string())); },
+                    Err(e) => { let _ = tx.send(AppAction::SearchError(format!("Could not save attachment: {:#}", e))); },
+                }
+            });
+        },
+        Effect::ExtractTodos { prompt, title, model, provider, path } => {
+            tokio::spawn(async move {
+                let path = path.unwrap_or_else(todo::default_path);
+                let (mut pending, mut count, mut io_error) = (String::new(), 0, None);
+                // Items go to the file line by line as the answer streams; the
+                // heading is written with the first one.
+                let mut write = |line: &str| {
+                    let Some(item) = todo::checklist_line(line) else { return };
+                    if io_error.is_some() { return; }
+                    let res = (|| -> std::io::Result<()> {
+                        use std::io::Write;
+                        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) { std::fs::create_dir_all(dir)?; }
+                        let mut f = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+                        if count == 0 { write!(f, "\n{}", todo::heading(&title, store::now_secs()))?; }
+                        writeln!(f, "{}", item)
+                    })();
+                    match res {
+                        Ok(()) => { count += 1; let _ = tx.send(AppAction::TodoItem(item)); },
+                        Err(e) => io_error = Some(e),
+                    }
+                };
+                let res = api::ask_once(&prompt, &model, &provider, |chunk| {
+                    pending.push_str(chunk);
+                    while let Some(i) = pending.find('\n') {
+                        let line: String = pending.drain(..=i).collect();
+                        write(&line);
+                    }
+                }).await;
+                write(&pending);
+                let result = match (res, io_error) {
+                    (_, Some(e)) => Err(format!("could not write {}: {}", path.display(), e)),
+                    (Err(e), _) => Err(e.to_string()),
+                    (Ok(()), None) => Ok(count),
+                };
+                let _ = tx.send(AppAction::TodosDone { path: attachments::display_path(&path.to_string_lossy()), result });
+            });
+        },
+        Effect::SuggestFollowUps { prompt, model, provider } => {
+            tokio::spawn(async move {
+                let mut reply = String::new();
+                // Suggestions are a nicety: a failed request just leaves none.
+                if api::ask_once(&prompt, &model, &provider, |chunk| reply.push_str(chunk)).await.is_ok() {
+                    let questions = followup::parse(&reply);
+                    if !questions.is_empty() { let _ = tx.send(AppAction::FollowUpsReceived(questions)); }
+                }
+            });
+        },
+        Effect::IndexConversations(ids) => {
+            tokio::spawn(async move {
+                for id in ids {
+                    // A conversation that fails to load is indexed empty rather than retried.
+                    let json = api::load_conversation(id).await.unwrap_or_default();
+                    let _ = tx.send(AppAction::ConversationIndexed { id, json });
+                }
+            });
+        },
+        Effect::Summarize { id, save, prompt, model, provider } => {
+            tokio::spawn(async move {
+                let mut reply = String::new();
+                let result = api::ask_once(&prompt, &model, &provider, |chunk| reply.push_str(chunk)).await
+                    .map_err(|e| e.to_string())
+                    .and_then(|()| summary::clean(&reply).ok_or_else(|| "the model returned nothing".to_string()));
+                let _ = tx.send(AppAction::SummaryDone { id, save, result });
+            });
+        },
+        Effect::NameConversation { id, prompt, model, provider } => {
+            tokio::spawn(async move {
+                let mut reply = String::new();
+                // Left under its first query if any step fails.
+                if api::ask_once(&prompt, &model, &provider, |chunk| reply.push_str(chunk)).await.is_err() { return; }
+                let Some(title) = title::clean(&reply) else { return };
+                if api::rename_conversation(id, &title).await.is_ok() { let _ = tx.send(AppAction::ConversationRenamed { id, title }); }
+            });
+        },
+        Effect::ResolveProjectDir(path) => {
+            let action = match std::fs::canonicalize(&path) {
+                Ok(dir) if dir.is_dir() => AppAction::ProjectBound(dir.to_string_lossy().to_string()),
+                Ok(_) => AppAction::ChatNotice(format!("Not a directory: {}", path.display())),
+                Err(e) => AppAction::ChatNotice(format!("Cannot bind {}: {}", path.display(), e)),
+            };
+            let _ = tx.send(action);
+        },
+        Effect::Notify { how, title, body, in_tmux } => notify::send(how, &title, &body, in_tmux),
+        Effect::Speak { sink, text } => a11y::speak(sink, text),
+        Effect::RunHook { event, cmd, payload } => {
+            tokio::spawn(async move {
+                if let Err(e) = hooks::run(&cmd, &payload).await { let _ = tx.send(AppAction::LaunchResult(format!("Hook {} failed: {}", event, e))); }
+            });
+        },
+        Effect::ListDir(dir) => {
+            tokio::spawn(async move {
+                let listed = async {
+                    let dir = tokio::fs::canonicalize(&dir).await?;
+                    let mut entries = Vec::new();
+                    let mut rd = tokio::fs::read_dir(&dir).await?;
+                    while let Some(e) = rd.next_entry().await? {
+                        // Symlinks count as directories when they point at one.
+                        let is_dir = tokio::fs::metadata(e.path()).await.map(|m| m.is_dir()).unwrap_or(false);
+                        entries.push(files::Entry { name: e.file_name().to_string_lossy().into_owned(), is_dir });
+                    }
+                    Ok::<_, std::io::Error>((dir, entries))
+                };
+                let action = match listed.await {
+                    Ok((dir, entries)) => AppAction::FilesListed { dir, entries: Ok(entries) },
+                    Err(e) => AppAction::FilesListed { dir, entries: Err(e.to_string()) },
+                };
+                let _ = tx.send(action);
+            });
+        },
+        Effect::ReadContextFile { path, label } => {
+            tokio::spawn(async move {
+                let action = match tokio::fs::read(&path).await {
+                    Ok(bytes) if bytes.len() > MAX_CONTEXT_BYTES => AppAction::ChatNotice(format!("{} is too large to attach ({} KB max)", label, MAX_CONTEXT_BYTES / 1024)),
+                    Ok(bytes) => match String::from_utf8(bytes) {
+                        Ok(content) => AppAction::ContextAttached { label, content },
+                        Err(_) => AppAction::ChatNotice(format!("{} is not a text file", label)),
+                    },
+                    Err(e) => AppAction::ChatNotice(format!("Cannot read {}: {}", path.display(), e)),
+                };
+                let _ = tx.send(action);
+            });
+        },
+        Effect::RunGit { dir, args, label } => {
+            tokio::spawn(async move {
+                let output = tokio::process::Command::new("git").arg("-C").arg(&dir).args(&args).output().await;
+                let action = match output {
+                    Ok(out) if out.status.success() => {
+                        let mut text = String::from_utf8_lossy(&out.stdout).to_string();
+                        if text.trim().is_empty() {
+                            AppAction::ChatNotice(format!("`{}` printed nothing.", label))
+                        } else {
+                            if text.len() > MAX_CONTEXT_BYTES {
+                                let mut cut = MAX_CONTEXT_BYTES;
+                                while !text.is_char_boundary(cut) { cut -= 1; }
+                                text.truncate(cut);
+                                text.push_str("\n[... truncated]");
+                            }
+                            AppAction::ContextAttached { label, content: text }
+                        }
+                    },
+                    Ok(out) => AppAction::ChatNotice(format!("`{}` failed: {}", label, String::from_utf8_lossy(&out.stderr).trim())),
+                    Err(e) => AppAction::ChatNotice(format!("Cannot run git: {}", e)),
+                };
+                let _ = tx.send(action);
+            });
+        },
+        Effect::OpenExternal(target) => {
+            if let Err(e) = open_external(&target) { let _ = tx.send(AppAction::LaunchResult(format!("Open failed for {}: {}", target, e))); }
+        },
+        Effect::CopyToClipboard(text) => {
+            tokio::task::spawn_blocking(move || {
+                let lines = text.lines().count().max(1);
+                let note = match clipboard::copy(&text) {
+                    Ok(via) => format!("Copied {} line{} ({}).", lines, if lines == 1 { "" } else { "s" }, via),
+                    Err(e) => format!("Copy failed: {}", e),
+                };
+                let _ = tx.send(AppAction::ChatNotice(note));
+            });
+        },
+        Effect::PurgeStore => {
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = store::purge_all() { let _ = tx.send(AppAction::ChatNotice(format!("Purge incomplete: {:#}", e))); }
+            });
+        },
+        Effect::SetBackends { launcher, search } => api::set_backends(&launcher, &search),
+        Effect::WriteStore { name, data } => {
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = store::save(name, &data) { let _ = tx.send(AppAction::LaunchResult(format!("Could not save {}: {:#}", name, e))); }
+            });
+        },
+        Effect::StartSearch(req) => {
+            tokio::spawn(async move {
+                if let Err(e) = api::start_search_stream(req, tx.clone()).await {
+                    let _ = tx.send(if api::is_offline(&e) { AppAction::SearchOffline(e.to_string()) } else { AppAction::SearchError(e.to_string()) });
+                }
+            });
+        },
+        Effect::StartDirectChat(req) => {
+            tokio::spawn(async move {
+                let req = direct::ground(req, &tx).await;
+                if let Err(e) = direct::stream_chat(req, tx.clone()).await { let _ = tx.send(AppAction::SearchError(e.to_string())); }
+            });
+        },
+        Effect::StartOllamaChat(req) => {
+            tokio::spawn(async move {
+                let req = direct::ground(req, &tx).await;
+                if let Err(e) = ollama::stream_chat(req, tx.clone()).await { let _ = tx.send(AppAction::SearchError(e.to_string())); }
+            });
+        },
+    }
+}
+
+type TeeJob = (PathBuf, Vec<String>, UnboundedSender<AppAction>);
+
+/// Appends to the session log on one long-lived thread, so lines land in the
+/// order they were produced (separate blocking tasks could interleave).
+fn tee(path: PathBuf, lines: Vec<String>, tx: UnboundedSender<AppAction>) {
+    static WRITER: std::sync::OnceLock<std::sync::mpsc::Sender<TeeJob>> = std::sync::OnceLock::new();
+    let writer = WRITER.get_or_init(|| {
+        let (jobs, rx) = std::sync::mpsc::channel::<TeeJob>();
+        std::thread::spawn(move || {
+            use std::io::Write;
+            let (mut started, mut failing) = (false, false);
+            for (path, lines, tx) in rx {
+                let res = std::fs::OpenOptions::new().create(true).append(true).open(&path).and_then(|mut f| {
+                    if !started { writeln!(f, "=== bpt session {} ===", todo::date(store::now_secs()))?; }
+                    for line in &lines { writeln!(f, "{}", line)?; }
+                    Ok(())
+                });
+                // Report a broken log once; the report itself is Output and would be tee'd again.
+                match res {
+                    Ok(()) => { started = true; failing = false; },
+                    Err(e) if !failing => { failing = true; let _ = tx.send(AppAction::LaunchResult(format!("Could not write {}: {}", path.display(), e))); },
+                    Err(_) => {},
+                }
+            }
+        });
+        jobs
+    });
+    let _ = writer.send((path, lines, tx));
+}
+
+/// Opens `target` with `xdg-open` / `open` / `explorer` without waiting on
+/// it. Only http(s) URLs and existing paths are handed over: targets come
+/// from app configs and model output, and a handler would run anything else.
+pub fn open_external(target: &str) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+    if !openable(target) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "only http(s) URLs and existing files can be opened"));
+    }
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut c = Command::new("open"); c.arg(target); c
+    } else if cfg!(windows) {
+        // Not `cmd /C start`: cmd would parse `&` and `|` in the URL as commands.
+        let mut c = Command::new("explorer"); c.arg(target); c
+    } else {
+        let mut c = Command::new("xdg-open"); c.arg(target); c
+    };
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().map(|_| ())
+}
+
+fn openable(target: &str) -> bool {
+    let lower = target.to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") { return !target.contains(char::is_whitespace); }
+    // A leading `-` would be read as an option by the opener.
+    !target.starts_with('-') && std::path::Path::new(target).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_only_web_urls_and_existing_paths() {
+        assert!(openable("https://example.com/?a=1&b=2"));
+        assert!(openable("HTTP://localhost:8080"));
+        assert!(openable(env!("CARGO_MANIFEST_DIR")));
+        assert!(!openable("file:///etc/passwd"));
+        assert!(!openable("javascript:alert(1)"));
+        assert!(!openable("calc.exe & whoami"));
+        assert!(!openable("/no/such/file.png"));
+        assert!(!openable("--help"));
+    }
+}
@@ -0,0 +1,129 @@
+// ================================================
+// FILE: src/files.rs
+// ================================================
+// A small file browser shown over the Search tab (`/file` without a path)
+// and the app editor (`Ctrl+o` on Working dir): walk directories, narrow the
+// listing by typing, and pick a file to attach or a directory to launch in.
+// Directories are read by effects.rs; this is just the state and its rules.
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// What the browser was opened for, and so what a pick does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pick {
+    /// A file attached as context to the next message.
+    Attachment,
+    /// A directory for the app editor's Working dir field.
+    WorkingDir,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileBrowser {
+    pub pick: Pick,
+    /// The directory listed; empty until the first listing arrives.
+    pub dir: PathBuf,
+    /// Directories first, then files, each by name.
+    pub entries: Vec<Entry>,
+    pub filter: String,
+    /// Selection among `visible()`.
+    pub idx: usize,
+    /// Why the last listing failed, shown in place of the entries.
+    pub error: Option<String>,
+}
+
+impl FileBrowser {
+    pub fn new(pick: Pick) -> Self {
+        FileBrowser { pick, dir: PathBuf::new(), entries: vec![], filter: String::new(), idx: 0, error: None }
+    }
+
+    /// Shows the listing of `dir`, sorted, with the filter cleared.
+    pub fn show(&mut self, dir: PathBuf, mut entries: Vec<Entry>) {
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+        if dir.parent().is_some() { entries.insert(0, Entry { name: "..".into(), is_dir: true }); }
+        self.dir = dir;
+        self.entries = entries;
+        self.filter.clear();
+        self.idx = 0;
+        self.error = None;
+    }
+
+    /// Entries matching the filter, ignoring case. A filter starting with a
+    /// dot shows only hidden ones (`.name`), any other hides them; `..` only
+    /// shows without a filter.
+    pub fn visible(&self) -> Vec<&Entry> {
+        let needle = self.filter.to_lowercase();
+        self.entries.iter().filter(|e| {
+            if e.name == ".." { return needle.is_empty(); }
+            if e.name.starts_with('.') != needle.starts_with('.') { return false; }
+            e.name.to_lowercase().contains(&needle)
+        }).collect()
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.visible().get(self.idx).copied()
+    }
+
+    /// Moves the selection by `delta`, stopping at either end.
+    pub fn step(&mut self, delta: i32) {
+        let n = self.visible().len();
+        if n == 0 { return; }
+        self.idx = (self.idx as i64 + delta as i64).clamp(0, n as i64 - 1) as usize;
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.idx = 0;
+    }
+
+    /// Removes a filter character; `false` if there was none.
+    pub fn pop_filter(&mut self) -> bool {
+        self.idx = 0;
+        self.filter.pop().is_some()
+    }
+
+    /// The path of entry `e`, `..` resolved to the parent.
+    pub fn path_of(&self, e: &Entry) -> PathBuf {
+        if e.name == ".." { return self.dir.parent().map_or_else(|| self.dir.clone(), Path::to_path_buf); }
+        self.dir.join(&e.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool) -> Entry { Entry { name: name.into(), is_dir } }
+
+    #[test]
+    fn lists_directories_first_and_filters() {
+        let mut b = FileBrowser::new(Pick::Attachment);
+        b.show(PathBuf::from("/srv/app"), vec![entry("main.rs", false), entry("src", true), entry(".env", false), entry("Cargo.toml", false), entry("docs", true)]);
+        let names = |b: &FileBrowser| b.visible().iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&b), ["..", "docs", "src", "Cargo.toml", "main.rs"]);
+        assert_eq!(b.path_of(b.selected().unwrap()), PathBuf::from("/srv"));
+
+        b.push_filter('R');
+        assert_eq!(names(&b), ["src", "Cargo.toml", "main.rs"]);
+        b.step(5);
+        assert_eq!(b.path_of(b.selected().unwrap()), PathBuf::from("/srv/app/main.rs"));
+        b.pop_filter();
+        b.push_filter('.');
+        assert_eq!(names(&b), [".env"], "hidden files need a leading dot");
+        assert!(b.pop_filter() && !b.pop_filter());
+    }
+
+    #[test]
+    fn root_has_no_parent_entry() {
+        let mut b = FileBrowser::new(Pick::WorkingDir);
+        b.show(PathBuf::from("/"), vec![entry("etc", true)]);
+        assert_eq!(b.visible().len(), 1);
+        b.step(-3);
+        assert_eq!(b.idx, 0);
+    }
+}
@@ -0,0 +1,81 @@
+// ================================================
+// FILE: src/followup.rs
+// ================================================
+// Follow-up questions offered under the latest answer. The backend may send
+// them itself (a `follow-ups` event); otherwise, when the setting is on, the
+// model is asked for some once the answer is done. Prompt building and
+// parsing are pure so effects.rs only has to stream the reply.
+use serde_json::Value;
+use crate::app::ChatMessage;
+
+/// Suggestions kept per answer.
+pub const MAX: usize = 3;
+
+/// Characters of the answer sent along with the question.
+const MAX_ANSWER: usize = 6_000;
+
+/// The request for follow-ups to the last question and its answer.
+pub fn prompt(messages: &[ChatMessage]) -> Option<String> {
+    let answer = messages.iter().rposition(|m| m.role == "assistant" && !m.content.trim().is_empty())?;
+    let question = messages[..answer].iter().rev().find(|m| m.role == "user")?;
+    let mut text = messages[answer].content.trim().to_string();
+    if text.len() > MAX_ANSWER {
+        let mut cut = MAX_ANSWER;
+        while !text.is_char_boundary(cut) { cut -= 1; }
+        text.truncate(cut);
+    }
+    Some(format!(
+        "Suggest {} short follow-up questions the user might ask next about the exchange below. \
+         Reply with the questions only, one per line, without numbering or commentary.\n\n---\n\nUser: {}\n\nAssistant: {}",
+        MAX, question.question().trim(), text
+    ))
+}
+
+/// Questions from the model's reply: list markers and quotes stripped,
+/// preamble lines (anything not ending in `?`) skipped.
+pub fn parse(reply: &str) -> Vec<String> {
+    reply.lines()
+        .map(|l| l.trim().trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '.' | ')' | '-' | '*' | '•')).trim().trim_matches('"').trim())
+        .filter(|l| l.ends_with('?') && l.len() > 1)
+        .take(MAX)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Questions from a backend event: a JSON list of strings, or an object
+/// holding one under `questions`, `suggestions` or `followUps`.
+pub fn from_event(data: &Value) -> Vec<String> {
+    let list = if data.is_array() { Some(data) } else { ["questions", "suggestions", "followUps", "follow_ups"].iter().map(|k| &data[*k]).find(|v| v.is_array()) };
+    list.and_then(Value::as_array).into_iter().flatten()
+        .filter_map(|q| q.as_str().or_else(|| q["text"].as_str()))
+        .map(str::trim).filter(|q| !q.is_empty())
+        .take(MAX)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_questions_from_a_reply() {
+        let reply = "Here are some follow-ups:\n1. How does borrowing work?\n- \"What is a lifetime?\"\n\nWhy Rust?\nAnd a fourth?";
+        assert_eq!(parse(reply), ["How does borrowing work?", "What is a lifetime?", "Why Rust?"]);
+    }
+
+    #[test]
+    fn reads_event_payloads() {
+        assert_eq!(from_event(&json!(["a?", " ", "b?"])), ["a?", "b?"]);
+        assert_eq!(from_event(&json!({"questions": [{"text": "c?"}]})), ["c?"]);
+        assert!(from_event(&json!({"text": "no list"})).is_empty());
+    }
+
+    #[test]
+    fn prompt_pairs_the_last_answer_with_its_question() {
+        let msgs = [ChatMessage::new("user", "what is rust"), ChatMessage::new("assistant", "A language."), ChatMessage::new("system", "Pinned.")];
+        assert!(prompt(&msgs).unwrap().ends_with("User: what is rust\n\nAssistant: A language."));
+        assert_eq!(prompt(&msgs[..1]), None);
+    }
+}
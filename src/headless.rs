@@ -0,0 +1,247 @@
+// ================================================
+// FILE: src/headless.rs
+// ================================================
+// The subcommands that work without the TUI, for scripts and other tools:
+// `bpt ask "<query>"` sends one question through the same pipeline as the
+// Search tab, and `bpt list` / `bpt launch <name|id>` use the Apps catalog.
+// Each drives an App the way the TUI would (so the saved config, provider,
+// model, sources, hosts and placeholders all apply) and prints what it
+// streams instead of drawing it.
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use crate::api::{self, AppModel, SearchSource};
+use crate::app::{App, AppAction, ChatMessage};
+use crate::effects::{self, Effect};
+use crate::sources::Source;
+use crate::{direct, local, ollama, store};
+
+/// An App with the saved settings applied and the backends pointed at.
+fn configured_app() -> App {
+    let mut app = App::new();
+    app.config = store::load(store::CONFIG);
+    app.apply_config();
+    api::set_backends(&app.config.launcher_url, &app.config.search_url);
+    app
+}
+
+/// The answer streams to stdout as markdown, or as one JSON object with `--json`.
+pub async fn ask(query: &str, as_json: bool, input: Option<String>) -> Result<()> {
+    let mut app = configured_app();
+    load_models(&mut app).await;
+
+    if let Some(content) = input { app.reduce(AppAction::ContextAttached { label: "stdin".into(), content }); }
+    // Slash commands are for the TUI; here a leading `/` is part of the question.
+    app.search_input = if query.starts_with('/') { format!("/{}", query) } else { query.to_string() };
+    let fx = app.reduce(AppAction::SubmitSearch);
+    if !app.is_searching {
+        // Answered locally (`= expr`), nothing to stream.
+        let answer = app.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        if as_json { println!("{}", json!({ "query": query, "answer": answer })); } else { println!("{}", answer); }
+        return Ok(());
+    }
+    for effect in fx.into_iter().filter(|e| matches!(e, Effect::StartSearch(_) | Effect::StartDirectChat(_) | Effect::StartOllamaChat(_))) {
+        effects::execute(effect, app.action_tx.clone());
+    }
+
+    let mut stdout = std::io::stdout();
+    let mut convo_id = None;
+    while let Some(action) = app.action_rx.recv().await {
+        match &action {
+            AppAction::SearchStreamToken(text) if !as_json => { print!("{}", text); let _ = stdout.flush(); },
+            AppAction::ConversationCreated(id) => convo_id = Some(*id),
+            AppAction::SearchError(e) | AppAction::SearchOffline(e) => bail!("{}", e),
+            _ => {},
+        }
+        let done = action == AppAction::SearchDone;
+        // Only the state matters here: follow-ups, titles and refreshes are TUI business.
+        app.reduce(action);
+        if done { break; }
+    }
+    let Some(answer) = app.messages.iter().rev().find(|m| m.role == "assistant") else { bail!("no answer") };
+    if as_json {
+        println!("{}", answer_json(query, answer, convo_id.or(app.current_convo_id)));
+    } else {
+        println!("{}", sources_list(&answer.sources));
+    }
+    Ok(())
+}
+
+/// Fills in the providers and models the Search tab would load, so the
+/// saved model and sources apply. A provider whose list can't be fetched
+/// gets the saved model, else the backend's default.
+async fn load_models(app: &mut App) {
+    if !app.is_direct() {
+        if let Ok(providers) = api::fetch_providers_list().await { app.reduce(AppAction::ProvidersLoaded(providers)); }
+    }
+    let models = match app.fetch_models() {
+        Effect::Dispatch(AppAction::ModelsLoaded(models)) => Ok(models),
+        Effect::FetchDirectModels { url, api_key } => direct::fetch_models(&url, api_key).await,
+        Effect::FetchOllamaModels(url) => ollama::fetch_models(&url).await,
+        Effect::FetchModels(provider) => api::fetch_models(&provider).await,
+        _ => Ok(vec![]),
+    };
+    match models {
+        Ok(models) => { app.reduce(AppAction::ModelsLoaded(models)); },
+        Err(e) => {
+            eprintln!("bpt: can't list models ({}), using the saved one", e);
+            app.selected_model = app.config.model.clone().unwrap_or_else(|| "default".into());
+        },
+    }
+}
+
+/// The backend's (or `--local`) apps, then those of each `--docker` / `--systemd` source.
+async fn catalog(app: &mut App, sources: &BTreeSet<Source>) -> Result<()> {
+    let apps = if local::enabled() { local::load_apps() } else { api::fetch_apps().await? };
+    app.reduce(AppAction::AppsLoaded(apps));
+    for source in sources {
+        app.reduce(AppAction::SourceLoaded(*source, source.list().await?));
+    }
+    Ok(())
+}
+
+/// `bpt list`: one app per line as `id<TAB>name<TAB>description`, ready for
+/// `cut`, fzf or rofi; `--json` prints the apps as stored.
+pub async fn list(sources: &BTreeSet<Source>, as_json: bool) -> Result<()> {
+    let mut app = configured_app();
+    catalog(&mut app, sources).await?;
+    if as_json { println!("{}", serde_json::to_string_pretty(&app.apps)?); }
+    else { for a in &app.apps { println!("{}", list_line(a)); } }
+    Ok(())
+}
+
+fn list_line(a: &AppModel) -> String {
+    let description = a.description.as_deref().unwrap_or("").replace(['\t', '\n'], " ");
+    format!("{}\t{}\t{}", a.id, a.name, description.trim())
+}
+
+/// The app `target` names: its id, else its name ignoring case.
+fn find_app<'a>(apps: &'a [AppModel], target: &str) -> Result<&'a AppModel> {
+    if let Some(a) = apps.iter().find(|a| a.id == target) { return Ok(a); }
+    let named: Vec<&AppModel> = apps.iter().filter(|a| a.name.eq_ignore_ascii_case(target)).collect();
+    match named.as_slice() {
+        [a] => Ok(a),
+        [] => bail!("no app named or with id '{}' (see `bpt list`)", target),
+        _ => bail!("'{}' names {} apps; use one of their ids: {}", target, named.len(), named.iter().map(|a| a.id.as_str()).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// `bpt launch`: launches like `Enter` in the Apps list (remote hosts, tmux,
+/// usage and launch history included) and streams the output until the
+/// launch finishes. Placeholders take `args`, else the values used last time.
+pub async fn launch(sources: &BTreeSet<Source>, target: &str, args: &BTreeMap<String, String>) -> Result<()> {
+    let mut app = configured_app();
+    app.usage = store::load(store::USAGE);
+    app.launch_history = store::load(store::HISTORY);
+    app.ssh_hosts = store::load(store::HOSTS);
+    app.tmux_modes = store::load(store::TMUX);
+    app.arg_defaults = store::load(store::ARGS);
+    app.in_tmux = std::env::var_os("TMUX").is_some_and(|v| !v.is_empty());
+    catalog(&mut app, sources).await?;
+    let id = find_app(&app.apps, target)?.id.clone();
+
+    let before = app.launch_history.last().map(|r| r.seq);
+    let mut fx = app.reduce(AppAction::LaunchApp(id));
+    if let Some(prompt) = app.args_prompt.as_mut() {
+        for (name, value) in prompt.fields.iter_mut() {
+            if let Some(v) = args.get(name) { *value = v.clone(); }
+        }
+        let missing: Vec<&str> = prompt.fields.iter().filter(|(_, v)| v.trim().is_empty()).map(|(n, _)| n.as_str()).collect();
+        if !missing.is_empty() { bail!("'{}' needs values for: {} (pass them as name=value)", prompt.name, missing.join(", ")); }
+        fx.extend(app.reduce(AppAction::SubmitArgs));
+    }
+    let Some(seq) = app.launch_history.last().map(|r| r.seq).filter(|s| Some(*s) != before) else {
+        bail!("{}", app.launcher_logs.last().cloned().unwrap_or_else(|| "nothing was launched".into()));
+    };
+    if let Some(line) = app.launcher_logs.last() { eprintln!("{}", line); }
+    run_effects(fx, &app);
+
+    while let Some(action) = app.action_rx.recv().await {
+        match &action {
+            AppAction::LaunchOutput { seq: s, line, stderr } if *s == seq => {
+                if *stderr { eprintln!("{}", line); } else { println!("{}", line); }
+            },
+            AppAction::LaunchFinished { seq: s, ok, output, .. } if *s == seq => {
+                let ok = *ok;
+                let mut lines = output.lines();
+                let status = lines.next().unwrap_or(if ok { "Success" } else { "Failed" }).trim_end_matches(':').to_string();
+                for l in lines.filter(|l| !l.trim().is_empty()) { if ok { println!("{}", l); } else { eprintln!("{}", l); } }
+                // Recorded in the launch history, as a launch from the TUI would be.
+                let fx = app.reduce(action);
+                run_effects(fx, &app);
+                if !ok { bail!("{}", status); }
+                eprintln!("{}", status);
+                return Ok(());
+            },
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
+/// Runs a launch's effects; stores are written before returning, since the
+/// process may exit right after.
+fn run_effects(fx: Vec<Effect>, app: &App) {
+    for effect in fx {
+        match effect {
+            Effect::WriteStore { name, data } => { if let Err(e) = store::save(name, &data) { eprintln!("bpt: could not save {}: {:#}", name, e); } },
+            Effect::LaunchApp { .. } | Effect::LaunchCommand { .. } | Effect::LaunchRemote { .. } | Effect::LaunchTmux { .. } => effects::execute(effect, app.action_tx.clone()),
+            _ => {},
+        }
+    }
+}
+
+/// The markdown printed after a streamed answer: its sources, numbered.
+fn sources_list(sources: &[SearchSource]) -> String {
+    if sources.is_empty() { return String::new(); }
+    let lines: Vec<String> = sources.iter().enumerate().map(|(i, s)| format!("{}. [{}]({})", i + 1, s.title, s.url)).collect();
+    format!("\nSources:\n{}", lines.join("\n"))
+}
+
+/// `--json` output: the answer with everything the Search tab shows about it.
+fn answer_json(query: &str, answer: &ChatMessage, convo_id: Option<i64>) -> Value {
+    json!({
+        "query": query,
+        "answer": answer.content,
+        "reasoning": Some(&answer.reasoning).filter(|r| !r.is_empty()),
+        "provider": answer.provider,
+        "model": answer.model,
+        "conversation_id": convo_id,
+        "sources": answer.sources,
+        "tools": answer.tools,
+        "usage": answer.usage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::Usage;
+
+    fn app(id: &str, name: &str) -> AppModel {
+        AppModel { id: id.into(), name: name.into(), description: Some("Local\tLLM".into()), command: String::new(), url: String::new(), launch: Default::default(), target: None }
+    }
+
+    #[test]
+    fn finds_apps_by_id_or_name() {
+        let apps = [app("1", "LM Studio"), app("2", "web"), app("3", "Web")];
+        assert_eq!(find_app(&apps, "lm studio").unwrap().id, "1");
+        assert_eq!(find_app(&apps, "3").unwrap().id, "3");
+        assert!(find_app(&apps, "web").unwrap_err().to_string().contains("2, 3"));
+        assert!(find_app(&apps, "nope").is_err());
+        assert_eq!(list_line(&apps[0]), "1\tLM Studio\tLocal LLM");
+    }
+
+    #[test]
+    fn prints_sources_and_json() {
+        let source = SearchSource { title: "Docs".into(), url: "https://docs.rs".into(), content: String::new(), engine: "ddg".into() };
+        assert_eq!(sources_list(&[]), "");
+        assert_eq!(sources_list(std::slice::from_ref(&source)), "\nSources:\n1. [Docs](https://docs.rs)");
+
+        let answer = ChatMessage { sources: vec![source], model: Some("m".into()), usage: Some(Usage { prompt: 5, completion: 2 }), ..ChatMessage::new("assistant", "Hi") };
+        let v = answer_json("hello", &answer, Some(4));
+        assert_eq!((v["answer"].as_str(), v["model"].as_str(), v["conversation_id"].as_i64()), (Some("Hi"), Some("m"), Some(4)));
+        assert_eq!((v["sources"][0]["url"].as_str(), v["usage"]["completion"].as_u64(), &v["reasoning"]), (Some("https://docs.rs"), Some(2), &Value::Null));
+    }
+}
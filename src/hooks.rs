@@ -0,0 +1,77 @@
+// ================================================
+// FILE: src/hooks.rs
+// ================================================
+// User commands run on events (`hooks` in config.json): a finished answer,
+// a launch that succeeded or failed. Each runs through the shell with the
+// event as one JSON object on stdin, so a one-liner can raise a desktop
+// notification or log to another system. The reducer decides when and with
+// what; effects.rs spawns them and reports failures in the Output pane.
+use std::process::Stdio;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use crate::local;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_search_done: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_launch_success: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_launch_failure: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event { SearchDone, LaunchSuccess, LaunchFailure }
+
+impl Event {
+    /// The `event` field of the payload.
+    pub fn name(self) -> &'static str {
+        match self { Event::SearchDone => "search_done", Event::LaunchSuccess => "launch_success", Event::LaunchFailure => "launch_failure" }
+    }
+}
+
+impl Hooks {
+    /// The command configured for `event`, if any.
+    pub fn command(&self, event: Event) -> Option<&str> {
+        let cmd = match event {
+            Event::SearchDone => &self.on_search_done,
+            Event::LaunchSuccess => &self.on_launch_success,
+            Event::LaunchFailure => &self.on_launch_failure,
+        };
+        cmd.as_deref().map(str::trim).filter(|c| !c.is_empty())
+    }
+}
+
+/// Runs `cmd` with `payload` on stdin and waits for it; a non-zero exit is
+/// an error carrying the first line of its stderr.
+pub async fn run(cmd: &str, payload: &Value) -> Result<()> {
+    let mut child = local::shell(cmd).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read its input is fine.
+        let _ = stdin.write_all(format!("{}\n", payload).as_bytes()).await;
+    }
+    let out = child.wait_with_output().await?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        bail!("{} ({})", err.lines().next().unwrap_or("").trim(), out.status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_command_for_each_event() {
+        let hooks: Hooks = serde_json::from_str(r#"{"on_search_done":"notify-send 'bpt' 'Answer ready'","on_launch_failure":"  "}"#).unwrap();
+        assert_eq!(hooks.command(Event::SearchDone), Some("notify-send 'bpt' 'Answer ready'"));
+        assert_eq!(hooks.command(Event::LaunchSuccess), None);
+        assert_eq!(hooks.command(Event::LaunchFailure), None, "blank commands are off");
+        assert_eq!(Event::LaunchFailure.name(), "launch_failure");
+    }
+}
@@ -0,0 +1,201 @@
+// ================================================
+// FILE: src/keymap.rs
+// ================================================
+// Rebindable keys for the navigation screens (Launcher, Processes). Defaults
+// live in `COMMANDS`; user changes made from the command palette are stored as
+// per-command overrides in store::KEYMAP. Text-entry modes keep fixed keys.
+use std::collections::BTreeMap;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::app::AppAction;
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Global,     // Any navigation screen
+    Launcher,
+    Processes,
+}
+
+impl Context {
+    fn overlaps(self, other: Context) -> bool {
+        self == other || self == Context::Global || other == Context::Global
+    }
+}
+
+pub struct Command {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub context: Context,
+    pub keys: &'static [&'static str],
+    pub action: fn() -> AppAction,
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command { id: "global.switch_tab", title: "Switch tab", context: Context::Global, keys: &["Tab"], action: || AppAction::SwitchTab },
+    Command { id: "global.quit", title: "Quit", context: Context::Global, keys: &["q"], action: || AppAction::Quit },
+    Command { id: "global.pane_narrower", title: "Narrow the left pane", context: Context::Global, keys: &["Ctrl+Left"], action: || AppAction::ResizePane(-config::SPLIT_STEP) },
+    Command { id: "global.pane_wider", title: "Widen the left pane", context: Context::Global, keys: &["Ctrl+Right"], action: || AppAction::ResizePane(config::SPLIT_STEP) },
+    Command { id: "launcher.next", title: "Next app", context: Context::Launcher, keys: &["Down", "j"], action: || AppAction::SelectNext },
+    Command { id: "launcher.prev", title: "Previous app", context: Context::Launcher, keys: &["Up", "k"], action: || AppAction::SelectPrev },
+    Command { id: "launcher.launch", title: "Launch selected app", context: Context::Launcher, keys: &["Enter"], action: || AppAction::LaunchSelected },
+    Command { id: "launcher.mark", title: "Mark app for bulk actions", context: Context::Launcher, keys: &["Space"], action: || AppAction::ToggleMark },
+    Command { id: "launcher.clear_marks", title: "Clear marks", context: Context::Launcher, keys: &["Esc"], action: || AppAction::ClearMarks },
+    Command { id: "launcher.export", title: "Export marked (or selected) apps", context: Context::Launcher, keys: &["w"], action: || AppAction::ExportApps },
+    Command { id: "launcher.focus_output", title: "Focus the Output pane", context: Context::Launcher, keys: &["Right", "l"], action: || AppAction::FocusOutput(true) },
+    Command { id: "launcher.export_output", title: "Save Output pane to a file", context: Context::Launcher, keys: &["W"], action: || AppAction::ExportOutput },
+    Command { id: "launcher.errors_only", title: "Show only errors in the Output pane", context: Context::Launcher, keys: &[], action: || AppAction::ToggleErrorsOnly },
+    Command { id: "launcher.ask_output", title: "Ask about the Output pane in Search", context: Context::Launcher, keys: &["A"], action: || AppAction::AskAboutOutput },
+    Command { id: "launcher.logs", title: "Show container or unit logs", context: Context::Launcher, keys: &["L"], action: || AppAction::ShowLogs },
+    Command { id: "launcher.tmux", title: "Tmux launch mode (window/pane/session)", context: Context::Launcher, keys: &["M"], action: || AppAction::CycleTmuxMode },
+    Command { id: "launcher.stop", title: "Stop selected app", context: Context::Launcher, keys: &["x"], action: || AppAction::StopSelected },
+    Command { id: "launcher.filter", title: "Filter apps", context: Context::Launcher, keys: &["/"], action: || AppAction::ToggleFilter },
+    Command { id: "launcher.add", title: "Add app", context: Context::Launcher, keys: &["a"], action: || AppAction::OpenAddModal },
+    Command { id: "launcher.edit", title: "Edit selected app", context: Context::Launcher, keys: &["e"], action: || AppAction::OpenEditModal },
+    Command { id: "launcher.delete", title: "Delete selected app", context: Context::Launcher, keys: &["d"], action: || AppAction::RequestDelete },
+    Command { id: "launcher.undo", title: "Undo delete", context: Context::Launcher, keys: &["u"], action: || AppAction::Undo },
+    Command { id: "launcher.tags", title: "Browse tags", context: Context::Launcher, keys: &["t"], action: || AppAction::OpenTagBrowser },
+    Command { id: "launcher.clear_tags", title: "Clear tag filter", context: Context::Launcher, keys: &["T"], action: || AppAction::ClearTags },
+    Command { id: "launcher.favorite", title: "Toggle favorite", context: Context::Launcher, keys: &["*"], action: || AppAction::ToggleFavorite },
+    Command { id: "launcher.favorites_only", title: "Show favorites only", context: Context::Launcher, keys: &["F"], action: || AppAction::ToggleFavoritesOnly },
+    Command { id: "launcher.sort", title: "Cycle sort mode", context: Context::Launcher, keys: &["s"], action: || AppAction::CycleSort },
+    Command { id: "launcher.editor", title: "Open file:line from output", context: Context::Launcher, keys: &["E"], action: || AppAction::OpenFileRef },
+    Command { id: "launcher.preview", title: "Preview app web page", context: Context::Launcher, keys: &["v"], action: || AppAction::PreviewSelected },
+    Command { id: "launcher.chains", title: "Launch chains", context: Context::Launcher, keys: &["c"], action: || AppAction::OpenChains },
+    Command { id: "launcher.open_url", title: "Open app URL in browser", context: Context::Launcher, keys: &["o"], action: || AppAction::OpenSelectedUrl },
+    Command { id: "launcher.health", title: "Check app URLs now", context: Context::Launcher, keys: &["H"], action: || AppAction::RefreshHealth },
+    Command { id: "launcher.auto_open", title: "Toggle opening URL after launch", context: Context::Launcher, keys: &["O"], action: || AppAction::ToggleAutoOpen },
+    Command { id: "launcher.history", title: "Launch history", context: Context::Launcher, keys: &["h"], action: || AppAction::OpenLaunchHistory },
+    Command { id: "launcher.sync", title: "Sync local apps with backend", context: Context::Launcher, keys: &["S"], action: || AppAction::SyncApps { prefer_local: false } },
+    Command { id: "launcher.sync_local", title: "Sync apps, local side wins conflicts", context: Context::Launcher, keys: &[], action: || AppAction::SyncApps { prefer_local: true } },
+    Command { id: "launcher.adhoc", title: "Run ad-hoc command", context: Context::Launcher, keys: &[":"], action: || AppAction::OpenAdHocModal },
+    Command { id: "processes.next", title: "Next process", context: Context::Processes, keys: &["Down", "j"], action: || AppAction::ProcessNext },
+    Command { id: "processes.prev", title: "Previous process", context: Context::Processes, keys: &["Up", "k"], action: || AppAction::ProcessPrev },
+    Command { id: "processes.output", title: "Show process output", context: Context::Processes, keys: &["Enter", "o"], action: || AppAction::ShowProcessOutput },
+    Command { id: "processes.stop", title: "Stop process", context: Context::Processes, keys: &["x"], action: || AppAction::StopProcess },
+    Command { id: "processes.restart", title: "Restart process", context: Context::Processes, keys: &["r"], action: || AppAction::RestartProcess },
+    Command { id: "processes.refresh", title: "Refresh processes", context: Context::Processes, keys: &["R"], action: || AppAction::LoadProcesses },
+    Command { id: "processes.editor", title: "Open file:line from output", context: Context::Processes, keys: &["E"], action: || AppAction::OpenFileRef },
+];
+
+/// Prefix of the per-app "launch this app" commands.
+pub const LAUNCH_PREFIX: &str = "launch:";
+
+/// Canonical name of a key press: `j`, `F`, `Ctrl+x`, `Alt+Enter`, `PageDown`.
+pub fn key_name(key: &KeyEvent) -> Option<String> {
+    let base = match key.code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "Enter".into(),
+        KeyCode::Tab => "Tab".into(),
+        KeyCode::BackTab => "BackTab".into(),
+        KeyCode::Esc => "Esc".into(),
+        KeyCode::Backspace => "Backspace".into(),
+        KeyCode::Delete => "Delete".into(),
+        KeyCode::Up => "Up".into(),
+        KeyCode::Down => "Down".into(),
+        KeyCode::Left => "Left".into(),
+        KeyCode::Right => "Right".into(),
+        KeyCode::Home => "Home".into(),
+        KeyCode::End => "End".into(),
+        KeyCode::PageUp => "PageUp".into(),
+        KeyCode::PageDown => "PageDown".into(),
+        KeyCode::F(n) => format!("F{}", n),
+        _ => return None,
+    };
+    // Shift is already in the character itself (`F`, `*`).
+    let mut name = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) { name.push_str("Ctrl+"); }
+    if key.modifiers.contains(KeyModifiers::ALT) { name.push_str("Alt+"); }
+    name.push_str(&base);
+    Some(name)
+}
+
+/// Overrides on top of `COMMANDS`, keyed by command id. An empty list means
+/// the command was unbound. `launch:<app id>` entries bind keys to one app.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keymap {
+    pub overrides: BTreeMap<String, Vec<String>>,
+}
+
+fn command(id: &str) -> Option<&'static Command> { COMMANDS.iter().find(|c| c.id == id) }
+
+fn context_of(id: &str) -> Context {
+    command(id).map(|c| c.context).unwrap_or(Context::Launcher)
+}
+
+impl Keymap {
+    pub fn keys(&self, id: &str) -> Vec<String> {
+        match self.overrides.get(id) {
+            Some(keys) => keys.clone(),
+            None => command(id).map(|c| c.keys.iter().map(|k| k.to_string()).collect()).unwrap_or_default(),
+        }
+    }
+
+    /// The action bound to `key` on a screen with context `ctx`.
+    pub fn lookup(&self, ctx: Context, key: &str) -> Option<AppAction> {
+        let app = self.overrides.iter()
+            .filter(|(id, keys)| id.starts_with(LAUNCH_PREFIX) && keys.iter().any(|k| k == key))
+            .map(|(id, _)| AppAction::LaunchApp(id[LAUNCH_PREFIX.len()..].to_string()));
+        let app = if ctx == Context::Launcher { app.into_iter().next() } else { None };
+        app.or_else(|| {
+            COMMANDS.iter()
+                .filter(|c| c.context == ctx || c.context == Context::Global)
+                .find(|c| self.keys(c.id).iter().any(|k| k == key))
+                .map(|c| (c.action)())
+        })
+    }
+
+    /// Binds `key` to `id` (in addition to its other keys), taking it away
+    /// from any command it would clash with.
+    pub fn bind(&mut self, id: &str, key: &str) {
+        let ctx = context_of(id);
+        let ids: Vec<String> = COMMANDS.iter().map(|c| c.id.to_string()).chain(self.overrides.keys().cloned()).collect();
+        for other in ids {
+            if other == id || !context_of(&other).overlaps(ctx) { continue; }
+            let keys = self.keys(&other);
+            if keys.iter().any(|k| k == key) { self.overrides.insert(other, keys.into_iter().filter(|k| k != key).collect()); }
+        }
+        let mut keys = self.keys(id);
+        if !keys.iter().any(|k| k == key) { keys.push(key.to_string()); }
+        self.overrides.insert(id.to_string(), keys);
+    }
+
+    pub fn unbind(&mut self, id: &str) {
+        if id.starts_with(LAUNCH_PREFIX) { self.overrides.remove(id); } else { self.overrides.insert(id.to_string(), vec![]); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names() {
+        assert_eq!(key_name(&KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT)).as_deref(), Some("F"));
+        assert_eq!(key_name(&KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)).as_deref(), Some("Ctrl+x"));
+        assert_eq!(key_name(&KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE)).as_deref(), Some("PageDown"));
+    }
+
+    #[test]
+    fn defaults_overrides_and_conflicts() {
+        let mut km = Keymap::default();
+        assert_eq!(km.lookup(Context::Launcher, "j"), Some(AppAction::SelectNext));
+        assert_eq!(km.lookup(Context::Processes, "j"), Some(AppAction::ProcessNext));
+        assert_eq!(km.lookup(Context::Processes, "Tab"), Some(AppAction::SwitchTab));
+
+        km.bind("launcher.launch", "l");
+        assert_eq!(km.keys("launcher.launch"), ["Enter", "l"]);
+        km.bind("launcher.sort", "x");
+        assert_eq!(km.lookup(Context::Launcher, "x"), Some(AppAction::CycleSort));
+        assert!(km.keys("launcher.stop").is_empty());
+        assert_eq!(km.lookup(Context::Processes, "x"), Some(AppAction::StopProcess), "other screens keep their key");
+
+        km.unbind("launcher.delete");
+        assert_eq!(km.lookup(Context::Launcher, "d"), None);
+
+        km.bind("launch:7", "g");
+        assert_eq!(km.lookup(Context::Launcher, "g"), Some(AppAction::LaunchApp("7".into())));
+        km.unbind("launch:7");
+        assert!(!km.overrides.contains_key("launch:7"));
+    }
+}
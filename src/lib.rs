@@ -0,0 +1,54 @@
+// ================================================
+// FILE: src/lib.rs
+// ================================================
+// The TUI is a thin binary (`main.rs`) over this library so benches and
+// tests can drive the reducer and renderer directly.
+pub mod a11y;
+pub mod ansi;
+pub mod api;
+pub mod app;
+pub mod attachments;
+pub mod backoff;
+pub mod calc;
+pub mod clipboard;
+pub mod clock;
+pub mod colors;
+pub mod commands;
+pub mod config;
+pub mod convo_search;
+pub mod crypt;
+pub mod direct;
+pub mod docker;
+pub mod editor;
+pub mod keymap;
+pub mod local;
+pub mod logbuf;
+pub mod mentions;
+pub mod cli;
+pub mod effects;
+pub mod files;
+pub mod followup;
+pub mod headless;
+pub mod hooks;
+pub mod mode;
+pub mod notify;
+pub mod ollama;
+pub mod plugins;
+pub mod preview;
+pub mod replay;
+pub mod share;
+pub mod snippets;
+pub mod sources;
+pub mod ssh;
+pub mod store;
+pub mod summary;
+pub mod sync;
+pub mod systemd;
+pub mod template;
+pub mod textarea;
+pub mod tmux;
+pub mod todo;
+pub mod title;
+pub mod tokens;
+pub mod transcript;
+pub mod ui;
@@ -0,0 +1,144 @@
+// ================================================
+// FILE: src/local.rs
+// ================================================
+// `--local`: no launcher backend. App definitions live in store::APPS and
+// commands run as child processes of the TUI, with stdout/stderr streamed
+// into the Output pane exactly like a streaming backend launch.
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use anyhow::{anyhow, Result};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+use crate::api::{AppModel, LaunchEnv};
+use crate::app::AppAction;
+use crate::commands;
+use crate::store;
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+pub fn enable() { let _ = ENABLED.set(true); }
+pub fn enabled() -> bool { ENABLED.get().copied().unwrap_or(false) }
+
+// Serializes read-modify-write of the apps file.
+static APPS_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn load_apps() -> Vec<AppModel> { store::load(store::APPS) }
+
+/// The next free id: one past the largest numeric id in use.
+pub fn next_id(apps: &[AppModel]) -> String {
+    (apps.iter().filter_map(|a| a.id.parse::<u64>().ok()).max().unwrap_or(0) + 1).to_string()
+}
+
+/// Creates (empty id) or replaces an app definition.
+pub fn save_app(mut model: AppModel) -> Result<()> {
+    let _guard = APPS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut apps = load_apps();
+    match apps.iter_mut().find(|a| !model.id.is_empty() && a.id == model.id) {
+        Some(existing) => *existing = model,
+        None => { if model.id.is_empty() { model.id = next_id(&apps); } apps.push(model); },
+    }
+    store::save(store::APPS, &apps)
+}
+
+/// Replaces the whole catalog (sync.rs).
+pub fn replace_apps(apps: &[AppModel]) -> Result<()> {
+    let _guard = APPS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    store::save(store::APPS, apps)
+}
+
+pub fn delete_app(id: &str) -> Result<()> {
+    let _guard = APPS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut apps = load_apps();
+    apps.retain(|a| a.id != id);
+    store::save(store::APPS, &apps)
+}
+
+// Stop signals for running children, keyed by app id (or `adhoc-<seq>`).
+static RUNNING: Mutex<BTreeMap<String, oneshot::Sender<()>>> = Mutex::new(BTreeMap::new());
+
+fn running() -> std::sync::MutexGuard<'static, BTreeMap<String, oneshot::Sender<()>>> {
+    RUNNING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Kills the child started under `key`; false if nothing is running.
+pub fn stop(key: &str) -> bool {
+    running().remove(key).map(|tx| tx.send(()).is_ok()).unwrap_or(false)
+}
+
+pub fn shell(cmd: &str) -> Command {
+    let (sh, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut c = Command::new(sh);
+    c.arg(flag).arg(cmd);
+    c
+}
+
+/// Runs `cmd` with `env` applied, streaming its output as `LaunchOutput` for
+/// `seq`. Returns whether it exited successfully, and a closing status line.
+pub async fn run(key: String, cmd: &str, env: &LaunchEnv, seq: u64, tx: &UnboundedSender<AppAction>) -> Result<(bool, String)> {
+    let mut command = shell(cmd);
+    command.envs(&env.env);
+    if let Some(dir) = &env.cwd { command.current_dir(commands::resolve_path(None, dir)); }
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true).spawn()
+        .map_err(|e| match &env.cwd { Some(dir) => anyhow!("{} (cwd {})", e, dir), None => anyhow!(e) })?;
+    let (stop_tx, stop_rx) = oneshot::channel();
+    running().insert(key.clone(), stop_tx);
+
+    let mut readers = vec![];
+    if let Some(out) = child.stdout.take() { readers.push(tokio::spawn(forward(BufReader::new(out), seq, false, tx.clone()))); }
+    if let Some(err) = child.stderr.take() { readers.push(tokio::spawn(forward(BufReader::new(err), seq, true, tx.clone()))); }
+
+    let result = tokio::select! {
+        status = child.wait() => status.map_err(|e| anyhow!(e)),
+        // A dropped sender (same app launched again) is not a stop request.
+        Ok(()) = stop_rx => { let _ = child.kill().await; return Ok((false, "Stopped".into())); },
+    };
+    for r in readers { let _ = r.await; }
+    running().remove(&key);
+    let status = result?;
+    Ok(if status.success() { (true, "Success".into()) } else { (false, format!("Failed: {}", status)) })
+}
+
+async fn forward<R: tokio::io::AsyncRead + Unpin>(reader: BufReader<R>, seq: u64, stderr: bool, tx: UnboundedSender<AppAction>) {
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let _ = tx.send(AppAction::LaunchOutput { seq, line, stderr });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str) -> AppModel { AppModel { id: id.into(), name: format!("app{}", id), ..Default::default() } }
+
+    #[test]
+    fn next_id_skips_used_numbers() {
+        assert_eq!(next_id(&[]), "1");
+        assert_eq!(next_id(&[app("2"), app("abc"), app("7")]), "8");
+    }
+
+    #[tokio::test]
+    async fn run_streams_lines_and_reports_exit() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let (ok, _) = run("t1".into(), "echo one; echo two >&2; exit 3", &LaunchEnv::default(), 5, &tx).await.unwrap();
+        assert!(!ok);
+        let mut got = vec![];
+        while let Ok(AppAction::LaunchOutput { seq, line, stderr }) = rx.try_recv() { got.push((seq, line, stderr)); }
+        got.sort();
+        assert_eq!(got, [(5, "one".to_string(), false), (5, "two".to_string(), true)]);
+    }
+
+    #[tokio::test]
+    async fn run_applies_env_and_cwd() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let env = LaunchEnv { env: [("GREETING".to_string(), "hi there".to_string())].into(), cwd: Some("/".into()) };
+        let (ok, _) = run("t2".into(), "echo \"$GREETING\"; pwd", &env, 1, &tx).await.unwrap();
+        assert!(ok);
+        let mut got = vec![];
+        while let Ok(AppAction::LaunchOutput { line, .. }) = rx.try_recv() { got.push(line); }
+        assert_eq!(got, ["hi there", "/"]);
+    }
+}
@@ -0,0 +1,115 @@
+// ================================================
+// FILE: src/logbuf.rs
+// ================================================
+// The launcher's Output pane: a ring buffer of the newest lines. Lines are
+// also numbered from the start of the session (`total`), so a scrolled view
+// can stay on the same lines while new output arrives and old lines drop off.
+// Each line is classified when it is pushed so the pane can color errors and
+// warnings and hide everything else on request.
+use std::collections::VecDeque;
+use crate::ansi;
+
+/// Lines kept by default; far more than one screen, small enough to search on every key.
+pub const LIMIT: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity { Info, Warning, Stderr, Error }
+
+impl Severity {
+    /// Shown when the Output pane only lists errors.
+    pub fn is_error(self) -> bool { matches!(self, Severity::Stderr | Severity::Error) }
+}
+
+const ERROR_PREFIXES: [&str; 9] = ["error", "err ", "failed", "fatal", "panic", "traceback", "exception", "could not", "cannot "];
+const ERROR_MARKERS: [&str; 9] = ["error:", "err!", "[error]", "level=error", " failed", "failed:", "exception:", "panicked at", "api error"];
+
+/// How a line of output reads: an error message (from the app or from a
+/// backend request), a warning, other stderr output, or ordinary output.
+pub fn classify(line: &str, stderr: bool) -> Severity {
+    let text = ansi::strip(line).trim_start().to_lowercase();
+    if ERROR_PREFIXES.iter().any(|p| text.starts_with(p)) || ERROR_MARKERS.iter().any(|m| text.contains(m)) { Severity::Error }
+    else if text.starts_with("warn") || text.contains("warning:") || text.contains("[warn") || text.contains("level=warn") { Severity::Warning }
+    else if stderr { Severity::Stderr }
+    else { Severity::Info }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogBuffer {
+    lines: VecDeque<String>,
+    kinds: VecDeque<Severity>,
+    limit: usize,
+    dropped: u64,  // Lines that fell off the front
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self { LogBuffer::new(LIMIT) }
+}
+
+impl From<Vec<String>> for LogBuffer {
+    fn from(lines: Vec<String>) -> Self {
+        let mut buf = LogBuffer::default();
+        for line in lines { buf.push(line); }
+        buf
+    }
+}
+
+impl LogBuffer {
+    pub fn new(limit: usize) -> Self { LogBuffer { lines: VecDeque::new(), kinds: VecDeque::new(), limit: limit.max(1), dropped: 0 } }
+
+    pub fn push(&mut self, line: String) { self.push_from(line, false); }
+
+    /// Pushes a line the app wrote to stdout or (`stderr`) stderr.
+    pub fn push_from(&mut self, line: String, stderr: bool) {
+        if self.lines.len() == self.limit { self.lines.pop_front(); self.kinds.pop_front(); self.dropped += 1; }
+        self.kinds.push_back(classify(&line, stderr));
+        self.lines.push_back(line);
+    }
+
+    pub fn len(&self) -> usize { self.lines.len() }
+    pub fn is_empty(&self) -> bool { self.lines.is_empty() }
+    pub fn last(&self) -> Option<&String> { self.lines.back() }
+    pub fn get(&self, i: usize) -> Option<&String> { self.lines.get(i) }
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, String> { self.lines.iter() }
+    pub fn severity(&self, i: usize) -> Severity { self.kinds.get(i).copied().unwrap_or(Severity::Info) }
+
+    /// Lines pushed this session, including dropped ones.
+    pub fn total(&self) -> u64 { self.dropped + self.lines.len() as u64 }
+    /// Session line number of the first line still kept.
+    pub fn first(&self) -> u64 { self.dropped }
+
+    /// Buffer index of session line `n`, clamped to what is kept.
+    pub fn index_of(&self, n: u64) -> usize { n.saturating_sub(self.dropped).min(self.lines.len() as u64) as usize }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_lines_and_counts_the_rest() {
+        let mut buf = LogBuffer::new(3);
+        for i in 0..5 { buf.push(i.to_string()); }
+        assert_eq!(buf.iter().map(String::as_str).collect::<Vec<_>>(), ["2", "3", "4"]);
+        assert_eq!((buf.total(), buf.first()), (5, 2));
+        assert_eq!(buf.index_of(3), 1);
+        assert_eq!(buf.index_of(0), 0, "dropped lines clamp to the oldest kept");
+        assert_eq!(buf.index_of(99), 3);
+    }
+
+    #[test]
+    fn classifies_lines() {
+        assert_eq!(classify("Error: port 80 in use", false), Severity::Error);
+        assert_eq!(classify("Launch failed: connection refused", false), Severity::Error);
+        assert_eq!(classify("\x1b[31mFAILED\x1b[0m tests/api.rs", false), Severity::Error);
+        assert_eq!(classify("2024-05-01 [ERROR] db down", true), Severity::Error);
+        assert_eq!(classify("warning: unused variable", true), Severity::Warning);
+        assert_eq!(classify("Compiling bpt v0.1.0", true), Severity::Stderr);
+        assert_eq!(classify("listening on :8080", false), Severity::Info);
+        assert_eq!(classify("0 errors", false), Severity::Info);
+        let mut buf = LogBuffer::new(2);
+        buf.push_from("a".into(), true);
+        buf.push("b".into());
+        buf.push("c".into());
+        assert_eq!((buf.severity(0), buf.severity(1)), (Severity::Info, Severity::Info), "kinds drop with their lines");
+    }
+}
@@ -1,82 +1,296 @@
 // ================================================
 // FILE: src/main.rs
 // ================================================
-mod api;
-mod app;
-mod ui;
-
-use std::{io, time::Duration};
+use std::{io::{self, IsTerminal, Write}, time::Duration};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste, EnableFocusChange, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use app::{App, AppAction, CurrentScreen, InputMode};
+use bpt::{api, cli, config, editor, keymap, replay, share, ui};
+use bpt::app::{App, AppAction, AppForm, ChainFocus, CurrentScreen, InputMode, SearchSidebarState};
+use bpt::files::Pick;
+use bpt::snippets::Verb;
+use bpt::textarea::CursorMove;
+use bpt::replay::ReplayState;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = match cli::Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => { eprintln!("{}\n\n{}", e, cli::USAGE); std::process::exit(2); }
+    };
+    if cli.help { println!("{}", cli::USAGE); return Ok(()); }
+    // Piped input has to be read before the terminal is switched to raw mode,
+    // and only for a question: otherwise stdin may be a pipe that never closes.
+    let asking = cli.ask.is_some() || matches!(cli.command, Some(cli::Command::Ask { .. }));
+    let piped = if asking { cli::read_stdin() } else { None };
+
+    // Bind before entering the alternate screen so address errors are readable.
+    let share_tx = match cli.serve_chat {
+        Some(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            let (tx, rx) = tokio::sync::watch::channel(share::ChatSnapshot::default());
+            tokio::spawn(share::serve(listener, rx));
+            Some(tx)
+        },
+        None => None,
+    };
+
+    // Fail early (and readably) on a wrong passphrase for encrypted local state.
+    if let Err(e) = bpt::crypt::key() { eprintln!("bpt: {:#}", e); std::process::exit(1); }
+
+    if let Some(command) = &cli.command {
+        if cli.local { bpt::local::enable(); }
+        let mut sources = std::collections::BTreeSet::new();
+        if cli.docker { sources.insert(bpt::sources::Source::Docker); }
+        if cli.systemd { sources.insert(bpt::sources::Source::Systemd); }
+        let done = match command {
+            cli::Command::Ask { query, json } => bpt::headless::ask(query, *json, piped).await,
+            cli::Command::List { json } => bpt::headless::list(&sources, *json).await,
+            cli::Command::Launch { target, args } => bpt::headless::launch(&sources, target, args).await,
+        };
+        if let Err(e) = done { eprintln!("bpt: {:#}", e); std::process::exit(1); }
+        return Ok(());
+    }
+
+    // The answers would land in the middle of the TUI.
+    if cli.a11y_out == Some(bpt::a11y::Sink::Stdout) && io::stdout().is_terminal() {
+        eprintln!("bpt: --a11y-out - needs stdout redirected or piped (e.g. `bpt --a11y-out - | espeak-ng`)");
+        std::process::exit(2);
+    }
+
+    let recorder = cli.record.as_deref().map(replay::Recorder::create).transpose()?;
+    let replay_records = cli.replay.as_deref().map(replay::load).transpose()?;
+
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    // With stdout redirected (`--dump-on-exit > chat.md`) the TUI draws on the terminal itself.
+    let mut screen: Box<dyn Write> = match std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(tty) if !io::stdout().is_terminal() => Box::new(tty),
+        _ => Box::new(io::stdout()),
+    };
+    execute!(screen, EnterAlternateScreen, EnableMouseCapture)?;
+    // Not every terminal (or Windows console) supports it; keys still work without.
+    let _ = execute!(screen, EnableBracketedPaste);
+    // Focus reports tell notifications whether bpt is in the background; optional too.
+    let _ = execute!(screen, EnableFocusChange);
+    let backend = CrosstermBackend::new(screen);
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new();
-    let _ = app.action_tx.send(AppAction::LoadApps);
-    let _ = app.action_tx.send(AppAction::LoadSearchState);
+    // Settings first, so the flags and variables below can override them.
+    if replay_records.is_none() { app.config = bpt::store::load(bpt::store::CONFIG); }
+    app.apply_config();
+    api::set_backends(&app.config.launcher_url, &app.config.search_url);
+    if cli.local {
+        bpt::local::enable();
+        app.launcher_logs.push(format!("Local mode: apps in {}", bpt::store::path(bpt::store::APPS).display()));
+    }
+    if cli.docker { app.sources.insert(bpt::sources::Source::Docker); }
+    if cli.systemd { app.sources.insert(bpt::sources::Source::Systemd); }
+    if !app.sources.is_empty() {
+        let labels: Vec<&str> = app.sources.iter().map(|s| s.label()).collect();
+        app.launcher_logs.push(format!("Listing {} with the apps ('L' shows logs).", labels.join(" and ")));
+    }
+    if let Some(addr) = cli.serve_chat { app.launcher_logs.push(format!("Sharing chat at http://{}", addr)); }
+    // Background refresh of the conversation list so chats created elsewhere show up.
+    // BPT_CONVO_POLL_SECS=0 disables it.
+    if let Some(secs) = std::env::var("BPT_CONVO_POLL_SECS").ok().and_then(|v| v.parse::<u64>().ok()) { app.convo_poll_ticks = config::ticks(secs); }
+    // Status dots in the Apps list; BPT_HEALTH_POLL_SECS=0 leaves them to `H`.
+    if let Some(secs) = std::env::var("BPT_HEALTH_POLL_SECS").ok().and_then(|v| v.parse::<u64>().ok()) { app.health_poll_ticks = config::ticks(secs); }
+    app.output_log = std::env::var_os("BPT_OUTPUT_LOG").filter(|p| !p.is_empty()).map(std::path::PathBuf::from);
+    if let Some(path) = &app.output_log { app.launcher_logs.push(format!("Appending output to {}", path.display())); }
+    if let Ok(v) = std::env::var("BPT_ANSI") { app.ansi_colors = v != "strip"; }
+    app.in_tmux = std::env::var_os("TMUX").is_some_and(|v| !v.is_empty());
+    if let Ok(v) = std::env::var("BPT_AUTO_OPEN") { app.auto_open_all = v == "1" || v == "true"; }
+    app.recorder = recorder;
+    app.dump_on_exit = cli.dump_on_exit;
+    app.color_mode = cli.color.unwrap_or_else(bpt::colors::detect);
+    app.a11y = cli.a11y;
+    app.a11y_out = cli.a11y_out.clone();
+    // Paint once before anything touches the network or disk, then start the
+    // apps fetch so it overlaps with reading local state. Search state is only
+    // fetched when the Search tab is first opened.
+    terminal.draw(|f| ui::draw(f, &mut app))?;
+    if replay_records.is_none() {
+        app.update(AppAction::LoadApps).await;
+        app.update(AppAction::StartPlugins).await;
+        app.favorites = bpt::store::load(bpt::store::FAVORITES);
+        app.project_bindings = bpt::store::load(bpt::store::PROJECTS);
+        app.summaries = bpt::store::load(bpt::store::SUMMARIES);
+        app.branches = bpt::store::load(bpt::store::BRANCHES);
+        app.usage = bpt::store::load(bpt::store::USAGE);
+        app.launch_history = bpt::store::load(bpt::store::HISTORY);
+        app.ssh_hosts = bpt::store::load(bpt::store::HOSTS);
+        app.tmux_modes = bpt::store::load(bpt::store::TMUX);
+        app.keymap.overrides = bpt::store::load(bpt::store::KEYMAP);
+        app.highlights = bpt::store::load(bpt::store::HIGHLIGHTS);
+        app.arg_defaults = bpt::store::load(bpt::store::ARGS);
+        app.chains = bpt::store::load(bpt::store::CHAINS);
+        app.auto_open = bpt::store::load(bpt::store::AUTO_OPEN);
+        app.model_prefs = bpt::store::load(bpt::store::MODELS);
+        app.query_history = bpt::store::load(bpt::store::QUERIES);
+        // A question from the command line replaces the session's open conversation.
+        if cli.ask.is_some() {
+            app.update(AppAction::AskOnStart { query: cli.ask.clone(), input: piped }).await;
+        } else {
+            app.update(AppAction::RestoreSession(bpt::store::load(bpt::store::SESSION))).await;
+        }
+        if let Some(days) = bpt::store::retention_days() {
+            if app.apply_retention(bpt::store::now_secs(), days) { let _ = bpt::store::save(bpt::store::HISTORY, &app.launch_history); }
+        }
+    }
+    if let Some(records) = replay_records {
+        app.replay = ReplayState::Playing;
+        app.launcher_logs.push(format!("Replaying {} records...", records.len()));
+        replay::spawn_player(records, app.action_tx.clone());
+    }
 
-    let mut interval = tokio::time::interval(Duration::from_millis(250));
+    let mut interval = tokio::time::interval(Duration::from_millis(config::TICK_MS));
 
     loop {
         terminal.draw(|f| ui::draw(f, &mut app))?;
 
         tokio::select! {
-            _ = interval.tick() => { app.update(AppAction::Tick).await; }
+            // While replaying, ticks and keys come from the recording instead.
+            _ = interval.tick() => { if app.replay != ReplayState::Playing { app.update(AppAction::Tick).await; } }
             event = tokio::task::spawn_blocking(|| event::poll(Duration::from_millis(10))) => {
                 if let Ok(Ok(true)) = event {
-                    if let Event::Key(key) = event::read()? {
-                        
+                    let ev = event::read()?;
+                    // Pastes arrive as one event instead of a key per character.
+                    if let Event::Paste(text) = &ev {
+                        if app.replay != ReplayState::Playing { app.update(AppAction::Paste(text.clone())).await; }
+                    }
+                    if matches!(ev, Event::FocusGained | Event::FocusLost) && app.replay != ReplayState::Playing {
+                        app.update(AppAction::TerminalFocus(ev == Event::FocusGained)).await;
+                    }
+                    if let Event::Key(key) = ev {
+                        if let Some(rec) = &mut app.recorder { rec.key(&key); }
+
                         if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
                             app.update(AppAction::Quit).await;
                         }
+                        if app.replay == ReplayState::Playing { continue; }
+                        // The file browser sits over the Search tab or the app editor and takes every key.
+                        if app.file_browser.is_some() {
+                            match key.code {
+                                KeyCode::Esc => app.update(AppAction::CloseFileBrowser).await,
+                                KeyCode::Up => app.update(AppAction::FileBrowserMove(-1)).await,
+                                KeyCode::Down => app.update(AppAction::FileBrowserMove(1)).await,
+                                KeyCode::PageUp => app.update(AppAction::FileBrowserMove(-10)).await,
+                                KeyCode::PageDown => app.update(AppAction::FileBrowserMove(10)).await,
+                                KeyCode::Enter | KeyCode::Right => app.update(AppAction::FileBrowserOpen).await,
+                                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::FileBrowserUseDir).await,
+                                KeyCode::Backspace => app.update(AppAction::FileBrowserBackspace).await,
+                                KeyCode::Char(c) => app.update(AppAction::FileBrowserChar(c)).await,
+                                _ => {}
+                            }
+                            continue;
+                        }
+                        if key.code == KeyCode::Char('p') && key.modifiers.contains(KeyModifiers::CONTROL) && !app.input_mode.is_modal() {
+                            app.update(AppAction::OpenPalette).await;
+                            continue;
+                        }
+                        if key.code == KeyCode::Char('t') && key.modifiers.contains(KeyModifiers::CONTROL) && app.input_mode.is_search() {
+                            app.update(AppAction::OpenPresets).await;
+                            continue;
+                        }
+                        if key.code == KeyCode::Char('f') && key.modifiers.contains(KeyModifiers::CONTROL) && app.input_mode.is_search() {
+                            app.update(AppAction::OpenHistorySearch).await;
+                            continue;
+                        }
+                        if matches!(key.code, KeyCode::Left | KeyCode::Right) && key.modifiers.contains(KeyModifiers::CONTROL) && app.input_mode.is_search() {
+                            let step = if key.code == KeyCode::Left { -config::SPLIT_STEP } else { config::SPLIT_STEP };
+                            app.update(AppAction::ResizePane(step)).await;
+                            continue;
+                        }
+                        if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) && app.input_mode.is_search() {
+                            app.update(AppAction::ToggleZen).await;
+                            continue;
+                        }
 
                         match app.input_mode {
                             InputMode::Normal => {
+                                let ctx = match app.current_screen {
+                                    CurrentScreen::Processes => keymap::Context::Processes,
+                                    _ => keymap::Context::Launcher,
+                                };
+                                if let Some(action) = keymap::key_name(&key).and_then(|k| app.keymap.lookup(ctx, &k)) { app.update(action).await; }
+                            },
+                            InputMode::Settings if app.settings_binding => {
+                                let bound = if key.code == KeyCode::Esc { None } else { keymap::key_name(&key) };
+                                app.update(AppAction::SettingsBindKey(bound)).await;
+                            },
+                            InputMode::Settings if app.settings_edit.is_some() => {
+                                match key.code {
+                                    KeyCode::Enter => app.update(AppAction::SettingsEditDone(true)).await,
+                                    KeyCode::Esc => app.update(AppAction::SettingsEditDone(false)).await,
+                                    KeyCode::Backspace => app.update(AppAction::SettingsBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::SettingsChar(c)).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Settings => {
                                 match key.code {
-                                    KeyCode::Tab => app.update(AppAction::SwitchTab).await,
+                                    KeyCode::Esc | KeyCode::Tab => app.update(AppAction::SwitchTab).await,
                                     KeyCode::Char('q') => app.update(AppAction::Quit).await,
-                                    
-                                    _ => match app.current_screen {
-                                        CurrentScreen::Launcher => {
-                                            match key.code {
-                                                KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::SelectNext).await,
-                                                KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::SelectPrev).await,
-                                                KeyCode::Enter => app.update(AppAction::LaunchSelected).await,
-                                                KeyCode::Char('/') => app.update(AppAction::ToggleFilter).await,
-                                                KeyCode::Char('a') => app.update(AppAction::OpenAddModal).await,
-                                                KeyCode::Char('e') => app.update(AppAction::OpenEditModal).await,
-                                                KeyCode::Char('d') => app.update(AppAction::ConfirmDelete).await,
-                                                KeyCode::Char(':') => app.update(AppAction::OpenAdHocModal).await,
-                                                _ => {}
-                                            }
-                                        },
-                                        CurrentScreen::Search => {
-                                            // Fallback
-                                        }
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::SettingsNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::SettingsPrev).await,
+                                    KeyCode::Enter | KeyCode::Char(' ') => app.update(AppAction::SettingsActivate).await,
+                                    KeyCode::Right | KeyCode::Char('l') => app.update(AppAction::SettingsCycle(true)).await,
+                                    KeyCode::Left | KeyCode::Char('h') => app.update(AppAction::SettingsCycle(false)).await,
+                                    KeyCode::Delete | KeyCode::Backspace => app.update(AppAction::SettingsUnbind).await,
+                                    KeyCode::Char('*') => app.update(AppAction::ToggleStarModel).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Palette => {
+                                if app.palette_binding {
+                                    let bound = if key.code == KeyCode::Esc { None } else { keymap::key_name(&key) };
+                                    app.update(AppAction::PaletteBindKey(bound)).await;
+                                } else {
+                                    match key.code {
+                                        KeyCode::Esc => app.update(AppAction::CloseModal).await,
+                                        KeyCode::Enter => app.update(AppAction::PaletteRun).await,
+                                        KeyCode::Down => app.update(AppAction::PaletteNext).await,
+                                        KeyCode::Up => app.update(AppAction::PalettePrev).await,
+                                        KeyCode::Backspace => app.update(AppAction::PaletteBackspace).await,
+                                        KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::PaletteStartBind).await,
+                                        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::PaletteUnbind).await,
+                                        KeyCode::Char(c) => app.update(AppAction::PaletteChar(c)).await,
+                                        _ => {}
                                     }
                                 }
                             },
-                            
+
                             // --- SEARCH MODES ---
+                            InputMode::SearchInput if app.query_search.is_some() => {
+                                match key.code {
+                                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::QuerySearchStart).await,
+                                    KeyCode::Enter => app.update(AppAction::QuerySearchDone(true)).await,
+                                    KeyCode::Esc => app.update(AppAction::QuerySearchDone(false)).await,
+                                    KeyCode::Backspace => app.update(AppAction::QuerySearchBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::QuerySearchChar(c)).await,
+                                    _ => {}
+                                }
+                            },
                             InputMode::SearchInput => {
+                                // Up/Down recall earlier input from an empty box, while browsing, or with Alt/Ctrl.
+                                let recall = app.search_input.is_empty() || app.query_pos.is_some() || key.modifiers.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL);
                                 match key.code {
-                                    // Esc exits Search Tab back to Launcher
+                                    // Esc leaves the Search tab for the next one
                                     KeyCode::Esc => app.update(AppAction::SwitchTab).await,
-                                    // Tab cycles focus within Search (Input -> Sidebar -> History)
+                                    // Tab completes an @app mention, else cycles focus within Search (Input -> Sidebar -> History)
+                                    KeyCode::Tab if !app.mention_suggestions().is_empty() => app.update(AppAction::CompleteMention).await,
                                     KeyCode::Tab => app.update(AppAction::CycleSearchFocus).await,
                                     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSearchSidebar).await,
+                                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSourcesPane).await,
                                     
+                                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::QuerySearchStart).await,
+                                    KeyCode::Up if recall => app.update(AppAction::QueryPrev).await,
+                                    KeyCode::Down if recall => app.update(AppAction::QueryNext).await,
+                                    KeyCode::Char(c @ '1'..='3') if key.modifiers.contains(KeyModifiers::ALT) => app.update(AppAction::UseFollowUp { n: c as usize - '0' as usize, send: true }).await,
                                     KeyCode::Enter => app.update(AppAction::SubmitSearch).await,
                                     KeyCode::Backspace => app.update(AppAction::DeleteSearchChar).await,
                                     KeyCode::Char(c) => app.update(AppAction::EnterSearchChar(c)).await,
@@ -88,23 +302,109 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     KeyCode::Esc => app.update(AppAction::SwitchTab).await,
                                     KeyCode::Tab => app.update(AppAction::CycleSearchFocus).await,
                                     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSearchSidebar).await,
+                                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSourcesPane).await,
                                     
                                     KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::SidebarNext).await,
                                     KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::SidebarPrev).await,
                                     KeyCode::Enter | KeyCode::Char(' ') => app.update(AppAction::SidebarSelect).await,
+                                    KeyCode::Char('d') | KeyCode::Delete => app.update(AppAction::RequestDeleteConversation).await,
+                                    KeyCode::Char('*') if app.search_sidebar == SearchSidebarState::Settings => app.update(AppAction::ToggleStarModel).await,
+                                    KeyCode::Char('a') if app.search_sidebar == SearchSidebarState::Settings => app.update(AppAction::OpenSourceEditor { edit: false }).await,
+                                    KeyCode::Char('e') if app.search_sidebar == SearchSidebarState::Settings => app.update(AppAction::OpenSourceEditor { edit: true }).await,
+                                    KeyCode::Char('u') => app.update(AppAction::Undo).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::ChatHistory if app.chat_visual.is_some() => {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('v') | KeyCode::Char('V') => app.update(AppAction::ChatVisualCancel).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::ChatVisualMove(-1)).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::ChatVisualMove(1)).await,
+                                    KeyCode::PageUp => app.update(AppAction::ChatVisualMove(-10)).await,
+                                    KeyCode::PageDown => app.update(AppAction::ChatVisualMove(10)).await,
+                                    KeyCode::Char('y') | KeyCode::Enter => app.update(AppAction::ChatYank).await,
+                                    KeyCode::Char('A') => app.update(AppAction::Snippet(Verb::SaveApp)).await,
                                     _ => {}
                                 }
                             },
+                            InputMode::ChatHistory if app.launch_choice.is_some() => {
+                                match key.code {
+                                    KeyCode::Char(c @ '1'..='9') => app.update(AppAction::ChooseLaunch(Some(c as usize - '1' as usize))).await,
+                                    _ => app.update(AppAction::ChooseLaunch(None)).await,
+                                }
+                            },
+                            InputMode::ChatHistory if app.code_picker.is_some() => {
+                                match key.code {
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::CodePickerMove(-1)).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::CodePickerMove(1)).await,
+                                    KeyCode::Enter | KeyCode::Char('y') => app.update(AppAction::CodePickerCopy(None)).await,
+                                    KeyCode::Char(c @ '1'..='9') => app.update(AppAction::CodePickerCopy(Some(c as usize - '1' as usize))).await,
+                                    KeyCode::Esc | KeyCode::Char('q') => app.update(AppAction::CodePickerCancel).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::ChatHistory if app.snippet_choice.is_some() => {
+                                match key.code {
+                                    KeyCode::Char(c @ '1'..='9') => app.update(AppAction::ChooseSnippet(Some(c as usize - '1' as usize))).await,
+                                    _ => app.update(AppAction::ChooseSnippet(None)).await,
+                                }
+                            },
                             InputMode::ChatHistory => {
                                 match key.code {
+                                    KeyCode::Esc if app.chat_focus.is_some() => app.update(AppAction::ClearMessageFocus).await,
                                     KeyCode::Esc => app.update(AppAction::SwitchTab).await,
                                     KeyCode::Tab => app.update(AppAction::CycleSearchFocus).await,
                                     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSearchSidebar).await,
+                                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSourcesPane).await,
                                     
                                     KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::ScrollChat(-1)).await,
                                     KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::ScrollChat(1)).await,
                                     KeyCode::PageUp => app.update(AppAction::ScrollChat(-10)).await,
                                     KeyCode::PageDown => app.update(AppAction::ScrollChat(10)).await,
+                                    KeyCode::Char('g') => app.update(AppAction::ChatTop).await,
+                                    KeyCode::Char('G') => app.update(AppAction::ChatBottom).await,
+                                    KeyCode::Char('v') | KeyCode::Char('V') => app.update(AppAction::ChatVisualStart).await,
+                                    KeyCode::Char('z') => app.update(AppAction::ToggleFold).await,
+                                    KeyCode::Char('s') => app.update(AppAction::ToggleSources).await,
+                                    KeyCode::Char('T') => app.update(AppAction::ToggleReasoning).await,
+                                    KeyCode::Char('x') => app.update(AppAction::ToggleExcluded).await,
+                                    KeyCode::Char('b') => app.update(AppAction::BranchFromMessage).await,
+                                    KeyCode::Char(c @ '1'..='3') => app.update(AppAction::UseFollowUp { n: c as usize - '0' as usize, send: key.modifiers.contains(KeyModifiers::ALT) }).await,
+                                    KeyCode::Char('o') => app.update(AppAction::OpenLastAttachment).await,
+                                    KeyCode::Char('E') => app.update(AppAction::OpenFileRef).await,
+                                    KeyCode::Char('p') => app.update(AppAction::PinLastAnswer).await,
+                                    KeyCode::Char('t') => app.update(AppAction::ExtractTodos(None)).await,
+                                    KeyCode::Char('r') => app.update(AppAction::RegenerateLast).await,
+                                    KeyCode::Char('L') => app.update(AppAction::LaunchRecommended).await,
+                                    KeyCode::Char('y') => app.update(AppAction::CopyCodeBlock).await,
+                                    KeyCode::Char('Y') => app.update(AppAction::Snippet(Verb::Copy)).await,
+                                    KeyCode::Char('!') => app.update(AppAction::Snippet(Verb::Run)).await,
+                                    KeyCode::Char('A') => app.update(AppAction::Snippet(Verb::SaveApp)).await,
+                                    KeyCode::Char('[') => app.update(AppAction::FocusMessage { forward: false, mine: true }).await,
+                                    KeyCode::Char(']') => app.update(AppAction::FocusMessage { forward: true, mine: true }).await,
+                                    KeyCode::Char('{') => app.update(AppAction::FocusMessage { forward: false, mine: false }).await,
+                                    KeyCode::Char('}') => app.update(AppAction::FocusMessage { forward: true, mine: false }).await,
+                                    KeyCode::Home => app.update(AppAction::FocusMessageEdge(false)).await,
+                                    KeyCode::End => app.update(AppAction::FocusMessageEdge(true)).await,
+                                    KeyCode::Char('e') => app.update(AppAction::EditMessage { cut: false }).await,
+                                    KeyCode::Char('c') => app.update(AppAction::EditMessage { cut: true }).await,
+                                    _ => {}
+                                }
+                            },
+
+                            InputMode::SourcesPane => {
+                                match key.code {
+                                    KeyCode::Esc => app.update(AppAction::ToggleSourcesPane).await,
+                                    KeyCode::Tab => app.update(AppAction::CycleSearchFocus).await,
+                                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSearchSidebar).await,
+                                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::ToggleSourcesPane).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::SourcesPaneNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::SourcesPanePrev).await,
+                                    KeyCode::Enter | KeyCode::Char('o') => app.update(AppAction::OpenPaneSource).await,
+                                    KeyCode::Char('v') => app.update(AppAction::PreviewPaneSource).await,
+                                    KeyCode::Char('y') => app.update(AppAction::CopyPaneSourceUrl).await,
+                                    KeyCode::Char('f') => app.update(AppAction::CycleSourceEngine).await,
+                                    KeyCode::Char('s') => app.update(AppAction::CycleSourceSort).await,
                                     _ => {}
                                 }
                             },
@@ -122,18 +422,178 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 match key.code {
                                     KeyCode::Esc => app.update(AppAction::CloseModal).await,
                                     KeyCode::Tab => app.update(AppAction::CycleFormFocus).await,
+                                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::SubmitForm).await,
+                                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) && app.active_form.focus_idx == AppForm::CWD => app.update(AppAction::OpenFileBrowser(Pick::WorkingDir)).await,
+                                    // Enter breaks the line in the command editor; Ctrl+s saves from anywhere.
+                                    KeyCode::Enter if app.active_form.focus_idx == AppForm::CMD => app.update(AppAction::FormNewline).await,
                                     KeyCode::Enter => app.update(AppAction::SubmitForm).await,
+                                    KeyCode::Left => app.update(AppAction::FormCursor(CursorMove::Left)).await,
+                                    KeyCode::Right => app.update(AppAction::FormCursor(CursorMove::Right)).await,
+                                    KeyCode::Up => app.update(AppAction::FormCursor(CursorMove::Up)).await,
+                                    KeyCode::Down => app.update(AppAction::FormCursor(CursorMove::Down)).await,
+                                    KeyCode::Home => app.update(AppAction::FormCursor(CursorMove::Home)).await,
+                                    KeyCode::End => app.update(AppAction::FormCursor(CursorMove::End)).await,
                                     KeyCode::Backspace => app.update(AppAction::FormBackspace).await,
                                     KeyCode::Char(c) => app.update(AppAction::FormChar(c)).await,
                                     _ => {}
                                 }
                             },
+                            InputMode::SourceEditor => {
+                                match key.code {
+                                    KeyCode::Esc => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Tab => app.update(AppAction::SourceFormNext).await,
+                                    KeyCode::Enter => app.update(AppAction::SubmitSourceForm).await,
+                                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::SubmitSourceForm).await,
+                                    KeyCode::Backspace => app.update(AppAction::SourceFormBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::SourceFormChar(c)).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::HistorySearch => {
+                                match key.code {
+                                    KeyCode::Esc => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down => app.update(AppAction::ConvoSearchNext).await,
+                                    KeyCode::Up => app.update(AppAction::ConvoSearchPrev).await,
+                                    KeyCode::Enter => app.update(AppAction::HistoryOpen).await,
+                                    KeyCode::Backspace => app.update(AppAction::HistoryBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::HistoryChar(c)).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Presets => {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('q') => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::PresetNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::PresetPrev).await,
+                                    KeyCode::Enter | KeyCode::Char(' ') => app.update(AppAction::PresetSelect).await,
+                                    KeyCode::Char('d') | KeyCode::Delete => app.update(AppAction::PresetDelete).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::TagBrowser => {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('t') | KeyCode::Char('q') => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::TagNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::TagPrev).await,
+                                    KeyCode::Enter | KeyCode::Char(' ') => app.update(AppAction::ToggleTag).await,
+                                    KeyCode::Char('c') => app.update(AppAction::ClearTags).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Args => {
+                                match key.code {
+                                    KeyCode::Esc => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Enter => app.update(AppAction::SubmitArgs).await,
+                                    KeyCode::Tab | KeyCode::Down => app.update(AppAction::ArgsNext).await,
+                                    KeyCode::BackTab | KeyCode::Up => app.update(AppAction::ArgsPrev).await,
+                                    KeyCode::Backspace => app.update(AppAction::ArgsBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::ArgsChar(c)).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Output if app.output_searching => {
+                                match key.code {
+                                    KeyCode::Enter => app.update(AppAction::OutputSearchDone(true)).await,
+                                    KeyCode::Esc => app.update(AppAction::OutputSearchDone(false)).await,
+                                    KeyCode::Backspace => app.update(AppAction::OutputSearchBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::OutputSearchChar(c)).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Output => {
+                                match key.code {
+                                    KeyCode::Char('/') => app.update(AppAction::OutputSearchStart).await,
+                                    KeyCode::Char('w') => app.update(AppAction::ExportOutput).await,
+                                    KeyCode::Char('e') => app.update(AppAction::ToggleErrorsOnly).await,
+                                    KeyCode::Char('a') => app.update(AppAction::AskAboutOutput).await,
+                                    KeyCode::Char('n') => app.update(AppAction::OutputNextMatch(true)).await,
+                                    KeyCode::Char('N') => app.update(AppAction::OutputNextMatch(false)).await,
+                                    KeyCode::Esc | KeyCode::Tab | KeyCode::Left | KeyCode::Char('h') => app.update(AppAction::FocusOutput(false)).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::ScrollOutput(-1)).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::ScrollOutput(1)).await,
+                                    KeyCode::PageUp => app.update(AppAction::ScrollOutput(-10)).await,
+                                    KeyCode::PageDown => app.update(AppAction::ScrollOutput(10)).await,
+                                    KeyCode::Home | KeyCode::Char('g') => app.update(AppAction::OutputTop).await,
+                                    KeyCode::End | KeyCode::Char('G') => app.update(AppAction::OutputBottom).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Chains if app.chain_draft.is_some() => {
+                                let naming = app.chain_draft.as_ref().is_some_and(|d| d.focus == ChainFocus::Name);
+                                match key.code {
+                                    KeyCode::Esc => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Tab => app.update(AppAction::ChainFocusNext).await,
+                                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.update(AppAction::SaveChain).await,
+                                    KeyCode::Enter if naming => app.update(AppAction::ChainFocusNext).await,
+                                    KeyCode::Enter => app.update(AppAction::ChainAddStep).await,
+                                    KeyCode::Backspace | KeyCode::Delete => app.update(AppAction::ChainBackspace).await,
+                                    KeyCode::Char(c) if naming => app.update(AppAction::ChainChar(c)).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::ChainNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::ChainPrev).await,
+                                    KeyCode::Char('J') => app.update(AppAction::ChainMoveStep(false)).await,
+                                    KeyCode::Char('K') => app.update(AppAction::ChainMoveStep(true)).await,
+                                    KeyCode::Char('d') => app.update(AppAction::ChainBackspace).await,
+                                    KeyCode::Char('h') => app.update(AppAction::ChainToggleHealth).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Chains => {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::ChainNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::ChainPrev).await,
+                                    KeyCode::Enter => app.update(AppAction::RunChain).await,
+                                    KeyCode::Char('n') | KeyCode::Char('a') => app.update(AppAction::NewChain).await,
+                                    KeyCode::Char('e') => app.update(AppAction::EditChain).await,
+                                    KeyCode::Char('d') | KeyCode::Delete => app.update(AppAction::DeleteChain).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Preview => {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('v') => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::PreviewScroll(1)).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::PreviewScroll(-1)).await,
+                                    KeyCode::PageDown | KeyCode::Char(' ') => app.update(AppAction::PreviewScroll(10)).await,
+                                    KeyCode::PageUp => app.update(AppAction::PreviewScroll(-10)).await,
+                                    KeyCode::Char('r') => app.update(AppAction::PreviewReload).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::LaunchHistory if app.history_output.is_some() => {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Left => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::ScrollLaunchOutput(1)).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::ScrollLaunchOutput(-1)).await,
+                                    KeyCode::PageDown => app.update(AppAction::ScrollLaunchOutput(10)).await,
+                                    KeyCode::PageUp => app.update(AppAction::ScrollLaunchOutput(-10)).await,
+                                    KeyCode::End | KeyCode::Char('G') => app.update(AppAction::ScrollLaunchOutput(i16::MAX)).await,
+                                    KeyCode::Home | KeyCode::Char('g') => app.update(AppAction::ScrollLaunchOutput(i16::MIN)).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::LaunchHistory => {
+                                match key.code {
+                                    KeyCode::Enter | KeyCode::Right => app.update(AppAction::ShowLaunchOutput).await,
+                                    KeyCode::Esc | KeyCode::Char('h') | KeyCode::Char('q') => app.update(AppAction::CloseModal).await,
+                                    KeyCode::Down | KeyCode::Char('j') => app.update(AppAction::HistoryNext).await,
+                                    KeyCode::Up | KeyCode::Char('k') => app.update(AppAction::HistoryPrev).await,
+                                    _ => {}
+                                }
+                            },
+                            InputMode::Confirm => {
+                                match key.code {
+                                    KeyCode::Char('y') | KeyCode::Char('Y') => app.update(AppAction::Confirm(true)).await,
+                                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.update(AppAction::Confirm(false)).await,
+                                    _ => {}
+                                }
+                            },
                             InputMode::AdHocCmd => {
                                 match key.code {
                                     KeyCode::Esc => app.update(AppAction::CloseModal).await,
                                     KeyCode::Enter => { let c = app.adhoc_input.clone(); app.update(AppAction::SubmitAdHoc(c)).await; },
-                                    KeyCode::Backspace => { app.adhoc_input.pop(); },
-                                    KeyCode::Char(c) => { app.adhoc_input.push(c); },
+                                    KeyCode::Backspace => app.update(AppAction::AdHocBackspace).await,
+                                    KeyCode::Char(c) => app.update(AppAction::AdHocChar(c)).await,
                                     _ => {}
                                 }
                             }
@@ -145,11 +605,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 app.update(action).await;
             }
         }
+        if let Some(tx) = &share_tx {
+            tx.send_if_modified(|snap| {
+                if snap.messages == app.messages && snap.streaming == app.is_searching { return false; }
+                *snap = share::ChatSnapshot { messages: app.messages.clone(), streaming: app.is_searching };
+                true
+            });
+        }
+        if let Some((file, r)) = app.editor_request.take() {
+            if app.replay == ReplayState::Off {
+                let cmd = editor::command_from_env(&file, &r);
+                let result = if cmd.detached { editor::run(&cmd) } else {
+                    // Hand the terminal to the editor, then take it back.
+                    disable_raw_mode()?;
+                    let _ = execute!(terminal.backend_mut(), DisableBracketedPaste);
+                    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+                    let result = editor::run(&cmd);
+                    enable_raw_mode()?;
+                    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+                    let _ = execute!(terminal.backend_mut(), EnableBracketedPaste);
+                    terminal.clear()?;
+                    result
+                };
+                if let Err(e) = result { app.launcher_logs.push(format!("Editor failed for {}: {}", r, e)); }
+            }
+        }
         if app.should_quit { break; }
     }
 
+    // Conversation deletes are held back for undo; commit them now.
+    if app.replay == ReplayState::Off {
+        for id in app.pending_conversation_deletes() { let _ = api::delete_conversation(id).await; }
+        let _ = bpt::store::save(bpt::store::SESSION, &app.session());
+        let _ = bpt::store::save(bpt::store::QUERIES, &app.query_history);
+    }
+
     disable_raw_mode()?;
+    let _ = execute!(terminal.backend_mut(), DisableBracketedPaste, DisableFocusChange);
     execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
     terminal.show_cursor()?;
+    // After the alternate screen is gone, so it can be redirected like any other output.
+    if app.dump_on_exit {
+        let text = app.transcript();
+        if !text.is_empty() { println!("{}", text); }
+    }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+// ================================================
+// FILE: src/mentions.rs
+// ================================================
+// `@app` mentions: typing `@` plus part of an app's name in the Search input
+// offers the matching apps (Tab completes), and each app mentioned in a sent
+// message goes along as context: its name, description, command and URL.
+// The other way round, the apps an answer names can be launched from the
+// chat with `L`. Apps are mentioned by their name with spaces as dashes.
+use crate::api::AppModel;
+
+/// At most this many suggestions are shown while typing.
+pub const MAX_SUGGESTIONS: usize = 5;
+
+/// How an app is written after the `@`: `LM Studio` is `@LM-Studio`.
+pub fn slug(name: &str) -> String { name.split_whitespace().collect::<Vec<_>>().join("-") }
+
+/// The part of a mention being typed at the end of `input`, without the `@`.
+pub fn partial(input: &str) -> Option<&str> {
+    if input.ends_with(char::is_whitespace) { return None; }
+    let word = input.rsplit(char::is_whitespace).next()?;
+    word.strip_prefix('@')
+}
+
+/// Apps whose slug starts with `partial`, then those containing it, ignoring case.
+pub fn suggestions<'a>(apps: &'a [AppModel], partial: &str) -> Vec<&'a AppModel> {
+    let partial = partial.to_lowercase();
+    let slugs: Vec<String> = apps.iter().map(|a| slug(&a.name).to_lowercase()).collect();
+    let starts = (0..apps.len()).filter(|&i| slugs[i].starts_with(&partial));
+    let contains = (0..apps.len()).filter(|&i| !slugs[i].starts_with(&partial) && slugs[i].contains(&partial));
+    starts.chain(contains).take(MAX_SUGGESTIONS).map(|i| &apps[i]).collect()
+}
+
+/// `input` with the mention being typed replaced by `@<slug of name>`.
+pub fn complete(input: &str, name: &str) -> String {
+    let at = input.rfind('@').unwrap_or(input.len());
+    format!("{}@{} ", &input[..at], slug(name))
+}
+
+/// Apps mentioned as `@slug` in `text`, once each, in order.
+pub fn mentioned<'a>(apps: &'a [AppModel], text: &str) -> Vec<&'a AppModel> {
+    let mut out: Vec<&AppModel> = vec![];
+    for word in text.split_whitespace().filter_map(|w| w.strip_prefix('@')) {
+        let word = word.trim_end_matches(|c: char| !c.is_alphanumeric());
+        if let Some(a) = apps.iter().find(|a| slug(&a.name).eq_ignore_ascii_case(word)) {
+            if !out.iter().any(|o| o.id == a.id) { out.push(a); }
+        }
+    }
+    out
+}
+
+/// The context sent for a mentioned app.
+pub fn context(app: &AppModel) -> String {
+    let mut out = format!("App \"{}\" from the user's launcher (id {})\n", app.name, app.id);
+    if let Some(d) = app.description.as_deref().map(str::trim).filter(|d| !d.is_empty()) { out.push_str(&format!("Description: {}\n", d)); }
+    if !app.command.trim().is_empty() { out.push_str(&format!("Command: {}\n", app.command.trim())); }
+    if !app.url.trim().is_empty() { out.push_str(&format!("URL: {}\n", app.url.trim())); }
+    out.push_str(&format!("To suggest launching it, write @{}.", slug(&app.name)));
+    out
+}
+
+/// Apps an answer recommends: mentioned as `@slug`, or by their full name
+/// as a word of its own (names shorter than 3 characters only as `@slug`),
+/// in the order they first appear.
+pub fn recommended<'a>(apps: &'a [AppModel], answer: &str) -> Vec<&'a AppModel> {
+    let lower = answer.to_lowercase();
+    let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric() && c != '-' && c != '_');
+    let mut found: Vec<(usize, &AppModel)> = vec![];
+    for a in apps {
+        let at_slug = format!("@{}", slug(&a.name).to_lowercase());
+        let name = a.name.trim().to_lowercase();
+        let by_name = (name.chars().count() >= 3).then(|| {
+            lower.match_indices(&name).map(|(i, _)| i)
+                .find(|&i| boundary(lower[..i].chars().next_back()) && boundary(lower[i + name.len()..].chars().next()))
+        }).flatten();
+        let by_slug = lower.match_indices(&at_slug).map(|(i, _)| i).find(|&i| boundary(lower[i + at_slug.len()..].chars().next()));
+        if let Some(i) = by_slug.into_iter().chain(by_name).min() { found.push((i, a)); }
+    }
+    found.sort_by_key(|(i, _)| *i);
+    found.into_iter().map(|(_, a)| a).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str, name: &str) -> AppModel {
+        AppModel { id: id.into(), name: name.into(), description: Some("Local models".into()), command: "lms server start".into(), url: "http://localhost:1234".into(), ..Default::default() }
+    }
+
+    #[test]
+    fn completes_mentions_while_typing() {
+        let apps = [app("1", "LM Studio"), app("2", "Jupyter"), app("3", "Open WebUI")];
+        assert_eq!(partial("ask @lm"), Some("lm"));
+        assert_eq!(partial("ask @"), Some(""));
+        assert_eq!((partial("ask @lm "), partial("mail me@"), partial("ask")), (None, None, None));
+        assert_eq!(suggestions(&apps, "O").iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["3", "1"]);
+        assert_eq!(suggestions(&apps, "").len(), 3);
+        assert_eq!(complete("how do I use @lm", "LM Studio"), "how do I use @LM-Studio ");
+    }
+
+    #[test]
+    fn finds_mentioned_and_recommended_apps() {
+        let apps = [app("1", "LM Studio"), app("2", "Jupyter"), app("3", "db")];
+        let ids = |found: Vec<&AppModel>| found.iter().map(|a| a.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(mentioned(&apps, "can @jupyter talk to @LM-Studio, or @lm-studio? @nope")), ["2", "1"]);
+        assert_eq!(ids(recommended(&apps, "Start **LM Studio**, then open a notebook in @Jupyter. The db is fine.")), ["1", "2"]);
+        assert_eq!(ids(recommended(&apps, "Use jupyterlab or @db.")), ["3"]);
+        assert!(context(&apps[0]).contains("Command: lms server start\nURL: http://localhost:1234\nTo suggest launching it, write @LM-Studio."));
+    }
+}
@@ -0,0 +1,116 @@
+// ================================================
+// FILE: src/mode.rs
+// ================================================
+/// Keyboard focus / input mode. All changes go through `App::set_mode`, which
+/// consults `allows` so illegal jumps (e.g. a tab switch out of an open modal)
+/// are refused in one place instead of being guarded ad hoc.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputMode {
+    Normal,         // Launcher navigation
+    Editing,        // Modal form
+    Filtering,      // Launcher filter
+    Output,         // Launcher Output pane focused (scrolling)
+    AdHocCmd,       // Ad-hoc command
+    Confirm,        // y/n prompt for destructive actions
+    TagBrowser,     // Pick #tags to narrow the app list
+    LaunchHistory,  // Recent launches and per-app counts
+    Palette,        // Command palette (also where keys are rebound)
+    Preview,        // Text view of an app's web page
+    Args,           // Values for a command's {{placeholders}}
+    Chains,         // Launch chains: list and editor
+    Settings,       // Settings tab (also while a value is typed or a key bound)
+    Presets,        // Preset picker over the Search tab
+    HistorySearch,  // Search across all conversations, over the Search tab
+    SourceEditor,   // Add or edit a search source, over the Search tab
+
+    // Search Specific Modes
+    SearchInput,    // Typing query
+    SearchSidebar,  // Navigating history/settings
+    ChatHistory,    // Scrolling chat
+    SourcesPane,    // The conversation's sources, right of the chat
+}
+
+impl InputMode {
+    /// Modals overlay a screen and return to the mode they were opened from.
+    pub fn is_modal(&self) -> bool {
+        matches!(self, InputMode::Editing | InputMode::AdHocCmd | InputMode::Confirm | InputMode::TagBrowser | InputMode::LaunchHistory | InputMode::Palette | InputMode::Preview | InputMode::Args | InputMode::Chains | InputMode::Presets | InputMode::HistorySearch | InputMode::SourceEditor)
+    }
+
+    pub fn is_search(&self) -> bool {
+        matches!(self, InputMode::SearchInput | InputMode::SearchSidebar | InputMode::ChatHistory | InputMode::SourcesPane)
+    }
+
+    /// Whether moving from `self` to `to` is a legal transition. Leaving a
+    /// modal is not a transition; it is done with `App::close_modal`.
+    pub fn allows(&self, to: &InputMode) -> bool {
+        use InputMode::*;
+        if self == to { return true; }
+        match (self, to) {
+            (from, _) if from.is_modal() => false,
+            (Normal, Filtering | Output | Editing | AdHocCmd | Confirm | TagBrowser | LaunchHistory | Palette | Preview | Args | Chains) => true,
+            (Filtering | Output, Normal) | (Normal, Settings) | (Settings, Normal) => true,
+            (from, Confirm | Palette | Presets | Preview | HistorySearch | SourceEditor) => from.is_search(),
+            (Normal, to) => to.is_search(),
+            (from, Normal) => from.is_search(),
+            (from, to) => from.is_search() && to.is_search(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputMode::{self, *};
+
+    const ALL: [InputMode; 20] = [Normal, Editing, Filtering, Output, AdHocCmd, Confirm, TagBrowser, LaunchHistory, Palette, Preview, Args, Chains, Settings, Presets, HistorySearch, SourceEditor, SearchInput, SearchSidebar, ChatHistory, SourcesPane];
+
+    #[test]
+    fn modals_only_close() {
+        for from in [Editing, AdHocCmd, Confirm, TagBrowser, LaunchHistory, Palette, Preview, Args, Chains, Presets, HistorySearch, SourceEditor] {
+            for to in ALL { assert_eq!(from.allows(&to), from == to, "{:?} -> {:?}", from, to); }
+        }
+    }
+
+    #[test]
+    fn launcher_modals_open_from_normal_only() {
+        for to in [Editing, AdHocCmd, Filtering, Output, TagBrowser, LaunchHistory, Args, Chains] {
+            for from in ALL {
+                let expected = from == Normal || from == to;
+                assert_eq!(from.allows(&to), expected, "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn previews_open_from_the_launcher_and_search() {
+        for from in ALL {
+            let expected = from == Normal || from == Preview || from.is_search();
+            assert_eq!(from.allows(&Preview), expected, "{:?} -> Preview", from);
+        }
+    }
+
+    #[test]
+    fn filter_and_output_return_to_normal() {
+        for from in [Filtering, Output] {
+            assert!(from.allows(&Normal));
+            assert!(!from.allows(&SearchInput));
+            assert!(!from.allows(&Confirm));
+        }
+    }
+
+    #[test]
+    fn settings_sits_between_processes_and_launcher() {
+        for to in ALL {
+            assert_eq!(Settings.allows(&to), matches!(to, Settings | Normal), "Settings -> {:?}", to);
+            if to != Settings { assert_eq!(to.allows(&Settings), to == Normal, "{:?} -> Settings", to); }
+        }
+    }
+
+    #[test]
+    fn search_focus_moves_freely_and_back_to_launcher() {
+        for from in [SearchInput, SearchSidebar, ChatHistory, SourcesPane] {
+            for to in [SearchInput, SearchSidebar, ChatHistory, SourcesPane, Normal, Confirm, Palette, Presets, Preview, HistorySearch, SourceEditor] { assert!(from.allows(&to), "{:?} -> {:?}", from, to); }
+            assert!(!from.allows(&Editing));
+            assert!(!from.allows(&Filtering));
+        }
+    }
+}
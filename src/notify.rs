@@ -0,0 +1,91 @@
+// ================================================
+// FILE: src/notify.rs
+// ================================================
+// Notifications for answers and launches that finish while you are looking
+// elsewhere (another tab, or another window when the terminal reports focus).
+// `terminal` asks the terminal itself with an OSC 9 / OSC 777 escape, which
+// also works over ssh; `desktop` runs the platform's notifier. The reducer
+// decides when; this only knows how.
+use std::io::Write;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notifications { #[default] Off, Terminal, Desktop }
+
+impl Notifications {
+    const ALL: [Notifications; 3] = [Notifications::Off, Notifications::Terminal, Notifications::Desktop];
+
+    pub fn cycle(self, forward: bool) -> Self {
+        let i = Notifications::ALL.iter().position(|n| *n == self).unwrap_or(0);
+        let n = Notifications::ALL.len();
+        Notifications::ALL[if forward { (i + 1) % n } else { (i + n - 1) % n }]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self { Notifications::Off => "off", Notifications::Terminal => "terminal", Notifications::Desktop => "desktop" }
+    }
+}
+
+/// Whether the terminal understands OSC 777 (VTE terminals, foot, urxvt)
+/// rather than OSC 9 (iTerm2, WezTerm, Windows Terminal, kitty, ...).
+fn wants_osc777() -> bool {
+    let term = std::env::var("TERM").unwrap_or_default();
+    std::env::var_os("VTE_VERSION").is_some() || term.starts_with("foot") || term.contains("rxvt")
+}
+
+/// The escape sequence for a notification, wrapped for tmux when inside it.
+/// Control characters are dropped so the text can't end the sequence early.
+pub fn osc(title: &str, body: &str, osc777: bool, in_tmux: bool) -> String {
+    let clean = |s: &str| s.chars().filter(|c| !c.is_control()).collect::<String>();
+    let seq = if osc777 {
+        format!("\x1b]777;notify;{};{}\x07", clean(title).replace(';', ","), clean(body))
+    } else {
+        format!("\x1b]9;{}: {}\x07", clean(title), clean(body))
+    };
+    if in_tmux { format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b")) } else { seq }
+}
+
+/// Sends the notification; failures are ignored, it is only a courtesy.
+pub fn send(how: Notifications, title: &str, body: &str, in_tmux: bool) {
+    match how {
+        Notifications::Off => {},
+        Notifications::Terminal => {
+            let seq = osc(title, body, wants_osc777(), in_tmux);
+            // The terminal, even when stdout is redirected (`--dump-on-exit`).
+            match std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+                Ok(mut tty) => { let _ = tty.write_all(seq.as_bytes()); },
+                Err(_) => { let mut out = std::io::stdout(); let _ = out.write_all(seq.as_bytes()); let _ = out.flush(); },
+            }
+        },
+        Notifications::Desktop => {
+            let mut cmd = if cfg!(target_os = "macos") {
+                let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+                let mut c = std::process::Command::new("osascript");
+                c.arg("-e").arg(format!("display notification {} with title {}", quote(body), quote(title)));
+                c
+            } else {
+                let mut c = std::process::Command::new("notify-send");
+                c.arg("--app-name=bpt").arg(title).arg(body);
+                c
+            };
+            if let Ok(mut child) = cmd.stdin(std::process::Stdio::null()).stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null()).spawn() {
+                std::thread::spawn(move || { let _ = child.wait(); });
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_escapes_for_each_terminal() {
+        assert_eq!(osc("bpt", "Answer ready", false, false), "\x1b]9;bpt: Answer ready\x07");
+        assert_eq!(osc("bpt; x", "done\x07\n", true, false), "\x1b]777;notify;bpt, x;done\x07");
+        assert_eq!(osc("bpt", "hi", false, true), "\x1bPtmux;\x1b\x1b]9;bpt: hi\x07\x1b\\");
+        assert_eq!(Notifications::Off.cycle(false), Notifications::Desktop);
+        assert_eq!(Notifications::Terminal.cycle(true).label(), "desktop");
+    }
+}
@@ -0,0 +1,95 @@
+// ================================================
+// FILE: src/ollama.rs
+// ================================================
+// Ollama's own API, used for the `ollama` provider in direct chat: models
+// come from `/api/tags` with their parameter sizes, and answers stream from
+// `/api/chat` as one JSON object per line rather than SSE. Without direct
+// chat the provider is passed through to the search backend like any other.
+use anyhow::{bail, Result};
+use futures::stream::StreamExt;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+use crate::api::Model;
+use crate::app::AppAction;
+use crate::direct::ChatRequest;
+use crate::tokens::Usage;
+
+/// Provider name, in App::llm_providers.
+pub const PROVIDER: &str = "ollama";
+
+pub const DEFAULT_URL: &str = "http://localhost:11434";
+
+pub async fn fetch_models(url: &str) -> Result<Vec<Model>> {
+    let resp: Value = Client::new().get(format!("{}/api/tags", url)).send().await?.error_for_status()?.json().await?;
+    Ok(resp["models"].as_array().into_iter().flatten().filter_map(model).collect())
+}
+
+/// A model as `/api/tags` lists it.
+fn model(m: &Value) -> Option<Model> {
+    let id = m["model"].as_str().or_else(|| m["name"].as_str())?;
+    let params = m["details"]["parameter_size"].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    Some(Model { id: id.to_string(), name: m["name"].as_str().unwrap_or(id).to_string(), params })
+}
+
+pub async fn stream_chat(req: ChatRequest, tx: UnboundedSender<AppAction>) -> Result<()> {
+    let mut body = serde_json::json!({
+        "model": req.model,
+        "messages": req.messages.iter().map(|(role, content)| serde_json::json!({ "role": role, "content": content })).collect::<Vec<_>>(),
+        "stream": true,
+    });
+    if let Some(t) = req.temperature { body["options"] = serde_json::json!({ "temperature": t }); }
+    let resp = Client::new().post(format!("{}/api/chat", req.url)).json(&body).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        let msg = serde_json::from_str::<Value>(&text).ok().and_then(|v| v["error"].as_str().map(str::to_string)).unwrap_or(text);
+        bail!("{}: {}", status, msg.trim());
+    }
+    let mut stream = resp.bytes_stream();
+    let mut buf: Vec<u8> = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        buf.extend_from_slice(&chunk?);
+        while let Some(end) = buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=end).collect();
+            let Ok(v) = serde_json::from_slice::<Value>(&line) else { continue };
+            if let Some(e) = v["error"].as_str() { bail!("{}", e); }
+            for action in line_actions(&v) { let _ = tx.send(action); }
+        }
+    }
+    let _ = tx.send(AppAction::SearchDone);
+    Ok(())
+}
+
+/// What one streamed line adds: answer text, thinking and, in the last
+/// (`done`) line, the token counts.
+pub fn line_actions(line: &Value) -> Vec<AppAction> {
+    let mut actions = Vec::new();
+    let msg = &line["message"];
+    if let Some(text) = msg["thinking"].as_str().filter(|t| !t.is_empty()) { actions.push(AppAction::SearchReasoningToken(text.to_string())); }
+    if let Some(text) = msg["content"].as_str().filter(|t| !t.is_empty()) { actions.push(AppAction::SearchStreamToken(text.to_string())); }
+    if line["done"].as_bool() == Some(true) {
+        let (prompt, completion) = (line["prompt_eval_count"].as_u64(), line["eval_count"].as_u64());
+        if prompt.is_some() || completion.is_some() {
+            actions.push(AppAction::SearchUsage(Usage { prompt: prompt.unwrap_or(0), completion: completion.unwrap_or(0) }));
+        }
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_tags_and_stream_lines() {
+        let m = model(&json!({"name": "llama3.1:8b", "model": "llama3.1:8b", "details": {"parameter_size": "8.0B", "quantization_level": "Q4_K_M"}})).unwrap();
+        assert_eq!((m.id.as_str(), m.params.as_deref()), ("llama3.1:8b", Some("8.0B")));
+        assert_eq!(model(&json!({"size": 1})), None);
+
+        assert_eq!(line_actions(&json!({"message": {"role": "assistant", "content": "Hi"}, "done": false})), [AppAction::SearchStreamToken("Hi".into())]);
+        assert_eq!(line_actions(&json!({"message": {"content": "", "thinking": "hmm"}, "done": false})), [AppAction::SearchReasoningToken("hmm".into())]);
+        assert_eq!(line_actions(&json!({"message": {"content": ""}, "done": true, "prompt_eval_count": 12, "eval_count": 4})), [AppAction::SearchUsage(Usage { prompt: 12, completion: 4 })]);
+    }
+}
@@ -0,0 +1,306 @@
+// ================================================
+// FILE: src/plugins.rs
+// ================================================
+// External plugins (`plugins` in config.json): executables that add slash
+// commands, Search sidebar panels or launcher apps without living in this
+// crate. Each runs as a child process speaking JSON-RPC 2.0 over stdio, one
+// message per line. bpt calls `initialize` once and the plugin answers with
+// what it provides; after that bpt calls `command`, `panel/render`,
+// `apps/list`, `apps/launch` and `apps/stop`, and the plugin may notify
+// `panel/update`, `apps/changed`, `output` and `log` at any time. Plugin apps
+// carry the `plugin:<name>:` id prefix, like the sources.rs ones.
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Instant;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use crate::api::{AppModel, LaunchEnv};
+use crate::app::AppAction;
+use crate::local;
+
+pub const PREFIX: &str = "plugin:";
+
+/// One entry of `plugins` in config.json.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PluginConfig {
+    pub name: String,
+    /// Run through the shell, like app commands.
+    pub command: String,
+}
+
+/// What a plugin provides, from its `initialize` result.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    pub commands: Vec<PluginCommand>,
+    pub panels: Vec<PanelSpec>,
+    /// Whether it answers `apps/list` and `apps/launch`.
+    pub apps: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginCommand {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelSpec {
+    pub id: String,
+    pub title: String,
+}
+
+/// A sidebar panel and the lines the plugin last sent for it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Panel {
+    pub plugin: String,
+    pub id: String,
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+/// An app as a plugin lists it; `state` shows next to it like a container's.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+struct ListedApp {
+    id: String,
+    name: String,
+    description: Option<String>,
+    url: String,
+    state: Option<String>,
+}
+
+/// The app id bpt uses for a plugin's app.
+pub fn app_id(plugin: &str, id: &str) -> String { format!("{}{}:{}", PREFIX, plugin, id) }
+
+/// The plugin owning an app id, and the id the plugin knows it by.
+pub fn owner(id: &str) -> Option<(&str, &str)> {
+    id.strip_prefix(PREFIX)?.split_once(':').filter(|(p, _)| !p.is_empty())
+}
+
+/// What to do with the result of a call once it comes back.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Initialize,
+    /// A slash command's text goes into the chat.
+    Command,
+    Apps,
+    Launch { seq: u64 },
+    Panel { id: String },
+    /// The result's `message` (or the error) goes to the Output pane.
+    Log,
+}
+
+impl Reply {
+    /// The actions a call's result (or failure) turns into.
+    pub fn actions(self, plugin: &str, result: Result<Value, String>, elapsed_ms: u64) -> Vec<AppAction> {
+        let plugin = plugin.to_string();
+        let note = |e: String| AppAction::LaunchResult(format!("Plugin '{}': {}", plugin, e));
+        match (self, result) {
+            (Reply::Launch { seq }, result) => {
+                let (ok, output) = match result {
+                    Ok(v) => (v["ok"].as_bool().unwrap_or(true), v["output"].as_str().unwrap_or("").to_string()),
+                    Err(e) => (false, format!("Plugin Error: {}", e)),
+                };
+                vec![AppAction::LaunchFinished { seq, ok, duration_ms: elapsed_ms, output }]
+            },
+            (_, Err(e)) => vec![note(e)],
+            (Reply::Initialize, Ok(v)) => match serde_json::from_value::<Manifest>(v) {
+                Ok(manifest) => vec![AppAction::PluginReady { plugin, manifest }],
+                Err(e) => vec![note(format!("bad initialize result: {}", e))],
+            },
+            (Reply::Command, Ok(v)) => {
+                let mut out = vec![];
+                if let Some(text) = v["text"].as_str().filter(|t| !t.trim().is_empty()) {
+                    out.push(AppAction::PluginMessage { plugin: plugin.clone(), text: text.to_string() });
+                }
+                if let Some(content) = v["context"]["content"].as_str() {
+                    let label = v["context"]["label"].as_str().unwrap_or(&plugin).to_string();
+                    out.push(AppAction::ContextAttached { label, content: content.to_string() });
+                }
+                out
+            },
+            (Reply::Apps, Ok(v)) => match serde_json::from_value::<Vec<ListedApp>>(v["apps"].clone()) {
+                Ok(listed) => {
+                    let apps = listed.into_iter().filter(|a| !a.id.is_empty()).map(|a| {
+                        let model = AppModel { id: app_id(&plugin, &a.id), name: a.name, description: a.description, command: String::new(), url: a.url, launch: LaunchEnv::default(), target: None };
+                        (model, a.state.unwrap_or_default())
+                    }).collect();
+                    vec![AppAction::PluginApps { plugin, apps }]
+                },
+                Err(e) => vec![note(format!("bad apps/list result: {}", e))],
+            },
+            (Reply::Panel { id }, Ok(v)) => vec![AppAction::PluginPanel { plugin, panel: id, lines: lines(&v) }],
+            (Reply::Log, Ok(v)) => v["message"].as_str().map(|m| vec![note(m.to_string())]).unwrap_or_default(),
+        }
+    }
+}
+
+/// `lines` of a panel result or update, as an array or one string.
+fn lines(v: &Value) -> Vec<String> {
+    match &v["lines"] {
+        Value::Array(items) => items.iter().map(|l| l.as_str().map(String::from).unwrap_or_else(|| l.to_string())).collect(),
+        Value::String(s) => s.lines().map(String::from).collect(),
+        _ => vec![],
+    }
+}
+
+/// A request line.
+fn request(id: u64, method: &str, params: &Value) -> String {
+    format!("{}\n", json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    Response { id: u64, result: Result<Value, String> },
+    Notification { method: String, params: Value },
+}
+
+fn parse(line: &str) -> Option<Message> {
+    let v: Value = serde_json::from_str(line.trim()).ok()?;
+    if let Some(method) = v["method"].as_str() {
+        return Some(Message::Notification { method: method.to_string(), params: v["params"].clone() });
+    }
+    let id = v["id"].as_u64()?;
+    let result = match v.get("error") {
+        Some(e) => Err(e["message"].as_str().map(String::from).unwrap_or_else(|| e.to_string())),
+        None => Ok(v.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    Some(Message::Response { id, result })
+}
+
+/// What a notification from the plugin means for the App.
+fn notification(plugin: &str, method: &str, params: &Value) -> Vec<AppAction> {
+    match method {
+        "panel/update" => match params["id"].as_str() {
+            Some(id) => vec![AppAction::PluginPanel { plugin: plugin.into(), panel: id.into(), lines: lines(params) }],
+            None => vec![],
+        },
+        "apps/changed" => vec![AppAction::PluginAppsChanged(plugin.into())],
+        "output" => match (params["seq"].as_u64(), params["line"].as_str()) {
+            (Some(seq), Some(line)) => vec![AppAction::LaunchOutput { seq, line: line.into(), stderr: params["stderr"].as_bool().unwrap_or(false) }],
+            _ => vec![],
+        },
+        "log" => params["message"].as_str().map(|m| vec![AppAction::LaunchResult(format!("Plugin '{}': {}", plugin, m))]).unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+struct Call { method: String, params: Value, reply: Reply }
+
+// Running plugins by name.
+static RUNNING: Mutex<BTreeMap<String, UnboundedSender<Call>>> = Mutex::new(BTreeMap::new());
+
+/// Sends a call to a running plugin; its result arrives as actions.
+pub fn call(plugin: &str, method: &str, params: Value, reply: Reply) -> Result<()> {
+    let running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    let sender = running.get(plugin).ok_or_else(|| anyhow!("plugin '{}' is not running", plugin))?;
+    sender.send(Call { method: method.to_string(), params, reply }).map_err(|_| anyhow!("plugin '{}' has exited", plugin))
+}
+
+/// Spawns the plugin and calls `initialize`. Calls are written in order;
+/// replies may come back in any order. When it exits, calls still waiting
+/// fail and the App hears PluginExited.
+pub fn start(plugin: PluginConfig, tx: UnboundedSender<AppAction>) {
+    let spawned = local::shell(&plugin.command).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).kill_on_drop(true).spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => { let _ = tx.send(AppAction::PluginExited { plugin: plugin.name, error: e.to_string() }); return; },
+    };
+    let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else { return };
+    let (call_tx, mut call_rx) = mpsc::unbounded_channel::<Call>();
+    let _ = call_tx.send(Call { method: "initialize".into(), params: json!({ "client": "bpt", "version": env!("CARGO_PKG_VERSION") }), reply: Reply::Initialize });
+    RUNNING.lock().unwrap_or_else(|e| e.into_inner()).insert(plugin.name.clone(), call_tx);
+
+    let name = plugin.name;
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut pending: BTreeMap<u64, (Reply, Instant)> = BTreeMap::new();
+        let mut next_id = 1;
+        let error = loop {
+            tokio::select! {
+                call = call_rx.recv() => {
+                    let Some(call) = call else { break String::new() };
+                    if let Err(e) = stdin.write_all(request(next_id, &call.method, &call.params).as_bytes()).await {
+                        for a in call.reply.actions(&name, Err(e.to_string()), 0) { let _ = tx.send(a); }
+                        break e.to_string();
+                    }
+                    pending.insert(next_id, (call.reply, Instant::now()));
+                    next_id += 1;
+                },
+                line = lines.next_line() => {
+                    let line = match line { Ok(Some(line)) => line, Ok(None) => break String::new(), Err(e) => break e.to_string() };
+                    let actions = match parse(&line) {
+                        Some(Message::Response { id, result }) => match pending.remove(&id) {
+                            Some((reply, started)) => reply.actions(&name, result, started.elapsed().as_millis() as u64),
+                            None => vec![],
+                        },
+                        Some(Message::Notification { method, params }) => notification(&name, &method, &params),
+                        None => vec![],
+                    };
+                    for a in actions { let _ = tx.send(a); }
+                },
+            }
+        };
+        RUNNING.lock().unwrap_or_else(|e| e.into_inner()).remove(&name);
+        for (reply, started) in std::mem::take(&mut pending).into_values() {
+            for a in reply.actions(&name, Err("the plugin exited".into()), started.elapsed().as_millis() as u64) { let _ = tx.send(a); }
+        }
+        let error = match child.wait().await {
+            Ok(status) if !status.success() && error.is_empty() => status.to_string(),
+            _ => error,
+        };
+        let _ = tx.send(AppAction::PluginExited { plugin: name, error });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_map_to_their_plugin() {
+        assert_eq!(app_id("jira", "BPT-1"), "plugin:jira:BPT-1");
+        assert_eq!(owner("plugin:jira:BPT-1"), Some(("jira", "BPT-1")));
+        assert_eq!(owner("plugin:ha:light:kitchen"), Some(("ha", "light:kitchen")));
+        assert_eq!(owner("docker:web"), None);
+        assert_eq!(owner("plugin::x"), None);
+    }
+
+    #[test]
+    fn speaks_line_delimited_json_rpc() {
+        assert_eq!(request(3, "apps/list", &json!({})), "{\"id\":3,\"jsonrpc\":\"2.0\",\"method\":\"apps/list\",\"params\":{}}\n");
+        assert_eq!(parse(r#"{"jsonrpc":"2.0","id":3,"result":{"apps":[]}}"#), Some(Message::Response { id: 3, result: Ok(json!({"apps": []})) }));
+        assert_eq!(parse(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-32601,"message":"no such method"}}"#), Some(Message::Response { id: 4, result: Err("no such method".into()) }));
+        assert_eq!(parse(r#"{"jsonrpc":"2.0","method":"log","params":{"message":"hi"}}"#), Some(Message::Notification { method: "log".into(), params: json!({"message": "hi"}) }));
+        assert_eq!(parse("starting up..."), None);
+    }
+
+    #[test]
+    fn turns_results_into_actions() {
+        let manifest = json!({"commands": [{"name": "jira", "description": "Look up an issue"}], "panels": [{"id": "mine", "title": "My issues"}]});
+        let fx = Reply::Initialize.actions("jira", Ok(manifest), 0);
+        let [AppAction::PluginReady { manifest, .. }] = fx.as_slice() else { panic!("{:?}", fx) };
+        assert_eq!((manifest.commands[0].name.as_str(), manifest.panels[0].title.as_str(), manifest.apps), ("jira", "My issues", false));
+
+        let fx = Reply::Command.actions("jira", Ok(json!({"text": "BPT-1: Crash", "context": {"label": "BPT-1", "content": "Steps..."}})), 0);
+        assert_eq!(fx, vec![
+            AppAction::PluginMessage { plugin: "jira".into(), text: "BPT-1: Crash".into() },
+            AppAction::ContextAttached { label: "BPT-1".into(), content: "Steps...".into() },
+        ]);
+
+        let fx = Reply::Apps.actions("ha", Ok(json!({"apps": [{"id": "light:kitchen", "name": "Kitchen", "state": "on"}, {"name": "no id"}]})), 0);
+        let [AppAction::PluginApps { apps, .. }] = fx.as_slice() else { panic!("{:?}", fx) };
+        assert_eq!(apps.iter().map(|(a, s)| (a.id.as_str(), s.as_str())).collect::<Vec<_>>(), [("plugin:ha:light:kitchen", "on")]);
+
+        assert_eq!(Reply::Launch { seq: 7 }.actions("ha", Err("the plugin exited".into()), 12), vec![AppAction::LaunchFinished { seq: 7, ok: false, duration_ms: 12, output: "Plugin Error: the plugin exited".into() }]);
+        assert_eq!(Reply::Panel { id: "mine".into() }.actions("jira", Ok(json!({"lines": "a\nb"})), 0), vec![AppAction::PluginPanel { plugin: "jira".into(), panel: "mine".into(), lines: vec!["a".into(), "b".into()] }]);
+        assert_eq!(notification("jira", "output", &json!({"seq": 7, "line": "ok"})), vec![AppAction::LaunchOutput { seq: 7, line: "ok".into(), stderr: false }]);
+    }
+}
@@ -0,0 +1,148 @@
+// ================================================
+// FILE: src/preview.rs
+// ================================================
+// Text preview of an app's web UI: the fetched HTML is reduced to its main
+// content (the <main> or <article> element when there is one, minus scripts,
+// navigation and chrome) and turned into markdown for ui::markdown_to_text.
+
+/// Upper bound for the extracted text; a preview, not an archive.
+const MAX_CHARS: usize = 20_000;
+
+/// Elements whose content is never shown.
+const SKIP: [&str; 10] = ["script", "style", "noscript", "template", "svg", "head", "nav", "header", "footer", "aside"];
+
+pub fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let Some(end) = rest[..rest.len().min(10)].find(';') else { out.push('&'); rest = &rest[1..]; continue };
+        let name = &rest[1..end];
+        let ch = match name {
+            "amp" => Some('&'), "lt" => Some('<'), "gt" => Some('>'), "quot" => Some('"'),
+            "apos" => Some('\''), "nbsp" => Some(' '), "mdash" => Some('—'), "ndash" => Some('–'), "hellip" => Some('…'),
+            _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => name.strip_prefix('#').and_then(|d| d.parse().ok()).and_then(char::from_u32),
+            },
+        };
+        match ch {
+            Some(c) => { out.push(c); rest = &rest[end + 1..]; },
+            None => { out.push('&'); rest = &rest[1..]; },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The inside of the first `<tag ...>...</tag>`, if the page has one.
+fn element<'a>(html: &'a str, lower: &str, tag: &str) -> Option<&'a str> {
+    let open = [format!("<{}>", tag), format!("<{} ", tag)].iter().filter_map(|o| lower.find(o.as_str())).min()?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = lower.rfind(&format!("</{}", tag)).filter(|e| *e >= start)?;
+    Some(&html[start..end])
+}
+
+/// `(title, markdown)` for an HTML page.
+pub fn extract(html: &str) -> (String, String) {
+    let lower = html.to_ascii_lowercase();
+    let title = element(html, &lower, "title").map(|t| decode_entities(t.trim())).unwrap_or_default();
+    let body = ["main", "article", "body"].iter().find_map(|t| element(html, &lower, t)).unwrap_or(html);
+
+    let mut md = String::new();
+    let mut skip: Vec<String> = vec![];
+    let mut pre = 0u32;  // Depth of <pre>: whitespace is kept inside
+    let mut rest = body;
+    while !rest.is_empty() {
+        let (text, tag) = match rest.find('<') {
+            Some(i) => (&rest[..i], Some(&rest[i..])),
+            None => (rest, None),
+        };
+        if skip.is_empty() && !text.is_empty() {
+            let text = decode_entities(text);
+            if pre > 0 { md.push_str(&text); }
+            else {
+                let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !words.is_empty() {
+                    if text.starts_with(char::is_whitespace) && !md.ends_with([' ', '\n']) && !md.is_empty() { md.push(' '); }
+                    md.push_str(&words);
+                    if text.ends_with(char::is_whitespace) { md.push(' '); }
+                }
+            }
+        }
+        let Some(tag) = tag else { break };
+        if tag.starts_with("<!--") {
+            rest = tag.find("-->").map(|e| &tag[e + 3..]).unwrap_or("");
+            continue;
+        }
+        let Some(end) = tag.find('>') else { break };
+        let inner = &tag[1..end];
+        rest = &tag[end + 1..];
+        let closing = inner.starts_with('/');
+        let name = inner.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("").to_ascii_lowercase();
+
+        if SKIP.contains(&name.as_str()) {
+            if closing { if skip.last() == Some(&name) { skip.pop(); } }
+            else if !inner.ends_with('/') { skip.push(name); }
+            continue;
+        }
+        if !skip.is_empty() { continue; }
+        let trim_line = |md: &mut String| { while md.ends_with(' ') { md.pop(); } };
+        match (name.as_str(), closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                trim_line(&mut md);
+                md.push_str("\n\n");
+                md.push_str(&"#".repeat(name[1..].parse().unwrap_or(1)));
+                md.push(' ');
+            },
+            ("p" | "div" | "section" | "table" | "tr" | "ul" | "ol" | "blockquote" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "dl", _) => { trim_line(&mut md); md.push_str("\n\n"); },
+            ("li" | "dt", false) => { trim_line(&mut md); md.push_str("\n- "); },
+            ("br", _) => { trim_line(&mut md); md.push('\n'); },
+            ("td" | "th" | "dd", false) if !md.ends_with([' ', '\n']) => md.push_str(" · "),
+            ("pre", false) => { pre += 1; md.push_str("\n\n```\n"); },
+            ("pre", true) => { pre = pre.saturating_sub(1); md.push_str("\n```\n\n"); },
+            _ => {}
+        }
+    }
+
+    // Collapse the blank lines left behind by nested blocks.
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in md.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() { blank += 1; continue; }
+        if !out.is_empty() { out.push_str(if blank > 0 { "\n\n" } else { "\n" }); }
+        blank = 0;
+        out.push_str(line);
+    }
+    if out.len() > MAX_CHARS {
+        let cut = (0..=MAX_CHARS).rev().find(|i| out.is_char_boundary(*i)).unwrap_or(0);
+        out.truncate(cut);
+        out.push_str("\n\n_(truncated)_");
+    }
+    (title, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_main_content_as_markdown() {
+        let html = r#"<html><head><title>Grafana &amp; co</title><script>var x = "<p>no</p>";</script></head>
+            <body><nav><a href="/">Home</a></nav>
+            <main class="page"><h1>Dashboard</h1><p>CPU   is <b>42%</b>
+            today.</p><!-- hidden --><ul><li>disk ok</li><li>net &lt;1ms</li></ul>
+            <pre>a  b
+c</pre></main><footer>(c) 2024</footer></body></html>"#;
+        let (title, md) = extract(html);
+        assert_eq!(title, "Grafana & co");
+        assert_eq!(md, "# Dashboard\n\nCPU is 42% today.\n\n- disk ok\n- net <1ms\n\n```\na  b\nc\n```");
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(decode_entities("a&nbsp;&#65;&#x42;&bogus; & b"), "a AB&bogus; & b");
+    }
+}
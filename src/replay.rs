@@ -0,0 +1,123 @@
+// ================================================
+// FILE: src/replay.rs
+// ================================================
+// Session recording (`--record`) and playback (`--replay`).
+// Every action that reaches `App::update` is logged with its offset from the
+// start of the session; terminal key events are logged alongside for context.
+// Playback re-dispatches the actions with the original timing while effects
+// are disabled, so a session is reproduced without any backend running.
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use crossterm::event::KeyEvent;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use crate::app::AppAction;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayState {
+    Off,
+    Playing,
+    Finished,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    Key { t_ms: u64, key: String },
+    Action { t_ms: u64, action: AppAction },
+}
+
+pub struct Recorder {
+    out: BufWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        Ok(Self { out: BufWriter::new(file), start: Instant::now() })
+    }
+
+    fn elapsed_ms(&self) -> u64 { self.start.elapsed().as_millis() as u64 }
+
+    pub fn key(&mut self, key: &KeyEvent) {
+        let rec = Record::Key { t_ms: self.elapsed_ms(), key: format!("{:?}+{:?}", key.code, key.modifiers) };
+        self.write(&rec);
+    }
+
+    pub fn action(&mut self, action: &AppAction) {
+        let rec = Record::Action { t_ms: self.elapsed_ms(), action: action.clone() };
+        self.write(&rec);
+    }
+
+    // Flushed per line so a crash still leaves a usable file.
+    fn write(&mut self, rec: &Record) {
+        if let Ok(line) = serde_json::to_string(rec) {
+            let _ = writeln!(self.out, "{}", line);
+            let _ = self.out.flush();
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<Record>> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    BufReader::new(file).lines().enumerate()
+        .filter(|(_, l)| l.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true))
+        .map(|(i, l)| serde_json::from_str(&l?).with_context(|| format!("{}:{}", path.display(), i + 1)))
+        .collect()
+}
+
+/// Re-dispatches recorded actions at their original offsets, then reports completion.
+pub fn spawn_player(records: Vec<Record>, tx: UnboundedSender<AppAction>) {
+    tokio::spawn(async move {
+        let start = Instant::now();
+        for rec in records {
+            if let Record::Action { t_ms, action } = rec {
+                let due = Duration::from_millis(t_ms);
+                if let Some(wait) = due.checked_sub(start.elapsed()) { tokio::time::sleep(wait).await; }
+                if tx.send(action).is_err() { return; }
+            }
+        }
+        let _ = tx.send(AppAction::ReplayFinished);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::AppModel;
+    use crate::app::App;
+
+    #[test]
+    fn recorded_session_replays_to_same_state() {
+        let path = std::env::temp_dir().join(format!("bpt-replay-{}.jsonl", std::process::id()));
+        let actions = vec![
+            AppAction::AppsLoaded(vec![AppModel { id: "1".into(), name: "web".into(), description: None, command: "npm start".into(), url: "http://localhost:3000".into(), ..Default::default() }]),
+            AppAction::OpenAdHocModal,
+            AppAction::AdHocChar('l'),
+            AppAction::AdHocChar('s'),
+            AppAction::SubmitAdHoc("ls".into()),
+            AppAction::SwitchTab,
+            AppAction::EnterSearchChar('?'),
+        ];
+
+        let mut live = App::new();
+        let mut recorder = Recorder::create(&path).unwrap();
+        for a in &actions { recorder.action(a); live.reduce(a.clone()); }
+        drop(recorder);
+
+        let mut replayed = App::new();
+        for rec in load(&path).unwrap() {
+            if let Record::Action { action, .. } = rec { replayed.reduce(action); }
+        }
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(replayed.launcher_logs, live.launcher_logs);
+        assert_eq!(replayed.current_screen, live.current_screen);
+        assert_eq!(replayed.search_input, "?");
+        assert_eq!(replayed.apps, live.apps);
+    }
+}
@@ -0,0 +1,184 @@
+// ================================================
+// FILE: src/share.rs
+// ================================================
+// Read-only HTTP view of the current conversation (`--serve-chat`).
+// Deliberately tiny: GET-only, one request per connection, no dependencies
+// beyond tokio. The page polls `/fragment` and swaps in the rendered chat.
+// Requests must name the bound address in `Host`, so a page on some other
+// site can't read the chat through DNS rebinding.
+use std::net::{IpAddr, SocketAddr};
+use anyhow::Result;
+use pulldown_cmark::{html, Event, Parser, Tag};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use crate::app::ChatMessage;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatSnapshot {
+    pub messages: Vec<ChatMessage>,
+    pub streaming: bool,
+}
+
+const PAGE: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><title>bplus chat</title>
+<style>
+body { font-family: system-ui, sans-serif; max-width: 60rem; margin: 1rem auto; padding: 0 1rem; background: #111; color: #ddd; }
+.msg { margin: 1rem 0; } .role { font-weight: bold; } .user .role { color: #5cc; } .assistant .role { color: #6c6; } .system .role { color: #c66; }
+pre, code { background: #282828; color: #6cc; } pre { padding: .5rem; overflow-x: auto; }
+a { color: #c6c; } .status { color: #888; font-size: .9em; }
+</style></head>
+<body><div id="chat"></div>
+<script>
+let last = "";
+async function refresh() {
+  try {
+    const html = await (await fetch("/fragment")).text();
+    if (html !== last) {
+      const atBottom = window.innerHeight + window.scrollY >= document.body.offsetHeight - 40;
+      document.getElementById("chat").innerHTML = html; last = html;
+      if (atBottom) window.scrollTo(0, document.body.scrollHeight);
+    }
+  } catch (e) {}
+  setTimeout(refresh, 1000);
+}
+refresh();
+</script></body></html>"#;
+
+pub async fn serve(listener: TcpListener, rx: watch::Receiver<ChatSnapshot>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else { continue };
+        let rx = rx.clone();
+        tokio::spawn(async move { let _ = handle(stream, rx).await; });
+    }
+}
+
+async fn handle(mut stream: TcpStream, rx: watch::Receiver<ChatSnapshot>) -> Result<()> {
+    let mut buf = vec![0u8; 4096];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (status, content_type, body) = match (method, path) {
+        _ if !host_allowed(&request, stream.local_addr()?) => ("403 Forbidden", "text/plain", "unexpected Host".to_string()),
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", PAGE.to_string()),
+        ("GET", "/fragment") => ("200 OK", "text/html; charset=utf-8", render_fragment(&rx.borrow())),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "read-only".to_string()),
+    };
+    let head = format!("HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n", status, content_type, body.len());
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    Ok(())
+}
+
+/// Whether the request's `Host` is the address it arrived on (or `localhost`
+/// for a loopback one). The port may be left out only when it is 80.
+fn host_allowed(request: &str, local: SocketAddr) -> bool {
+    let Some(host) = request.lines().skip(1).take_while(|l| !l.is_empty())
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("host"))
+        .map(|(_, value)| value.trim()) else { return false };
+    let ip = match local.ip() { IpAddr::V4(ip) => ip.to_string(), IpAddr::V6(ip) => format!("[{}]", ip) };
+    let names = if local.ip().is_loopback() { vec![ip, "localhost".to_string()] } else { vec![ip] };
+    names.iter().any(|name| host.eq_ignore_ascii_case(&format!("{}:{}", name, local.port())) || (local.port() == 80 && host.eq_ignore_ascii_case(name)))
+}
+
+/// Only web links are followed; `javascript:`, `data:` and the like are not.
+fn is_web_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Markdown to HTML, with any raw HTML in the model output shown as text.
+/// Images become their alt text (nothing is fetched from the viewer's
+/// browser), and links that aren't http(s) become plain text.
+fn markdown_to_html(markdown: &str) -> String {
+    let events = Parser::new(markdown).filter_map(|e| match e {
+        Event::Html(raw) => Some(Event::Text(raw)),
+        Event::Start(Tag::Image(..)) | Event::End(Tag::Image(..)) => None,
+        Event::Start(Tag::Link(_, ref url, _)) | Event::End(Tag::Link(_, ref url, _)) if !is_web_url(url) => None,
+        other => Some(other),
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+pub fn render_fragment(snapshot: &ChatSnapshot) -> String {
+    let mut out = String::new();
+    for msg in &snapshot.messages {
+        out.push_str(&format!("<div class=\"msg {}\"><div class=\"role\">{}:</div>", escape(&msg.role), escape(&msg.role.to_uppercase())));
+        out.push_str(&markdown_to_html(&msg.content));
+        if !msg.sources.is_empty() {
+            out.push_str("<ol class=\"sources\">");
+            for s in &msg.sources {
+                let title = if is_web_url(&s.url) { format!("<a href=\"{}\">{}</a>", escape(&s.url), escape(&s.title)) } else { escape(&s.title) };
+                out.push_str(&format!("<li>{} ({})</li>", title, escape(&s.engine)));
+            }
+            out.push_str("</ol>");
+        }
+        out.push_str("</div>");
+    }
+    if snapshot.streaming { out.push_str("<div class=\"status\">streaming…</div>"); }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SearchSource;
+
+    #[test]
+    fn fragment_escapes_raw_html_and_lists_sources() {
+        let snapshot = ChatSnapshot {
+            messages: vec![ChatMessage {
+                sources: vec![SearchSource { title: "a<b".into(), url: "https://x".into(), content: String::new(), engine: "ddg".into() }],
+                ..ChatMessage::new("assistant", "**hi** <script>x</script>")
+            }],
+            streaming: true,
+        };
+        let html = render_fragment(&snapshot);
+        assert!(html.contains("<strong>hi</strong>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<a href=\"https://x\">a&lt;b</a> (ddg)"));
+        assert!(html.contains("streaming"));
+    }
+
+    #[test]
+    fn fragment_links_only_to_the_web() {
+        let snapshot = ChatSnapshot {
+            messages: vec![ChatMessage {
+                sources: vec![SearchSource { title: "evil".into(), url: "javascript:alert(1)".into(), content: String::new(), engine: "ddg".into() }],
+                ..ChatMessage::new("assistant", "![a chart](https://tracker.example/x.png) [docs](https://docs.rs) [click](javascript:alert(1)) [data](data:text/html,hi)")
+            }],
+            streaming: false,
+        };
+        let html = render_fragment(&snapshot);
+        assert!(!html.contains("<img") && html.contains("a chart"));
+        assert!(html.contains("<a href=\"https://docs.rs\">docs</a>"));
+        assert!(!html.contains("javascript:") && !html.contains("data:") && html.contains("click"));
+        assert!(html.contains("<li>evil (ddg)</li>"));
+    }
+
+    #[test]
+    fn only_the_bound_address_is_served() {
+        let get = |host: &str| format!("GET / HTTP/1.1\r\nHost: {}\r\nAccept: */*\r\n\r\n", host);
+        let local: SocketAddr = "127.0.0.1:7878".parse().unwrap();
+        assert!(host_allowed(&get("127.0.0.1:7878"), local));
+        assert!(host_allowed(&get("localhost:7878"), local));
+        assert!(!host_allowed(&get("attacker.example:7878"), local));
+        assert!(!host_allowed(&get("127.0.0.1:80"), local));
+        assert!(!host_allowed("GET / HTTP/1.1\r\n\r\n", local), "no Host at all");
+
+        let lan: SocketAddr = "192.168.1.5:80".parse().unwrap();
+        assert!(host_allowed(&get("192.168.1.5"), lan));
+        assert!(!host_allowed(&get("localhost"), lan));
+        assert!(host_allowed(&get("[::1]:7878"), "[::1]:7878".parse().unwrap()));
+    }
+}
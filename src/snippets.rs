@@ -0,0 +1,122 @@
+// ================================================
+// FILE: src/snippets.rs
+// ================================================
+// Fenced code blocks in assistant answers. Shell blocks (`bash`, `sh`, ...)
+// are commands the chat can copy, run as an ad-hoc launcher command after a
+// y/n, or turn into a new app. Prompts pasted along (`$ make`) are dropped
+// so the text runs as written. Saving as an app takes any block when the
+// answer has no shell ones, and guesses a name from the command.
+use serde::{Deserialize, Serialize};
+
+/// What the chat does with a shell block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Verb { Copy, Run, SaveApp }
+
+impl Verb {
+    pub fn label(self) -> &'static str {
+        match self { Verb::Copy => "Copy", Verb::Run => "Run", Verb::SaveApp => "Save as app" }
+    }
+}
+
+/// One fenced block: the first word of its info string, lowercased, and its body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    pub lang: String,
+    pub code: String,
+}
+
+const SHELLS: [&str; 6] = ["bash", "sh", "shell", "zsh", "console", "shell-session"];
+
+impl CodeBlock {
+    pub fn is_shell(&self) -> bool { SHELLS.contains(&self.lang.as_str()) }
+
+    /// The block as a command: `$ ` prompts stripped, blank edges trimmed.
+    pub fn command(&self) -> String {
+        let lines: Vec<&str> = self.code.lines().map(|l| l.strip_prefix("$ ").unwrap_or(l)).collect();
+        lines.join("\n").trim().to_string()
+    }
+}
+
+/// First line of a command, with a count of the rest, for prompts and hints.
+pub fn summary(cmd: &str) -> String {
+    let mut lines = cmd.trim().lines();
+    let first = lines.next().unwrap_or("").to_string();
+    match lines.count() {
+        0 => first,
+        n => format!("{} (+{} more line{})", first, n, if n == 1 { "" } else { "s" }),
+    }
+}
+
+/// The fenced blocks of `text`, ``` or ~~~, in order. A fence closes on the
+/// same marker at least as long; an unclosed block runs to the end.
+pub fn blocks(text: &str) -> Vec<CodeBlock> {
+    let mut out = vec![];
+    let mut open: Option<(String, String, Vec<&str>)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match &mut open {
+            Some((fence, _, body)) => {
+                if trimmed.starts_with(fence.as_str()) && trimmed.trim_start_matches(fence.chars().next().unwrap_or('`')).trim().is_empty() {
+                    let (_, lang, body) = open.take().unwrap_or_default();
+                    out.push(CodeBlock { lang, code: body.join("\n") });
+                } else {
+                    body.push(line);
+                }
+            },
+            None => {
+                let marker = if trimmed.starts_with("```") { '`' } else if trimmed.starts_with("~~~") { '~' } else { continue };
+                let fence: String = trimmed.chars().take_while(|&c| c == marker).collect();
+                let lang = trimmed[fence.len()..].split_whitespace().next().unwrap_or("").to_lowercase();
+                open = Some((fence, lang, vec![]));
+            },
+        }
+    }
+    if let Some((_, lang, body)) = open { out.push(CodeBlock { lang, code: body.join("\n") }); }
+    out
+}
+
+/// The shell blocks of `text` that hold a command.
+pub fn commands(text: &str) -> Vec<CodeBlock> {
+    blocks(text).into_iter().filter(|b| b.is_shell() && !b.command().is_empty()).collect()
+}
+
+/// Blocks worth saving as an app: the shell ones, else any with code.
+pub fn saveable(text: &str) -> Vec<CodeBlock> {
+    match commands(text) {
+        cmds if cmds.is_empty() => blocks(text).into_iter().filter(|b| !b.command().is_empty()).collect(),
+        cmds => cmds,
+    }
+}
+
+/// A name for an app running `cmd`: its program, past `sudo` and `KEY=value`
+/// assignments, without the path.
+pub fn app_name(cmd: &str) -> String {
+    let first = cmd.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with('#')).unwrap_or("");
+    let program = first.split_whitespace().find(|w| *w != "sudo" && !w.contains('=')).unwrap_or("");
+    program.rsplit('/').next().unwrap_or(program).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_fenced_blocks_and_shell_commands() {
+        let answer = "Install it:\n\n```bash\n$ cargo install bpt\n$ bpt --help\n```\n\nConfig:\n```json\n{}\n```\n~~~~sh title\nmake\n~~~~\n```\n\n```";
+        let found = blocks(answer);
+        assert_eq!(found.iter().map(|b| b.lang.as_str()).collect::<Vec<_>>(), ["bash", "json", "sh", ""]);
+        let cmds = commands(answer);
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].command(), "cargo install bpt\nbpt --help");
+        assert_eq!((summary(&cmds[0].command()), summary(&cmds[1].command())), ("cargo install bpt (+1 more line)".to_string(), "make".to_string()));
+        assert_eq!(blocks("```SH\nls\n").pop(), Some(CodeBlock { lang: "sh".into(), code: "ls".into() }), "unclosed runs to the end");
+    }
+
+    #[test]
+    fn saves_any_block_and_names_it() {
+        assert_eq!(saveable("```python\nprint(1)\n```\n```\n\n```").len(), 1, "empty blocks are skipped");
+        assert_eq!(saveable("```\nx\n```\n```sh\nls\n```")[0].code, "ls", "shell blocks win");
+        assert_eq!(app_name("# start it\nsudo PORT=8080 /usr/local/bin/jupyter lab --no-browser"), "jupyter");
+        assert_eq!(app_name(""), "");
+    }
+}
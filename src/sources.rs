@@ -0,0 +1,58 @@
+// ================================================
+// FILE: src/sources.rs
+// ================================================
+// Launcher sources besides the backend: local Docker containers (docker.rs)
+// and systemd user services (systemd.rs). Their apps carry an id prefix
+// (`docker:web`, `systemd:syncthing.service`) so every launcher action can
+// tell who owns an app. They are listed next to the backend's apps with a
+// state, driven through the tool's own CLI, and can't be edited or deleted.
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::api::AppModel;
+use crate::{docker, local, systemd};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Source { Docker, Systemd }
+
+impl Source {
+    pub const ALL: [Source; 2] = [Source::Docker, Source::Systemd];
+
+    pub fn prefix(self) -> &'static str {
+        match self { Source::Docker => docker::PREFIX, Source::Systemd => systemd::PREFIX }
+    }
+
+    /// The source an app id belongs to; None for backend (or --local) apps.
+    pub fn of(id: &str) -> Option<Source> { Source::ALL.into_iter().find(|s| id.starts_with(s.prefix())) }
+
+    pub fn label(self) -> &'static str {
+        match self { Source::Docker => "Docker containers", Source::Systemd => "systemd units" }
+    }
+
+    /// Shell command for `verb` (start, stop, logs) on app `id`.
+    pub fn command(self, verb: &str, id: &str) -> String {
+        match self { Source::Docker => docker::command(verb, id), Source::Systemd => systemd::command(verb, id) }
+    }
+
+    /// Current apps and their states (`running`, `exited`, `failed`, ...).
+    pub async fn list(self) -> Result<Vec<(AppModel, String)>> {
+        match self { Source::Docker => docker::list().await, Source::Systemd => systemd::list().await }
+    }
+}
+
+/// Runs `cmd` to completion; its stderr is the error if it fails.
+pub async fn exec(cmd: &str) -> Result<()> {
+    let out = local::shell(cmd).output().await.map_err(|e| anyhow!(e))?;
+    if out.status.success() { Ok(()) } else { Err(anyhow!("{}", String::from_utf8_lossy(&out.stderr).trim())) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_map_to_their_source() {
+        assert_eq!(Source::of("docker:web"), Some(Source::Docker));
+        assert_eq!(Source::of("systemd:syncthing.service"), Some(Source::Systemd));
+        assert_eq!(Source::of("12"), None);
+    }
+}
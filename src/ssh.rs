@@ -0,0 +1,74 @@
+// ================================================
+// FILE: src/ssh.rs
+// ================================================
+// Remote launch targets. Hosts are defined in store::HOSTS; an app whose
+// `target` names one is launched by running `ssh` on this machine, whatever
+// the launcher backend, with the remote stdout/stderr streamed into the
+// Output pane through local.rs. Stopping the launch closes the connection.
+use serde::{Deserialize, Serialize};
+use crate::api::LaunchEnv;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SshHost {
+    /// What apps refer to in their `target`.
+    pub name: String,
+    /// `user@host`, or a `Host` alias from ~/.ssh/config.
+    pub destination: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Extra `ssh` arguments, e.g. `["-i", "~/.ssh/work"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// The local command that runs `cmd` on `host` with `env`'s variables and
+/// working directory applied there. BatchMode keeps a password prompt from
+/// hanging a launch that has no terminal; `tty` asks for a remote terminal
+/// (tmux launches).
+pub fn command(host: &SshHost, cmd: &str, env: &LaunchEnv, tty: bool) -> String {
+    let mut remote = String::new();
+    if let Some(dir) = &env.cwd {
+        // `~` is the remote home, so it stays outside the quotes.
+        let dir = match dir.strip_prefix('~') { Some(rest) => format!("~{}", quote_rest(rest)), None => quote(dir) };
+        remote.push_str(&format!("cd {} && ", dir));
+    }
+    remote.push_str("exec env");
+    for (k, v) in &env.env { remote.push_str(&format!(" {}={}", k, quote(v))); }
+    remote.push_str(&format!(" sh -c {}", quote(cmd)));
+    let mut args = vec!["ssh".to_string(), "-o".into(), "BatchMode=yes".into()];
+    if tty { args.push("-t".into()); }
+    if let Some(port) = host.port { args.extend(["-p".to_string(), port.to_string()]); }
+    args.extend(host.options.iter().map(|o| quote(o)));
+    args.extend([quote(&host.destination), quote(&remote)]);
+    args.join(" ")
+}
+
+fn quote_rest(s: &str) -> String { if s.is_empty() { String::new() } else { quote(s) } }
+
+/// `s` as one POSIX shell word.
+pub fn quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "@%+=:,./-_~".contains(c)) && !s.starts_with('~') { return s.to_string(); }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_ssh_command() {
+        let host = SshHost { name: "nas".into(), destination: "me@nas.lan".into(), port: Some(2222), options: vec!["-i".into(), "/k/id".into()] };
+        let env = LaunchEnv { env: [("PORT".to_string(), "80".to_string())].into(), cwd: Some("~/srv".into()) };
+        assert_eq!(command(&host, "make run", &env, false), "ssh -o BatchMode=yes -p 2222 -i /k/id me@nas.lan 'cd ~/srv && exec env PORT=80 sh -c '\\''make run'\\'''");
+        assert_eq!(command(&SshHost { destination: "box".into(), ..Default::default() }, "uptime", &LaunchEnv::default(), true), "ssh -o BatchMode=yes -t box 'exec env sh -c uptime'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted_words_survive_the_shell() {
+        for s in ["plain", "two words", "it's", "$HOME `x` \"q\"", ""] {
+            let out = std::process::Command::new("sh").arg("-c").arg(format!("printf %s {}", quote(s))).output().unwrap();
+            assert_eq!(String::from_utf8_lossy(&out.stdout), s);
+        }
+    }
+}
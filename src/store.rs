@@ -0,0 +1,90 @@
+// ================================================
+// FILE: src/store.rs
+// ================================================
+// Small JSON files for state that lives only on this machine (favorites,
+// history, ...). Everything is best-effort: a missing or corrupt file loads
+// as the default value rather than stopping the TUI from starting. With a
+// passphrase configured (see crypt.rs) files are encrypted at rest; existing
+// plain files are still read and get encrypted on their next save.
+use std::path::PathBuf;
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::crypt;
+
+pub const FAVORITES: &str = "favorites.json";
+pub const PROJECTS: &str = "projects.json";
+pub const USAGE: &str = "usage.json";
+pub const HISTORY: &str = "launch_history.json";
+pub const KEYMAP: &str = "keymap.json";
+pub const HIGHLIGHTS: &str = "highlights.json";
+pub const ARGS: &str = "command_args.json";  // Last placeholder values per app
+pub const APPS: &str = "apps.json";  // App definitions in --local mode
+pub const SYNC_BASE: &str = "apps_sync_base.json";  // APPS as of the last sync
+pub const CHAINS: &str = "chains.json";  // Named launch sequences
+pub const AUTO_OPEN: &str = "auto_open.json";  // Apps whose URL opens after launch
+pub const HOSTS: &str = "ssh_hosts.json";  // Remote launch targets (ssh.rs)
+pub const TMUX: &str = "tmux_apps.json";  // Apps launched in tmux, and how
+pub const CONFIG: &str = "config.json";  // Settings tab (config.rs)
+pub const MODELS: &str = "models.json";  // Starred and recent models per provider
+pub const SESSION: &str = "session.json";  // Tab, sidebar and conversation at the last exit
+pub const QUERIES: &str = "queries.json";  // Inputs submitted on the Search tab
+pub const SUMMARIES: &str = "summaries.json";  // Conversation descriptions from `/summarize save`
+pub const BRANCHES: &str = "branches.json";  // History replayed for branched conversations
+
+/// Every data file, for "purge all local data". `store.key` is kept: it
+/// holds no data, and the running session may still be encrypting with it.
+pub const FILES: [&str; 19] = [FAVORITES, PROJECTS, USAGE, HISTORY, KEYMAP, HIGHLIGHTS, ARGS, APPS, SYNC_BASE, CHAINS, AUTO_OPEN, HOSTS, TMUX, CONFIG, MODELS, SESSION, QUERIES, SUMMARIES, BRANCHES];
+
+/// `$BPT_RETENTION_DAYS`: drop launch history older than this at startup.
+pub fn retention_days() -> Option<u64> {
+    std::env::var("BPT_RETENTION_DAYS").ok().and_then(|v| v.parse().ok()).filter(|d| *d > 0)
+}
+
+pub fn purge_all() -> Result<()> {
+    for name in FILES {
+        match std::fs::remove_file(path(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e).with_context(|| format!("removing {}", path(name).display())),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `$BPT_DATA_DIR`, else `$XDG_DATA_HOME/bplus-tui`, else `~/.local/share/bplus-tui`.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("BPT_DATA_DIR") { return PathBuf::from(dir); }
+    let base = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("bplus-tui")
+}
+
+pub fn path(name: &str) -> PathBuf { data_dir().join(name) }
+
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let Ok(bytes) = std::fs::read(path(name)) else { return T::default() };
+    let bytes = if crypt::is_sealed(&bytes) {
+        match crypt::key() {
+            Ok(Some(key)) => match crypt::open(key, &bytes) { Ok(plain) => plain, Err(_) => return T::default() },
+            _ => return T::default(),
+        }
+    } else { bytes };
+    serde_json::from_slice(&bytes).unwrap_or_default()
+}
+
+/// Writes via a temp file + rename so a crash never leaves half a file.
+pub fn save<T: Serialize + ?Sized>(name: &str, value: &T) -> Result<()> {
+    let target = path(name);
+    if let Some(dir) = target.parent() { std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?; }
+    let tmp = target.with_extension("tmp");
+    let json = serde_json::to_vec_pretty(value)?;
+    let bytes = match crypt::key()? { Some(key) => crypt::seal(key, &json), None => json };
+    std::fs::write(&tmp, bytes).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, &target).with_context(|| format!("writing {}", target.display()))?;
+    Ok(())
+}
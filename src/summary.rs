@@ -0,0 +1,37 @@
+// ================================================
+// FILE: src/summary.rs
+// ================================================
+// `/summarize`: the model condenses the conversation into a few sentences,
+// which are either added to the chat or kept (in store::SUMMARIES) as the
+// conversation's description under its title in the History sidebar.
+use crate::app::ChatMessage;
+use crate::todo;
+
+/// The summary request for a conversation.
+pub fn prompt(messages: &[ChatMessage]) -> String {
+    format!(
+        "Summarize the conversation below in two to four sentences: what was asked, what was found or decided, and anything left open. \
+         Reply with the summary only, as plain prose without headings or lists.\n\n---\n\n{}",
+        todo::transcript(messages)
+    )
+}
+
+/// The model's reply without a `Summary:` label, or `None` if it is empty.
+pub fn clean(reply: &str) -> Option<String> {
+    let text = reply.trim().trim_start_matches('#').trim();
+    let text = text.strip_prefix("Summary:").or_else(|| text.strip_prefix("**Summary:**")).unwrap_or(text).trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_and_cleanup() {
+        let msgs = [ChatMessage::new("user", "deploy?"), ChatMessage::new("assistant", "Run make deploy")];
+        assert!(prompt(&msgs).ends_with("---\n\nUser: deploy?\n\nAssistant: Run make deploy"));
+        assert_eq!(clean("\nSummary: Deploys run via make.\n").as_deref(), Some("Deploys run via make."));
+        assert_eq!(clean(" \n"), None);
+    }
+}
@@ -0,0 +1,143 @@
+// ================================================
+// FILE: src/sync.rs
+// ================================================
+// Two-way sync between the local app catalog (store::APPS, what --local runs
+// from) and the launcher backend. Changes are detected against the state both
+// sides agreed on at the last sync (store::SYNC_BASE): a side that differs
+// from it was edited. Edits on both sides are a conflict and are left alone
+// unless `prefer_local` is set.
+use std::collections::BTreeSet;
+use anyhow::Result;
+use crate::api::{self, AppModel};
+use crate::{local, store};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Create(AppModel),       // New locally: push, then adopt the backend's id
+    Update(AppModel),       // Edited locally: push
+    Delete(String),         // Deleted locally: delete on the backend
+    Pull(AppModel),         // New or edited on the backend: copy into the catalog
+    Drop(String),           // Deleted on the backend: remove from the catalog
+    Conflict { id: String, name: String, reason: &'static str },
+}
+
+fn find<'a>(apps: &'a [AppModel], id: &str) -> Option<&'a AppModel> { apps.iter().find(|a| a.id == id) }
+
+/// What it takes to bring `local` and `remote` back in line, given the
+/// `base` they shared after the last sync.
+pub fn plan(base: &[AppModel], local: &[AppModel], remote: &[AppModel], prefer_local: bool) -> Vec<Step> {
+    let ids: BTreeSet<&str> = base.iter().chain(local).chain(remote).map(|a| a.id.as_str()).collect();
+    let mut steps = vec![];
+    for id in ids {
+        let conflict = |name: &str, reason| Step::Conflict { id: id.to_string(), name: name.to_string(), reason };
+        match (find(base, id), find(local, id), find(remote, id)) {
+            // Created on both sides since the last sync: the same id by coincidence.
+            (None, Some(l), Some(r)) if l != r => { steps.push(Step::Create(l.clone())); steps.push(Step::Pull(r.clone())); },
+            (None, Some(_), Some(_)) | (None, None, None) | (Some(_), None, None) => {},
+            (None, Some(l), None) => steps.push(Step::Create(l.clone())),
+            (None, None, Some(r)) => steps.push(Step::Pull(r.clone())),
+            (Some(b), Some(l), Some(r)) => {
+                if l == r || (l == b && r == b) { continue; }
+                if r == b { steps.push(Step::Update(l.clone())); }
+                else if l == b { steps.push(Step::Pull(r.clone())); }
+                else if prefer_local { steps.push(Step::Update(l.clone())); }
+                else { steps.push(conflict(&l.name, "edited on both sides")); }
+            },
+            (Some(b), None, Some(r)) => {
+                if r == b || prefer_local { steps.push(Step::Delete(id.to_string())); }
+                else { steps.push(conflict(&r.name, "deleted locally, edited on the backend")); }
+            },
+            (Some(b), Some(l), None) => {
+                if l == b { steps.push(Step::Drop(id.to_string())); }
+                else if prefer_local { steps.push(Step::Create(l.clone())); }
+                else { steps.push(conflict(&l.name, "edited locally, deleted on the backend")); }
+            },
+        }
+    }
+    steps
+}
+
+/// Runs a sync and returns a report for the Output pane. Steps that went
+/// through are recorded even if a later one fails, so a retry never pushes
+/// the same new app twice.
+pub async fn run(prefer_local: bool) -> Result<String> {
+    let mut remote = api::fetch_apps().await?;
+    remote.retain(|a| a.name != api::TEMP_APP_NAME);
+    let base: Vec<AppModel> = store::load(store::SYNC_BASE);
+    let mut apps = local::load_apps();
+    let steps = plan(&base, &apps, &remote, prefer_local);
+
+    let mut conflicts: BTreeSet<String> = BTreeSet::new();
+    let (mut pushed, mut pulled, mut report) = (0, 0, vec![]);
+    let mut failed = None;
+    for step in steps {
+        let res = match step {
+            Step::Create(app) => api::create_app(&AppModel { id: String::new(), ..app.clone() }).await.map(|created| {
+                if let Some(mine) = apps.iter_mut().find(|a| **a == app) { *mine = created; }
+                pushed += 1;
+            }),
+            Step::Update(app) => api::update_app(&app).await.map(|_| pushed += 1),
+            Step::Delete(id) => api::delete_app(&id).await.map(|_| pushed += 1),
+            Step::Pull(app) => {
+                match apps.iter_mut().find(|a| a.id == app.id) { Some(mine) => *mine = app, None => apps.push(app) }
+                pulled += 1;
+                Ok(())
+            },
+            Step::Drop(id) => { apps.retain(|a| a.id != id); pulled += 1; Ok(()) },
+            Step::Conflict { id, name, reason } => {
+                report.push(format!("Conflict: '{}' {}; left as is.", name, reason));
+                conflicts.insert(id);
+                Ok(())
+            },
+        };
+        if let Err(e) = res { failed = Some(e); break; }
+    }
+
+    // Agreed state: everything but conflicts, which keep their old base so
+    // they are detected again until resolved.
+    let mut new_base: Vec<AppModel> = apps.iter().filter(|a| !conflicts.contains(&a.id)).cloned().collect();
+    new_base.extend(base.into_iter().filter(|a| conflicts.contains(&a.id)));
+    local::replace_apps(&apps)?;
+    if failed.is_none() { store::save(store::SYNC_BASE, &new_base)?; }
+
+    report.insert(0, format!("Synced apps: {} pushed, {} pulled, {} conflicts.", pushed, pulled, conflicts.len()));
+    if let Some(e) = failed { report.push(format!("Sync stopped early: {}", e)); }
+    Ok(report.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str, cmd: &str) -> AppModel { AppModel { id: id.into(), name: format!("app{}", id), command: cmd.into(), ..Default::default() } }
+
+    #[test]
+    fn plans_against_last_synced_state() {
+        let base = [app("1", "a"), app("2", "b"), app("3", "c"), app("4", "d"), app("5", "e")];
+        let local = [app("1", "a"), app("2", "b2"), app("3", "c"), app("4", "d-mine"), app("9", "new")];
+        let remote = [app("1", "a"), app("2", "b"), app("3", "c3"), app("4", "d-theirs"), app("5", "e"), app("6", "theirs")];
+        assert_eq!(plan(&base, &local, &remote, false), vec![
+            Step::Update(app("2", "b2")),
+            Step::Pull(app("3", "c3")),
+            Step::Conflict { id: "4".into(), name: "app4".into(), reason: "edited on both sides" },
+            Step::Delete("5".into()),
+            Step::Pull(app("6", "theirs")),
+            Step::Create(app("9", "new")),
+        ]);
+        assert!(plan(&base, &local, &remote, true).contains(&Step::Update(app("4", "d-mine"))));
+    }
+
+    #[test]
+    fn plans_deletions_and_id_collisions() {
+        let base = [app("1", "a"), app("2", "b")];
+        let local = [app("2", "b-edited"), app("3", "mine")];
+        let remote = [app("1", "a-edited"), app("3", "theirs")];
+        assert_eq!(plan(&base, &local, &remote, false), vec![
+            Step::Conflict { id: "1".into(), name: "app1".into(), reason: "deleted locally, edited on the backend" },
+            Step::Conflict { id: "2".into(), name: "app2".into(), reason: "edited locally, deleted on the backend" },
+            Step::Create(app("3", "mine")),
+            Step::Pull(app("3", "theirs")),
+        ]);
+        assert_eq!(plan(&base, &[app("2", "b")], &[app("1", "a")], false), vec![Step::Delete("1".into()), Step::Drop("2".into())]);
+    }
+}
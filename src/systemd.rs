@@ -0,0 +1,82 @@
+// ================================================
+// FILE: src/systemd.rs
+// ================================================
+// systemd user services as launcher apps (`--systemd`). The units from
+// `systemctl --user list-units` are listed under ids like
+// `systemd:syncthing.service` with their active state; launching one
+// restarts it (which also starts a stopped unit) and follows its journal in
+// the Output pane until `x` stops the unit.
+use anyhow::{anyhow, Result};
+use tokio::process::Command;
+use crate::api::AppModel;
+
+pub const PREFIX: &str = "systemd:";
+/// Journal lines shown by `L`, and before the follow after a restart.
+pub const LOG_TAIL: u32 = 200;
+const FOLLOW_TAIL: u32 = 20;
+
+/// The unit name behind an app id.
+pub fn unit(id: &str) -> &str { id.strip_prefix(PREFIX).unwrap_or(id) }
+
+/// `list-units --plain --no-legend` lines (UNIT LOAD ACTIVE SUB DESCRIPTION)
+/// as apps and their state: the sub-state while active (`running`,
+/// `exited`), else the active state (`failed`, `inactive`).
+pub fn parse_units(out: &str) -> Vec<(AppModel, String)> {
+    out.lines().filter_map(|line| {
+        // Failed units are flagged with a leading bullet on some versions.
+        let mut cols = line.split_whitespace().skip_while(|c| *c == "●" || *c == "*");
+        let (unit, _load, active, sub) = (cols.next()?, cols.next()?, cols.next()?, cols.next()?);
+        if !unit.ends_with(".service") { return None; }
+        let description = cols.collect::<Vec<_>>().join(" ");
+        let state = if active == "active" { sub } else { active };
+        let app = AppModel {
+            id: format!("{}{}", PREFIX, unit),
+            name: unit.trim_end_matches(".service").to_string(),
+            description: Some(format!("{} #systemd", description).trim_start().to_string()),
+            command: format!("systemctl --user restart {}", unit),
+            ..Default::default()
+        };
+        Some((app, state.to_string()))
+    }).collect()
+}
+
+/// The user's service units, loaded ones only (running or not).
+pub async fn list() -> Result<Vec<(AppModel, String)>> {
+    let out = Command::new("systemctl").args(["--user", "list-units", "--type=service", "--all", "--plain", "--no-legend", "--no-pager"]).output().await
+        .map_err(|e| anyhow!("could not run systemctl: {}", e))?;
+    if !out.status.success() { return Err(anyhow!("{}", String::from_utf8_lossy(&out.stderr).trim())); }
+    Ok(parse_units(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Shell command for `verb` (start, stop, logs) on the unit behind `id`.
+pub fn command(verb: &str, id: &str) -> String {
+    let unit = quote(unit(id));
+    match verb {
+        "start" => format!("systemctl --user restart {0} && journalctl --user -u {0} -f -n {1} --no-pager -o cat", unit, FOLLOW_TAIL),
+        "logs" => format!("journalctl --user -u {} -n {} --no-pager -o cat", unit, LOG_TAIL),
+        verb => format!("systemctl --user {} {}", verb, unit),
+    }
+}
+
+/// Unit names may hold `\x2d`-style escapes; keep the shell away from them.
+fn quote(s: &str) -> String { format!("'{}'", s.replace('\'', r"'\''")) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_units_with_state() {
+        let out = "syncthing.service loaded active running Syncthing - Open Source Continuous File Synchronization\n\
+                   ● backup.service loaded failed failed Nightly backup\n\
+                   dbus.socket loaded active running D-Bus User Message Bus Socket\n\
+                   setup.service loaded active exited\n";
+        let units = parse_units(out);
+        assert_eq!(units.iter().map(|(a, s)| (a.name.as_str(), s.as_str())).collect::<Vec<_>>(), [("syncthing", "running"), ("backup", "failed"), ("setup", "exited")]);
+        assert_eq!(units[1].0.id, "systemd:backup.service");
+        assert_eq!(units[1].0.description.as_deref(), Some("Nightly backup #systemd"));
+        assert_eq!(units[2].0.description.as_deref(), Some("#systemd"));
+        assert_eq!(command("stop", &units[1].0.id), "systemctl --user stop 'backup.service'");
+        assert!(command("start", &units[0].0.id).starts_with("systemctl --user restart 'syncthing.service' && journalctl --user -u 'syncthing.service' -f"));
+    }
+}
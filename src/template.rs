@@ -0,0 +1,53 @@
+// ================================================
+// FILE: src/template.rs
+// ================================================
+// `{{name}}` placeholders in app commands (`git checkout {{branch}}`). The
+// launcher asks for each value before running; the last values used per app
+// are remembered in store::ARGS and offered as defaults next time.
+use std::collections::BTreeMap;
+
+/// Placeholder names in order of first appearance.
+pub fn placeholders(cmd: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    let mut rest = cmd;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        let name = rest[start + 2..start + 2 + len].trim();
+        if !name.is_empty() && !name.contains('{') && !names.iter().any(|n| n == name) { names.push(name.to_string()); }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    names
+}
+
+/// `cmd` with every known placeholder replaced. Values are inserted as typed
+/// (no shell quoting), so one placeholder can stand for several arguments.
+pub fn fill(cmd: &str, values: &BTreeMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = cmd;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        out.push_str(&rest[..start]);
+        let raw = &rest[start..start + 2 + len + 2];
+        match values.get(rest[start + 2..start + 2 + len].trim()) {
+            Some(v) => out.push_str(v),
+            None => out.push_str(raw),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_and_fills_placeholders() {
+        let cmd = "git checkout {{ branch }} && PORT={{port}} npm start -- --port {{port}} {{}} {{oops";
+        assert_eq!(placeholders(cmd), ["branch", "port"]);
+        let values = BTreeMap::from([("branch".to_string(), "main".to_string()), ("port".to_string(), "8080".to_string())]);
+        assert_eq!(fill(cmd, &values), "git checkout main && PORT=8080 npm start -- --port 8080 {{}} {{oops");
+        assert!(placeholders("echo ${HOME} {x}").is_empty());
+    }
+}
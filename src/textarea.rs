@@ -0,0 +1,178 @@
+// ================================================
+// FILE: src/textarea.rs
+// ================================================
+// A small multi-line text buffer with a cursor, for the editor's command
+// field. Wrapping is done here rather than by ratatui so the UI knows where
+// the cursor ends up on screen. The cursor moves by grapheme (an emoji or a
+// letter with its accents is one step) and columns are display columns, so
+// CJK text and emoji, two columns wide, line up; the helpers below do the
+// same for the single-line inputs.
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns `s` takes on screen.
+pub fn width(s: &str) -> usize { s.width() }
+
+/// The end of `s` that fits in `cols` columns, cut between graphemes.
+pub fn tail(s: &str, cols: usize) -> &str {
+    let mut used = 0;
+    for (i, g) in s.grapheme_indices(true).rev() {
+        used += g.width();
+        if used > cols { return &s[i + g.len()..]; }
+    }
+    s
+}
+
+/// Removes the last grapheme of `s`, as Backspace does in a single-line input.
+pub fn pop_grapheme(s: &mut String) {
+    if let Some((i, _)) = s.grapheme_indices(true).next_back() { s.truncate(i); }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorMove { Left, Right, Up, Down, Home, End }
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextArea {
+    pub text: String,
+    /// Byte offset into `text`, always on a char boundary.
+    pub cursor: usize,
+}
+
+impl From<&str> for TextArea {
+    fn from(text: &str) -> Self { TextArea::new(text.to_string()) }
+}
+
+impl TextArea {
+    /// `text` with the cursor at its end.
+    pub fn new(text: String) -> Self { TextArea { cursor: text.len(), text } }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(g) = self.text[..self.cursor].graphemes(true).next_back() {
+            self.cursor -= g.len();
+            self.text.replace_range(self.cursor..self.cursor + g.len(), "");
+        }
+    }
+
+    fn line_start(&self, at: usize) -> usize { self.text[..at].rfind('\n').map_or(0, |i| i + 1) }
+    fn line_end(&self, at: usize) -> usize { self.text[at..].find('\n').map_or(self.text.len(), |i| at + i) }
+
+    /// Offset of the first grapheme reaching past column `col` of the line
+    /// starting at `start`, clamped to its end.
+    fn at_column(&self, start: usize, col: usize) -> usize {
+        let end = self.line_end(start);
+        let mut used = 0;
+        for (i, g) in self.text[start..end].grapheme_indices(true) {
+            used += g.width();
+            if used > col { return start + i; }
+        }
+        end
+    }
+
+    pub fn move_cursor(&mut self, m: CursorMove) {
+        let start = self.line_start(self.cursor);
+        let col = width(&self.text[start..self.cursor]);
+        self.cursor = match m {
+            CursorMove::Left => self.text[..self.cursor].graphemes(true).next_back().map_or(0, |g| self.cursor - g.len()),
+            CursorMove::Right => self.text[self.cursor..].graphemes(true).next().map_or(self.cursor, |g| self.cursor + g.len()),
+            CursorMove::Home => start,
+            CursorMove::End => self.line_end(self.cursor),
+            CursorMove::Up if start == 0 => 0,
+            CursorMove::Up => self.at_column(self.line_start(start - 1), col),
+            CursorMove::Down => match self.text[self.cursor..].find('\n') {
+                Some(i) => self.at_column(self.cursor + i + 1, col),
+                None => self.text.len(),
+            },
+        };
+    }
+
+    /// The text hard-wrapped at `cols` columns, and the cursor's (row, col)
+    /// in it. A grapheme that doesn't fit starts the next row, and a cursor
+    /// right after a full row goes to the start of the next.
+    pub fn wrap(&self, cols: usize) -> (Vec<String>, (usize, usize)) {
+        let cols = cols.max(1);
+        let (mut rows, mut pos) = (vec![], (0, 0));
+        let mut offset = 0;
+        for line in self.text.split('\n') {
+            let (mut row, mut used) = (String::new(), 0);
+            for (i, g) in line.grapheme_indices(true) {
+                let w = g.width();
+                if used + w > cols && used > 0 { rows.push(std::mem::take(&mut row)); used = 0; }
+                if offset + i == self.cursor { pos = (rows.len(), used); }
+                row.push_str(g);
+                used += w;
+            }
+            if self.cursor == offset + line.len() {
+                if used >= cols { rows.push(std::mem::take(&mut row)); used = 0; }
+                pos = (rows.len(), used);
+            }
+            rows.push(row);
+            offset += line.len() + 1;
+        }
+        (rows, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_and_moves_across_lines() {
+        let mut t = TextArea::from("cd app");
+        t.insert('\n');
+        t.insert_str("PORT=1 npm start");
+        t.move_cursor(CursorMove::Up);
+        assert_eq!(t.cursor, "cd app".len(), "column clamps to the shorter line");
+        t.move_cursor(CursorMove::Home);
+        t.move_cursor(CursorMove::Down);
+        assert_eq!(&t.text[t.cursor..], "PORT=1 npm start");
+        t.move_cursor(CursorMove::End);
+        t.backspace();
+        t.move_cursor(CursorMove::Left);
+        t.insert('é');
+        assert_eq!(t.text, "cd app\nPORT=1 npm staér");
+        t.move_cursor(CursorMove::Right);
+        t.move_cursor(CursorMove::Right);
+        assert_eq!(t.cursor, t.text.len());
+    }
+
+    #[test]
+    fn wraps_with_cursor_position() {
+        let mut t = TextArea::from("abcdef\n\nxy");
+        assert_eq!(t.wrap(4), (vec!["abcd".into(), "ef".into(), "".into(), "xy".into()], (3, 2)));
+        t.cursor = 5;
+        assert_eq!(t.wrap(4).1, (1, 1));
+        let t = TextArea::from("abcd");
+        assert_eq!(t.wrap(4), (vec!["abcd".into(), "".into()], (1, 0)));
+    }
+
+    #[test]
+    fn wide_text_and_emoji_line_up() {
+        let mut t = TextArea::from("日本語ab");
+        assert_eq!(t.wrap(4), (vec!["日本".into(), "語ab".into(), "".into()], (2, 0)));
+        t.move_cursor(CursorMove::Home);
+        t.move_cursor(CursorMove::Right);
+        assert_eq!(t.wrap(4).1, (0, 2), "one step over a two-column character");
+        let mut t = TextArea::from("ok 👍🏽\nxyz");
+        t.move_cursor(CursorMove::Up);
+        assert_eq!(&t.text[..t.cursor], "ok ", "column 3 is where the emoji starts");
+        t.move_cursor(CursorMove::End);
+        t.backspace();
+        assert_eq!(t.text, "ok \nxyz", "the emoji and its skin tone go together");
+        let mut s = String::from("é👨‍👩‍👧");
+        pop_grapheme(&mut s);
+        assert_eq!(s, "é");
+        assert_eq!((width("日本"), tail("ab日本", 5), tail("ab日本", 3)), (4, "b日本", "本"));
+    }
+}
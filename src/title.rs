@@ -0,0 +1,65 @@
+// ================================================
+// FILE: src/title.rs
+// ================================================
+// Short titles for new conversations. A conversation is created under its
+// first query; once the first answer is in, the model is asked for a few
+// words to replace it with (unless the backend named it already). Prompt
+// building and cleanup are pure so effects.rs only has to stream the reply.
+use crate::app::ChatMessage;
+
+/// Characters kept of a title, in case the model ignores the word limit.
+pub const MAX_CHARS: usize = 60;
+
+/// Characters of the answer sent along with the question.
+const MAX_ANSWER: usize = 2_000;
+
+/// The request for a title for the first question and its answer.
+pub fn prompt(messages: &[ChatMessage]) -> Option<String> {
+    let question = messages.iter().find(|m| m.role == "user")?;
+    let answer = messages.iter().rfind(|m| m.role == "assistant" && !m.content.trim().is_empty())?;
+    let mut text = answer.content.trim().to_string();
+    if text.len() > MAX_ANSWER {
+        let mut cut = MAX_ANSWER;
+        while !text.is_char_boundary(cut) { cut -= 1; }
+        text.truncate(cut);
+    }
+    Some(format!(
+        "Write a title of at most six words for the conversation below. \
+         Reply with the title only, without quotes or a trailing period.\n\n---\n\nUser: {}\n\nAssistant: {}",
+        question.question().trim(), text
+    ))
+}
+
+/// The title in the model's reply: its first non-empty line, with any
+/// `Title:` label, Markdown heading, quotes and trailing period removed.
+pub fn clean(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line.trim_start_matches('#').trim();
+    let line = line.strip_prefix("Title:").or_else(|| line.strip_prefix("title:")).unwrap_or(line).trim();
+    let line = line.trim_matches(|c: char| matches!(c, '"' | '\'' | '*' | '`' | '“' | '”')).trim_end_matches('.').trim();
+    if line.is_empty() { return None; }
+    Some(match line.char_indices().nth(MAX_CHARS) {
+        Some((cut, _)) => format!("{}…", line[..cut].trim_end()),
+        None => line.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleans_the_reply() {
+        assert_eq!(clean("\n\"Rust Ownership Basics.\"\nHope that helps!").as_deref(), Some("Rust Ownership Basics"));
+        assert_eq!(clean("## Title: **Borrow checker**").as_deref(), Some("Borrow checker"));
+        assert_eq!(clean("  \n"), None);
+        assert!(clean(&"word ".repeat(40)).unwrap().ends_with('…'));
+    }
+
+    #[test]
+    fn prompt_uses_the_first_question() {
+        let msgs = [ChatMessage::new("system", "New conversation started."), ChatMessage::new("user", "what is rust"), ChatMessage::new("assistant", "A language.")];
+        assert!(prompt(&msgs).unwrap().ends_with("User: what is rust\n\nAssistant: A language."));
+        assert_eq!(prompt(&msgs[..2]), None);
+    }
+}
@@ -0,0 +1,68 @@
+// ================================================
+// FILE: src/tmux.rs
+// ================================================
+// Interactive launches: an app set to a tmux mode (store::TMUX, `M` cycles
+// it) is started in a new tmux window, a split pane or a detached session
+// instead of having its output captured, so apps that are TUIs themselves
+// get a real terminal. Windows and panes need bpt to run inside tmux; outside
+// it every mode starts a detached session to attach to later.
+use serde::{Deserialize, Serialize};
+use crate::api::LaunchEnv;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TmuxMode { Window, Pane, Session }
+
+impl TmuxMode {
+    /// Off, window, pane, session, off, ...
+    pub fn cycle(mode: Option<TmuxMode>) -> Option<TmuxMode> {
+        match mode {
+            None => Some(TmuxMode::Window),
+            Some(TmuxMode::Window) => Some(TmuxMode::Pane),
+            Some(TmuxMode::Pane) => Some(TmuxMode::Session),
+            Some(TmuxMode::Session) => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self { TmuxMode::Window => "window", TmuxMode::Pane => "pane", TmuxMode::Session => "session" }
+    }
+}
+
+/// `tmux` arguments that start `cmd` for app `name` (launch `seq`), and what
+/// gets started. `-P` makes tmux print the new target, which is logged.
+pub fn args(mode: TmuxMode, inside_tmux: bool, name: &str, seq: u64, cmd: &str, env: &LaunchEnv) -> (Vec<String>, &'static str) {
+    let mut args: Vec<String> = match mode {
+        TmuxMode::Window if inside_tmux => vec!["new-window".into(), "-P".into(), "-F".into(), "#{session_name}:#{window_index}".into(), "-n".into(), name.into()],
+        TmuxMode::Pane if inside_tmux => vec!["split-window".into(), "-P".into(), "-F".into(), "#{session_name}:#{window_index}.#{pane_index}".into()],
+        _ => vec!["new-session".into(), "-d".into(), "-P".into(), "-F".into(), "#{session_name}".into(), "-s".into(), session_name(name, seq)],
+    };
+    if let Some(dir) = &env.cwd { args.extend(["-c".to_string(), crate::commands::resolve_path(None, dir).display().to_string()]); }
+    for (k, v) in &env.env { args.extend(["-e".to_string(), format!("{}={}", k, v)]); }
+    args.push(cmd.to_string());
+    let what = match mode { TmuxMode::Window | TmuxMode::Pane if inside_tmux => mode.label(), _ => "session" };
+    (args, what)
+}
+
+/// `bpt-<name>-<seq>`, without the `.` and `:` tmux reserves for targets.
+fn session_name(name: &str, seq: u64) -> String {
+    let slug: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' }).collect();
+    let slug = slug.split('-').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("-");
+    format!("bpt-{}-{}", if slug.is_empty() { "app" } else { &slug }, seq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_tmux_arguments() {
+        let env = LaunchEnv { env: [("TERM".to_string(), "xterm-256color".to_string())].into(), cwd: Some("/srv".into()) };
+        let (args, what) = args(TmuxMode::Window, true, "htop", 3, "htop -d 5", &env);
+        assert_eq!(what, "window");
+        assert_eq!(args, ["new-window", "-P", "-F", "#{session_name}:#{window_index}", "-n", "htop", "-c", "/srv", "-e", "TERM=xterm-256color", "htop -d 5"]);
+        let (args, what) = super::args(TmuxMode::Pane, false, "My App: v2", 7, "x", &LaunchEnv::default());
+        assert_eq!((what, args[6].as_str()), ("session", "bpt-my-app-v2-7"), "outside tmux a session is started");
+        assert_eq!(TmuxMode::cycle(Some(TmuxMode::Session)), None);
+    }
+}
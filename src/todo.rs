@@ -0,0 +1,116 @@
+// ================================================
+// FILE: src/todo.rs
+// ================================================
+// Wrap-up for a conversation: the model is asked for its action items and
+// the answer is appended to a TODO file as a markdown checklist instead of
+// being added to the chat. Prompt building and line cleanup are pure so the
+// streaming side in effects.rs only has to write lines.
+use std::path::PathBuf;
+use crate::app::ChatMessage;
+use crate::store;
+
+/// Characters of transcript sent along; older messages are dropped first.
+const MAX_TRANSCRIPT: usize = 24_000;
+
+/// `$BPT_TODO_FILE`, else `todo.md` in the data directory.
+pub fn default_path() -> PathBuf {
+    match std::env::var_os("BPT_TODO_FILE") {
+        Some(path) => PathBuf::from(path),
+        None => store::path("todo.md"),
+    }
+}
+
+/// The user and assistant turns as `User: ...` / `Assistant: ...`
+/// paragraphs, without system notes; also used by summary.rs.
+pub fn transcript(messages: &[ChatMessage]) -> String {
+    let mut turns: Vec<String> = messages.iter()
+        .filter(|m| matches!(m.role.as_str(), "user" | "assistant") && !m.content.trim().is_empty())
+        .map(|m| format!("{}: {}", if m.role == "user" { "User" } else { "Assistant" }, m.content.trim()))
+        .collect();
+    let mut len: usize = turns.iter().map(|t| t.len() + 2).sum();
+    while len > MAX_TRANSCRIPT && turns.len() > 1 { len -= turns.remove(0).len() + 2; }
+    turns.join("\n\n")
+}
+
+/// The extraction request for a conversation.
+pub fn prompt(messages: &[ChatMessage]) -> String {
+    format!(
+        "List the action items from the conversation below: tasks, follow-ups and decisions someone still has to act on. \
+         Reply with a markdown checklist only, one `- [ ] ` item per line, each short and self-contained. \
+         Reply with `NONE` if there are no action items.\n\n---\n\n{}",
+        transcript(messages)
+    )
+}
+
+/// One line of the model's answer as a checklist item, or `None` for
+/// anything that isn't an item (preamble, blank lines, `NONE`).
+pub fn checklist_line(line: &str) -> Option<String> {
+    let line = line.trim();
+    let (done, rest) = if let Some(r) = ["- [ ]", "* [ ]", "[ ]"].iter().find_map(|p| line.strip_prefix(p)) { (false, r) }
+        else if let Some(r) = ["- [x]", "- [X]", "* [x]", "[x]"].iter().find_map(|p| line.strip_prefix(p)) { (true, r) }
+        else if let Some(r) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).or_else(|| line.strip_prefix("• ")) { (false, r) }
+        else {
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match line[digits..].strip_prefix(". ").or_else(|| line[digits..].strip_prefix(") ")) {
+                Some(r) if digits > 0 => (false, r),
+                _ => return None,
+            }
+        };
+    let text = rest.trim();
+    if text.is_empty() { return None; }
+    Some(format!("- [{}] {}", if done { "x" } else { " " }, text))
+}
+
+/// `2024-05-01` for a Unix timestamp (UTC).
+pub fn date(secs: u64) -> String {
+    // Days-to-civil, after Howard Hinnant's algorithm.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Heading written above each extraction.
+pub fn heading(title: &str, secs: u64) -> String {
+    format!("## {} ({})\n\n", if title.trim().is_empty() { "Conversation" } else { title.trim() }, date(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_answer_lines_to_checklist_items() {
+        assert_eq!(checklist_line("- [ ] Ship the fix").as_deref(), Some("- [ ] Ship the fix"));
+        assert_eq!(checklist_line("  * rotate keys ").as_deref(), Some("- [ ] rotate keys"));
+        assert_eq!(checklist_line("12. Email Sam").as_deref(), Some("- [ ] Email Sam"));
+        assert_eq!(checklist_line("- [x] done already").as_deref(), Some("- [x] done already"));
+        assert_eq!(checklist_line("Here are the action items:"), None);
+        assert_eq!(checklist_line("NONE"), None);
+        assert_eq!(checklist_line("2024 was busy"), None);
+        assert_eq!(checklist_line("- "), None);
+    }
+
+    #[test]
+    fn prompt_keeps_recent_turns_and_skips_system_notes() {
+        let msgs = [ChatMessage::new("user", "deploy?"), ChatMessage::new("system", "Attached x"), ChatMessage::new("assistant", "Run make deploy")];
+        let p = prompt(&msgs);
+        assert!(p.ends_with("User: deploy?\n\nAssistant: Run make deploy"));
+        assert!(!p.contains("Attached"));
+        let long = vec![ChatMessage::new("user", "x".repeat(MAX_TRANSCRIPT)), ChatMessage::new("user", "latest")];
+        assert!(prompt(&long).ends_with("---\n\nUser: latest"));
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(1_714_521_600), "2024-05-01");
+        assert_eq!(heading(" ", 951_782_400), "## Conversation (2000-02-29)\n\n");
+    }
+}
@@ -0,0 +1,86 @@
+// ================================================
+// FILE: src/tokens.rs
+// ================================================
+// Token counts for the chat: the usage the backend reports with an answer,
+// a rough estimate where it reports none, and the context window of common
+// models so the Message box can warn before a conversation outgrows it.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Tokens one answer took, as reported by the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt: u64,
+    pub completion: u64,
+}
+
+impl Usage {
+    pub fn total(&self) -> u64 { self.prompt + self.completion }
+}
+
+/// The gauge turns yellow at this share of the context window, red at DANGER_PERCENT.
+pub const WARN_PERCENT: u64 = 80;
+pub const DANGER_PERCENT: u64 = 95;
+
+/// Usage from an event payload: OpenAI (`prompt_tokens`), Anthropic
+/// (`input_tokens`) or camelCase names, at the top level or under `usage`.
+pub fn parse_usage(v: &Value) -> Option<Usage> {
+    let v = if v["usage"].is_object() { &v["usage"] } else { v };
+    let count = |keys: &[&str]| keys.iter().find_map(|k| v[*k].as_u64());
+    let prompt = count(&["prompt_tokens", "input_tokens", "promptTokens", "inputTokens"]);
+    let completion = count(&["completion_tokens", "output_tokens", "completionTokens", "outputTokens"]);
+    if prompt.is_none() && completion.is_none() { return None; }
+    Some(Usage { prompt: prompt.unwrap_or(0), completion: completion.unwrap_or(0) })
+}
+
+/// About four characters per token, which holds well enough for English
+/// prose and code across the common tokenizers.
+pub fn estimate(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// Context window of `model` by family, where it is well known.
+pub fn context_limit(model: &str) -> Option<u64> {
+    const LIMITS: &[(&str, u64)] = &[
+        ("gpt-4.1", 1_047_576), ("gpt-4o", 128_000), ("gpt-4-turbo", 128_000), ("gpt-4", 8_192), ("gpt-3.5", 16_385),
+        ("o1", 200_000), ("o3", 200_000), ("o4", 200_000), ("claude", 200_000),
+        ("gemini-1.5", 1_048_576), ("gemini-2", 1_048_576), ("gemini", 32_768),
+        ("llama-3.1", 128_000), ("llama3.1", 128_000), ("llama-3.2", 128_000), ("llama3.2", 128_000), ("llama-3", 8_192), ("llama3", 8_192),
+        ("mistral", 32_768), ("mixtral", 32_768), ("qwen", 32_768), ("deepseek", 64_000), ("phi-3", 4_096), ("gemma", 8_192),
+    ];
+    // Provider prefixes (`openai/gpt-4o`) are ignored; the first matching family wins.
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    LIMITS.iter().find(|(family, _)| name.starts_with(family) || name.contains(&format!("-{}", family))).map(|(_, n)| *n)
+}
+
+/// `950`, `3.2k`, `128k`, `1M`.
+pub fn short(n: u64) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        1_000..=9_999 => format!("{:.1}k", n as f64 / 1000.0).replace(".0k", "k"),
+        10_000..=999_999 => format!("{}k", n / 1000),
+        _ => format!("{:.1}M", n as f64 / 1_000_000.0).replace(".0M", "M"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_usage_in_either_shape() {
+        assert_eq!(parse_usage(&json!({"usage": {"prompt_tokens": 120, "completion_tokens": 30}})), Some(Usage { prompt: 120, completion: 30 }));
+        assert_eq!(parse_usage(&json!({"input_tokens": 5})), Some(Usage { prompt: 5, completion: 0 }));
+        assert_eq!(parse_usage(&json!({"text": "done"})), None);
+    }
+
+    #[test]
+    fn knows_common_context_windows() {
+        assert_eq!(context_limit("openai/gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_limit("gpt-4-0613"), Some(8_192));
+        assert_eq!(context_limit("meta-llama-3.1-8b-instruct"), Some(128_000));
+        assert_eq!(context_limit("my-finetune"), None);
+        assert_eq!((short(950), short(3_240), short(128_000), short(1_048_576)), ("950".into(), "3.2k".into(), "128k".into(), "1M".into()));
+    }
+}
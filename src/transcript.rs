@@ -0,0 +1,56 @@
+// ================================================
+// FILE: src/transcript.rs
+// ================================================
+// The conversation as plain Markdown, printed to stdout once the alternate
+// screen is gone (`--dump-on-exit` or `/dump`) so a session can be piped into
+// a file or another program. Only the exchange itself is kept: questions,
+// answers and their sources, without the notes bpt adds to the chat.
+use crate::app::ChatMessage;
+
+pub fn markdown(title: Option<&str>, messages: &[ChatMessage]) -> String {
+    let mut out = String::new();
+    if let Some(title) = title.map(str::trim).filter(|t| !t.is_empty()) { out.push_str(&format!("# {}\n\n", title)); }
+    for m in messages.iter().filter(|m| !m.content.trim().is_empty()) {
+        match m.role.as_str() {
+            "user" => {
+                out.push_str(&format!("## You\n\n{}\n\n", m.question().trim()));
+                for c in &m.context { out.push_str(&format!("_Attached: `{}`_\n\n", c.label)); }
+            },
+            "assistant" => {
+                match &m.model {
+                    Some(model) => out.push_str(&format!("## Assistant ({})\n\n", model)),
+                    None => out.push_str("## Assistant\n\n"),
+                }
+                out.push_str(m.content.trim());
+                out.push_str("\n\n");
+                if !m.sources.is_empty() {
+                    out.push_str("Sources:\n");
+                    for (i, s) in m.sources.iter().enumerate() { out.push_str(&format!("{}. [{}]({})\n", i + 1, s.title, s.url)); }
+                    out.push('\n');
+                }
+            },
+            _ => {},
+        }
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SearchSource;
+    use crate::app::ContextItem;
+
+    #[test]
+    fn keeps_questions_answers_and_sources() {
+        let source = SearchSource { title: "Rust".into(), url: "https://rust-lang.org".into(), content: String::new(), engine: String::new() };
+        let messages = vec![
+            ChatMessage::new("system", "New conversation started."),
+            ChatMessage { context: vec![ContextItem { label: "a.rs".into(), content: "fn a() {}".into() }], ..ChatMessage::new("user", "What is this?\n\n_(with attached context)_") },
+            ChatMessage { model: Some("qwen".into()), sources: vec![source], ..ChatMessage::new("assistant", "A function.\n") },
+            ChatMessage::new("assistant", ""),
+        ];
+        assert_eq!(markdown(Some("Code"), &messages), "# Code\n\n## You\n\nWhat is this?\n\n_Attached: `a.rs`_\n\n## Assistant (qwen)\n\nA function.\n\nSources:\n1. [Rust](https://rust-lang.org)");
+        assert_eq!(markdown(None, &messages[..1]), "");
+    }
+}
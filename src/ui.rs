@@ -2,72 +2,203 @@
 // FILE: src/ui.rs
 // ================================================
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap, Tabs, Clear},
+    widgets::{block::Title, Block, Borders, List, ListItem, ListState, Paragraph, Wrap, Tabs, Clear},
     Frame,
 };
-use crate::app::{App, CurrentScreen, InputMode, SearchSidebarState};
+use crate::a11y;
+use crate::config::Setting;
+use crate::app::{app_tags, App, AppForm, ChainFocus, CurrentScreen, InputMode, SearchSidebarState, SourceForm, FOLDED_LINES};
+use crate::attachments;
+use crate::files::Pick;
+use crate::clock;
+use crate::logbuf::Severity;
+use crate::snippets;
+use crate::textarea;
+use crate::mentions;
+use crate::tokens;
 use pulldown_cmark::{Parser, Event, Tag};
+use unicode_width::UnicodeWidthChar;
+
+/// Below this width the Launcher stacks its panes and the Search sidebar
+/// and Sources pane overlay the chat; footer hints are cut at a whole hint.
+pub const NARROW_WIDTH: u16 = 60;
+/// Below this height the tab bar loses its border.
+pub const SHORT_HEIGHT: u16 = 14;
 
 pub fn draw(f: &mut Frame, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)])
-        .split(f.size());
+    if app.zen && app.current_screen == CurrentScreen::Search {
+        // Zen mode: the conversation and input get the whole terminal.
+        render_search(f, app, f.size());
+    } else {
+        let tabs_height = if f.size().height < SHORT_HEIGHT { 1 } else { 3 };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(tabs_height), Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
 
-    render_tabs(f, app, chunks[0]);
-    
-    match app.current_screen {
-        CurrentScreen::Launcher => render_launcher(f, app, chunks[1]),
-        CurrentScreen::Search => render_search(f, app, chunks[1]),
-    }
+        render_tabs(f, app, chunks[0]);
 
-    render_footer(f, app, chunks[2]);
+        match app.current_screen {
+            CurrentScreen::Launcher => render_launcher(f, app, chunks[1]),
+            CurrentScreen::Search => render_search(f, app, chunks[1]),
+            CurrentScreen::Processes => render_processes(f, app, chunks[1], crate::store::now_secs()),
+            CurrentScreen::Settings => render_settings(f, app, chunks[1]),
+        }
+
+        render_footer(f, app, chunks[2]);
+    }
 
     if app.input_mode == InputMode::Editing { render_edit_modal(f, app); }
     if app.input_mode == InputMode::AdHocCmd { render_adhoc_modal(f, app); }
+    if app.input_mode == InputMode::Confirm { render_confirm_modal(f, app); }
+    if app.input_mode == InputMode::TagBrowser { render_tag_browser(f, app); }
+    if app.input_mode == InputMode::LaunchHistory { render_launch_history(f, app, crate::store::now_secs()); }
+    if app.input_mode == InputMode::Palette { render_palette(f, app); }
+    if app.input_mode == InputMode::Preview { render_preview(f, app); }
+    if app.input_mode == InputMode::Args { render_args_prompt(f, app); }
+    if app.input_mode == InputMode::Chains { render_chains(f, app); }
+    if app.input_mode == InputMode::Presets { render_presets(f, app); }
+    if app.input_mode == InputMode::HistorySearch { render_history_search(f, app); }
+    if app.input_mode == InputMode::SourceEditor { render_source_editor(f, app); }
+    if app.file_browser.is_some() { render_file_browser(f, app); }
+    if app.code_picker.is_some() { render_code_picker(f, app); }
+    crate::colors::adapt(f.buffer_mut(), app.color_mode);
 }
 
 fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
-    let titles = vec![" [L]auncher ", " [S]earch "];
-    let idx = match app.current_screen { CurrentScreen::Launcher => 0, CurrentScreen::Search => 1 };
+    let idx = match app.current_screen { CurrentScreen::Launcher => 0, CurrentScreen::Search => 1, CurrentScreen::Processes => 2, CurrentScreen::Settings => 3 };
+    let titles: Vec<String> = [" [L]auncher ", " [S]earch ", " [P]rocesses ", " Settings "].iter().enumerate()
+        .map(|(i, t)| if app.a11y && i == idx { format!(">{}", &t[1..]) } else { t.to_string() }).collect();
     let tabs = Tabs::new(titles)
-        .block(Block::default().borders(Borders::ALL).title(" bplus-tui "))
         .select(idx)
         .highlight_style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD));
+    let tabs = if area.height < 3 { tabs } else { tabs.block(Block::default().borders(Borders::ALL).title(" bplus-tui ")) };
     f.render_widget(tabs, area);
 }
 
+/// A pane drawn over `area` along its left (or right) edge: `pct` percent
+/// of the width, but at least 30 columns where there are that many.
+fn overlay(area: Rect, pct: u16, right: bool) -> Rect {
+    let width = (area.width * pct / 100).max(30).min(area.width);
+    Rect { x: if right { area.x + area.width - width } else { area.x }, width, ..area }
+}
+
+/// `title`, marked as focused in accessibility mode when `on`.
+fn focus_title(app: &App, mut title: String, on: bool) -> String {
+    if app.a11y && on { title.push_str(a11y::FOCUSED); }
+    title
+}
+
+/// What the selected row of a list starts with: nothing but its highlight,
+/// unless in accessibility mode.
+fn selected_symbol(app: &App) -> &'static str { if app.a11y { a11y::SELECTED } else { "" } }
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 fn render_launcher(f: &mut Frame, app: &mut App, area: Rect) {
-    let chunks = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(40), Constraint::Percentage(60)]).split(area);
+    // Narrow terminals get the apps above their details and output.
+    let direction = if area.width < NARROW_WIDTH { Direction::Vertical } else { Direction::Horizontal };
+    let chunks = Layout::default().direction(direction).constraints([Constraint::Percentage(app.config.launcher_split), Constraint::Percentage(100 - app.config.launcher_split)]).split(area);
     let left_chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(3), Constraint::Min(0)]).split(chunks[0]);
     
     let filter_style = if app.input_mode == InputMode::Filtering { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::DarkGray) };
     let filter_text = if app.filter_input.is_empty() { if app.input_mode == InputMode::Filtering { "" } else { "Press '/' to filter" } } else { &app.filter_input };
-    f.render_widget(Paragraph::new(filter_text).style(filter_style).block(Block::default().borders(Borders::ALL).title(" Filter ")), left_chunks[0]);
+    let filter_title = if app.input_mode == InputMode::Filtering { " Filter · cmd: url: name: desc: " } else { " Filter " };
+    let filter_title = focus_title(app, filter_title.to_string(), app.input_mode == InputMode::Filtering);
+    f.render_widget(Paragraph::new(filter_text).style(filter_style).block(Block::default().borders(Borders::ALL).title(filter_title)), left_chunks[0]);
 
+    // Dots only once checks are on (or one was asked for), not a column of unknowns.
+    let show_health = app.health_poll_ticks > 0 || !app.url_health.is_empty();
     let items: Vec<ListItem> = app.filtered_apps.iter().map(|&idx| {
         let item = &app.apps[idx];
-        let tags = item.description.as_deref().unwrap_or("").split_whitespace().filter(|s| s.starts_with('#')).collect::<Vec<_>>().join(" ");
-        ListItem::new(vec![Line::from(Span::styled(&item.name, Style::default().add_modifier(Modifier::BOLD))), Line::from(Span::styled(tags, Style::default().fg(Color::DarkGray)))])
+        let tags = app_tags(item).join(" ");
+        let star = if app.favorites.contains(&item.id) { Span::styled("★ ", Style::default().fg(Color::Yellow)) } else { Span::raw("") };
+        let (mark, name_style) = if app.marked.contains(&item.id) { (Span::styled("● ", Style::default().fg(Color::Cyan)), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)) } else { (Span::raw(""), Style::default().add_modifier(Modifier::BOLD)) };
+        let health = match app.url_health.get(&item.id) {
+            _ if !show_health => Span::raw(""),
+            up if app.a11y => Span::raw(a11y::health(up.copied())),
+            Some(true) => Span::styled(" •", Style::default().fg(Color::Green)),
+            Some(false) => Span::styled(" •", Style::default().fg(Color::Red)),
+            None => Span::styled(" ·", Style::default().fg(Color::DarkGray)),
+        };
+        let state = match app.source_states.get(&item.id).map(String::as_str) {
+            None | Some("") => Span::raw(""),
+            Some(s @ ("running" | "active")) => Span::styled(format!(" {}", s), Style::default().fg(Color::Green)),
+            Some(s @ ("failed" | "dead" | "restarting")) => Span::styled(format!(" {}", s), Style::default().fg(Color::Red)),
+            Some(s) => Span::styled(format!(" {}", s), Style::default().fg(Color::DarkGray)),
+        };
+        ListItem::new(vec![Line::from(vec![mark, star, Span::styled(&item.name, name_style), health, state]), Line::from(Span::styled(tags, Style::default().fg(Color::DarkGray)))])
     }).collect();
     let mut state = ListState::default(); state.select(Some(app.apps_idx));
-    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Apps ")).highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White)), left_chunks[1], &mut state);
+    let mut apps_title = String::from(" Apps ");
+    if let Some(sort) = app.sort_mode.label() { apps_title.push_str(&format!("↕{} ", sort)); }
+    if app.favorites_only { apps_title.push_str("★ "); }
+    if !app.marked.is_empty() { apps_title.push_str(&format!("●{} ", app.marked.len())); }
+    if !app.selected_tags.is_empty() { apps_title.push_str(&format!("[{}] ", app.selected_tags.iter().cloned().collect::<Vec<_>>().join(" "))); }
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(apps_title)).highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White)).highlight_symbol(selected_symbol(app)), left_chunks[1], &mut state);
 
-    let right_chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(8), Constraint::Min(0)]).split(chunks[1]);
+    let right_chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(if direction == Direction::Vertical { 5 } else { 8 }), Constraint::Min(0)]).split(chunks[1]);
     let details = if let Some(a) = app.get_selected_app() {
-        vec![Line::from(format!("Name: {}", a.name)), Line::from(format!("Cmd : {}", a.command)), Line::from(format!("URL : {}", a.url)), Line::from(format!("Desc: {}", a.description.as_deref().unwrap_or("")))]
+        let mut lines = vec![Line::from(format!("Name: {}", a.name)), Line::from(format!("Cmd : {}{}", command_summary(&a.command), app.tmux_modes.get(&a.id).map_or(String::new(), |m| format!(" (in tmux {})", m.label())))), Line::from(format!("URL : {}{}", a.url, if app.auto_open_all || app.auto_open.contains(&a.id) { " (opens after launch)" } else { "" })), Line::from(format!("Desc: {}", a.description.as_deref().unwrap_or("")))];
+        if let Some(host) = &a.target { lines.push(Line::from(format!("Host: {}", host))); }
+        if let Some(dir) = &a.launch.cwd { lines.push(Line::from(format!("Dir : {}", dir))); }
+        if !a.launch.env.is_empty() { lines.push(Line::from(format!("Env : {}", a.launch.env.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(" ")))); }
+        lines
     } else { vec![Line::from("No app selected")] };
     f.render_widget(Paragraph::new(details).block(Block::default().borders(Borders::ALL).title(" Details ")), right_chunks[0]);
     
-    let log_start = if app.launcher_logs.len() > 15 { app.launcher_logs.len() - 15 } else { 0 };
-    let logs: Vec<ListItem> = app.launcher_logs[log_start..].iter().map(|l| ListItem::new(Line::from(l.as_str()))).collect();
-    f.render_widget(List::new(logs).block(Block::default().borders(Borders::ALL).title(" Output ")), right_chunks[1]);
+    let height = right_chunks[1].height.saturating_sub(2) as usize;
+    let logs = &app.launcher_logs;
+    let shown = app.output_lines();
+    // A view scrolled above the first screen shows the first screen.
+    if let Some(end) = app.output_end {
+        let top = shown.get(height.min(shown.len()).saturating_sub(1)).map_or(logs.first(), |n| n + 1);
+        app.output_end = Some(end.max(top)).filter(|e| *e < logs.total());
+    }
+    let end = shown.partition_point(|n| *n < app.output_end.unwrap_or(logs.total()));
+    let query = app.output_query.to_lowercase();
+    let items: Vec<ListItem> = shown[end.saturating_sub(height)..end].iter().map(|n| {
+        let i = logs.index_of(*n);
+        let l = logs.get(i).map_or("", String::as_str);
+        // The line's own ANSI colors win over its severity color.
+        let mut spans = crate::ansi::spans(l, app.ansi_colors);
+        if app.a11y { spans.insert(0, Span::raw(a11y::severity(logs.severity(i)))); }
+        let line = Line::from(spans).style(severity_style(logs.severity(i)));
+        // Search highlighting wins over both.
+        if app.output_match == Some(*n) { ListItem::new(line.patch_style(Style::default().fg(Color::Black).bg(Color::Yellow))) }
+        else if !query.is_empty() && crate::ansi::strip(l).to_lowercase().contains(&query) { ListItem::new(line.patch_style(Style::default().fg(Color::Yellow))) }
+        else { ListItem::new(line) }
+    }).collect();
+    let mut output_title = match app.running_launch_name() {
+        Some(name) if app.a11y => format!(" Output · running {} ", name),
+        Some(name) => format!(" Output {} running {} ", SPINNER[(app.tick_count as usize) % SPINNER.len()], name),
+        None => " Output ".to_string(),
+    };
+    if app.output_errors_only { output_title.push_str("[errors only] "); }
+    if app.output_end.is_some() { output_title.push_str(&format!("[{}/{}] ", end, shown.len())); }
+    if app.output_searching || !app.output_query.is_empty() {
+        let matches = app.output_matches();
+        let pos = app.output_match.and_then(|m| matches.iter().position(|x| *x == m)).map_or(String::new(), |p| format!(" {}/{}", p + 1, matches.len()));
+        output_title.push_str(&format!("/{}{}{} ", app.output_query, if app.output_searching { "_" } else { "" }, pos));
+    }
+    let border = if app.input_mode == InputMode::Output { Style::default().fg(Color::Yellow) } else { Style::default() };
+    let output_title = focus_title(app, output_title, app.input_mode == InputMode::Output);
+    f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(output_title).border_style(border)), right_chunks[1]);
 }
 
-fn markdown_to_text<'a>(markdown: &str) -> Vec<Line<'a>> {
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::Info => Style::default(),
+        Severity::Warning => Style::default().fg(Color::Yellow),
+        Severity::Stderr => Style::default().fg(Color::LightMagenta),
+        Severity::Error => Style::default().fg(Color::Red),
+    }
+}
+
+pub fn markdown_to_text<'a>(markdown: &str) -> Vec<Line<'a>> {
     let parser = Parser::new(markdown);
     let mut lines = Vec::new();
     let mut current_line = Vec::new();
@@ -122,58 +253,97 @@ fn markdown_to_text<'a>(markdown: &str) -> Vec<Line<'a>> {
 }
 
 fn render_search(f: &mut Frame, app: &mut App, area: Rect) {
+    let sidebar = if app.zen { SearchSidebarState::Hidden } else { app.search_sidebar.clone() };
+    // Narrow terminals keep the whole width for the chat; the sidebar and
+    // Sources pane are drawn over it instead of squeezing it.
+    let narrow = area.width < NARROW_WIDTH;
     let main_layout = Layout::default().direction(Direction::Horizontal)
-        .constraints(if app.search_sidebar != SearchSidebarState::Hidden {
-            [Constraint::Percentage(25), Constraint::Percentage(75)]
+        .constraints(if sidebar != SearchSidebarState::Hidden && !narrow {
+            [Constraint::Percentage(app.config.sidebar_split), Constraint::Percentage(100 - app.config.sidebar_split)]
         } else {
             [Constraint::Percentage(0), Constraint::Percentage(100)]
         }).split(area);
 
-    let sidebar_area = main_layout[0];
-    let chat_area = main_layout[1];
-
-    if app.search_sidebar != SearchSidebarState::Hidden {
-        let block_style = if app.input_mode == InputMode::SearchSidebar { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::DarkGray) };
-        let block = Block::default().borders(Borders::ALL).border_style(block_style);
-        
-        match app.search_sidebar {
-            SearchSidebarState::History => {
-                let mut items = vec![ListItem::new(Span::styled("[+] New Chat", Style::default().fg(Color::Green)))];
-                items.extend(app.conversations.iter().map(|c| ListItem::new(c.title.clone())));
-                
-                let mut state = ListState::default(); 
-                state.select(Some(app.conversation_idx));
-                f.render_stateful_widget(List::new(items).block(block.title(" History ")).highlight_style(Style::default().bg(Color::Blue)), sidebar_area, &mut state);
-            },
-            SearchSidebarState::Settings => {
-                let mut items = Vec::new();
-                items.push(ListItem::new(format!("Provider: < {} >", app.selected_llm_provider)));
-                items.push(ListItem::new(format!("Model:    < {} >", app.selected_model)));
-                items.push(ListItem::new("--- Search Sources ---"));
-                for p in &app.search_providers {
-                    let check = if p.is_enabled { "[x]" } else { "[ ]" };
-                    items.push(ListItem::new(format!("{} {}", check, p.name)));
-                }
-                let mut state = ListState::default(); state.select(Some(app.settings_idx));
-                f.render_stateful_widget(List::new(items).block(block.title(" Settings ")).highlight_style(Style::default().bg(Color::Blue)), sidebar_area, &mut state);
-            },
-            _ => {}
-        }
-    }
+    let sidebar_area = if narrow { overlay(area, app.config.sidebar_split, false) } else { main_layout[0] };
+    let mut chat_area = main_layout[1];
+    let sources_area = if narrow { overlay(area, 30, true) } else {
+        let split = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Min(0), Constraint::Percentage(30)]).split(chat_area);
+        if app.sources_pane && !app.zen { chat_area = split[0]; }
+        split[1]
+    };
 
     let chat_chunks = Layout::default().direction(Direction::Vertical)
         .constraints([Constraint::Min(0), Constraint::Length(3)]).split(chat_area);
 
     let mut messages_visual = Vec::new();
-    for msg in &app.messages {
+    let (mut focus_line, mut focus_range) = (0, 0..0);
+    // With a trimmed history, questions and answers that won't be sent are dimmed.
+    let sent: Option<Vec<usize>> = app.context_limited().then(|| app.context_turns(app.messages.len()));
+    for (i, msg) in app.messages.iter().enumerate() {
         let role_style = match msg.role.as_str() {
             "user" => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
             "assistant" => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
             _ => Style::default().fg(Color::Red),
         };
-        messages_visual.push(Line::from(Span::styled(format!("{}:", msg.role.to_uppercase()), role_style)));
-        messages_visual.extend(markdown_to_text(&msg.content));
-        if !msg.sources.is_empty() {
+        let start = messages_visual.len();
+        let mut header = vec![Span::styled(format!("{}:", msg.role.to_uppercase()), role_style)];
+        let by = match (&msg.model, &msg.provider) {
+            (Some(m), Some(p)) => Some(format!("{} via {}", m, p)),
+            (m, p) => m.clone().or(p.clone()),
+        };
+        let used = match msg.usage {
+            Some(u) => Some(format!("{} tokens", tokens::short(u.completion))),
+            None if msg.role == "assistant" && !msg.content.is_empty() => Some(format!("~{} tokens", tokens::short(tokens::estimate(&msg.content)))),
+            None => None,
+        };
+        let dropped = matches!(msg.role.as_str(), "user" | "assistant") && !msg.content.trim().is_empty() && sent.as_ref().is_some_and(|s| !s.contains(&i));
+        let note = match (msg.queued, dropped) {
+            (true, _) => Some("queued, not sent yet".to_string()),
+            (false, true) => Some(if msg.excluded { "excluded" } else { "outside window" }.to_string()),
+            (false, false) => None,
+        };
+        let dim = dropped || msg.queued;
+        let meta: Vec<String> = by.into_iter().chain(msg.time.map(clock::hm)).chain(used).chain(note).collect();
+        if !meta.is_empty() { header.push(Span::styled(format!(" {}", meta.join(" · ")), Style::default().fg(Color::DarkGray))); }
+        messages_visual.push(Line::from(header));
+        if !msg.reasoning.is_empty() {
+            let dim = Style::default().fg(Color::DarkGray);
+            let lines = msg.reasoning.trim().lines().count();
+            if app.reasoning_open.contains(&i) {
+                messages_visual.push(Line::from(Span::styled("▾ Thinking", dim)));
+                messages_visual.extend(msg.reasoning.trim().lines().map(|l| Line::from(Span::styled(format!("│ {}", l), dim.add_modifier(Modifier::ITALIC)))));
+            } else {
+                let streaming = app.is_searching && i + 1 == app.messages.len() && msg.content.is_empty();
+                messages_visual.push(Line::from(Span::styled(format!("▸ Thinking{} ({} line{}) · T to show", if streaming { "…" } else { "" }, lines, if lines == 1 { "" } else { "s" }), dim)));
+            }
+        }
+        for call in &msg.tools {
+            let mut line = vec![
+                Span::styled(format!("⚙ {}", call.name), Style::default().fg(Color::Yellow)),
+                Span::styled(format!(": {}", one_line(&call.args, TOOL_ARGS_CHARS)), Style::default().fg(Color::DarkGray)),
+            ];
+            line.push(match &call.result {
+                Some(result) => Span::raw(format!(" → {}", one_line(result, TOOL_RESULT_CHARS))),
+                None => Span::styled(" …", Style::default().fg(Color::DarkGray)),
+            });
+            messages_visual.push(Line::from(line));
+        }
+        let mut body = markdown_to_text(&msg.content);
+        if app.is_folded(i) && body.len() > FOLDED_LINES {
+            let hidden = body.len() - FOLDED_LINES;
+            body.truncate(FOLDED_LINES);
+            body.push(Line::from(Span::styled(format!("… {} more lines (z to expand)", hidden), Style::default().fg(Color::DarkGray))));
+        }
+        messages_visual.extend(body);
+        for path in &msg.attachments {
+            messages_visual.push(Line::from(vec![
+                Span::styled(format!(" saved: {} ", attachments::display_path(path)), Style::default().bg(app.config.theme.accent()).fg(Color::White)),
+                Span::styled(" (o to open)", Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+        if !msg.sources.is_empty() && !app.sources_open.contains(&i) {
+            messages_visual.push(Line::from(Span::styled(format!("▸ Sources ({}) · s to show", msg.sources.len()), Style::default().fg(Color::Magenta))));
+        } else if !msg.sources.is_empty() {
             messages_visual.push(Line::from(""));
             messages_visual.push(Line::from(Span::styled("Sources:", Style::default().fg(Color::Magenta).add_modifier(Modifier::UNDERLINED))));
             for (i, source) in msg.sources.iter().enumerate() {
@@ -184,8 +354,56 @@ fn render_search(f: &mut Frame, app: &mut App, area: Rect) {
                 ]));
             }
         }
+        if dim {
+            for line in &mut messages_visual[start..] { *line = std::mem::take(line).patch_style(Style::default().add_modifier(Modifier::DIM)); }
+        }
+        let latest = app.messages.iter().rposition(|m| m.role == "assistant") == Some(i);
+        if latest && !app.is_searching && !msg.follow_ups.is_empty() {
+            messages_visual.push(Line::from(""));
+            for (n, question) in msg.follow_ups.iter().enumerate() {
+                messages_visual.push(Line::from(vec![
+                    Span::styled(format!(" [{}] ", n + 1), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                    Span::styled(question.as_str(), Style::default().fg(Color::Cyan)),
+                ]));
+            }
+            messages_visual.push(Line::from(Span::styled(format!(" 1-{0}: ask · Alt+1-{0}: send", msg.follow_ups.len()), Style::default().fg(Color::DarkGray))));
+        }
+        if !app.is_searching && app.launch_answer() == Some(i) {
+            match &app.launch_choice {
+                Some(ids) => {
+                    let names = ids.iter().enumerate().filter_map(|(n, id)| app.apps.iter().find(|a| a.id == *id).map(|a| format!("[{}] {}", n + 1, a.name)));
+                    messages_visual.push(Line::from(Span::styled(format!(" Launch which? {} · other key: cancel", names.collect::<Vec<_>>().join("  ")), Style::default().fg(Color::Yellow))));
+                },
+                None => {
+                    let apps = app.recommended_apps(i);
+                    if !apps.is_empty() {
+                        let names: Vec<&str> = apps.iter().map(|a| a.name.as_str()).collect();
+                        messages_visual.push(Line::from(Span::styled(format!(" ▸ Apps: {} · L to launch", names.join(", ")), Style::default().fg(Color::Yellow))));
+                    }
+                },
+            }
+            let cmds = app.answer_commands(i);
+            match app.snippet_choice {
+                Some(verb) => {
+                    let items = app.snippet_blocks(i, verb).into_iter().enumerate().map(|(n, b)| format!("[{}] {}", n + 1, snippets::summary(&b.command())));
+                    messages_visual.push(Line::from(Span::styled(format!(" {} which? {} · other key: cancel", verb.label(), items.collect::<Vec<_>>().join("  ")), Style::default().fg(Color::Yellow))));
+                },
+                None if !cmds.is_empty() => {
+                    let what = if cmds.len() == 1 { format!("`{}`", snippets::summary(&cmds[0].command())) } else { format!("{} commands", cmds.len()) };
+                    messages_visual.push(Line::from(Span::styled(format!(" ▸ Shell: {} · Y copy · ! run · A save as app", what), Style::default().fg(Color::Green))));
+                },
+                None => {},
+            }
+        }
+        if app.chat_focus == Some(i) { focus_line = start as u16; focus_range = start..messages_visual.len(); }
         messages_visual.push(Line::from(""));
     }
+    app.chat_lines = messages_visual.iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect();
+    // The focused message gets a bar down its left edge, a visual selection a highlight.
+    for line in &mut messages_visual[focus_range] { line.spans.insert(0, Span::styled("▌ ", Style::default().fg(app.config.theme.accent()))); }
+    if let Some((anchor, cursor)) = app.chat_visual {
+        for line in messages_visual.iter_mut().take(anchor.max(cursor) + 1).skip(anchor.min(cursor)) { *line = std::mem::take(line).patch_style(Style::default().add_modifier(Modifier::REVERSED)); }
+    }
 
     // FIX: Add visual padding at the bottom so auto-scroll reveals the last line clearly
     // This helps prevents text from being "cut off" by the bottom border or input box
@@ -197,35 +415,250 @@ fn render_search(f: &mut Frame, app: &mut App, area: Rect) {
     let view_height = chat_chunks[0].height.saturating_sub(2);
     let max_scroll = total_lines.saturating_sub(view_height);
 
-    if app.chat_auto_scroll {
-        app.chat_scroll = max_scroll;
-    } else if app.chat_scroll > max_scroll {
+    if std::mem::take(&mut app.chat_focus_moved) { app.chat_scroll = focus_line; }
+    if let Some((_, cursor)) = app.chat_visual {
+        let cursor = cursor as u16;
+        app.chat_scroll = app.chat_scroll.clamp(cursor.saturating_sub(view_height.saturating_sub(1)), cursor);
+    }
+    if app.chat_auto_scroll || app.chat_scroll > max_scroll {
         app.chat_scroll = max_scroll;
     }
 
     let chat_style = if app.input_mode == InputMode::ChatHistory { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::White) };
-    f.render_widget(Paragraph::new(messages_visual).block(Block::default().borders(Borders::ALL).title(" Conversation ").border_style(chat_style)).wrap(Wrap { trim: false }).scroll((app.chat_scroll, 0)), chat_chunks[0]);
+    let mut chat_title = " Conversation ".to_string();
+    if app.config.direct_chat { chat_title.push_str("· direct "); }
+    if let Some(p) = app.active_preset() { chat_title.push_str(&format!("· {} ", p.name)); }
+    if let Some(dir) = app.current_project() { chat_title.push_str(&format!("· {} ", attachments::display_path(dir))); }
+    let chat_title = focus_title(app, chat_title, app.input_mode == InputMode::ChatHistory);
+    f.render_widget(Paragraph::new(messages_visual).block(Block::default().borders(Borders::ALL).title(chat_title).border_style(chat_style)).wrap(Wrap { trim: false }).scroll((app.chat_scroll, 0)), chat_chunks[0]);
 
+    let mut input_title = if app.query_search.is_some() { " Message · history search ".to_string() } else if app.edit_from.is_some() { " Message · replaces the chat from your edited message ".to_string() } else if app.pending_context.is_empty() { " Message ".to_string() } else {
+        format!(" Message [+ {}] ", app.pending_context.iter().map(|c| c.label.as_str()).collect::<Vec<_>>().join(", "))
+    };
+    if app.a11y && app.stream_progress().is_some() {
+        input_title.push_str("· answering… ");
+    } else if let Some((elapsed_ms, rate)) = app.stream_progress() {
+        let frame = SPINNER[(app.tick_count % SPINNER.len() as u64) as usize];
+        input_title.push_str(&format!("· {} {:.1}s ", frame, elapsed_ms as f64 / 1000.0));
+        if let Some(rate) = rate { input_title.push_str(&format!("· {:.0} tok/s ", rate)); }
+    }
+    let input_title = focus_title(app, input_title, app.input_mode == InputMode::SearchInput);
     let input_block = Block::default().borders(Borders::ALL)
         .border_style(if app.input_mode == InputMode::SearchInput { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::White) })
-        .title(" Message ");
-    f.render_widget(Paragraph::new(app.search_input.clone()).block(input_block), chat_chunks[1]);
+        .title(input_title);
+    // Context gauge: what the conversation takes of the model's window, colored as it fills.
+    let used = app.context_tokens();
+    let gauge = match tokens::context_limit(&app.selected_model) {
+        Some(limit) => {
+            let percent = used * 100 / limit;
+            let color = if percent >= tokens::DANGER_PERCENT { Color::Red } else if percent >= tokens::WARN_PERCENT { Color::Yellow } else { Color::DarkGray };
+            let warn = if percent >= tokens::WARN_PERCENT { "⚠ " } else { "" };
+            Span::styled(format!(" {}ctx {}/{} ({}%) ", warn, tokens::short(used), tokens::short(limit), percent), Style::default().fg(color))
+        },
+        None => Span::styled(format!(" ctx ~{} ", tokens::short(used)), Style::default().fg(Color::DarkGray)),
+    };
+    let input_block = input_block.title(Title::from(gauge).alignment(Alignment::Right));
+    // One row: pasted line breaks show as ↵ and a long message shows its end.
+    let shown = match &app.query_search {
+        Some(h) => format!("({}reverse-search) '{}': {}", if h.hit.is_none() && !h.query.is_empty() { "failed " } else { "" }, h.query, h.hit.map_or("", |i| app.query_history[i].as_str())),
+        None => app.search_input.clone(),
+    }.replace('\n', "↵");
+    let width = chat_chunks[1].width.saturating_sub(2) as usize;
+    f.render_widget(Paragraph::new(textarea::tail(&shown, width.saturating_sub(1))).block(input_block), chat_chunks[1]);
+
+    // `@` suggestions, just above the Message box.
+    let suggestions = app.mention_suggestions();
+    if app.input_mode == InputMode::SearchInput && !suggestions.is_empty() {
+        let height = (suggestions.len() as u16 + 2).min(chat_chunks[0].height);
+        let width = chat_chunks[1].width.min(48);
+        let area = Rect { x: chat_chunks[1].x, y: chat_chunks[1].y.saturating_sub(height), width, height };
+        let items: Vec<ListItem> = suggestions.iter().enumerate().map(|(n, a)| {
+            let style = if n == 0 { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() };
+            let description = a.description.as_deref().unwrap_or("");
+            ListItem::new(Line::from(vec![Span::styled(format!("@{}", mentions::slug(&a.name)), style), Span::styled(format!(" {}", description), Style::default().fg(Color::DarkGray))]))
+        }).collect();
+        f.render_widget(Clear, area);
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Apps · Tab to complete ")), area);
+    }
+
+    if app.sources_pane && !app.zen {
+        if narrow { f.render_widget(Clear, sources_area); }
+        render_sources_pane(f, app, sources_area);
+    }
+    if sidebar != SearchSidebarState::Hidden {
+        if narrow { f.render_widget(Clear, sidebar_area); }
+        render_search_sidebar(f, app, sidebar_area);
+    }
+}
+
+fn render_search_sidebar(f: &mut Frame, app: &App, area: Rect) {
+    let block_style = if app.input_mode == InputMode::SearchSidebar { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::DarkGray) };
+    let block = Block::default().borders(Borders::ALL).border_style(block_style);
+    let focused = app.input_mode == InputMode::SearchSidebar;
+    
+    match app.search_sidebar {
+        SearchSidebarState::History => {
+            let mut items = vec![ListItem::new(Span::styled("[+] New Chat", Style::default().fg(Color::Green)))];
+            items.extend(app.conversations.iter().map(|c| {
+                let title = if app.updated_convos.contains(&c.id) {
+                    Line::from(vec![Span::styled("● ", Style::default().fg(Color::Yellow)), Span::raw(c.title.clone())])
+                } else {
+                    Line::from(c.title.clone())
+                };
+                match app.summaries.get(&c.id) {
+                    Some(text) => ListItem::new(vec![title, Line::from(Span::styled(format!("  {}", one_line(text, area.width.saturating_sub(4) as usize)), Style::default().fg(Color::DarkGray)))]),
+                    None => ListItem::new(title),
+                }
+            }));
+            
+            let mut state = ListState::default(); 
+            state.select(Some(app.conversation_idx));
+            f.render_stateful_widget(List::new(items).block(block.title(focus_title(app, " History ".to_string(), focused))).highlight_style(Style::default().bg(app.config.theme.accent())).highlight_symbol(selected_symbol(app)), area, &mut state);
+        },
+        SearchSidebarState::Highlights => {
+            let items: Vec<ListItem> = app.highlights.iter().rev().map(|h| {
+                let first = h.content.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+                ListItem::new(vec![
+                    Line::from(Span::styled(h.convo_title.clone(), Style::default().fg(Color::DarkGray))),
+                    Line::from(first.trim_start_matches('#').trim().to_string()),
+                ])
+            }).collect();
+            let title = focus_title(app, format!(" Highlights ({}) ", app.highlights.len()), focused);
+            let mut state = ListState::default();
+            state.select((!app.highlights.is_empty()).then_some(app.highlight_idx));
+            f.render_stateful_widget(List::new(items).block(block.title(title)).highlight_style(Style::default().bg(app.config.theme.accent())).highlight_symbol(selected_symbol(app)), area, &mut state);
+        },
+        SearchSidebarState::Settings => {
+            let mut items = Vec::new();
+            items.push(ListItem::new(format!("Provider: < {} >", app.selected_llm_provider)));
+            let star = if app.is_starred(&app.selected_model) { "★ " } else { "" };
+            items.push(ListItem::new(format!("Model:    < {}{} >", star, app.model_label())));
+            items.push(ListItem::new("--- Search Sources ---"));
+            for p in &app.search_providers {
+                let check = if p.is_enabled { "[x]" } else { "[ ]" };
+                items.push(ListItem::new(format!("{} {}", check, p.name)));
+            }
+            let mut state = ListState::default(); state.select(Some(app.settings_idx));
+            f.render_stateful_widget(List::new(items).block(block.title(focus_title(app, " Settings ".to_string(), focused))).highlight_style(Style::default().bg(app.config.theme.accent())).highlight_symbol(selected_symbol(app)), area, &mut state);
+        },
+        SearchSidebarState::Plugins => {
+            let items: Vec<ListItem> = app.plugin_panels.iter().map(|p| {
+                let mut lines = vec![Line::from(Span::styled(format!("{} ({})", p.title, p.plugin), Style::default().add_modifier(Modifier::BOLD)))];
+                if p.lines.is_empty() { lines.push(Line::from(Span::styled("  ...", Style::default().fg(Color::DarkGray)))); }
+                lines.extend(p.lines.iter().map(|l| Line::from(format!("  {}", l))));
+                ListItem::new(lines)
+            }).collect();
+            let mut state = ListState::default();
+            state.select((!app.plugin_panels.is_empty()).then_some(app.plugin_panel_idx));
+            f.render_stateful_widget(List::new(items).block(block.title(focus_title(app, " Plugins ".to_string(), focused))).highlight_style(Style::default().bg(app.config.theme.accent())).highlight_symbol(selected_symbol(app)), area, &mut state);
+        },
+        _ => {}
+    }
+}
+
+/// Every source of the conversation once, with its engine, host and how
+/// many answers cited it.
+fn render_sources_pane(f: &mut Frame, app: &App, area: Rect) {
+    let rows = app.pane_sources();
+    let items: Vec<ListItem> = rows.iter().map(|r| {
+        let host = r.source.url.split("://").nth(1).unwrap_or(&r.source.url).split('/').next().unwrap_or("");
+        let cited = if r.cited > 1 { format!(" · ×{}", r.cited) } else { String::new() };
+        ListItem::new(vec![
+            Line::from(Span::styled(r.source.title.clone(), Style::default().fg(Color::White))),
+            Line::from(Span::styled(format!("  {} · {}{}", r.source.engine, host, cited), Style::default().fg(Color::DarkGray))),
+        ])
+    }).collect();
+    let border = if app.input_mode == InputMode::SourcesPane { Color::Yellow } else { Color::DarkGray };
+    let title = focus_title(app, format!(" Sources ({}) · {} · {} ", rows.len(), app.sources_engine.as_deref().unwrap_or("all engines"), app.sources_sort.label()), app.input_mode == InputMode::SourcesPane);
+    let mut state = ListState::default();
+    state.select((!rows.is_empty()).then(|| app.sources_pane_idx.min(rows.len() - 1)));
+    let block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title);
+    f.render_stateful_widget(List::new(items).block(block).highlight_style(Style::default().bg(app.config.theme.accent())).highlight_symbol(selected_symbol(app)), area, &mut state);
+}
+
+/// Longest tool arguments and result shown on a `⚙` line.
+const TOOL_ARGS_CHARS: usize = 60;
+const TOOL_RESULT_CHARS: usize = 80;
+
+/// `text` on one line, whitespace collapsed, cut to `max` columns.
+fn one_line(text: &str, max: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if textarea::width(&flat) <= max { return flat; }
+    let (mut out, mut used) = (String::new(), 0);
+    for c in flat.chars() {
+        used += c.width().unwrap_or(0);
+        if used + 1 > max { break; }
+        out.push(c);
+    }
+    format!("{}…", out)
+}
+
+/// First line of a command, noting how many more there are.
+fn command_summary(cmd: &str) -> String {
+    let mut lines = cmd.lines();
+    let first = lines.next().unwrap_or("").to_string();
+    match lines.count() { 0 => first, n => format!("{} (+{} more line{})", first, n, if n == 1 { "" } else { "s" }) }
 }
 
 fn render_footer(f: &mut Frame, app: &App, area: Rect) {
-    let msg = match app.current_screen {
+    let msg = if app.file_browser.as_ref().is_some_and(|b| b.pick == Pick::WorkingDir) { "Type to filter | Enter/Right:Open dir | Backspace:Up | Ctrl+s:Use this dir | Esc:Cancel" }
+    else if app.file_browser.is_some() { "Type to filter | Enter:Open dir / attach file | Backspace:Up | Esc:Cancel" }
+    else if app.input_mode == InputMode::Confirm { "y:Yes | n/Esc:No" }
+    else if app.input_mode == InputMode::TagBrowser { "Up/Down:Nav | Space:Toggle tag | c:Clear | Esc:Close" }
+    else if app.input_mode == InputMode::LaunchHistory && app.history_output.is_some() { "Up/Dn/PgUp/PgDn:Scroll | g/G:Top/End | Esc:Back" }
+    else if app.input_mode == InputMode::LaunchHistory { "Up/Down:Select | Enter:Output | Esc:Close" }
+    else if app.input_mode == InputMode::Editing && app.active_form.focus_idx == AppForm::CMD { "Tab:Next field | Enter:New line | Arrows:Move | Ctrl+s:Save | Esc:Cancel" }
+    else if app.input_mode == InputMode::Editing && app.active_form.focus_idx == AppForm::CWD { "Tab:Next field | Ctrl+o:Browse | Enter/Ctrl+s:Save | Esc:Cancel" }
+    else if app.input_mode == InputMode::Editing { "Tab:Next field | Enter/Ctrl+s:Save | Esc:Cancel" }
+    else if app.input_mode == InputMode::Args { "Enter:Launch | Tab/Up/Down:Field | Esc:Cancel" }
+    else if app.input_mode == InputMode::Chains && app.chain_draft.is_some() { "Tab:Focus | Enter:Add | J/K:Move | d:Remove | h:Health | Ctrl+s:Save | Esc:Back" }
+    else if app.input_mode == InputMode::Chains { "Enter:Run | n:New | e:Edit | d:Delete | Esc:Close" }
+    else if app.input_mode == InputMode::Presets { "Enter:Use | Up/Down:Nav | d:Delete | Esc:Close" }
+    else if app.input_mode == InputMode::HistorySearch { "Type to search | Enter:Open | Up/Down:Nav | Esc:Close" }
+    else if app.input_mode == InputMode::SourceEditor { "Tab:Next field | Enter/Ctrl+s:Save | Esc:Cancel" }
+    else if app.input_mode == InputMode::Output && app.output_searching { "Type to search output | Enter:Find | Esc:Clear" }
+    else if app.input_mode == InputMode::Output { "Up/Dn:Scroll | g/G:Top/End | /:Search | n/N:Match | e:Errors | w:Save | Esc:Apps" }
+    else if app.input_mode == InputMode::Preview { "Up/Down/PgUp/PgDn:Scroll | r:Reload | Esc:Close" }
+    else if app.input_mode == InputMode::Palette && app.palette_binding { "Press the key to bind | Esc:Cancel" }
+    else if app.input_mode == InputMode::Palette { "Enter:Run | Up/Down:Nav | Ctrl+b:Bind key | Ctrl+d:Unbind | Esc:Close" }
+    else { match app.current_screen {
         CurrentScreen::Launcher => match app.input_mode {
-            InputMode::Normal => "Tab:Switch | q:Quit | Enter:Launch | /:Filter | a:Add | e:Edit",
+            InputMode::Normal if !app.marked.is_empty() => "Marked apps: Space:Mark | Enter:Launch | d:Delete | w:Export | Esc:Clear marks",
+            InputMode::Normal => "Tab:Switch | q:Quit | Enter:Launch | x:Stop | /:Filter | a:Add | e:Edit | *:Fav | F:Favs only | s:Sort | t:Tags | d:Delete | u:Undo",
             _ => "Esc:Cancel | Enter:Confirm"
         },
         CurrentScreen::Search => match app.input_mode {
-            InputMode::SearchInput => "Tab:Cycle Focus | Esc:Launcher | Enter:Send | Ctrl+s:Sidebar",
-            InputMode::SearchSidebar => "Tab:Cycle Focus | Esc:Launcher | Up/Down:Nav | Enter:Select",
-            InputMode::ChatHistory => "Tab:Cycle Focus | Esc:Launcher | Up/Down:Scroll | PgUp/PgDn:Page Scroll",
+            InputMode::SearchInput if app.query_search.is_some() => "Type to search history | Ctrl+r:Older | Enter:Use | Esc:Cancel",
+            InputMode::SearchInput => "Tab:Cycle Focus | Esc:Next tab | Enter:Send | Up/Ctrl+r:History | Ctrl+s:Sidebar | Ctrl+o:Sources",
+            InputMode::SearchSidebar if app.search_sidebar == SearchSidebarState::Highlights => "Tab:Cycle Focus | Esc:Next tab | Up/Down:Nav | Enter:Open conversation | d:Unpin",
+            InputMode::SearchSidebar if app.search_sidebar == SearchSidebarState::Plugins => "Tab:Cycle Focus | Esc:Next tab | Up/Down:Nav | Enter:Refresh panel",
+            InputMode::SearchSidebar if app.search_sidebar == SearchSidebarState::Settings => "Tab:Cycle Focus | Esc:Next tab | Up/Down:Nav | Enter:Select | *:Star model | a:Add source | e:Edit | d:Remove",
+            InputMode::SearchSidebar => "Tab:Cycle Focus | Esc:Next tab | Up/Down:Nav | Enter:Select | d:Delete | u:Undo",
+            InputMode::SourcesPane => "Up/Down:Nav | Enter:Open | v:Preview | y:Copy URL | f:Engine | s:Sort | Tab:Cycle Focus | Esc:Close",
+            InputMode::ChatHistory if app.chat_visual.is_some() => "Up/Down/PgUp/PgDn:Extend selection | y:Copy | A:Save as app | Esc:Cancel",
+            InputMode::ChatHistory if app.chat_focus.is_some() => "{/}:Message | [/]:Yours | Home/End:First/Last | e:Edit | c:Cut & edit | Esc:Done",
+            InputMode::ChatHistory => "Tab:Cycle Focus | Esc:Next tab | Up/Down:Scroll | PgUp/PgDn:Page Scroll | g/G:Top/Follow | v:Select | z:Fold | s:Sources | T:Thinking | {/}:Message | [/]:Yours | r:Retry | L:Launch app | y:Copy code | Y/!/A:Copy/Run/Save cmd | p:Pin answer | t:TODOs | o:Open file | E:Editor",
             _ => "Esc:Back"
-        }
-    };
-    f.render_widget(Paragraph::new(msg).style(Style::default().bg(Color::Blue).fg(Color::White)), area);
+        },
+        CurrentScreen::Processes => "Tab:Switch | q:Quit | Up/Down:Nav | Enter:Output | x:Stop | r:Restart | R:Refresh",
+        CurrentScreen::Settings if app.settings_binding => "Press the key to bind | Esc:Cancel",
+        CurrentScreen::Settings if app.settings_edit.is_some() => "Type the new value | Enter:Save | Esc:Cancel",
+        CurrentScreen::Settings => "Tab:Switch | q:Quit | Up/Down:Nav | Enter:Change | Left/Right:Cycle | Del:Unbind key",
+    } };
+    let msg = if area.width < NARROW_WIDTH { fit_hints(msg, area.width as usize) } else { msg.to_string() };
+    f.render_widget(Paragraph::new(msg).style(Style::default().bg(app.config.theme.accent()).fg(Color::White)), area);
+}
+
+/// The `a | b | c` hints that fit in `width` whole, with `…` when some are left out.
+fn fit_hints(msg: &str, width: usize) -> String {
+    if msg.chars().count() <= width { return msg.to_string(); }
+    let mut out = String::new();
+    for hint in msg.split(" | ") {
+        let next = if out.is_empty() { hint.to_string() } else { format!("{} | {}", out, hint) };
+        if next.chars().count() + 4 > width { break; }
+        out = next;
+    }
+    if out.is_empty() { msg.chars().take(width.saturating_sub(1)).chain(['…']).collect() } else { format!("{} | …", out) }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -233,18 +666,670 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage((100 - percent_x) / 2), Constraint::Percentage(percent_x), Constraint::Percentage((100 - percent_x) / 2)]).split(popup_layout[1])[1]
 }
 fn render_edit_modal(f: &mut Frame, app: &App) {
-    let area = centered_rect(60, 50, f.size()); f.render_widget(Clear, area);
+    const CMD_ROWS: u16 = 4;
+    let area = centered_rect(60, 100, f.size());
+    let height = (AppForm::FIELDS as u16 * 3 + CMD_ROWS - 1 + 2).min(area.height);
+    let area = Rect { y: area.y + area.height.saturating_sub(height) / 2, height, ..area };
+    f.render_widget(Clear, area);
     f.render_widget(Block::default().borders(Borders::ALL).title(" Editor ").style(Style::default().bg(Color::Black)), area);
-    let chunks = Layout::default().direction(Direction::Vertical).margin(1).constraints([Constraint::Length(3),Constraint::Length(3),Constraint::Length(3),Constraint::Length(3),Constraint::Min(0)]).split(area);
-    let fields = [("Name",&app.active_form.name),("Desc",&app.active_form.desc),("Cmd",&app.active_form.cmd),("URL",&app.active_form.url)];
+    let constraints: Vec<Constraint> = (0..AppForm::FIELDS).map(|i| Constraint::Length(if i == AppForm::CMD { CMD_ROWS + 2 } else { 3 })).collect();
+    let chunks = Layout::default().direction(Direction::Vertical).margin(1).constraints(constraints).split(area);
+    let form = &app.active_form;
+    let fields = [("Name",&form.name),("Desc",&form.desc),("Cmd",&form.cmd.text),("URL",&form.url),("Env (KEY=VALUE ...)",&form.env),("Working dir",&form.cwd),("Target (SSH host)",&form.target)];
     for (i,(l,v)) in fields.iter().enumerate() {
         let style = if app.active_form.focus_idx==i { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::White) };
-        f.render_widget(Paragraph::new(v.as_str()).block(Block::default().borders(Borders::ALL).title(*l)).style(style), chunks[i]);
+        let block = match form.errors.get(&i) {
+            Some(err) => Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Red)).title(Line::from(vec![Span::raw(*l), Span::styled(format!(" ✗ {}", err), Style::default().fg(Color::Red))])),
+            None => Block::default().borders(Borders::ALL).title(*l),
+        };
+        if i != AppForm::CMD {
+            // The field being typed in shows its end, like the Search input.
+            let inner = block.inner(chunks[i]).width as usize;
+            let shown = if form.focus_idx == i { textarea::tail(v, inner.saturating_sub(1)) } else { v.as_str() };
+            f.render_widget(Paragraph::new(shown).block(block).style(style), chunks[i]);
+            continue;
+        }
+        // The command wraps inside its box and scrolls to keep the cursor in view.
+        let inner = block.inner(chunks[i]);
+        let (rows, (row, col)) = form.cmd.wrap(inner.width as usize);
+        let top = (row as u16).saturating_sub(inner.height.saturating_sub(1));
+        let lines: Vec<Line> = rows.into_iter().map(Line::from).collect();
+        f.render_widget(Paragraph::new(lines).block(block).style(style).scroll((top, 0)), chunks[i]);
+        if form.focus_idx == i && inner.width > 0 { f.set_cursor(inner.x + col as u16, inner.y + row as u16 - top); }
     }
 }
+fn render_source_editor(f: &mut Frame, app: &App) {
+    let Some(form) = &app.source_form else { return };
+    let area = centered_rect(60, 100, f.size());
+    let height = (SourceForm::FIELDS as u16 * 3 + 2).min(area.height);
+    let area = Rect { y: area.y + area.height.saturating_sub(height) / 2, height, ..area };
+    f.render_widget(Clear, area);
+    let title = if form.id == 0 { " New search source " } else { " Search source " };
+    f.render_widget(Block::default().borders(Borders::ALL).title(title).style(Style::default().bg(Color::Black)), area);
+    let chunks = Layout::default().direction(Direction::Vertical).margin(1).constraints([Constraint::Length(3); SourceForm::FIELDS]).split(area);
+    let fields = [("Name", &form.name), ("Type", &form.kind), ("Settings (key=value ...)", &form.settings)];
+    for (i, (l, v)) in fields.iter().enumerate() {
+        let style = if form.focus_idx == i { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::White) };
+        let block = match form.errors.get(&i) {
+            Some(err) => Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Red)).title(Line::from(vec![Span::raw(*l), Span::styled(format!(" ✗ {}", err), Style::default().fg(Color::Red))])),
+            None => Block::default().borders(Borders::ALL).title(*l),
+        };
+        f.render_widget(Paragraph::new(v.as_str()).block(block).style(style), chunks[i]);
+    }
+}
+fn render_file_browser(f: &mut Frame, app: &App) {
+    let Some(b) = &app.file_browser else { return };
+    let area = centered_rect(60, 70, f.size());
+    f.render_widget(Clear, area);
+    let dir = if b.dir.as_os_str().is_empty() { "…".to_string() } else { attachments::display_path(&b.dir.display().to_string()) };
+    let title = match b.pick { Pick::Attachment => format!(" Attach a file · {} ", dir), Pick::WorkingDir => format!(" Working dir · {} ", dir) };
+    let block = Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Yellow)).style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(1), Constraint::Min(0)]).split(inner);
+    f.render_widget(Paragraph::new(format!("Filter: {}", b.filter)).style(Style::default().fg(Color::DarkGray)), chunks[0]);
+    if let Some(err) = &b.error {
+        f.render_widget(Paragraph::new(err.as_str()).style(Style::default().fg(Color::Red)).wrap(Wrap { trim: true }), chunks[1]);
+        return;
+    }
+    let items: Vec<ListItem> = b.visible().into_iter().map(|e| {
+        if e.is_dir { ListItem::new(Span::styled(format!("{}/", e.name), Style::default().fg(Color::Cyan))) } else { ListItem::new(e.name.clone()) }
+    }).collect();
+    let empty = items.is_empty();
+    let mut state = ListState::default();
+    state.select((!empty).then_some(b.idx));
+    f.render_stateful_widget(List::new(items).highlight_style(Style::default().bg(app.config.theme.accent())), chunks[1], &mut state);
+    if empty { f.render_widget(Paragraph::new("Nothing matches.").style(Style::default().fg(Color::DarkGray)), chunks[1]); }
+}
 fn render_adhoc_modal(f: &mut Frame, app: &App) {
     let area = centered_rect(60, 20, f.size()); f.render_widget(Clear, area);
     f.render_widget(Block::default().borders(Borders::ALL).title(" Ad-Hoc ").style(Style::default().bg(Color::Black)), area);
     let chunks = Layout::default().direction(Direction::Vertical).margin(2).constraints([Constraint::Length(3)]).split(area);
     f.render_widget(Paragraph::new(app.adhoc_input.clone()).style(Style::default().fg(Color::Yellow)).block(Block::default().borders(Borders::ALL)), chunks[0]);
-}
\ No newline at end of file
+}fn render_confirm_modal(f: &mut Frame, app: &App) {
+    let Some(action) = &app.pending_confirm else { return };
+    let area = centered_rect(50, 100, f.size());
+    let area = Rect { y: area.y + area.height.saturating_sub(5) / 2, height: area.height.min(5), ..area };
+    f.render_widget(Clear, area);
+    let text = vec![Line::from(action.prompt()), Line::from(""), Line::from(Span::styled("[y] Yes   [n] No", Style::default().fg(Color::DarkGray)))];
+    f.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }).block(Block::default().borders(Borders::ALL).title(" Confirm ").border_style(Style::default().fg(Color::Red))).style(Style::default().bg(Color::Black)), area);
+}
+fn render_code_picker(f: &mut Frame, app: &App) {
+    let Some((i, sel)) = app.code_picker else { return };
+    let area = centered_rect(60, 40, f.size()); f.render_widget(Clear, area);
+    let items: Vec<ListItem> = app.code_blocks(i).iter().enumerate().map(|(n, b)| {
+        let lang = if b.lang.is_empty() { "text" } else { b.lang.as_str() };
+        let first = b.code.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("[{}] ", n + 1), Style::default().fg(Color::Cyan)),
+            Span::styled(format!("{:<6} ", lang), Style::default().fg(Color::DarkGray)),
+            Span::raw(first.to_string()),
+        ]))
+    }).collect();
+    let mut state = ListState::default(); state.select(Some(sel));
+    let block = Block::default().borders(Borders::ALL).title(" Copy code block (1-9/Enter: copy, Esc: cancel) ");
+    f.render_stateful_widget(List::new(items).block(block).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), area, &mut state);
+}
+fn render_tag_browser(f: &mut Frame, app: &App) {
+    let area = centered_rect(40, 60, f.size()); f.render_widget(Clear, area);
+    let items: Vec<ListItem> = app.tag_index.iter().map(|(tag, count)| {
+        let check = if app.selected_tags.contains(tag) { "[x]" } else { "[ ]" };
+        ListItem::new(format!("{} {} ({})", check, tag, count))
+    }).collect();
+    let mut state = ListState::default(); state.select(Some(app.tag_idx));
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Tags ")).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), area, &mut state);
+}
+
+/// Names for a chain's app ids; ids without an app show as `?id`.
+fn chain_steps(app: &App, ids: &[String]) -> Vec<String> {
+    ids.iter().map(|id| app.apps.iter().find(|a| a.id == *id).map(|a| a.name.clone()).unwrap_or_else(|| format!("?{}", id))).collect()
+}
+
+fn render_chains(f: &mut Frame, app: &App) {
+    let area = centered_rect(70, 70, f.size()); f.render_widget(Clear, area);
+    let focused = |on: bool| Style::default().fg(if on { Color::Yellow } else { Color::White });
+    let Some(d) = &app.chain_draft else {
+        let items: Vec<ListItem> = app.chains.iter().map(|c| ListItem::new(Line::from(vec![
+            Span::styled(format!("{} ", c.name), Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(chain_steps(app, &c.app_ids).join(" → "), Style::default().fg(Color::DarkGray)),
+            Span::styled(if c.health_check { "  (health checks)" } else { "" }, Style::default().fg(Color::Cyan)),
+        ]))).collect();
+        let block = Block::default().borders(Borders::ALL).title(" Launch chains ");
+        if items.is_empty() {
+            f.render_widget(Paragraph::new("No chains yet. Press n to create one.").style(Style::default().fg(Color::DarkGray).bg(Color::Black)).block(block), area);
+            return;
+        }
+        let mut state = ListState::default(); state.select(Some(app.chain_idx));
+        f.render_stateful_widget(List::new(items).block(block).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), area, &mut state);
+        return;
+    };
+    f.render_widget(Block::default().borders(Borders::ALL).title(if d.index.is_some() { " Edit chain " } else { " New chain " }).style(Style::default().bg(Color::Black)), area);
+    let chunks = Layout::default().direction(Direction::Vertical).margin(1).constraints([Constraint::Length(3), Constraint::Min(3), Constraint::Length(1), Constraint::Length(1)]).split(area);
+    f.render_widget(Paragraph::new(d.name.as_str()).block(Block::default().borders(Borders::ALL).title("Name")).style(focused(d.focus == ChainFocus::Name)), chunks[0]);
+    let lists = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(50), Constraint::Percentage(50)]).split(chunks[1]);
+    let highlight = |on: bool| if on { Style::default().bg(app.config.theme.accent()) } else { Style::default() };
+    let apps: Vec<ListItem> = app.apps.iter().map(|a| ListItem::new(format!("{} {}", if d.steps.contains(&a.id) { "✓" } else { " " }, a.name))).collect();
+    let mut state = ListState::default(); state.select(if apps.is_empty() { None } else { Some(d.app_idx) });
+    f.render_stateful_widget(List::new(apps).block(Block::default().borders(Borders::ALL).title("Apps")).style(focused(d.focus == ChainFocus::Apps)).highlight_style(highlight(d.focus == ChainFocus::Apps)), lists[0], &mut state);
+    let steps: Vec<ListItem> = chain_steps(app, &d.steps).into_iter().enumerate().map(|(i, name)| ListItem::new(format!("{}. {}", i + 1, name))).collect();
+    let mut state = ListState::default(); state.select(if steps.is_empty() { None } else { Some(d.step_idx) });
+    f.render_stateful_widget(List::new(steps).block(Block::default().borders(Borders::ALL).title("Launch order")).style(focused(d.focus == ChainFocus::Steps)).highlight_style(highlight(d.focus == ChainFocus::Steps)), lists[1], &mut state);
+    f.render_widget(Paragraph::new(format!("[{}] Health check: wait for each app's URL to answer", if d.health_check { "x" } else { " " })), chunks[2]);
+    if let Some(err) = &d.error { f.render_widget(Paragraph::new(Span::styled(format!("✗ {}", err), Style::default().fg(Color::Red))), chunks[3]); }
+}
+
+fn uptime(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        3600..=86399 => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d{:02}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+fn render_processes(f: &mut Frame, app: &App, area: Rect, now: u64) {
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Percentage(45), Constraint::Min(0)]).split(area);
+    let header = ListItem::new(Line::from(Span::styled(format!("{:<10} {:<24} {:>7} {:>8}  {}", "ID", "APP", "PID", "UPTIME", "STATUS"), Style::default().fg(Color::DarkGray))));
+    let rows = app.processes.iter().map(|p| {
+        let pid = p.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".into());
+        let up = p.started_at.map(|s| uptime(now.saturating_sub(s))).unwrap_or_else(|| "-".into());
+        let color = match p.status.as_str() { "running" => Color::Green, "exited" | "stopped" => Color::DarkGray, _ => Color::Red };
+        ListItem::new(Line::from(vec![
+            Span::raw(format!("{:<10} {:<24} {:>7} {:>8}  ", p.id, p.name, pid, up)),
+            Span::styled(p.status.clone(), Style::default().fg(color)),
+        ]))
+    });
+    let items: Vec<ListItem> = std::iter::once(header).chain(rows).collect();
+    let mut state = ListState::default(); state.select(if app.processes.is_empty() { None } else { Some(app.process_idx + 1) });
+    let title = if app.processes_remote { " Processes " } else { " Processes (this session) " };
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)).highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White)).highlight_symbol(selected_symbol(app)), chunks[0], &mut state);
+
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let start = app.process_output.len().saturating_sub(height);
+    let lines: Vec<Line> = app.process_output[start..].iter().map(|l| Line::from(crate::ansi::spans(l, app.ansi_colors))).collect();
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Process output ")), chunks[1]);
+}
+
+fn render_settings(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Min(0), Constraint::Length(3)]).split(area);
+    let rows = app.settings_rows();
+    let width = rows.iter().map(|r| r.label.chars().count()).max().unwrap_or(0);
+    let (mut items, mut selected, mut section) = (vec![], None, "");
+    for (i, row) in rows.iter().enumerate() {
+        if row.section != section {
+            section = row.section;
+            items.push(ListItem::new(Line::from(Span::styled(section, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))));
+        }
+        if i == app.settings_row { selected = Some(items.len()); }
+        let value = if i == app.settings_row { app.settings_edit.clone().unwrap_or_else(|| row.value.clone()) } else { row.value.clone() };
+        let value_style = match row.setting {
+            Setting::Theme => Style::default().fg(app.config.theme.accent()),
+            Setting::Binding(_) => Style::default().fg(Color::Cyan),
+            _ => Style::default(),
+        };
+        items.push(ListItem::new(Line::from(vec![Span::raw(format!("  {:<width$}  ", row.label, width = width)), Span::styled(value, value_style)])));
+    }
+    let mut state = ListState::default(); state.select(selected);
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Settings ")).highlight_style(Style::default().bg(app.config.theme.accent())).highlight_symbol(selected_symbol(app)), chunks[0], &mut state);
+
+    let row = rows.get(app.settings_row);
+    let label = row.map_or("", |r| r.label.as_str());
+    let (text, style) = match (&app.settings_error, &app.settings_edit, row.map(|r| r.setting)) {
+        (Some(e), _, _) => (format!("{}: {}", label, e), Style::default().fg(Color::Red)),
+        (None, Some(v), _) => (format!("{}: {}_", label, v), Style::default().fg(Color::Yellow)),
+        (None, None, _) if app.settings_binding => (format!("Press a key for '{}'", label), Style::default().fg(Color::Yellow)),
+        (None, None, Some(Setting::Binding(_))) => ("Enter adds a key, Del removes them all".to_string(), Style::default().fg(Color::DarkGray)),
+        (None, None, Some(s)) if s.is_text() => ("Enter to edit".to_string(), Style::default().fg(Color::DarkGray)),
+        (None, None, Some(Setting::Model)) => ("Enter or Left/Right to change (starred and recent models first), * stars it".to_string(), Style::default().fg(Color::DarkGray)),
+        _ => ("Enter or Left/Right to change".to_string(), Style::default().fg(Color::DarkGray)),
+    };
+    f.render_widget(Paragraph::new(text).style(style).block(Block::default().borders(Borders::ALL)), chunks[1]);
+}
+
+fn render_presets(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 50, f.size()); f.render_widget(Clear, area);
+    let active = app.config.preset.as_deref();
+    let mut items = vec![ListItem::new(Line::from(vec![
+        Span::raw(if active.is_none() { "● " } else { "  " }),
+        Span::raw("No preset"),
+        Span::styled("  default prompt and temperature", Style::default().fg(Color::DarkGray)),
+    ]))];
+    items.extend(app.config.presets.iter().map(|p| ListItem::new(Line::from(vec![
+        Span::raw(if active == Some(p.name.as_str()) { "● " } else { "  " }),
+        Span::raw(p.name.clone()),
+        Span::styled(format!("  {}", p.summary()), Style::default().fg(Color::DarkGray)),
+    ]))));
+    let mut state = ListState::default(); state.select(Some(app.preset_idx));
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Presets ")).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), area, &mut state);
+}
+
+fn render_history_search(f: &mut Frame, app: &App) {
+    let area = centered_rect(70, 70, f.size()); f.render_widget(Clear, area);
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(3), Constraint::Min(0)]).split(area);
+    f.render_widget(Paragraph::new(format!("> {}", app.history_query)).style(Style::default().fg(Color::Yellow).bg(Color::Black)).block(Block::default().borders(Borders::ALL).title(" Search all conversations ")), chunks[0]);
+
+    let hits = app.history_hits();
+    let items: Vec<ListItem> = hits.iter().map(|h| match h.message {
+        None => ListItem::new(Line::from(vec![Span::styled(h.title.clone(), Style::default().add_modifier(Modifier::BOLD)), Span::styled("  title", Style::default().fg(Color::DarkGray))])),
+        Some(_) => ListItem::new(vec![Line::from(Span::styled(h.title.clone(), Style::default().fg(Color::DarkGray))), Line::from(format!("  {}", h.snippet))]),
+    }).collect();
+    let mut title = match (app.history_query.trim().is_empty(), hits.len()) {
+        (true, _) => " Type to search titles and messages ".to_string(),
+        (false, n) if n >= crate::convo_search::MAX_HITS => format!(" First {} matches ", n),
+        (false, n) => format!(" {} match{} ", n, if n == 1 { "" } else { "es" }),
+    };
+    if app.indexing > 0 { title.push_str(&format!("· fetching {} conversation{}… ", app.indexing, if app.indexing == 1 { "" } else { "s" })); }
+    let mut state = ListState::default(); state.select(if hits.is_empty() { None } else { Some(app.convo_search_idx) });
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), chunks[1], &mut state);
+}
+
+fn render_palette(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 60, f.size()); f.render_widget(Clear, area);
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(3), Constraint::Min(0)]).split(area);
+    f.render_widget(Paragraph::new(format!("> {}", app.palette_query)).style(Style::default().fg(Color::Yellow).bg(Color::Black)).block(Block::default().borders(Borders::ALL).title(" Commands ")), chunks[0]);
+
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    let entries = app.palette_entries();
+    let items: Vec<ListItem> = entries.iter().map(|e| {
+        let keys = if e.keys.is_empty() { String::new() } else { e.keys.join(" ") };
+        let pad = width.saturating_sub(textarea::width(&e.title) + textarea::width(&keys) + 1);
+        ListItem::new(Line::from(vec![Span::raw(format!("{}{} ", e.title, " ".repeat(pad))), Span::styled(keys, Style::default().fg(Color::Cyan))]))
+    }).collect();
+    let title = match (app.palette_binding, entries.get(app.palette_idx)) {
+        (true, Some(e)) => format!(" Press a key for '{}' ", e.title),
+        _ => format!(" {} commands ", entries.len()),
+    };
+    let mut state = ListState::default(); state.select(if entries.is_empty() { None } else { Some(app.palette_idx) });
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), chunks[1], &mut state);
+}
+
+/// `3m ago`-style age of a Unix timestamp.
+fn ago(now: u64, then: u64) -> String {
+    let s = now.saturating_sub(then);
+    match s {
+        0..=59 => format!("{}s ago", s),
+        60..=3599 => format!("{}m ago", s / 60),
+        3600..=86399 => format!("{}h ago", s / 3600),
+        _ => format!("{}d ago", s / 86400),
+    }
+}
+
+fn render_args_prompt(f: &mut Frame, app: &App) {
+    let Some(p) = &app.args_prompt else { return };
+    let area = centered_rect(60, 100, f.size());
+    let height = (p.fields.len() as u16 * 3 + 4).min(area.height);
+    let area = Rect { y: area.y + area.height.saturating_sub(height) / 2, height, ..area };
+    f.render_widget(Clear, area);
+    let template = app.apps.iter().find(|a| a.id == p.app_id).map(|a| a.command.as_str()).unwrap_or("");
+    f.render_widget(Block::default().borders(Borders::ALL).title(format!(" Launch {} ", p.name)).style(Style::default().bg(Color::Black)), area);
+    let mut constraints = vec![Constraint::Length(1)];
+    constraints.extend(p.fields.iter().map(|_| Constraint::Length(3)));
+    let chunks = Layout::default().direction(Direction::Vertical).margin(1).constraints(constraints).split(area);
+    f.render_widget(Paragraph::new(Span::styled(template.to_string(), Style::default().fg(Color::DarkGray))), chunks[0]);
+    for (i, (name, value)) in p.fields.iter().enumerate() {
+        let style = if p.focus == i { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::White) };
+        f.render_widget(Paragraph::new(value.as_str()).block(Block::default().borders(Borders::ALL).title(name.as_str())).style(style), chunks[i + 1]);
+    }
+}
+
+fn render_preview(f: &mut Frame, app: &App) {
+    let Some(p) = &app.preview else { return };
+    let area = centered_rect(80, 80, f.size()); f.render_widget(Clear, area);
+    let title = if p.title.is_empty() { format!(" {} ", p.url) } else { format!(" {} · {} ", p.title, p.url) };
+    let text = if p.body.is_empty() { vec![Line::from(Span::styled("Loading...", Style::default().fg(Color::DarkGray)))] } else { markdown_to_text(&p.body) };
+    f.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }).scroll((app.preview_scroll, 0))
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Cyan))).style(Style::default().bg(Color::Black)), area);
+}
+
+fn render_launch_history(f: &mut Frame, app: &App, now: u64) {
+    let area = centered_rect(80, 70, f.size()); f.render_widget(Clear, area);
+    let chunks = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(65), Constraint::Percentage(35)]).split(area);
+    let items: Vec<ListItem> = app.launch_history.iter().rev().map(|r| {
+        let (status, color) = match r.ok { Some(true) => ("ok  ", Color::Green), Some(false) => ("FAIL", Color::Red), None => ("…   ", Color::DarkGray) };
+        let took = r.duration_ms.map(|ms| format!(" {:.1}s", ms as f64 / 1000.0)).unwrap_or_default();
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("{:>8} ", ago(now, r.started)), Style::default().fg(Color::DarkGray)),
+            Span::styled(status, Style::default().fg(color)),
+            Span::raw(format!(" {}{}", if r.app_id.is_some() { r.name.clone() } else { format!("$ {}", r.name) }, took)),
+        ]))
+    }).collect();
+    let mut state = ListState::default(); state.select(if items.is_empty() { None } else { Some(app.history_idx) });
+    f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Recent launches ")).style(Style::default().bg(Color::Black)).highlight_style(Style::default().bg(app.config.theme.accent())), chunks[0], &mut state);
+
+    let mut counts: Vec<(&str, u32)> = app.usage.iter().map(|(id, s)| (app.apps.iter().find(|a| &a.id == id).map(|a| a.name.as_str()).unwrap_or(id.as_str()), s.count)).collect();
+    counts.sort_by_key(|&(name, count)| (std::cmp::Reverse(count), name));
+    let items: Vec<ListItem> = counts.iter().map(|(name, count)| ListItem::new(format!("{:>4}  {}", count, name))).collect();
+    f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Most launched ")).style(Style::default().bg(Color::Black)), chunks[1]);
+    if let (Some(top), Some(r)) = (app.history_output, app.selected_launch()) { render_launch_output(f, app, r, top, now); }
+}
+
+fn render_launch_output(f: &mut Frame, app: &App, r: &crate::app::LaunchRecord, top: u16, now: u64) {
+    let area = centered_rect(90, 80, f.size()); f.render_widget(Clear, area);
+    let took = r.duration_ms.map(|ms| format!(" in {:.1}s", ms as f64 / 1000.0)).unwrap_or_default();
+    let status = if r.ok.is_none() { "running".to_string() } else { r.status.clone() };
+    let title = format!(" {} · {} · {}{} ", r.name, ago(now, r.started), status, took);
+    let mut lines: Vec<Line> = vec![];
+    if r.dropped_lines > 0 { lines.push(Line::from(Span::styled(format!("({} earlier lines not kept)", r.dropped_lines), Style::default().fg(Color::DarkGray)))); }
+    if r.output.is_empty() { lines.push(Line::from(Span::styled("No output recorded.", Style::default().fg(Color::DarkGray)))); }
+    lines.extend(r.output.iter().map(|l| Line::from(crate::ansi::spans(&l.text, app.ansi_colors)).style(severity_style(crate::logbuf::classify(&l.text, l.stderr)))));
+    let border = match r.ok { Some(true) => Color::Green, Some(false) => Color::Red, None => Color::DarkGray };
+    f.render_widget(Paragraph::new(lines).scroll((top, 0))
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(border))).style(Style::default().bg(Color::Black)), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{AppModel, SearchSource};
+    use crate::app::{AppAction, ChatMessage};
+    use crate::textarea::CursorMove;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn render(app: &mut App, width: u16, height: u16) -> TestBackend {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| draw(f, app)).unwrap();
+        terminal.backend().clone()
+    }
+
+    fn sample_apps() -> Vec<AppModel> {
+        vec![
+            AppModel { id: "1".into(), name: "Grafana".into(), description: Some("dashboards #ops".into()), command: "grafana-server".into(), url: "http://localhost:3000".into(), ..Default::default() },
+            AppModel { id: "2".into(), name: "Jupyter".into(), description: Some("notebooks #py #lab".into()), command: "jupyter lab".into(), url: "http://localhost:8888".into(), ..Default::default() },
+        ]
+    }
+
+    #[test]
+    fn launcher_screen() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::SelectNext);
+        insta::assert_snapshot!(render(&mut app, 80, 20));
+    }
+
+    #[test]
+    fn edit_modal() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::OpenEditModal);
+        insta::assert_snapshot!(render(&mut app, 80, 30));
+    }
+
+    #[test]
+    fn edit_modal_marks_invalid_fields() {
+        let mut app = App::new();
+        app.reduce(AppAction::OpenAddModal);
+        app.active_form.url = "ftp://files".into();
+        app.reduce(AppAction::SubmitForm);
+        insta::assert_snapshot!(render(&mut app, 80, 30));
+    }
+
+    #[test]
+    fn multi_line_command_editor() {
+        let mut app = App::new();
+        let mut apps = sample_apps();
+        apps[0].command = "cd ~/src/grafana &&\nPORT=3000 ./bin/grafana-server --config conf/dev.ini --homepath .".into();
+        app.reduce(AppAction::AppsLoaded(apps));
+        app.reduce(AppAction::OpenEditModal);
+        app.reduce(AppAction::CycleFormFocus);
+        app.reduce(AppAction::CycleFormFocus);
+        app.reduce(AppAction::FormCursor(CursorMove::Up));
+        insta::assert_snapshot!(render(&mut app, 80, 30));
+    }
+
+    #[test]
+    fn marked_apps_in_list() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::ToggleMark);
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn url_health_dots() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::UrlHealth(vec![(app.apps[0].id.clone(), true)]));
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn scrolled_output_pane() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::LaunchResult((1..=40).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n")));
+        app.reduce(AppAction::FocusOutput(true));
+        app.reduce(AppAction::ScrollOutput(-20));
+        insta::assert_snapshot!(render(&mut app, 80, 20));
+    }
+
+    #[test]
+    fn output_search_highlights_matches() {
+        let mut app = App::new();
+        app.reduce(AppAction::LaunchResult("starting\nerror: port busy\nretrying\nError: gave up".into()));
+        app.reduce(AppAction::FocusOutput(true));
+        app.reduce(AppAction::OutputSearchStart);
+        for c in "error".chars() { app.reduce(AppAction::OutputSearchChar(c)); }
+        app.reduce(AppAction::OutputSearchDone(true));
+        app.reduce(AppAction::OutputNextMatch(true));
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn ansi_output_is_rendered_without_escapes() {
+        let mut app = App::new();
+        app.reduce(AppAction::LaunchResult("\x1b[32m✓\x1b[0m built in \x1b[1m2.1s\x1b[22m\n\x1b[2K\rDownloading 100%".into()));
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn source_apps_show_their_state() {
+        use crate::sources::Source;
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::SourceLoaded(Source::Systemd, crate::systemd::parse_units("syncthing.service loaded active running Syncthing\nbackup.service loaded failed failed Backup")));
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn errors_only_output() {
+        let mut app = App::new();
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "listening on :80\nthread 'main' panicked at src/main.rs:3".into(), stderr: false });
+        app.reduce(AppAction::LaunchResult("Error fetching apps: 502".into()));
+        app.reduce(AppAction::FocusOutput(true));
+        app.reduce(AppAction::ToggleErrorsOnly);
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn chain_editor() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::OpenChains);
+        app.reduce(AppAction::NewChain);
+        for c in "dashboards".chars() { app.reduce(AppAction::ChainChar(c)); }
+        app.reduce(AppAction::ChainFocusNext);
+        app.reduce(AppAction::ChainNext);
+        app.reduce(AppAction::ChainAddStep);
+        app.reduce(AppAction::ChainPrev);
+        app.reduce(AppAction::ChainAddStep);
+        app.reduce(AppAction::ChainAddStep);
+        insta::assert_snapshot!(render(&mut app, 80, 20));
+    }
+
+    #[test]
+    fn delete_confirmation() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::RequestDelete);
+        insta::assert_snapshot!(render(&mut app, 80, 20));
+    }
+
+    #[test]
+    fn command_palette_shows_bindings() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::OpenPalette);
+        for c in "launch".chars() { app.reduce(AppAction::PaletteChar(c)); }
+        app.keymap.bind("launch:1", "g");
+        insta::assert_snapshot!(render(&mut app, 80, 20));
+    }
+
+    #[test]
+    fn processes_screen() {
+        use crate::api::ProcessInfo;
+        let mut app = App::new();
+        app.processes_remote = true;
+        app.processes = vec![
+            ProcessInfo { id: "p1".into(), app_id: Some("1".into()), name: "Grafana".into(), pid: Some(4242), started_at: Some(6_310), status: "running".into() },
+            ProcessInfo { id: "p2".into(), app_id: None, name: "backup".into(), pid: None, started_at: None, status: "exited".into() },
+        ];
+        app.process_output = vec!["listening on :3000".into()];
+        let mut terminal = Terminal::new(TestBackend::new(80, 14)).unwrap();
+        terminal.draw(|f| render_processes(f, &app, f.size(), 10_000)).unwrap();
+        insta::assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn settings_tab_while_editing() {
+        use crate::api::ProviderConfig;
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Settings;
+        app.input_mode = InputMode::Settings;
+        app.search_providers = vec![ProviderConfig { id: 1, name: "searxng".into(), type_: "searxng".into(), is_enabled: false, config: serde_json::Value::Null }];
+        app.settings_row = 1;
+        app.reduce(AppAction::SettingsActivate);
+        app.reduce(AppAction::SettingsChar('x'));
+        insta::assert_snapshot!(render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn preset_picker() {
+        use crate::config::Preset;
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Search;
+        app.input_mode = InputMode::SearchInput;
+        app.config.presets = vec![
+            Preset { name: "fast local".into(), provider: Some("lmstudio".into()), temperature: Some(0.7), ..Default::default() },
+            Preset { name: "code helper".into(), model: Some("qwen-coder".into()), system_prompt: Some("Answer with code.".into()), ..Default::default() },
+        ];
+        app.config.preset = Some("code helper".into());
+        app.reduce(AppAction::OpenPresets);
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+
+    #[test]
+    fn placeholder_prompt_modal() {
+        let mut app = App::new();
+        let mut apps = sample_apps();
+        apps[0].command = "grafana-server --port {{port}} --config {{config}}".into();
+        app.reduce(AppAction::AppsLoaded(apps));
+        app.arg_defaults.insert("1".into(), [("port".to_string(), "3000".to_string())].into());
+        app.reduce(AppAction::LaunchSelected);
+        app.reduce(AppAction::ArgsNext);
+        for c in "dev.ini".chars() { app.reduce(AppAction::ArgsChar(c)); }
+        insta::assert_snapshot!(render(&mut app, 60, 16));
+    }
+
+    #[test]
+    fn page_preview_modal() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::PreviewSelected);
+        let url = app.preview.as_ref().unwrap().url.clone();
+        let (title, body) = crate::preview::extract("<title>Grafana</title><main><h1>Home</h1><p>3 dashboards, all <b>green</b>.</p><ul><li>CPU 12%</li></ul></main>");
+        app.reduce(AppAction::PreviewLoaded { url, title, body });
+        insta::assert_snapshot!(render(&mut app, 60, 14));
+    }
+
+    #[test]
+    fn launch_history_view() {
+        use crate::app::{LaunchRecord, LaunchStats};
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.launch_history = vec![
+            LaunchRecord { seq: 1, app_id: Some("1".into()), name: "Grafana".into(), started: 1_000, ok: Some(true), duration_ms: Some(1200), ..Default::default() },
+            LaunchRecord { seq: 2, app_id: None, name: "uptime".into(), started: 4_000, ok: Some(false), duration_ms: Some(80), ..Default::default() },
+            LaunchRecord { seq: 3, app_id: Some("1".into()), name: "Grafana".into(), started: 9_990, ok: None, duration_ms: None, ..Default::default() },
+        ];
+        app.usage.insert("1".into(), LaunchStats { count: 2, last_launched: 9_990 });
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|f| render_launch_history(f, &app, 10_000)).unwrap();
+        insta::assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn launch_output_popup() {
+        let mut app = App::new();
+        app.reduce(AppAction::SubmitAdHoc("cargo test".into()));
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "running 2 tests".into(), stderr: false });
+        app.reduce(AppAction::LaunchOutput { seq: 1, line: "error: test failed".into(), stderr: true });
+        app.reduce(AppAction::LaunchFinished { seq: 1, ok: false, duration_ms: 2500, output: "Failed: exit status: 101".into() });
+        app.reduce(AppAction::OpenLaunchHistory);
+        app.reduce(AppAction::ShowLaunchOutput);
+        let started = app.launch_history[0].started;
+        let mut terminal = Terminal::new(TestBackend::new(70, 10)).unwrap();
+        terminal.draw(|f| render_launch_history(f, &app, started + 30)).unwrap();
+        insta::assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn search_screen_with_sources() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.messages.push(ChatMessage::new("user", "what is rust"));
+        app.messages.push(ChatMessage {
+            sources: vec![SearchSource { title: "rust-lang.org".into(), url: "https://rust-lang.org".into(), content: String::new(), engine: "duckduckgo".into() }],
+            attachments: vec!["/tmp/chart.png".into()],
+            ..ChatMessage::new("assistant", "# Rust\nA **systems** language with `cargo`.")
+        });
+        app.reduce(AppAction::ToggleSources);
+        insta::assert_snapshot!(render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn small_terminals_stack_panes_and_cut_hints() {
+        let mut app = App::new();
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        let screen = render(&mut app, 50, 12).to_string();
+        let rows: Vec<&str> = screen.lines().collect();
+        assert!(rows[0].contains("[L]auncher") && !rows[0].contains("bplus-tui"), "one-line tab bar: {}", rows[0]);
+        let apps = rows.iter().position(|r| r.contains("┌ Apps")).unwrap();
+        assert!(rows.iter().skip(apps).any(|r| r.contains("┌ Details")), "details below the apps:\n{}", screen);
+        assert!(rows[11].contains("x:Stop | …"), "{}", rows[11]);
+        assert_eq!(fit_hints("Tab:Switch | q:Quit | Enter:Launch", 20), "Tab:Switch | …");
+        assert_eq!(fit_hints("Tab:Switch | q:Quit", 20), "Tab:Switch | q:Quit");
+        assert_eq!(overlay(Rect::new(0, 0, 50, 10), 25, true), Rect::new(20, 0, 30, 10));
+    }
+
+    #[test]
+    fn accessibility_mode_marks_state_in_text() {
+        let mut app = App::new();
+        app.a11y = true;
+        app.reduce(AppAction::AppsLoaded(sample_apps()));
+        app.reduce(AppAction::UrlHealth(vec![(app.apps[0].id.clone(), true)]));
+        app.reduce(AppAction::LaunchResult("Error: port in use".into()));
+        app.reduce(AppAction::FocusOutput(true));
+        let screen = render(&mut app, 100, 20).to_string();
+        assert!(screen.contains(">[L]auncher"), "{}", screen);
+        assert!(screen.contains("> Grafana [up]") && screen.contains("Jupyter [unchecked]"), "{}", screen);
+        assert!(screen.contains("Output [focused]") && screen.contains("[error] Error: port in use"), "{}", screen);
+
+        app.reduce(AppAction::FocusOutput(false));
+        app.reduce(AppAction::SwitchTab);
+        app.is_searching = true;
+        app.stream_ticks = Some((0, None));
+        let screen = render(&mut app, 100, 20).to_string();
+        assert!(screen.contains("Message · answering… [focused]") && !screen.contains('⠋'), "no spinner frame: {}", screen);
+    }
+
+    #[test]
+    fn chat_with_focused_message() {
+        let mut app = App::new();
+        app.reduce(AppAction::SwitchTab);
+        app.messages.push(ChatMessage::new("user", "what is rust"));
+        app.messages.push(ChatMessage::new("assistant", "A **systems** language.\nWith `cargo`."));
+        app.input_mode = InputMode::ChatHistory;
+        app.reduce(AppAction::FocusMessage { forward: false, mine: false });
+        insta::assert_snapshot!(render(&mut app, 80, 16));
+    }
+}